@@ -0,0 +1,71 @@
+//! Times the codec paths the daemon and the command line go through for
+//! every dump: decoding a received dump, with and without layout detection,
+//! and encoding it again for sending or saving.
+//!
+//! `cargo bench -p fcb1010-core [-- <rounds>]`
+
+use fcb1010_core::firmware::{Firmware, FirmwareSetting};
+use fcb1010_core::model::{PresetField, SysExMessage};
+use fcb1010_core::values::Value7;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ROUNDS: usize = 10_000;
+
+fn main() {
+    // `cargo bench` passes `--bench` along; the rounds may follow it
+    let rounds = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_ROUNDS);
+    let raw = sample_dump();
+    let decoded = SysExMessage::decode_with(&raw, Firmware::V2_5).expect("the sample decodes");
+    let fresh = SysExMessage::default();
+
+    let bytes = raw.len() * rounds;
+    println!("{} rounds over a {} byte dump", rounds, raw.len());
+    time("decode (fixed layout)", rounds, bytes, || {
+        black_box(SysExMessage::decode_with(black_box(&raw), Firmware::V2_5).ok());
+    });
+    time("decode (detect layout)", rounds, bytes, || {
+        black_box(SysExMessage::decode(black_box(&raw), FirmwareSetting::AutoDetect).ok());
+    });
+    time("encode (received dump)", rounds, bytes, || {
+        black_box(black_box(&decoded).encode());
+    });
+    time("encode (new dump)", rounds, bytes, || {
+        black_box(black_box(&fresh).encode());
+    });
+}
+
+fn time(name: &str, rounds: usize, bytes: usize, case: impl Fn()) {
+    // One untimed pass so allocation and caches settle
+    case();
+    let started = Instant::now();
+    for _ in 0..rounds {
+        case();
+    }
+    let elapsed = started.elapsed();
+    println!(
+        "{:<24} {:>8.2} µs/dump {:>8.1} MB/s",
+        name,
+        per_round(elapsed, rounds),
+        bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON) / 1e6
+    );
+}
+
+fn per_round(elapsed: Duration, rounds: usize) -> f64 {
+    elapsed.as_secs_f64() * 1e6 / rounds.max(1) as f64
+}
+
+/// A dump with every preset filled, as the pedal sends it
+fn sample_dump() -> Vec<u8> {
+    let mut message = SysExMessage::default();
+    for index in 0..100 {
+        for (slot, field) in PresetField::ALL.into_iter().enumerate() {
+            let value = Value7::clamped(((index * 7 + slot * 13) % 120) as u8);
+            message.set_preset_field(index, field, value);
+        }
+    }
+    message.encode()
+}
@@ -0,0 +1,262 @@
+//! Building complete dumps from code, e.g. to generate a setup or the
+//! expected dump of a test.
+//!
+//! Banks, pedals and slots are counted the way the FCB1010 labels them:
+//! banks 0-9 (shown as 00-09), pedals 1-10, PC slots 1-5, CC slots 1-2 and
+//! relays 1-2. Every value is checked, and [`DumpBuilder::build`] returns the
+//! first mistake instead of a dump.
+//!
+//! ```
+//! use fcb1010_core::builder::DumpBuilder;
+//!
+//! let dump = DumpBuilder::new()
+//!     .bank(3)
+//!     .pedal(7)
+//!     .pc(1, 12)
+//!     .cc(1, 80, 127)
+//!     .pedal(8)
+//!     .pc(1, 13)
+//!     .expression_a(7, 0, 127)
+//!     .build()?;
+//!
+//! let preset = dump.preset(36);
+//! assert_eq!(preset.program_change(0).map(|p| p.get()), Some(12));
+//! assert_eq!(
+//!     preset.control_change(0).map(|(n, v)| (n.get(), v.get())),
+//!     Some((80, 127))
+//! );
+//! assert!(dump.preset(37).expression_pedal_a().is_some());
+//! let bytes = dump.encode();
+//! # assert_eq!(bytes.first(), Some(&0xf0));
+//! # Ok::<(), fcb1010_core::builder::BuildError>(())
+//! ```
+//!
+//! Mistakes are reported rather than clamped:
+//!
+//! ```
+//! use fcb1010_core::builder::{BuildError, DumpBuilder};
+//!
+//! let result = DumpBuilder::new().bank(0).pedal(11).pc(1, 1).build();
+//! assert_eq!(result.unwrap_err(), BuildError::NoSuch { what: "pedal", number: 11 });
+//! ```
+
+use crate::firmware::Firmware;
+use crate::model::{
+    ExpressionPart, ExpressionPedal, PresetField, SwitchMode, SysExMessage, PEDALS_PER_BANK,
+};
+use crate::values::{Channel, ControllerNumber, OutOfRange, Value7};
+use std::fmt;
+
+const BANKS: usize = 10;
+
+/// Why a [`DumpBuilder`] could not build its dump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// A bank, pedal, slot, relay, global function or channel the FCB1010
+    /// does not have
+    NoSuch { what: &'static str, number: usize },
+    /// A MIDI value above its range
+    OutOfRange(OutOfRange),
+    /// A preset value was given before [`DumpBuilder::pedal`] picked a preset
+    NoPedal,
+    /// An expression pedal assignment the pedal cannot send
+    InvalidExpression(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::NoSuch { what, number } => write!(f, "There is no {} {}", what, number),
+            BuildError::OutOfRange(e) => e.fmt(f),
+            BuildError::NoPedal => write!(f, "No pedal was picked before setting a value"),
+            BuildError::InvalidExpression(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<OutOfRange> for BuildError {
+    fn from(e: OutOfRange) -> Self {
+        BuildError::OutOfRange(e)
+    }
+}
+
+/// Builds a dump one value at a time. Every method takes and returns the
+/// builder, so a whole setup reads as one chain.
+#[derive(Debug, Clone, Default)]
+pub struct DumpBuilder {
+    message: SysExMessage,
+    bank: usize,
+    /// The preset index the preset values go to
+    preset: Option<usize>,
+    error: Option<BuildError>,
+}
+
+impl DumpBuilder {
+    /// Starts from a blank dump, in which every slot is switched off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts from an existing dump, to change some of it
+    pub fn from_message(message: SysExMessage) -> Self {
+        Self {
+            message,
+            ..Self::default()
+        }
+    }
+
+    pub fn firmware(self, firmware: Firmware) -> Self {
+        self.edit(|builder| {
+            builder.message.set_firmware(firmware);
+            Ok(())
+        })
+    }
+
+    /// Sets the channel, 1-16, of global function 1-10 in the order of the
+    /// FCB1010's global setup: PC 1-5, CC 1-2, expression A and B, note
+    pub fn global_channel(self, function: usize, channel: u8) -> Self {
+        self.edit(|builder| {
+            let function = number(function, 1, 10, "global function")?;
+            let channel = Channel::new(number(usize::from(channel), 1, 16, "channel")? as u8)?;
+            builder.message.set_global_channel(function, channel);
+            Ok(())
+        })
+    }
+
+    /// Sets how relay 1 or 2 switches
+    pub fn switch_mode(self, relay: usize, mode: SwitchMode) -> Self {
+        self.edit(|builder| {
+            let relay = number(relay, 1, 2, "relay")?;
+            builder.message.set_switch_mode(relay, mode);
+            Ok(())
+        })
+    }
+
+    /// Picks bank 0-9; the next values need a [`pedal`](Self::pedal) in it
+    pub fn bank(self, bank: usize) -> Self {
+        self.edit(|builder| {
+            if bank >= BANKS {
+                return Err(BuildError::NoSuch {
+                    what: "bank",
+                    number: bank,
+                });
+            }
+            builder.bank = bank;
+            builder.preset = None;
+            Ok(())
+        })
+    }
+
+    /// Picks pedal 1-10 of the current bank, bank 0 unless one was picked
+    pub fn pedal(self, pedal: usize) -> Self {
+        self.edit(|builder| {
+            let pedal = number(pedal, 1, PEDALS_PER_BANK, "pedal")?;
+            builder.preset = Some(builder.bank * PEDALS_PER_BANK + pedal);
+            Ok(())
+        })
+    }
+
+    /// Sends program change `program` from PC slot 1-5
+    pub fn pc(self, slot: usize, program: u8) -> Self {
+        self.edit(|builder| {
+            let slot = number(slot, 1, 5, "PC slot")?;
+            builder.put(PresetField::ProgramChange(slot), program)
+        })
+    }
+
+    /// Sends controller `controller` with `value` from CC slot 1-2
+    pub fn cc(self, slot: usize, controller: u8, value: u8) -> Self {
+        self.edit(|builder| {
+            let slot = number(slot, 1, 2, "CC slot")?;
+            builder.put(PresetField::ControlNumber(slot), controller)?;
+            builder.put(PresetField::ControlValue(slot), value)
+        })
+    }
+
+    /// Lets expression pedal A sweep `controller` from `min` (heel) to `max`
+    pub fn expression_a(self, controller: u8, min: u8, max: u8) -> Self {
+        self.edit(|builder| builder.expression(PresetField::ExpressionA, controller, min, max))
+    }
+
+    /// Lets expression pedal B sweep `controller` from `min` (heel) to `max`
+    pub fn expression_b(self, controller: u8, min: u8, max: u8) -> Self {
+        self.edit(|builder| builder.expression(PresetField::ExpressionB, controller, min, max))
+    }
+
+    /// Sends `note`, released straight away
+    pub fn note(self, note: u8) -> Self {
+        self.edit(|builder| builder.put(PresetField::Note, note))
+    }
+
+    /// Sets any field of the picked preset and switches its slot on, for
+    /// code that works with fields generically
+    pub fn set(self, field: PresetField, value: u8) -> Self {
+        self.edit(|builder| builder.put(field, value))
+    }
+
+    /// The dump, or the first mistake made while building it
+    pub fn build(self) -> Result<SysExMessage, BuildError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.message),
+        }
+    }
+
+    // Later calls do nothing once something went wrong, so the first mistake
+    // is the one reported
+    fn edit(mut self, change: impl FnOnce(&mut Self) -> Result<(), BuildError>) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = change(&mut self) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    fn put(&mut self, field: PresetField, value: u8) -> Result<(), BuildError> {
+        let index = self.preset.ok_or(BuildError::NoPedal)?;
+        let value = Value7::new(value)?;
+        self.message.set_preset_field(index, field, value);
+        self.message.set_slot_enabled(index, field.slot(), true);
+        Ok(())
+    }
+
+    fn expression(
+        &mut self,
+        pedal: fn(ExpressionPart) -> PresetField,
+        controller: u8,
+        min: u8,
+        max: u8,
+    ) -> Result<(), BuildError> {
+        let assignment = ExpressionPedal::new(
+            ControllerNumber::new(controller)?,
+            Value7::new(min)?,
+            Value7::new(max)?,
+        );
+        if let Some(e) = assignment.validate() {
+            return Err(BuildError::InvalidExpression(e));
+        }
+        self.put(pedal(ExpressionPart::Controller), controller)?;
+        self.put(pedal(ExpressionPart::Min), min)?;
+        self.put(pedal(ExpressionPart::Max), max)
+    }
+}
+
+/// A 1-based number as an index, if it is in `first..=last`
+fn number(
+    value: usize,
+    first: usize,
+    last: usize,
+    what: &'static str,
+) -> Result<usize, BuildError> {
+    if (first..=last).contains(&value) {
+        Ok(value - first)
+    } else {
+        Err(BuildError::NoSuch {
+            what,
+            number: value,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+//! The 7-bit packing of the dump's data area
+
+use alloc::vec::Vec;
+
+/// Start byte, manufacturer ID, global channel, device ID and the patch byte
+pub const HEADER_SIZE: usize = 7;
+
+/// The 8-bit data area of a raw dump, group by group: every 8 bytes carry 7
+/// data bytes followed by their high bits
+pub fn unpacked(data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let body = data
+        .get(HEADER_SIZE..data.len().saturating_sub(1))
+        .unwrap_or_default();
+    unpacked_groups(body)
+}
+
+/// Unpacks packed data bytes without the framing. An incomplete last group
+/// is left out, so a dump can be read while it still arrives.
+pub fn unpacked_groups(body: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    body.chunks_exact(8).flat_map(|group| {
+        let msb_byte = group[7];
+        group[..7]
+            .iter()
+            .enumerate()
+            .map(move |(i, &low)| low | ((msb_byte >> i) & 0x01) << 7)
+    })
+}
+
+/// Undoes the 7-bit packing of a raw dump, returning the 8-bit data area
+pub fn unpack(data: &[u8]) -> Vec<u8> {
+    let body = data
+        .get(HEADER_SIZE..data.len().saturating_sub(1))
+        .unwrap_or_default();
+    let mut fixed_data = Vec::with_capacity(body.len() / 8 * 7);
+    // Whole groups at a time; pushing byte by byte from `unpacked` does not
+    // get the capacity check out of the loop
+    for group in body.chunks_exact(8) {
+        let mut bytes = [0u8; 7];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = group[i] | ((group[7] >> i) & 0x01) << 7;
+        }
+        fixed_data.extend_from_slice(&bytes);
+    }
+    fixed_data
+}
+
+/// Where the low seven bits of data byte `offset` are in a raw dump; its
+/// high bit is in the last byte of the same group
+pub fn packed_offset(offset: usize) -> usize {
+    HEADER_SIZE + offset / 7 * 8 + offset % 7
+}
+
+/// Bytes taken by `len` bytes of 8-bit data once packed
+pub fn packed_len(len: usize) -> usize {
+    len.div_ceil(7) * 8
+}
+
+/// Appends `data` in 7-bit groups: seven data bytes (the last group padded
+/// with zeros) followed by their high bits
+pub fn pack_into(data: &[u8], encoded: &mut Vec<u8>) {
+    for chunk in data.chunks(7) {
+        let mut group = [0u8; 8];
+        for (i, &byte) in chunk.iter().enumerate() {
+            group[7] |= (byte >> 7) << i;
+            group[i] = byte & 0x7f;
+        }
+        encoded.extend_from_slice(&group);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn framed(data: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![0xf0, 0x00, 0x20, 0x32, 0x00, 0x0c, 0x0f];
+        pack_into(data, &mut encoded);
+        encoded.push(0xf7);
+        encoded
+    }
+
+    #[test]
+    fn packing_round_trips_every_byte() {
+        let data: Vec<u8> = (0..=255).collect();
+        let encoded = framed(&data);
+        assert_eq!(encoded.len(), HEADER_SIZE + packed_len(data.len()) + 1);
+        assert!(encoded[1..encoded.len() - 1]
+            .iter()
+            .all(|&byte| byte < 0x80));
+
+        let unpacked = unpack(&encoded);
+        // The last group is padded with zeros
+        assert_eq!(unpacked.len(), packed_len(data.len()) / 8 * 7);
+        assert_eq!(unpacked[..data.len()], data[..]);
+        assert!(unpacked[data.len()..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn offsets_point_at_the_low_bits() {
+        let data: Vec<u8> = (0..50).map(|i| i * 5).collect();
+        let encoded = framed(&data);
+        for (offset, &byte) in data.iter().enumerate() {
+            let low = packed_offset(offset);
+            let high = low - offset % 7 + 7;
+            assert_eq!(encoded[low], byte & 0x7f);
+            assert_eq!((encoded[high] >> (offset % 7)) & 1, byte >> 7);
+        }
+    }
+
+    #[test]
+    fn unfinished_groups_are_left_out() {
+        let encoded = framed(&[0x81; 14]);
+        let body = &encoded[HEADER_SIZE..HEADER_SIZE + 12];
+        assert_eq!(unpacked_groups(body).collect::<Vec<_>>(), [0x81; 7]);
+    }
+}
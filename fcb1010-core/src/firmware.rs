@@ -0,0 +1,181 @@
+use crate::model::{Preset, PresetField, MAX_CONTROLLER_NUMBER};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// FCB1010 firmware revisions whose dumps are laid out differently
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Default)]
+pub enum Firmware {
+    #[default]
+    V2_5,
+    /// Stores the note before the expression pedals and moves the switch bytes
+    V2_4,
+    /// A layout loaded from a memory map file, for clones and other variants
+    Custom(Box<MemoryMap>),
+}
+
+/// Where each value lives in the unpacked (8-bit) dump
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryMap {
+    pub preset_size: usize,
+    /// Offset inside the preset record of every field, in `PresetField::ALL` order
+    pub preset_fields: [usize; 16],
+    pub global_channels: usize,
+    pub switch_modes: usize,
+    /// Number of unpacked bytes the dump must contain
+    pub data_size: usize,
+}
+
+impl MemoryMap {
+    pub fn offset_of(&self, field: PresetField) -> usize {
+        self.preset_fields[field.index()]
+    }
+
+    /// The byte ranges the editor models, sorted by offset
+    pub fn known_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = vec![
+            0..100 * self.preset_size,
+            self.switch_modes..self.switch_modes + 2,
+            self.global_channels..self.global_channels + 10,
+        ];
+        ranges.sort_by_key(|range| range.start);
+        ranges
+    }
+
+    /// Checks that every value fits in the dump and no two share a byte
+    pub fn validate(&self) -> Result<(), String> {
+        if self.preset_size == 0 {
+            return Err("The preset size must not be 0".to_string());
+        }
+        if self.data_size > MAX_DATA_SIZE {
+            return Err(format!(
+                "The data size must not be above 0x{:x}",
+                MAX_DATA_SIZE
+            ));
+        }
+        for (i, &offset) in self.preset_fields.iter().enumerate() {
+            if offset >= self.preset_size {
+                return Err(format!(
+                    "Field {} is at {} but presets are only {} bytes",
+                    i + 1,
+                    offset,
+                    self.preset_size
+                ));
+            }
+            if self.preset_fields[..i].contains(&offset) {
+                return Err(format!("Two preset fields share offset {}", offset));
+            }
+        }
+        let ranges = self.known_ranges();
+        if let Some(last) = ranges.last().filter(|r| r.end > self.data_size) {
+            return Err(format!(
+                "Bytes up to 0x{:x} are used but the dump only has 0x{:x}",
+                last.end, self.data_size
+            ));
+        }
+        if let Some(pair) = ranges.windows(2).find(|pair| pair[0].end > pair[1].start) {
+            return Err(format!(
+                "0x{:x}..0x{:x} overlaps 0x{:x}..0x{:x}",
+                pair[0].start, pair[0].end, pair[1].start, pair[1].end
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Far more than any FCB1010 firmware stores, to catch typos in map files
+const MAX_DATA_SIZE: usize = 0x4000;
+
+// How plausible the expression settings look when decoded with a layout
+fn plausibility(unpacked: &[u8], map: &MemoryMap) -> usize {
+    unpacked
+        .chunks_exact(map.preset_size)
+        .take(100)
+        .map(|record| Preset::from_bytes(record, map))
+        .filter(|preset| {
+            [preset.expression_pedal_a(), preset.expression_pedal_b()]
+                .into_iter()
+                .flatten()
+                .all(|pedal| {
+                    pedal.controller.get() <= MAX_CONTROLLER_NUMBER && pedal.min <= pedal.max
+                })
+        })
+        .count()
+}
+
+impl Firmware {
+    pub const ALL: [Firmware; 2] = [Firmware::V2_5, Firmware::V2_4];
+
+    /// The built-in layouts plus `custom` once one is loaded, for the layout
+    /// pickers
+    pub fn selectable(custom: Option<&MemoryMap>) -> Vec<Firmware> {
+        Self::ALL
+            .into_iter()
+            .chain(custom.map(|map| Firmware::Custom(Box::new(map.clone()))))
+            .collect()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Firmware::V2_5 => "v2.5",
+            Firmware::V2_4 => "v2.4",
+            Firmware::Custom(_) => "custom",
+        }
+    }
+
+    pub fn memory_map(&self) -> MemoryMap {
+        match self {
+            Firmware::V2_5 => MemoryMap {
+                preset_size: 16,
+                preset_fields: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+                global_channels: 0x7e0,
+                switch_modes: 0x7d0,
+                data_size: 0x7ea,
+            },
+            Firmware::V2_4 => MemoryMap {
+                preset_size: 16,
+                preset_fields: [0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12, 13, 14, 15, 9],
+                global_channels: 0x7e0,
+                switch_modes: 0x7d2,
+                data_size: 0x7ea,
+            },
+            Firmware::Custom(map) => (**map).clone(),
+        }
+    }
+
+    /// Guesses the revision from unpacked dump data. Returns `None` when the
+    /// data looks equally plausible with every layout.
+    pub fn detect(unpacked: &[u8]) -> Option<Firmware> {
+        let mut scores: Vec<(Firmware, usize)> = Self::ALL
+            .into_iter()
+            .map(|firmware| {
+                let score = plausibility(unpacked, &firmware.memory_map());
+                (firmware, score)
+            })
+            .collect();
+        scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+
+        match scores.as_slice() {
+            [(best, best_score), (_, runner_up), ..] if best_score > runner_up => {
+                Some(best.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The user's choice of how received dumps are interpreted
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub enum FirmwareSetting {
+    #[default]
+    AutoDetect,
+    Fixed(Firmware),
+}
+
+impl FirmwareSetting {
+    pub fn label(&self) -> String {
+        match self {
+            FirmwareSetting::AutoDetect => "Auto-detect".to_string(),
+            FirmwareSetting::Fixed(firmware) => firmware.label().to_string(),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! The FCB1010 dump format and transfer logic, without any GUI, for the
+//! editor, its command line and headless tools such as a backup daemon on a
+//! single-board computer. Every type is `Send` and `Sync`.
+//!
+//! Without the default `std` feature only [`codec`] is built, which needs
+//! nothing but `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod builder;
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod firmware;
+#[cfg(feature = "std")]
+pub mod model;
+#[cfg(feature = "std")]
+pub mod receive;
+#[cfg(feature = "std")]
+pub mod values;
@@ -0,0 +1,1324 @@
+use crate::codec::{pack_into, packed_len, HEADER_SIZE};
+pub use crate::codec::{packed_offset, unpack, unpacked};
+use crate::firmware::{Firmware, FirmwareSetting, MemoryMap};
+use crate::values::{Channel, ControllerNumber, NoteNumber, OutOfRange, ProgramNumber, Value7};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Presets are addressed on the device as ten banks of ten pedals
+pub const PEDALS_PER_BANK: usize = 10;
+
+pub fn bank_and_pedal(index: usize) -> (usize, usize) {
+    (index / PEDALS_PER_BANK, index % PEDALS_PER_BANK)
+}
+
+/// The messages a preset can send, numbered like the global functions:
+/// PC 1-5, CC 1-2, the two expression pedals and the note
+pub const SLOTS: usize = 10;
+
+/// Keys naming the slots in the HTTP API, in slot order
+pub const SLOT_KEYS: [&str; SLOTS] = [
+    "pc1", "pc2", "pc3", "pc4", "pc5", "cc1", "cc2", "exp_a", "exp_b", "note",
+];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(from = "PresetRepr")]
+pub struct Preset {
+    program_changes: [ProgramNumber; 5],
+    control_changes: [(ControllerNumber, Value7); 2],
+    expression_pedal_a: ExpressionPedal,
+    expression_pedal_b: ExpressionPedal,
+    note: NoteNumber,
+    /// Slots switched off; their values are kept for when they are switched
+    /// back on. Only projects store them: the FCB1010 has no switches of its
+    /// own and leaves a slot holding 0 unused, so dumps get 0 for a slot
+    /// switched off (see [`Preset::write_bytes`]) and switches guessed from
+    /// the values when read (see [`Preset::switches_from_values`]).
+    disabled: [bool; SLOTS],
+}
+
+// The values as a file stores them, so the ones out of range can be
+// reported. Projects saved before slots could be switched off have no
+// switches.
+#[derive(Deserialize)]
+struct PresetRepr {
+    program_changes: [u8; 5],
+    control_changes: [(u8, u8); 2],
+    expression_pedal_a: ExpressionPedalRepr,
+    expression_pedal_b: ExpressionPedalRepr,
+    note: u8,
+    #[serde(default)]
+    disabled: Option<[bool; SLOTS]>,
+}
+
+impl PresetRepr {
+    fn value(&self, field: PresetField) -> u8 {
+        match field {
+            PresetField::ProgramChange(i) => self.program_changes[i],
+            PresetField::ControlNumber(i) => self.control_changes[i].0,
+            PresetField::ControlValue(i) => self.control_changes[i].1,
+            PresetField::ExpressionA(part) => self.expression_pedal_a.part(part),
+            PresetField::ExpressionB(part) => self.expression_pedal_b.part(part),
+            PresetField::Note => self.note,
+        }
+    }
+
+    fn checked(&self) -> (Preset, Vec<(PresetField, OutOfRange)>) {
+        let (mut preset, errors) = Preset::from_raw(|field| self.value(field));
+        if let Some(disabled) = self.disabled {
+            preset.disabled = disabled;
+        }
+        (preset, errors)
+    }
+}
+
+impl From<PresetRepr> for Preset {
+    fn from(repr: PresetRepr) -> Self {
+        repr.checked().0
+    }
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Preset {
+    /// A blank preset, which sends nothing until a slot is switched on, as
+    /// [`Preset::switches_from_values`] has it for all zeros
+    pub fn new() -> Self {
+        Self {
+            program_changes: [ProgramNumber::default(); 5],
+            control_changes: [(ControllerNumber::default(), Value7::default()); 2],
+            expression_pedal_a: ExpressionPedal::default(),
+            expression_pedal_b: ExpressionPedal::default(),
+            note: NoteNumber::default(),
+            disabled: [true; SLOTS],
+        }
+    }
+
+    /// Reads a preset record laid out according to the memory map. The slot
+    /// switches are guessed from the values.
+    pub fn from_bytes(bytes: &[u8], map: &MemoryMap) -> Self {
+        Self::from_bytes_checked(bytes, map).0
+    }
+
+    /// Like [`Preset::from_bytes`], also returning the bytes too big for
+    /// their field, which are clamped
+    pub fn from_bytes_checked(
+        bytes: &[u8],
+        map: &MemoryMap,
+    ) -> (Self, Vec<(PresetField, OutOfRange)>) {
+        Self::from_raw(|field| bytes[map.offset_of(field)])
+    }
+
+    // Every value goes through the checked constructor, so whoever reads a
+    // dump or file learns about each one that had to be clamped. Built in
+    // one go rather than field by field with `set`, which took most of the
+    // time of decoding a dump; the fields are read in `PresetField::ALL`
+    // order.
+    fn from_raw(value: impl Fn(PresetField) -> u8) -> (Self, Vec<(PresetField, OutOfRange)>) {
+        let mut errors = Vec::new();
+        let mut checked = |field: PresetField| {
+            let byte = value(field);
+            Value7::new(byte).unwrap_or_else(|error| {
+                errors.push((field, error));
+                Value7::clamped(byte)
+            })
+        };
+        use ExpressionPart::{Controller, Max, Min};
+        let mut preset = Self {
+            program_changes: [0, 1, 2, 3, 4].map(|i| checked(PresetField::ProgramChange(i)).into()),
+            control_changes: [0, 1].map(|i| {
+                (
+                    checked(PresetField::ControlNumber(i)).into(),
+                    checked(PresetField::ControlValue(i)),
+                )
+            }),
+            expression_pedal_a: ExpressionPedal::new(
+                checked(PresetField::ExpressionA(Controller)).into(),
+                checked(PresetField::ExpressionA(Min)),
+                checked(PresetField::ExpressionA(Max)),
+            ),
+            expression_pedal_b: ExpressionPedal::new(
+                checked(PresetField::ExpressionB(Controller)).into(),
+                checked(PresetField::ExpressionB(Min)),
+                checked(PresetField::ExpressionB(Max)),
+            ),
+            note: checked(PresetField::Note).into(),
+            disabled: [true; SLOTS],
+        };
+        preset.switches_from_values();
+        (preset, errors)
+    }
+
+    /// Writes the preset into its record as the pedal is to act on it, 0 in
+    /// every field of a slot switched off, leaving bytes the map does not
+    /// assign untouched
+    pub fn write_bytes(self, bytes: &mut [u8], map: &MemoryMap) {
+        for field in PresetField::ALL {
+            bytes[map.offset_of(field)] = self.sent(field);
+        }
+    }
+
+    /// The field as a dump carries it: its value, or 0 while its slot is off
+    pub fn sent(&self, field: PresetField) -> u8 {
+        if self.sends(field) {
+            self.get(field)
+        } else {
+            0
+        }
+    }
+
+    /// Switches on the slots with a value other than 0 and off the others,
+    /// which is how the pedal is read when nothing says otherwise: 0 is what
+    /// a fresh preset holds, and the editor treated it as unused before
+    /// slots had switches
+    pub fn switches_from_values(&mut self) {
+        let unused = |values: &[u8]| values.iter().all(|&value| value == 0);
+        let pedal = |pedal: &ExpressionPedal| {
+            unused(&[pedal.controller.get(), pedal.min.get(), pedal.max.get()])
+        };
+        for (slot, program) in self.program_changes.iter().enumerate() {
+            self.disabled[slot] = program.get() == 0;
+        }
+        for (i, (number, value)) in self.control_changes.iter().enumerate() {
+            self.disabled[5 + i] = unused(&[number.get(), value.get()]);
+        }
+        self.disabled[7] = pedal(&self.expression_pedal_a);
+        self.disabled[8] = pedal(&self.expression_pedal_b);
+        self.disabled[9] = self.note.get() == 0;
+    }
+
+    pub fn is_enabled(&self, slot: usize) -> bool {
+        !self.disabled[slot]
+    }
+
+    /// Switches a slot on or off, returning whether that changed anything
+    pub fn set_enabled(&mut self, slot: usize, enabled: bool) -> bool {
+        let changed = self.disabled[slot] == enabled;
+        self.disabled[slot] = !enabled;
+        changed
+    }
+
+    /// Whether the slot the field belongs to is sent
+    pub fn sends(&self, field: PresetField) -> bool {
+        self.is_enabled(field.slot())
+    }
+
+    pub fn expression_pedal_a(&self) -> Option<&ExpressionPedal> {
+        self.is_enabled(7).then_some(&self.expression_pedal_a)
+    }
+
+    pub fn expression_pedal_b(&self) -> Option<&ExpressionPedal> {
+        self.is_enabled(8).then_some(&self.expression_pedal_b)
+    }
+
+    pub fn program_change(&self, slot: usize) -> Option<ProgramNumber> {
+        self.is_enabled(slot).then_some(self.program_changes[slot])
+    }
+
+    pub fn control_change(&self, slot: usize) -> Option<(ControllerNumber, Value7)> {
+        self.is_enabled(5 + slot)
+            .then_some(self.control_changes[slot])
+    }
+
+    pub fn note(&self) -> Option<NoteNumber> {
+        self.is_enabled(9).then_some(self.note)
+    }
+
+    /// Any field as a plain data byte, for code that handles them alike
+    pub fn value(&self, field: PresetField) -> Value7 {
+        match field {
+            PresetField::ProgramChange(i) => self.program_changes[i].into(),
+            PresetField::ControlNumber(i) => self.control_changes[i].0.into(),
+            PresetField::ControlValue(i) => self.control_changes[i].1,
+            PresetField::ExpressionA(part) => part.get(&self.expression_pedal_a),
+            PresetField::ExpressionB(part) => part.get(&self.expression_pedal_b),
+            PresetField::Note => self.note.into(),
+        }
+    }
+
+    pub fn get(&self, field: PresetField) -> u8 {
+        self.value(field).get()
+    }
+
+    /// Sets a single field, returning whether the value actually changed
+    pub fn set(&mut self, field: PresetField, value: Value7) -> bool {
+        let changed = self.value(field) != value;
+        match field {
+            PresetField::ProgramChange(i) => self.program_changes[i] = value.into(),
+            PresetField::ControlNumber(i) => self.control_changes[i].0 = value.into(),
+            PresetField::ControlValue(i) => self.control_changes[i].1 = value,
+            PresetField::ExpressionA(part) => part.set(&mut self.expression_pedal_a, value),
+            PresetField::ExpressionB(part) => part.set(&mut self.expression_pedal_b, value),
+            PresetField::Note => self.note = value.into(),
+        }
+        changed
+    }
+}
+
+/// Addresses one editable value inside a preset
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
+pub enum PresetField {
+    ProgramChange(usize),
+    ControlNumber(usize),
+    ControlValue(usize),
+    ExpressionA(ExpressionPart),
+    ExpressionB(ExpressionPart),
+    Note,
+}
+
+impl PresetField {
+    /// Every field, in the order the v2.5 firmware stores them
+    pub const ALL: [PresetField; 16] = [
+        PresetField::ProgramChange(0),
+        PresetField::ProgramChange(1),
+        PresetField::ProgramChange(2),
+        PresetField::ProgramChange(3),
+        PresetField::ProgramChange(4),
+        PresetField::ControlNumber(0),
+        PresetField::ControlValue(0),
+        PresetField::ControlNumber(1),
+        PresetField::ControlValue(1),
+        PresetField::ExpressionA(ExpressionPart::Controller),
+        PresetField::ExpressionA(ExpressionPart::Min),
+        PresetField::ExpressionA(ExpressionPart::Max),
+        PresetField::ExpressionB(ExpressionPart::Controller),
+        PresetField::ExpressionB(ExpressionPart::Min),
+        PresetField::ExpressionB(ExpressionPart::Max),
+        PresetField::Note,
+    ];
+
+    /// Keys naming the fields in files and the HTTP API, in `ALL` order
+    pub const KEYS: [&'static str; 16] = [
+        "pc1",
+        "pc2",
+        "pc3",
+        "pc4",
+        "pc5",
+        "cc1_number",
+        "cc1_value",
+        "cc2_number",
+        "cc2_value",
+        "exp_a_controller",
+        "exp_a_min",
+        "exp_a_max",
+        "exp_b_controller",
+        "exp_b_min",
+        "exp_b_max",
+        "note",
+    ];
+
+    /// The slot the field belongs to, which is also the global function
+    /// whose channel it is sent on
+    pub fn slot(self) -> usize {
+        match self {
+            PresetField::ProgramChange(i) => i,
+            PresetField::ControlNumber(i) | PresetField::ControlValue(i) => 5 + i,
+            PresetField::ExpressionA(_) => 7,
+            PresetField::ExpressionB(_) => 8,
+            PresetField::Note => 9,
+        }
+    }
+
+    /// Position of the field in `ALL`. Computed rather than searched for:
+    /// every dump decoded or encoded looks up each field of each preset.
+    pub fn index(self) -> usize {
+        match self {
+            PresetField::ProgramChange(i) => i,
+            PresetField::ControlNumber(i) => 5 + 2 * i,
+            PresetField::ControlValue(i) => 6 + 2 * i,
+            PresetField::ExpressionA(part) => 9 + part as usize,
+            PresetField::ExpressionB(part) => 12 + part as usize,
+            PresetField::Note => 15,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        Self::KEYS[self.index()]
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::KEYS
+            .iter()
+            .position(|&k| k == key)
+            .map(|index| Self::ALL[index])
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
+pub enum ExpressionPart {
+    Controller,
+    Min,
+    Max,
+}
+
+impl ExpressionPart {
+    fn get(self, pedal: &ExpressionPedal) -> Value7 {
+        match self {
+            ExpressionPart::Controller => pedal.controller.into(),
+            ExpressionPart::Min => pedal.min,
+            ExpressionPart::Max => pedal.max,
+        }
+    }
+
+    fn set(self, pedal: &mut ExpressionPedal, value: Value7) {
+        match self {
+            ExpressionPart::Controller => pedal.controller = value.into(),
+            ExpressionPart::Min => pedal.min = value,
+            ExpressionPart::Max => pedal.max = value,
+        }
+    }
+}
+
+/// The continuous controller an expression pedal sends, and its sweep
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(from = "ExpressionPedalRepr")]
+pub struct ExpressionPedal {
+    pub controller: ControllerNumber,
+    pub min: Value7,
+    pub max: Value7,
+}
+
+// Projects saved before the fields were named store the pedal as a plain tuple
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpressionPedalRepr {
+    Tuple(u8, u8, u8),
+    Named { controller: u8, min: u8, max: u8 },
+}
+
+impl ExpressionPedalRepr {
+    fn part(&self, part: ExpressionPart) -> u8 {
+        match *self {
+            ExpressionPedalRepr::Tuple(controller, min, max)
+            | ExpressionPedalRepr::Named {
+                controller,
+                min,
+                max,
+            } => match part {
+                ExpressionPart::Controller => controller,
+                ExpressionPart::Min => min,
+                ExpressionPart::Max => max,
+            },
+        }
+    }
+}
+
+impl From<ExpressionPedalRepr> for ExpressionPedal {
+    fn from(repr: ExpressionPedalRepr) -> Self {
+        Self::new(
+            ControllerNumber::clamped(repr.part(ExpressionPart::Controller)),
+            Value7::clamped(repr.part(ExpressionPart::Min)),
+            Value7::clamped(repr.part(ExpressionPart::Max)),
+        )
+    }
+}
+
+/// Controllers 120-127 are channel mode messages, not continuous controllers
+pub const MAX_CONTROLLER_NUMBER: u8 = 119;
+
+impl ExpressionPedal {
+    pub fn new(controller: ControllerNumber, min: Value7, max: Value7) -> Self {
+        Self {
+            controller,
+            min,
+            max,
+        }
+    }
+
+    /// Explains what is wrong with the assignment, if anything
+    pub fn validate(&self) -> Option<String> {
+        if self.controller.get() > MAX_CONTROLLER_NUMBER {
+            Some(format!(
+                "CC {} is a channel mode message, not a controller",
+                self.controller
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// A run of unpacked dump bytes the editor does not interpret
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct UnknownRegion {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+impl UnknownRegion {
+    /// Splits everything outside the map's known ranges into regions
+    fn collect(data: &[u8], map: &MemoryMap) -> Vec<UnknownRegion> {
+        let mut regions = Vec::new();
+        let mut start = 0;
+        let ends = map
+            .known_ranges()
+            .into_iter()
+            .chain(std::iter::once(data.len()..data.len()));
+        for known in ends {
+            let end = known.start.min(data.len());
+            if start < end {
+                regions.push(UnknownRegion {
+                    offset: start,
+                    bytes: data[start..end].to_vec(),
+                });
+            }
+            start = start.max(known.end);
+        }
+        regions
+    }
+}
+
+/// Where a value was read that did not fit its field
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ValueLocation {
+    Preset(usize, PresetField),
+    GlobalChannel(usize),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(from = "SysExMessageRepr")]
+pub struct SysExMessage {
+    start_byte: u8,
+    manufacturer_id: [u8; 3],
+    global_channel: u8,
+    device_id: u8,
+    #[serde(with = "serde_arrays")]
+    presets: [Preset; 100],
+    #[serde(with = "serde_arrays")]
+    global_channels: [Channel; 10],
+    #[serde(default)]
+    switch_modes: [SwitchMode; 2],
+    /// The layout the dump was decoded with, and will be encoded with
+    #[serde(default)]
+    firmware: Firmware,
+    /// Bytes outside the modelled areas, written back unchanged
+    #[serde(default)]
+    unknown_regions: Vec<UnknownRegion>,
+    end_byte: u8,
+    original_data: Option<Vec<u8>>,
+    /// Values clamped while decoding or loading, as they were found, until
+    /// the import check takes them
+    #[serde(skip)]
+    out_of_range: Vec<(ValueLocation, OutOfRange)>,
+}
+
+// The presets and channels as a file stores them, read through the same
+// checks as a dump
+#[derive(Deserialize)]
+struct SysExMessageRepr {
+    start_byte: u8,
+    manufacturer_id: [u8; 3],
+    global_channel: u8,
+    device_id: u8,
+    #[serde(with = "serde_arrays")]
+    presets: [PresetRepr; 100],
+    global_channels: [u8; 10],
+    #[serde(default)]
+    switch_modes: [SwitchMode; 2],
+    #[serde(default)]
+    firmware: Firmware,
+    #[serde(default)]
+    unknown_regions: Vec<UnknownRegion>,
+    end_byte: u8,
+    original_data: Option<Vec<u8>>,
+}
+
+impl From<SysExMessageRepr> for SysExMessage {
+    fn from(repr: SysExMessageRepr) -> Self {
+        let mut out_of_range = Vec::new();
+        let presets = checked_presets(
+            repr.presets.iter().map(PresetRepr::checked),
+            &mut out_of_range,
+        );
+        Self {
+            start_byte: repr.start_byte,
+            manufacturer_id: repr.manufacturer_id,
+            global_channel: repr.global_channel,
+            device_id: repr.device_id,
+            presets,
+            global_channels: checked_channels(&repr.global_channels, &mut out_of_range),
+            switch_modes: repr.switch_modes,
+            firmware: repr.firmware,
+            unknown_regions: repr.unknown_regions,
+            end_byte: repr.end_byte,
+            original_data: repr.original_data,
+            out_of_range,
+        }
+    }
+}
+
+fn checked_presets(
+    checked: impl Iterator<Item = (Preset, Vec<(PresetField, OutOfRange)>)>,
+    out_of_range: &mut Vec<(ValueLocation, OutOfRange)>,
+) -> [Preset; 100] {
+    let mut presets = [Preset::new(); 100];
+    for (index, (preset, (checked, errors))) in presets.iter_mut().zip(checked).enumerate() {
+        *preset = checked;
+        out_of_range.extend(
+            errors
+                .into_iter()
+                .map(|(field, error)| (ValueLocation::Preset(index, field), error)),
+        );
+    }
+    presets
+}
+
+fn checked_channels(
+    bytes: &[u8],
+    out_of_range: &mut Vec<(ValueLocation, OutOfRange)>,
+) -> [Channel; 10] {
+    let mut channels = [Channel::default(); 10];
+    for (function, (channel, &byte)) in channels.iter_mut().zip(bytes).enumerate() {
+        *channel = Channel::new(byte).unwrap_or_else(|error| {
+            out_of_range.push((ValueLocation::GlobalChannel(function), error));
+            Channel::clamped(byte)
+        });
+    }
+    channels
+}
+
+impl Default for SysExMessage {
+    fn default() -> Self {
+        Self {
+            start_byte: 0xf0,
+            manufacturer_id: [0x00, 0x20, 0x32],
+            global_channel: 0x00,
+            device_id: 0x0c,
+            presets: [Preset::new(); 100],
+            global_channels: [Channel::default(); 10],
+            switch_modes: [SwitchMode::Momentary; 2],
+            firmware: Firmware::default(),
+            unknown_regions: Vec::new(),
+            end_byte: 0xf7,
+            original_data: None,
+            out_of_range: Vec::new(),
+        }
+    }
+}
+
+impl SysExMessage {
+    pub fn presets(&self) -> &[Preset; 100] {
+        &self.presets
+    }
+
+    pub fn preset(&self, index: usize) -> &Preset {
+        &self.presets[index]
+    }
+
+    /// Replaces a whole preset, returning whether anything changed
+    pub fn set_preset(&mut self, index: usize, preset: Preset) -> bool {
+        let changed = self.presets[index] != preset;
+        self.presets[index] = preset;
+        changed
+    }
+
+    pub fn set_preset_field(&mut self, index: usize, field: PresetField, value: Value7) -> bool {
+        self.presets[index].set(field, value)
+    }
+
+    pub fn set_slot_enabled(&mut self, index: usize, slot: usize, enabled: bool) -> bool {
+        self.presets[index].set_enabled(slot, enabled)
+    }
+
+    /// Takes the preset from `previous` wherever this dump holds what
+    /// `previous` sends. A decoded dump only has switches guessed from its
+    /// values and 0 where a slot was off, so this keeps the switches set in
+    /// the editor, and the values behind them, across a round trip through
+    /// the device.
+    pub fn keep_switches(&mut self, previous: &SysExMessage) {
+        for (preset, previous) in self.presets.iter_mut().zip(previous.presets.iter()) {
+            if PresetField::ALL
+                .iter()
+                .all(|&field| preset.get(field) == previous.sent(field))
+            {
+                *preset = *previous;
+            }
+        }
+    }
+
+    /// Hands over the values that were out of range when the message was
+    /// decoded or loaded, as they were found
+    pub fn take_out_of_range(&mut self) -> Vec<(ValueLocation, OutOfRange)> {
+        std::mem::take(&mut self.out_of_range)
+    }
+
+    pub fn global_channels(&self) -> &[Channel; 10] {
+        &self.global_channels
+    }
+
+    pub fn set_global_channel(&mut self, index: usize, channel: Channel) -> bool {
+        let changed = self.global_channels[index] != channel;
+        self.global_channels[index] = channel;
+        changed
+    }
+
+    pub fn switch_modes(&self) -> &[SwitchMode; 2] {
+        &self.switch_modes
+    }
+
+    pub fn set_switch_mode(&mut self, index: usize, mode: SwitchMode) -> bool {
+        let changed = self.switch_modes[index] != mode;
+        self.switch_modes[index] = mode;
+        changed
+    }
+
+    pub fn firmware(&self) -> &Firmware {
+        &self.firmware
+    }
+
+    pub fn set_firmware(&mut self, firmware: Firmware) {
+        self.firmware = firmware;
+    }
+
+    pub fn unknown_regions(&self) -> &[UnknownRegion] {
+        &self.unknown_regions
+    }
+
+    /// How many preserved bytes outside the known areas are not zero
+    pub fn unknown_nonzero_bytes(&self) -> usize {
+        self.unknown_regions
+            .iter()
+            .flat_map(|region| &region.bytes)
+            .filter(|&&byte| byte != 0)
+            .count()
+    }
+
+    /// Every preset field whose value differs from the other dump
+    pub fn changed_fields(&self, other: &SysExMessage) -> Vec<(usize, PresetField)> {
+        let mut changed = Vec::new();
+        for (index, (a, b)) in self.presets.iter().zip(other.presets.iter()).enumerate() {
+            for field in PresetField::ALL {
+                if a.get(field) != b.get(field) {
+                    changed.push((index, field));
+                }
+            }
+        }
+        changed
+    }
+
+    /// The raw bytes this dump was decoded from, if it came from the device
+    pub fn original_data(&self) -> Option<&[u8]> {
+        self.original_data.as_deref()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let map = self.firmware.memory_map();
+        let mut patched_data: Vec<u8> = if let Some(ref data) = self.original_data {
+            unpack(data)
+        } else {
+            vec![0u8; map.data_size] // Size to cover the entire data area including global channels
+        };
+        let regions_end = self
+            .unknown_regions
+            .iter()
+            .map(|region| region.offset + region.bytes.len())
+            .max()
+            .unwrap_or(0);
+        patched_data.resize(patched_data.len().max(map.data_size).max(regions_end), 0);
+
+        for region in &self.unknown_regions {
+            patched_data[region.offset..region.offset + region.bytes.len()]
+                .copy_from_slice(&region.bytes);
+        }
+
+        // Encode the presets and global channels into the patched data
+        for (preset, record) in self
+            .presets
+            .iter()
+            .zip(patched_data.chunks_exact_mut(map.preset_size))
+        {
+            preset.write_bytes(record, &map);
+        }
+
+        for (i, &channel) in self.global_channels.iter().enumerate() {
+            patched_data[map.global_channels + i] = channel.get();
+        }
+
+        for (i, mode) in self.switch_modes.iter().enumerate() {
+            patched_data[map.switch_modes + i] = mode.to_byte();
+        }
+
+        let mut encoded = Vec::with_capacity(HEADER_SIZE + packed_len(patched_data.len()) + 1);
+        encoded.push(self.start_byte);
+        encoded.extend_from_slice(&self.manufacturer_id);
+        encoded.push(self.global_channel);
+        encoded.push(self.device_id);
+        encoded.push(0x0f); // Hacked patch
+        pack_into(&patched_data, &mut encoded);
+        encoded.push(self.end_byte);
+        encoded
+    }
+
+    /// Encodes the dump and checks it against the size its firmware layout
+    /// expects, so a short or overlong dump never reaches the device memory
+    pub fn encode_checked(&self) -> Result<Vec<u8>, String> {
+        let encoded = self.encode();
+        let data_size = self.firmware.memory_map().data_size;
+        let expected = encoded_len(&self.firmware);
+        if encoded.len() != expected {
+            return Err(format!(
+                "The encoded dump is {} bytes, but the {} layout expects {} \
+                 ({} header bytes, {} data bytes packed into {}, the end byte)",
+                encoded.len(),
+                self.firmware.label(),
+                expected,
+                HEADER_SIZE,
+                data_size,
+                packed_len(data_size)
+            ));
+        }
+        Ok(encoded)
+    }
+
+    /// Decodes a dump, detecting the firmware layout if the setting asks for it
+    pub fn decode(data: &[u8], setting: FirmwareSetting) -> Result<Self, MidiError> {
+        Self::check_framing(data)?;
+        // Unpacked once for both the detection and the decoding
+        let fixed_data = unpack(data);
+        let firmware = match setting {
+            FirmwareSetting::Fixed(firmware) => firmware,
+            FirmwareSetting::AutoDetect => Firmware::detect(&fixed_data).unwrap_or_default(),
+        };
+        Self::from_unpacked(data, &fixed_data, firmware)
+    }
+
+    fn check_framing(data: &[u8]) -> Result<(), MidiError> {
+        if data.len() < 6 {
+            return Err(MidiError::InvalidDataLength {
+                expected: 6,
+                found: data.len(),
+            });
+        }
+
+        if data[0] != 0xf0 {
+            return Err(MidiError::InvalidSysExStart { found: data[0] });
+        }
+
+        let end = data.len() - 1;
+        if data[end] != 0xf7 {
+            return Err(MidiError::InvalidSysExEnd {
+                offset: end,
+                found: data[end],
+            });
+        }
+
+        // Only the start and end bytes may have the high bit set; anything
+        // else means another message cut into the dump
+        if let Some(offset) = (1..end).find(|&offset| data[offset] & 0x80 != 0) {
+            return Err(MidiError::StatusByte {
+                offset,
+                found: data[offset],
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn decode_with(data: &[u8], firmware: Firmware) -> Result<Self, MidiError> {
+        Self::check_framing(data)?;
+        Self::from_unpacked(data, &unpack(data), firmware)
+    }
+
+    fn from_unpacked(
+        data: &[u8],
+        fixed_data: &[u8],
+        firmware: Firmware,
+    ) -> Result<Self, MidiError> {
+        let manufacturer_id = [data[1], data[2], data[3]];
+        let global_channel = data[4];
+        let device_id = data[5];
+
+        let map = firmware.memory_map();
+        if fixed_data.len() < map.data_size {
+            return Err(MidiError::InvalidDataLength {
+                expected: HEADER_SIZE + packed_len(map.data_size) + 1,
+                found: data.len(),
+            });
+        }
+
+        let mut out_of_range = Vec::new();
+        let presets = checked_presets(
+            fixed_data
+                .chunks_exact(map.preset_size)
+                .map(|record| Preset::from_bytes_checked(record, &map)),
+            &mut out_of_range,
+        );
+
+        let global_channels = checked_channels(
+            &fixed_data[map.global_channels..map.global_channels + 10],
+            &mut out_of_range,
+        );
+
+        let switch_modes = [
+            SwitchMode::from_byte(fixed_data[map.switch_modes]),
+            SwitchMode::from_byte(fixed_data[map.switch_modes + 1]),
+        ];
+
+        Ok(Self {
+            start_byte: 0xf0,
+            manufacturer_id,
+            global_channel,
+            device_id,
+            presets,
+            global_channels,
+            switch_modes,
+            firmware,
+            unknown_regions: UnknownRegion::collect(fixed_data, &map),
+            original_data: Some(data.to_vec()), // Save the original data
+            end_byte: 0xf7,
+            out_of_range,
+        })
+    }
+}
+
+/// The length of a whole dump laid out for `firmware`, framing included
+pub fn encoded_len(firmware: &Firmware) -> usize {
+    HEADER_SIZE + packed_len(firmware.memory_map().data_size) + 1
+}
+
+/// How a relay switch output (SW1/SW2) reacts to its footswitch
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum SwitchMode {
+    /// Closed only while the footswitch is held down
+    #[default]
+    Momentary,
+    /// Alternates between open and closed on every press
+    Toggle,
+}
+
+impl SwitchMode {
+    pub fn from_byte(byte: u8) -> Self {
+        if byte & 0x01 != 0 {
+            SwitchMode::Toggle
+        } else {
+            SwitchMode::Momentary
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            SwitchMode::Momentary => 0,
+            SwitchMode::Toggle => 1,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SwitchMode::Momentary => "Momentary",
+            SwitchMode::Toggle => "Toggle",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SwitchMode::Momentary => {
+                "The relay closes while the pedal is held and opens on release"
+            }
+            SwitchMode::Toggle => "Each press flips the relay between open and closed",
+        }
+    }
+}
+
+/// Why data is not a dump, with where in it the problem is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MidiError {
+    /// The first byte is not the SysEx start byte
+    InvalidSysExStart { found: u8 },
+    /// The last byte is not the SysEx end byte
+    InvalidSysExEnd { offset: usize, found: u8 },
+    /// A status byte inside the message
+    StatusByte { offset: usize, found: u8 },
+    /// The dump is too short for the layout, `expected` and `found` in bytes
+    InvalidDataLength { expected: usize, found: usize },
+}
+
+impl MidiError {
+    /// The byte the problem was found at; for a short dump, where it ended
+    pub fn offset(&self) -> usize {
+        match *self {
+            MidiError::InvalidSysExStart { .. } => 0,
+            MidiError::InvalidSysExEnd { offset, .. } | MidiError::StatusByte { offset, .. } => {
+                offset
+            }
+            MidiError::InvalidDataLength { found, .. } => found.saturating_sub(1),
+        }
+    }
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MidiError::InvalidSysExStart { found } => write!(
+                f,
+                "byte 0 is 0x{:02X}, expected the SysEx start byte 0xF0",
+                found
+            ),
+            MidiError::InvalidSysExEnd { offset, found } => write!(
+                f,
+                "byte {} (the last) is 0x{:02X}, expected the SysEx end byte 0xF7",
+                offset, found
+            ),
+            MidiError::StatusByte { offset, found } => write!(
+                f,
+                "byte {} is 0x{:02X}, expected a data byte (0x00-0x7F)",
+                offset, found
+            ),
+            MidiError::InvalidDataLength { expected, found } => {
+                write!(f, "the dump is {} bytes, expected {}", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MidiError {}
+
+pub fn hexdump(data: &[u8]) -> String {
+    let mut result = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        result.push_str(&format!("{:08x}: ", i * 16));
+        for byte in chunk {
+            result.push_str(&format!("{:02x} ", byte));
+        }
+        for _ in 0..(16 - chunk.len()) {
+            result.push_str("   ");
+        }
+        result.push_str("  ");
+        for byte in chunk {
+            let ch = if byte.is_ascii_graphic() {
+                *byte as char
+            } else {
+                '.'
+            };
+            result.push(ch);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::DumpBuilder;
+
+    fn data(dump: &SysExMessage) -> Vec<u8> {
+        unpack(&dump.encode())
+    }
+
+    #[test]
+    fn dumps_send_switched_off_slots_as_zero() {
+        let map = Firmware::V2_5.memory_map();
+        let mut preset = Preset::new();
+        preset.set(PresetField::ProgramChange(0), Value7::clamped(12));
+        preset.set_enabled(0, false);
+        preset.set(PresetField::ControlNumber(1), Value7::clamped(7));
+        preset.set(PresetField::ControlValue(1), Value7::clamped(90));
+        preset.set_enabled(6, false);
+        preset.set(PresetField::Note, Value7::clamped(60));
+        preset.set_enabled(9, true);
+
+        let mut record = vec![0x55; map.preset_size];
+        preset.write_bytes(&mut record, &map);
+        assert_eq!(record[map.offset_of(PresetField::ProgramChange(0))], 0);
+        assert_eq!(record[map.offset_of(PresetField::ControlNumber(1))], 0);
+        assert_eq!(record[map.offset_of(PresetField::ControlValue(1))], 0);
+        assert_eq!(record[map.offset_of(PresetField::Note)], 60);
+        assert!(record.iter().all(|&byte| byte < 0x80));
+        // Read back the pedal leaves them unused, as they were sent
+        let read = Preset::from_bytes(&record, &map);
+        assert!(!read.is_enabled(0) && !read.is_enabled(6) && read.is_enabled(9));
+    }
+
+    #[test]
+    fn custom_layouts_travel_with_the_dump() {
+        let mut map = Firmware::V2_5.memory_map();
+        map.preset_fields.swap(0, 1);
+        let custom = Firmware::Custom(Box::new(map));
+        let dump = DumpBuilder::new()
+            .firmware(custom.clone())
+            .bank(0)
+            .pedal(1)
+            .pc(2, 5)
+            .build()
+            .unwrap();
+        // The second program change sits where v2.5 keeps the first
+        assert_eq!(data(&dump)[0], 5);
+        let decoded = SysExMessage::decode_with(&dump.encode(), custom.clone()).unwrap();
+        assert_eq!(decoded.firmware(), &custom);
+        assert_eq!(
+            decoded.preset(0).program_change(1).map(ProgramNumber::get),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn decoding_switches_on_slots_with_values() {
+        let dump = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(2, 5)
+            .cc(1, 0, 64)
+            .build()
+            .unwrap();
+        let decoded = SysExMessage::decode_with(&dump.encode(), Firmware::V2_5).unwrap();
+        let preset = decoded.preset(0);
+        assert_eq!(preset.program_change(1).map(ProgramNumber::get), Some(5));
+        assert_eq!(preset.program_change(0), None);
+        assert!(preset.control_change(0).is_some());
+        assert_eq!(preset.note(), None);
+        assert!((0..SLOTS).all(|slot| !decoded.preset(1).is_enabled(slot)));
+    }
+
+    #[test]
+    fn switches_survive_a_round_trip_when_values_match() {
+        let mut editor = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(1, 0)
+            .pedal(2)
+            .pc(1, 7)
+            .build()
+            .unwrap();
+        editor.set_slot_enabled(1, 0, false);
+        let mut received = SysExMessage::decode_with(&editor.encode(), Firmware::V2_5).unwrap();
+        // PC 0 reads as unused, and so does the PC 7 switched off, sent as 0
+        assert!(!received.preset(0).is_enabled(0));
+        assert!(!received.preset(1).is_enabled(0));
+        assert_eq!(received.preset(1).get(PresetField::ProgramChange(0)), 0);
+
+        received.keep_switches(&editor);
+        assert!(received.preset(0).is_enabled(0));
+        assert!(!received.preset(1).is_enabled(0));
+        assert_eq!(received.preset(1).get(PresetField::ProgramChange(0)), 7);
+
+        let mut changed = received.clone();
+        changed.set_preset_field(0, PresetField::ProgramChange(1), Value7::clamped(3));
+        changed.set_slot_enabled(0, 1, true);
+        let mut decoded = SysExMessage::decode_with(&changed.encode(), Firmware::V2_5).unwrap();
+        decoded.keep_switches(&editor);
+        assert!(!decoded.preset(0).is_enabled(0));
+        assert!(decoded.preset(0).is_enabled(1));
+    }
+
+    #[test]
+    fn encoding_round_trips_the_bytes() {
+        let dump = DumpBuilder::new()
+            .bank(4)
+            .pedal(3)
+            .pc(1, 99)
+            .cc(2, 7, 100)
+            .expression_b(11, 10, 120)
+            .note(64)
+            .global_channel(6, 9)
+            .switch_mode(2, SwitchMode::Toggle)
+            .build()
+            .unwrap();
+        let bytes = dump.encode();
+        let decoded = SysExMessage::decode_with(&bytes, Firmware::V2_5).unwrap();
+        assert_eq!(decoded.encode(), bytes);
+        assert_eq!(data(&decoded), data(&dump));
+        assert_eq!(decoded.presets(), dump.presets());
+        assert_eq!(decoded.global_channels(), dump.global_channels());
+        assert_eq!(decoded.switch_modes(), dump.switch_modes());
+    }
+
+    #[test]
+    fn old_projects_get_switches_from_values() {
+        let mut preset = Preset::new();
+        preset.set(PresetField::ControlValue(1), Value7::clamped(1));
+        let mut json = serde_json::to_value(preset).unwrap();
+        json.as_object_mut().unwrap().remove("disabled");
+
+        let read: Preset = serde_json::from_value(json).unwrap();
+        assert!(read.is_enabled(6));
+        assert!((0..SLOTS)
+            .filter(|&slot| slot != 6)
+            .all(|slot| !read.is_enabled(slot)));
+
+        let mut explicit = Preset::new();
+        explicit.set_enabled(0, true);
+        let json = serde_json::to_string(&explicit).unwrap();
+        assert_eq!(serde_json::from_str::<Preset>(&json).unwrap(), explicit);
+    }
+
+    #[test]
+    fn field_indexes_follow_all() {
+        for (index, field) in PresetField::ALL.into_iter().enumerate() {
+            assert_eq!(field.index(), index);
+        }
+    }
+
+    #[test]
+    fn full_dumps_round_trip_byte_for_byte() {
+        // No dump captured from a pedal is at hand, so this one fills every
+        // byte the way one does: values in range, anything in the areas the
+        // editor does not interpret
+        let map = Firmware::V2_5.memory_map();
+        let mut data: Vec<u8> = (0..map.data_size)
+            .map(|offset| (offset * 37 % 251) as u8)
+            .collect();
+        for index in 0..100 {
+            for field in PresetField::ALL {
+                data[index * map.preset_size + map.offset_of(field)] &= 0x7f;
+            }
+        }
+        for function in 0..10 {
+            data[map.global_channels + function] &= 0x0f;
+        }
+        data[map.switch_modes] = SwitchMode::Momentary.to_byte();
+        data[map.switch_modes + 1] = SwitchMode::Toggle.to_byte();
+        let mut bytes = vec![0xf0, 0x00, 0x20, 0x32, 0x00, 0x0c, 0x0f];
+        pack_into(&data, &mut bytes);
+        bytes.push(0xf7);
+
+        let mut decoded = SysExMessage::decode_with(&bytes, Firmware::V2_5).unwrap();
+        assert!(decoded.take_out_of_range().is_empty());
+        assert_eq!(decoded.encode(), bytes);
+
+        // The same without the original bytes to patch
+        let mut json = serde_json::to_value(&decoded).unwrap();
+        json["original_data"] = serde_json::Value::Null;
+        let loaded: SysExMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.encode(), bytes);
+    }
+
+    #[test]
+    fn framing_errors_point_at_the_bad_byte() {
+        let bytes = DumpBuilder::new().build().unwrap().encode();
+        let end = bytes.len() - 1;
+        let decode = |bytes: &[u8]| SysExMessage::decode_with(bytes, Firmware::V2_5).unwrap_err();
+
+        let mut start = bytes.clone();
+        start[0] = 0xf1;
+        let error = decode(&start);
+        assert_eq!(error, MidiError::InvalidSysExStart { found: 0xf1 });
+        assert_eq!(error.offset(), 0);
+
+        let mut cut = bytes.clone();
+        cut[end] = 0x12;
+        let error = decode(&cut);
+        assert_eq!(
+            error,
+            MidiError::InvalidSysExEnd {
+                offset: end,
+                found: 0x12
+            }
+        );
+        assert_eq!(error.offset(), end);
+        assert!(error
+            .to_string()
+            .starts_with(&format!("byte {} (the last)", end)));
+
+        let mut interrupted = bytes.clone();
+        interrupted[200] = 0xb0;
+        let error = decode(&interrupted);
+        assert_eq!(
+            error,
+            MidiError::StatusByte {
+                offset: 200,
+                found: 0xb0
+            }
+        );
+        assert_eq!(error.offset(), 200);
+        assert_eq!(
+            error.to_string(),
+            "byte 200 is 0xB0, expected a data byte (0x00-0x7F)"
+        );
+
+        let mut short = bytes[..100].to_vec();
+        short.push(0xf7);
+        let error = decode(&short);
+        assert_eq!(
+            error,
+            MidiError::InvalidDataLength {
+                expected: encoded_len(&Firmware::V2_5),
+                found: 101
+            }
+        );
+        assert_eq!(error.offset(), 100);
+        assert_eq!(
+            decode(&[0xf0, 0xf7]),
+            MidiError::InvalidDataLength {
+                expected: 6,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn decoding_keeps_what_it_clamped() {
+        let dump = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(1, 9)
+            .build()
+            .unwrap();
+        let map = dump.firmware().memory_map();
+        let mut data = data(&dump);
+        data[map.preset_size + map.offset_of(PresetField::Note)] = 0xc8;
+        data[map.global_channels + 3] = 0x20;
+        let encoded = dump.encode();
+        let mut bytes = encoded[..HEADER_SIZE].to_vec();
+        pack_into(&data, &mut bytes);
+        bytes.push(0xf7);
+
+        let mut decoded = SysExMessage::decode_with(&bytes, Firmware::V2_5).unwrap();
+        assert_eq!(decoded.preset(1).get(PresetField::Note), 127);
+        assert_eq!(decoded.global_channels()[3], Channel::clamped(15));
+        let found: Vec<_> = decoded
+            .take_out_of_range()
+            .into_iter()
+            .map(|(at, error)| (at, error.value))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ValueLocation::Preset(1, PresetField::Note), 0xc8),
+                (ValueLocation::GlobalChannel(3), 0x20),
+            ]
+        );
+        assert!(decoded.take_out_of_range().is_empty());
+    }
+
+    #[test]
+    fn loading_keeps_what_it_clamped() {
+        let dump = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(1, 9)
+            .build()
+            .unwrap();
+        let mut json = serde_json::to_value(&dump).unwrap();
+        json["presets"][0]["program_changes"][0] = 200.into();
+        json["presets"][2]["expression_pedal_a"] = serde_json::json!([7, 0, 130]);
+        json["global_channels"][9] = 16.into();
+
+        let mut loaded: SysExMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.preset(0).get(PresetField::ProgramChange(0)), 127);
+        let found: Vec<_> = loaded
+            .take_out_of_range()
+            .into_iter()
+            .map(|(at, error)| (at, error.value))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ValueLocation::Preset(0, PresetField::ProgramChange(0)), 200),
+                (
+                    ValueLocation::Preset(2, PresetField::ExpressionA(ExpressionPart::Max)),
+                    130
+                ),
+                (ValueLocation::GlobalChannel(9), 16),
+            ]
+        );
+
+        let saved = serde_json::to_string(&loaded).unwrap();
+        let mut reloaded: SysExMessage = serde_json::from_str(&saved).unwrap();
+        assert!(reloaded.take_out_of_range().is_empty());
+        assert_eq!(reloaded, loaded);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::codec::{unpacked_groups, HEADER_SIZE};
+use crate::firmware::Firmware;
+use crate::model::Preset;
+use std::time::{Duration, Instant};
+
+/// A dump that stops arriving for this long is given up on
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Puts a SysEx dump back together from the chunks an input port delivers
+/// it in, and keeps count of how far it has come
+#[derive(Debug, Default)]
+pub struct ReceiveProgress {
+    /// The bytes of the dump being assembled, from its start byte
+    partial: Vec<u8>,
+    first_byte: Option<Instant>,
+    last_byte: Option<Instant>,
+    /// How long the last whole message took to arrive
+    took: Option<Duration>,
+}
+
+impl ReceiveProgress {
+    /// Adds a chunk, returning the whole message once its end byte arrived.
+    /// Chunks outside a SysEx message are ignored.
+    pub fn push(&mut self, chunk: &[u8]) -> Option<Vec<u8>> {
+        // Clock and active sensing may arrive in the middle of a dump
+        if matches!(chunk, [0xf8..=0xff]) {
+            return None;
+        }
+        let now = Instant::now();
+        if chunk.first() == Some(&0xf0) {
+            self.partial.clear();
+            self.first_byte = Some(now);
+        } else if self.partial.is_empty() {
+            return None;
+        }
+        self.partial.extend_from_slice(chunk);
+        self.last_byte = Some(now);
+        if chunk.last() == Some(&0xf7) {
+            self.took = self.first_byte.map(|first| now - first);
+            return Some(std::mem::take(&mut self.partial));
+        }
+        None
+    }
+
+    /// How long the last message `push` returned took from its start byte
+    /// to its end byte, which leaves out the wait for the sender to start
+    pub fn took(&self) -> Option<Duration> {
+        self.took
+    }
+
+    /// Bytes of the unfinished dump received so far
+    pub fn received(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// The presets of the unfinished dump that arrived whole so far, in
+    /// preset order, read with `firmware`'s layout
+    pub fn presets_so_far(&self, firmware: &Firmware) -> Vec<Preset> {
+        let map = firmware.memory_map();
+        let body = self.partial.get(HEADER_SIZE..).unwrap_or_default();
+        let data: Vec<u8> = unpacked_groups(body).collect();
+        data.chunks_exact(map.preset_size)
+            .take(100)
+            .map(|record| Preset::from_bytes(record, &map))
+            .collect()
+    }
+
+    /// Whether a dump started but nothing came for `timeout`
+    pub fn stalled(&self, timeout: Duration) -> bool {
+        !self.partial.is_empty() && self.last_byte.is_some_and(|at| at.elapsed() >= timeout)
+    }
+
+    pub fn reset(&mut self) {
+        self.partial.clear();
+        self.first_byte = None;
+        self.last_byte = None;
+        self.took = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_a_dump_from_its_first_byte() {
+        let mut progress = ReceiveProgress::default();
+        // What came before the dump started does not count
+        assert_eq!(progress.push(&[0x01, 0x02]), None);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(progress.push(&[0xf0, 0x00]), None);
+        assert_eq!(progress.push(&[0xf8]), None);
+        assert_eq!(progress.took(), None);
+        assert_eq!(
+            progress.push(&[0x20, 0xf7]),
+            Some(vec![0xf0, 0x00, 0x20, 0xf7])
+        );
+        assert!(progress.took().unwrap() < Duration::from_millis(20));
+        assert_eq!(progress.received(), 0);
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// A number outside the range of the MIDI value it was meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRange {
+    pub what: &'static str,
+    pub value: u8,
+    pub max: u8,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} is out of range (0-{})",
+            self.what, self.value, self.max
+        )
+    }
+}
+
+impl std::error::Error for OutOfRange {}
+
+/// Declares a byte that only holds `0..=max`, stored and serialized as the
+/// plain number. Deserializing clamps numbers that are too big, as decoding
+/// a dump does, so one bad value does not keep a whole file from loading.
+macro_rules! midi_value {
+    ($(#[$doc:meta])* $name:ident, $max:expr, $what:literal) => {
+        $(#[$doc])*
+        #[derive(
+            Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize,
+        )]
+        #[serde(into = "u8")]
+        pub struct $name(u8);
+
+        impl $name {
+            pub const MAX: u8 = $max;
+
+            pub const fn new(value: u8) -> Result<Self, OutOfRange> {
+                if value <= Self::MAX {
+                    Ok(Self(value))
+                } else {
+                    Err(OutOfRange {
+                        what: $what,
+                        value,
+                        max: Self::MAX,
+                    })
+                }
+            }
+
+            /// The nearest valid value, for numbers that may overshoot
+            pub const fn clamped(value: u8) -> Self {
+                if value <= Self::MAX {
+                    Self(value)
+                } else {
+                    Self(Self::MAX)
+                }
+            }
+
+            /// The nearest valid value, for results of arithmetic that may
+            /// go below 0 as well
+            pub const fn saturating(value: i64) -> Self {
+                if value < 0 {
+                    Self(0)
+                } else if value > Self::MAX as i64 {
+                    Self(Self::MAX)
+                } else {
+                    Self(value as u8)
+                }
+            }
+
+            pub const fn get(self) -> u8 {
+                self.0
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = OutOfRange;
+
+            fn try_from(value: u8) -> Result<Self, OutOfRange> {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for u8 {
+            fn from(value: $name) -> u8 {
+                value.0
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                u8::deserialize(deserializer).map(Self::clamped)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+midi_value!(
+    /// Any 7-bit MIDI data byte, such as a controller value
+    Value7,
+    127,
+    "Value"
+);
+midi_value!(
+    /// The program of a program change message
+    ProgramNumber,
+    127,
+    "Program"
+);
+midi_value!(
+    /// The controller of a control change message
+    ControllerNumber,
+    127,
+    "Controller"
+);
+midi_value!(
+    /// The note of a note message
+    NoteNumber,
+    127,
+    "Note"
+);
+midi_value!(
+    /// A MIDI channel, stored as 0-15 and shown as 1-16
+    Channel,
+    15,
+    "Channel"
+);
+
+impl Channel {
+    /// The channel as musicians count it, 1-16
+    pub const fn number(self) -> u8 {
+        self.0 + 1
+    }
+}
+
+// The message-specific numbers are all 7-bit data bytes
+macro_rules! from_value7 {
+    ($($name:ident),*) => {
+        $(
+            impl From<Value7> for $name {
+                fn from(value: Value7) -> Self {
+                    Self(value.get())
+                }
+            }
+
+            impl From<$name> for Value7 {
+                fn from(value: $name) -> Self {
+                    Value7(value.get())
+                }
+            }
+        )*
+    };
+}
+
+from_value7!(ProgramNumber, ControllerNumber, NoteNumber);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_the_range() {
+        assert_eq!(Channel::new(15).map(Channel::get), Ok(15));
+        assert_eq!(
+            Channel::new(16),
+            Err(OutOfRange {
+                what: "Channel",
+                value: 16,
+                max: 15,
+            })
+        );
+        assert!(Value7::new(128).is_err());
+        assert_eq!(Channel::clamped(200).number(), 16);
+        assert_eq!(Value7::saturating(-3).get(), 0);
+        assert_eq!(Value7::saturating(64).get(), 64);
+        assert_eq!(Value7::saturating(300).get(), 127);
+    }
+
+    #[test]
+    fn serializes_as_the_plain_number() {
+        assert_eq!(
+            serde_json::to_string(&NoteNumber::clamped(60)).unwrap(),
+            "60"
+        );
+        assert_eq!(
+            serde_json::from_str::<ProgramNumber>("12").unwrap(),
+            ProgramNumber::clamped(12)
+        );
+    }
+
+    #[test]
+    fn deserializing_clamps() {
+        assert_eq!(serde_json::from_str::<Channel>("20").unwrap().get(), 15);
+        assert_eq!(serde_json::from_str::<Value7>("255").unwrap().get(), 127);
+        assert!(serde_json::from_str::<Value7>("256").is_err());
+        assert!(serde_json::from_str::<Value7>("-1").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::firmware::MemoryMap;
+use crate::model::{PresetField, SysExMessage};
+use crate::numbering::Numbering;
+use crate::spec::{field_title, GLOBAL_FUNCTIONS};
+
+// Bytes before the first 7-bit packet: F0, manufacturer ID, channel, device, command
+const HEADER_SIZE: usize = 7;
+/// Seven data bytes followed by the byte holding their top bits
+const PACKET_SIZE: usize = 8;
+const UNKNOWN: &str = "Unknown";
+
+/// The encoded dump as it is sent, one 7-bit packet per line, with every
+/// line naming the presets and fields its bytes carry
+pub fn annotated_dump(message: &SysExMessage, numbering: Numbering) -> String {
+    let encoded = message.encode();
+    let firmware = message.firmware();
+    let map = firmware.memory_map();
+
+    let mut out = format!(
+        "FCB1010 dump, {} bytes, {} layout\n\
+         Each packet holds 7 data bytes and a last byte with their top bits.\n\n",
+        encoded.len(),
+        firmware.label()
+    );
+    out.push_str(&line(0, &encoded[..HEADER_SIZE], "header"));
+
+    let body = &encoded[HEADER_SIZE..encoded.len() - 1];
+    for (packet, bytes) in body.chunks(PACKET_SIZE).enumerate() {
+        let first = packet * 7;
+        let offsets = first..(first + 7).min(map.data_size);
+        out.push_str(&line(
+            HEADER_SIZE + packet * PACKET_SIZE,
+            bytes,
+            &describe_range(&map, offsets, numbering),
+        ));
+    }
+
+    out.push_str(&line(
+        encoded.len() - 1,
+        &encoded[encoded.len() - 1..],
+        "end",
+    ));
+    out
+}
+
+fn line(offset: usize, bytes: &[u8], annotation: &str) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{:04x}: {:<width$}  ; {}\n",
+        offset,
+        hex.join(" "),
+        annotation,
+        width = PACKET_SIZE * 3 - 1
+    )
+}
+
+/// "Preset 3: EP B max, Note; Preset 4: PC 1, PC 2" for a run of data offsets
+fn describe_range(
+    map: &MemoryMap,
+    offsets: std::ops::Range<usize>,
+    numbering: Numbering,
+) -> String {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for offset in offsets {
+        let (owner, name) = describe_offset(map, offset, numbering);
+        match groups.last_mut() {
+            Some((last, names)) if *last == owner => names.push(name),
+            _ => groups.push((owner, vec![name])),
+        }
+    }
+    groups
+        .iter()
+        .map(|(owner, names)| match names.as_slice() {
+            // Runs of unmapped bytes read better as a range
+            [first, .., last] if owner == UNKNOWN => format!("{}: {}–{}", owner, first, last),
+            _ => format!("{}: {}", owner, names.join(", ")),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Who an unpacked data offset belongs to and what it holds there
+fn describe_offset(map: &MemoryMap, offset: usize, numbering: Numbering) -> (String, String) {
+    let presets = 100 * map.preset_size;
+    if offset < presets {
+        let index = offset / map.preset_size;
+        let within = offset % map.preset_size;
+        let name = PresetField::ALL
+            .iter()
+            .find(|&&field| map.offset_of(field) == within)
+            .map_or_else(|| format!("byte {}", within), |&field| field_title(field));
+        return (format!("Preset {}", numbering.preset(index)), name);
+    }
+    if (map.global_channels..map.global_channels + GLOBAL_FUNCTIONS.len()).contains(&offset) {
+        let function = GLOBAL_FUNCTIONS[offset - map.global_channels];
+        return ("Global".to_string(), format!("{} channel", function));
+    }
+    if (map.switch_modes..map.switch_modes + 2).contains(&offset) {
+        let switch = offset - map.switch_modes + 1;
+        return ("Global".to_string(), format!("switch {} mode", switch));
+    }
+    (UNKNOWN.to_string(), format!("0x{:03x}", offset))
+}
@@ -0,0 +1,529 @@
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8010";
+
+// Requests are small JSON documents; anything bigger is refused
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+// How long a request waits for the editor to answer it
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+// How often the accept loop checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Connections served at once; more are turned away until one finishes
+const MAX_CONNECTIONS: usize = 16;
+
+/// What an HTTP request asks of the editor. Presets are numbered from 1 as
+/// in the preset grid.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    /// `GET /presets`
+    ListPresets,
+    /// `GET /presets/<n>`
+    GetPreset(usize),
+    /// `PUT /presets/<n>` with an object of field keys (and `name`, and
+    /// `enabled` or `disabled` listing slots to switch on or off) to change
+    SetPreset(usize, Value),
+    /// `POST /dump/send`: sends all 100 presets and the global settings,
+    /// since the FCB1010 only takes whole dumps
+    SendDump,
+}
+
+/// A request handed to the UI thread, which owns the model and the ports
+pub struct ApiRequest {
+    pub route: Route,
+    reply: Sender<(u16, Value)>,
+}
+
+impl ApiRequest {
+    pub fn respond(self, status: u16, body: Value) {
+        // The connection may have timed out in the meantime
+        self.reply.send((status, body)).ok();
+    }
+}
+
+/// The embedded HTTP server; stops when dropped
+pub struct ApiServer {
+    pub address: SocketAddr,
+    requests: Receiver<ApiRequest>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ApiServer {
+    /// Listens on `address`; `wake` is called for every request so the UI
+    /// thread picks it up without waiting for input. With a `token`, every
+    /// request has to carry it; without one only this computer may connect.
+    pub fn start(
+        address: &str,
+        token: Option<String>,
+        wake: impl Fn() + Send + Sync + 'static,
+    ) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        if token.is_none() && !address.ip().is_loopback() {
+            return Err(format!(
+                "{} can be reached from the network; set a token first",
+                address
+            ));
+        }
+        let access = Access { address, token };
+        let (sender, requests) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = stop.clone();
+        let wake = Arc::new(wake);
+        let open = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                };
+                // Each connection gets its own thread, so a slow client
+                // holds up nobody else
+                if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    open.fetch_sub(1, Ordering::SeqCst);
+                    stream.set_nonblocking(false).ok();
+                    let busy = json!({ "error": "Too many connections" });
+                    write_response(&mut stream, 503, &busy);
+                    continue;
+                }
+                let (access, sender, wake, open) =
+                    (access.clone(), sender.clone(), wake.clone(), open.clone());
+                thread::spawn(move || {
+                    handle_connection(stream, &access, &sender, &*wake);
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(Self {
+            address,
+            requests,
+            stop,
+        })
+    }
+
+    pub fn try_recv(&self) -> Option<ApiRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A random token for requests to carry, as 32 hex digits
+pub fn new_token() -> String {
+    // Each RandomState is keyed from the operating system's random source
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    (0..2)
+        .map(|half| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(now);
+            hasher.write_u8(half);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// One HTTP request as read off the connection
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Who may use the API. Host and Origin have to name the server itself, so
+/// a web page open in a browser on this computer cannot reach it, not even
+/// through a DNS name it points at 127.0.0.1.
+#[derive(Debug, Clone)]
+struct Access {
+    address: SocketAddr,
+    token: Option<String>,
+}
+
+impl Access {
+    fn check(&self, request: &Request) -> Result<(), (u16, Value)> {
+        let forbidden = |error: &str| Err((403, json!({ "error": error })));
+        if !request
+            .header("host")
+            .is_some_and(|host| self.names_server(host))
+        {
+            return forbidden("The Host header does not name this server");
+        }
+        if let Some(origin) = request.header("origin") {
+            let allowed = origin
+                .strip_prefix("http://")
+                .is_some_and(|host| self.names_server(host));
+            if !allowed {
+                return forbidden("Requests from web pages are not allowed");
+            }
+        }
+        if let Some(token) = &self.token {
+            let given = request
+                .header("authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .unwrap_or_default()
+                .trim();
+            if !same_token(given, token) {
+                return Err((401, json!({ "error": "The API token is missing or wrong" })));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `host` ("127.0.0.1:8010", "localhost:8010", "[::1]:8010")
+    /// is the address the server listens on. Only IP addresses and
+    /// localhost count, since any other name could point anywhere.
+    fn names_server(&self, host: &str) -> bool {
+        let Some((name, port)) = host.trim().rsplit_once(':') else {
+            return false;
+        };
+        if port.parse::<u16>() != Ok(self.address.port()) {
+            return false;
+        }
+        let bound = self.address.ip();
+        if name.eq_ignore_ascii_case("localhost") {
+            return bound.is_loopback() || bound.is_unspecified();
+        }
+        let name = name.trim_start_matches('[').trim_end_matches(']');
+        match name.parse::<IpAddr>() {
+            Ok(ip) => {
+                ip == bound || bound.is_unspecified() || (ip.is_loopback() && bound.is_loopback())
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Compares every byte, so the time taken does not tell how much matched
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    access: &Access,
+    requests: &Sender<ApiRequest>,
+    wake: &dyn Fn(),
+) {
+    stream.set_nonblocking(false).ok();
+    stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok();
+    let (status, body) = match read_request(&mut stream) {
+        Ok(request) => match access
+            .check(&request)
+            .and_then(|()| route(&request.method, &request.path, &request.body))
+        {
+            Ok(route) => {
+                let (reply, answer) = channel();
+                requests.send(ApiRequest { route, reply }).ok();
+                wake();
+                answer.recv_timeout(REPLY_TIMEOUT).unwrap_or_else(|_| {
+                    (503, json!({ "error": "The editor did not answer in time" }))
+                })
+            }
+            Err(error) => error,
+        },
+        Err(e) => (400, json!({ "error": e })),
+    };
+
+    write_response(&mut stream, status, &body);
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &Value) {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).ok();
+}
+
+/// Reads the request line, headers and body of one request
+fn read_request(stream: &mut impl Read) -> Result<Request, String> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let header_end = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if data.len() > MAX_REQUEST_SIZE {
+            return Err("The request is too large".to_string());
+        }
+        let read = stream.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("The request ended early".to_string());
+        }
+        data.extend_from_slice(&buffer[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_REQUEST_SIZE {
+        return Err("The request is too large".to_string());
+    }
+
+    let mut body = data[header_end..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("The request ended early".to_string());
+        }
+        body.extend_from_slice(&buffer[..read]);
+    }
+    body.truncate(length);
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn route(method: &str, path: &str, body: &[u8]) -> Result<Route, (u16, Value)> {
+    let not_found = || {
+        (
+            404,
+            json!({ "error": format!("No such resource: {}", path) }),
+        )
+    };
+    let number = |text: &str| text.parse::<usize>().map_err(|_| not_found());
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["presets"]) => Ok(Route::ListPresets),
+        ("GET", ["presets", n]) => Ok(Route::GetPreset(number(n)?)),
+        ("PUT", ["presets", n]) => {
+            let fields = serde_json::from_slice(body)
+                .map_err(|e| (400, json!({ "error": format!("Invalid JSON: {}", e) })))?;
+            Ok(Route::SetPreset(number(n)?, fields))
+        }
+        ("POST", ["dump", "send"]) => Ok(Route::SendDump),
+        (_, ["presets"] | ["presets", _] | ["dump", "send"]) => Err((
+            405,
+            json!({ "error": format!("{} is not allowed here", method) }),
+        )),
+        _ => Err(not_found()),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        423 => "Locked",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Request {
+        read_request(&mut text.as_bytes()).unwrap()
+    }
+
+    fn access(address: &str, token: Option<&str>) -> Access {
+        Access {
+            address: address.parse().unwrap(),
+            token: token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn reads_headers_and_body() {
+        let request = parse(
+            "PUT /presets/37 HTTP/1.1\r\nHost: localhost:8010\r\n\
+             Content-Length: 11\r\n\r\n{\"pc1\": 12}trailing",
+        );
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/presets/37");
+        assert_eq!(request.header("HOST"), Some("localhost:8010"));
+        assert_eq!(request.body, b"{\"pc1\": 12}");
+    }
+
+    #[test]
+    fn refuses_short_and_oversized_requests() {
+        assert!(read_request(&mut "GET /presets HTTP/1.1\r\n".as_bytes()).is_err());
+        let body_missing = "PUT /presets/1 HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}";
+        assert!(read_request(&mut body_missing.as_bytes()).is_err());
+        let too_large = format!(
+            "PUT /presets/1 HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_REQUEST_SIZE + 1
+        );
+        assert!(read_request(&mut too_large.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn routes_requests() {
+        assert_eq!(route("GET", "/presets", b""), Ok(Route::ListPresets));
+        assert_eq!(route("GET", "/presets/5?x=1", b""), Ok(Route::GetPreset(5)));
+        assert_eq!(
+            route("PUT", "/presets/5", b"{\"pc1\": 3}"),
+            Ok(Route::SetPreset(5, json!({ "pc1": 3 })))
+        );
+        assert_eq!(route("PUT", "/presets/5", b"{").unwrap_err().0, 400);
+        assert_eq!(route("DELETE", "/presets/5", b"").unwrap_err().0, 405);
+        assert_eq!(route("GET", "/presets/five", b"").unwrap_err().0, 404);
+        assert_eq!(route("POST", "/dump/send", b""), Ok(Route::SendDump));
+        assert_eq!(route("GET", "/dump/send", b"").unwrap_err().0, 405);
+        assert_eq!(route("GET", "/", b"").unwrap_err().0, 404);
+    }
+
+    #[test]
+    fn host_has_to_name_the_server() {
+        let loopback = access("127.0.0.1:8010", None);
+        assert!(loopback.names_server("127.0.0.1:8010"));
+        assert!(loopback.names_server("localhost:8010"));
+        assert!(!loopback.names_server("localhost:8011"));
+        assert!(!loopback.names_server("rebind.example.com:8010"));
+        assert!(!loopback.names_server("localhost"));
+
+        let everywhere = access("0.0.0.0:8010", Some("secret"));
+        assert!(everywhere.names_server("192.168.1.20:8010"));
+        assert!(!everywhere.names_server("pedal.example.com:8010"));
+
+        let ipv6 = access("[::1]:8010", None);
+        assert!(ipv6.names_server("[::1]:8010"));
+    }
+
+    #[test]
+    fn checks_origin_and_token() {
+        let status = |access: &Access, text: &str| access.check(&parse(text)).err().map(|e| e.0);
+        let open = access("127.0.0.1:8010", None);
+        assert_eq!(
+            status(
+                &open,
+                "GET /presets HTTP/1.1\r\nHost: localhost:8010\r\n\r\n"
+            ),
+            None
+        );
+        assert_eq!(status(&open, "GET /presets HTTP/1.1\r\n\r\n"), Some(403));
+        assert_eq!(
+            status(
+                &open,
+                "POST /dump/send HTTP/1.1\r\nHost: localhost:8010\r\n\
+                 Origin: https://evil.example.com\r\n\r\n"
+            ),
+            Some(403)
+        );
+        assert_eq!(
+            status(
+                &open,
+                "GET /presets HTTP/1.1\r\nHost: localhost:8010\r\n\
+                 Origin: http://localhost:8010\r\n\r\n"
+            ),
+            None
+        );
+
+        let guarded = access("127.0.0.1:8010", Some("secret"));
+        let request = |authorization: &str| {
+            format!(
+                "GET /presets HTTP/1.1\r\nHost: 127.0.0.1:8010\r\n{}\r\n",
+                authorization
+            )
+        };
+        assert_eq!(status(&guarded, &request("")), Some(401));
+        assert_eq!(
+            status(&guarded, &request("Authorization: Bearer wrong\r\n")),
+            Some(401)
+        );
+        assert_eq!(
+            status(&guarded, &request("Authorization: Bearer secret\r\n")),
+            None
+        );
+    }
+
+    #[test]
+    fn refuses_network_binds_without_a_token() {
+        assert!(ApiServer::start("0.0.0.0:0", None, || {}).is_err());
+        assert!(ApiServer::start("127.0.0.1:0", None, || {}).is_ok());
+        assert!(ApiServer::start("0.0.0.0:0", Some(new_token()), || {}).is_ok());
+    }
+
+    #[test]
+    fn idle_connection_holds_up_nobody() {
+        let server = ApiServer::start("127.0.0.1:0", None, || {}).unwrap();
+        let address = server.address;
+        let _idle = TcpStream::connect(address).unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let request = format!("GET /presets HTTP/1.1\r\nHost: {}\r\n\r\n", address);
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        let request = loop {
+            if let Some(request) = server.try_recv() {
+                break request;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(request.route, Route::ListPresets);
+        request.respond(200, json!([]));
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn tokens_differ() {
+        let token = new_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, new_token());
+    }
+}
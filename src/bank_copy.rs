@@ -0,0 +1,80 @@
+use crate::model::{Preset, PresetField, SysExMessage, PEDALS_PER_BANK};
+use crate::values::Value7;
+
+/// A bank copied onto another with every program change it sends shifted;
+/// switched-off slots keep their values
+#[derive(Debug, Clone)]
+pub struct BankCopy {
+    pub from: usize,
+    pub to: usize,
+    pub offset: i16,
+    /// The new presets, by index
+    pub presets: Vec<(usize, Preset)>,
+    /// How many program changes hit 0 or 127 and were held there
+    pub clamped: usize,
+}
+
+impl BankCopy {
+    pub fn plan(message: &SysExMessage, from: usize, to: usize, offset: i16) -> Self {
+        let mut clamped = 0;
+        let presets = (0..PEDALS_PER_BANK)
+            .map(|pedal| {
+                let mut preset = *message.preset(from * PEDALS_PER_BANK + pedal);
+                for slot in 0..5 {
+                    if !preset.is_enabled(slot) {
+                        continue;
+                    }
+                    let field = PresetField::ProgramChange(slot);
+                    let shifted = i16::from(preset.get(field)) + offset;
+                    if !(0..=127).contains(&shifted) {
+                        clamped += 1;
+                    }
+                    preset.set(field, Value7::saturating(shifted.into()));
+                }
+                (to * PEDALS_PER_BANK + pedal, preset)
+            })
+            .collect();
+
+        Self {
+            from,
+            to,
+            offset,
+            presets,
+            clamped,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Bank {} → bank {}, program changes {:+}",
+            self.from + 1,
+            self.to + 1,
+            self.offset
+        )
+    }
+
+    /// One line per target preset showing its program changes before and
+    /// after, "-" for a slot switched off
+    pub fn describe(&self, message: &SysExMessage) -> Vec<String> {
+        let programs = |preset: &Preset| {
+            (0..5)
+                .map(|slot| match preset.program_change(slot) {
+                    Some(program) => program.to_string(),
+                    None => "-".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        self.presets
+            .iter()
+            .map(|(index, preset)| {
+                format!(
+                    "Preset {}: PC {} → {}",
+                    index + 1,
+                    programs(message.preset(*index)),
+                    programs(preset)
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,96 @@
+use crate::model::{Preset, SysExMessage, PEDALS_PER_BANK};
+use crate::project::{PresetMetadata, ProjectMetadata};
+use crate::values::Channel;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The bank file format written by this version; older ones are read too
+pub const BANK_FILE_VERSION: u32 = 1;
+pub const BANK_EXTENSION: &str = "fcbbank.json";
+
+/// One bank's ten presets with their names and notes, to share a complete
+/// bank setup instead of a whole dump
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BankFile {
+    pub version: u32,
+    /// What the bank is for, e.g. "Helix snapshot bank"
+    #[serde(default)]
+    pub name: String,
+    pub presets: Vec<Preset>,
+    #[serde(default)]
+    pub labels: Vec<PresetMetadata>,
+    /// The global channels the presets were made with; a dump only has one
+    /// channel per function, so they are not imported
+    pub channels: [Channel; 10],
+}
+
+impl BankFile {
+    pub fn from_bank(
+        message: &SysExMessage,
+        metadata: &ProjectMetadata,
+        bank: usize,
+        name: &str,
+    ) -> Self {
+        let indexes = bank * PEDALS_PER_BANK..(bank + 1) * PEDALS_PER_BANK;
+        Self {
+            version: BANK_FILE_VERSION,
+            name: name.to_string(),
+            presets: indexes
+                .clone()
+                .map(|index| *message.preset(index))
+                .collect(),
+            labels: indexes
+                .map(|index| metadata.presets.get(index).cloned().unwrap_or_default())
+                .collect(),
+            channels: *message.global_channels(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: Self = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if file.version > BANK_FILE_VERSION {
+            return Err(format!(
+                "The bank file is format version {}; this editor reads up to {}",
+                file.version, BANK_FILE_VERSION
+            ));
+        }
+        if file.presets.len() != PEDALS_PER_BANK {
+            return Err(format!(
+                "The bank file holds {} presets instead of {}",
+                file.presets.len(),
+                PEDALS_PER_BANK
+            ));
+        }
+        Ok(file)
+    }
+
+    /// The preset indexes of `bank` with the presets and labels they get
+    pub fn placed(&self, bank: usize) -> Vec<(usize, Preset, PresetMetadata)> {
+        self.presets
+            .iter()
+            .enumerate()
+            .map(|(pedal, preset)| {
+                (
+                    bank * PEDALS_PER_BANK + pedal,
+                    *preset,
+                    self.labels.get(pedal).cloned().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    /// The global functions whose channel in `message` differs from the one
+    /// the bank was made with, so the presets would send elsewhere
+    pub fn channel_differences(&self, message: &SysExMessage) -> Vec<usize> {
+        (0..self.channels.len())
+            .filter(|&function| self.channels[function] != message.global_channels()[function])
+            .collect()
+    }
+}
@@ -0,0 +1,68 @@
+use crate::model::{Preset, PresetField, SysExMessage, PEDALS_PER_BANK};
+use std::collections::BTreeSet;
+
+pub const BANKS: usize = 100 / PEDALS_PER_BANK;
+
+/// A dump received from the device waiting for the user to pick which of
+/// its banks replace the editor's; everything else keeps its local edits
+#[derive(Debug, Clone)]
+pub struct BankMerge {
+    pub incoming: SysExMessage,
+    pub banks: BTreeSet<usize>,
+    /// Also take the global channels and switch modes
+    pub globals: bool,
+}
+
+impl BankMerge {
+    /// Starts with every bank that differs from `current` selected
+    pub fn new(incoming: SysExMessage, current: &SysExMessage) -> Self {
+        let differences = differences(current, &incoming);
+        let banks = (0..BANKS).filter(|&bank| differences[bank] > 0).collect();
+        Self {
+            incoming,
+            banks,
+            globals: false,
+        }
+    }
+
+    /// Whether the global settings differ from `current`
+    pub fn globals_differ(&self, current: &SysExMessage) -> bool {
+        self.incoming.global_channels() != current.global_channels()
+            || self.incoming.switch_modes() != current.switch_modes()
+    }
+
+    /// The incoming presets of the selected banks, skipping those `skip` rejects
+    pub fn presets(&self, skip: impl Fn(usize) -> bool) -> Vec<(usize, Preset)> {
+        self.banks
+            .iter()
+            .filter(|&&bank| !skip(bank))
+            .flat_map(|&bank| bank * PEDALS_PER_BANK..(bank + 1) * PEDALS_PER_BANK)
+            .map(|index| (index, *self.incoming.preset(index)))
+            .collect()
+    }
+
+    /// The fields the merge changes, for highlighting them
+    pub fn changed_fields(
+        &self,
+        current: &SysExMessage,
+        skip: impl Fn(usize) -> bool,
+    ) -> Vec<(usize, PresetField)> {
+        current
+            .changed_fields(&self.incoming)
+            .into_iter()
+            .filter(|(index, _)| {
+                let bank = index / PEDALS_PER_BANK;
+                self.banks.contains(&bank) && !skip(bank)
+            })
+            .collect()
+    }
+}
+
+/// How many values differ between the two dumps in every bank
+pub fn differences(current: &SysExMessage, incoming: &SysExMessage) -> [usize; BANKS] {
+    let mut counts = [0; BANKS];
+    for (index, _) in current.changed_fields(incoming) {
+        counts[index / PEDALS_PER_BANK] += 1;
+    }
+    counts
+}
@@ -0,0 +1,98 @@
+use crate::model::PEDALS_PER_BANK;
+use crate::numbering::Numbering;
+use crate::project::{PresetMetadata, ProjectMetadata};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A photo of the user's rig with labels placed on its footswitches
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardPicture {
+    /// PNG file of the photo; the image itself is not stored in the project
+    #[serde(default)]
+    pub image_path: String,
+    #[serde(default)]
+    pub labels: Vec<BoardLabel>,
+}
+
+/// A label on the photo, positioned as a fraction of its width and height
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardLabel {
+    /// The footswitch (0-9) whose preset in the shown bank the label names
+    pub pedal: Option<usize>,
+    /// Shown after the preset, or alone for labels without a pedal
+    #[serde(default)]
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl BoardLabel {
+    pub fn caption(&self, bank: usize, presets: &[PresetMetadata], numbering: Numbering) -> String {
+        let Some(pedal) = self.pedal else {
+            return self.text.clone();
+        };
+        let index = bank * PEDALS_PER_BANK + pedal;
+        let name = presets
+            .get(index)
+            .map(|preset| preset.name.as_str())
+            .filter(|name| !name.is_empty());
+        let mut caption = match name {
+            Some(name) => format!("{}: {}", pedal + 1, name),
+            None => format!("{}: preset {}", pedal + 1, numbering.preset(index)),
+        };
+        if !self.text.is_empty() {
+            caption.push_str(&format!(" ({})", self.text));
+        }
+        caption
+    }
+}
+
+/// A printable HTML page with the labelled photo for every bank
+pub fn cheat_sheet_html(
+    metadata: &ProjectMetadata,
+    page_dir: &Path,
+    numbering: Numbering,
+) -> String {
+    let board = &metadata.board;
+    // Relative to the page, so the sheet and the photo can travel together
+    let image = Path::new(&board.image_path);
+    let image = image
+        .strip_prefix(page_dir)
+        .unwrap_or(image)
+        .to_string_lossy()
+        .replace('"', "%22");
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>FCB1010 cheat sheet</title>\n\
+         <style>\n\
+         .board { position: relative; display: inline-block; page-break-after: always; }\n\
+         .board img { max-width: 100%; }\n\
+         .label { position: absolute; transform: translate(-50%, -50%); background: #fffe; \
+         border: 1px solid #333; border-radius: 4px; padding: 1px 4px; font: 12px sans-serif; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    for bank in 0..100 / PEDALS_PER_BANK {
+        html.push_str(&format!(
+            "<h2>Bank {}</h2>\n<div class=\"board\">\n",
+            numbering.bank(bank)
+        ));
+        html.push_str(&format!("<img src=\"{}\" alt=\"Pedalboard\">\n", image));
+        for label in &board.labels {
+            html.push_str(&format!(
+                "<span class=\"label\" style=\"left: {:.1}%; top: {:.1}%\">{}</span>\n",
+                label.x * 100.0,
+                label.y * 100.0,
+                escape(&label.caption(bank, &metadata.presets, numbering))
+            ));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
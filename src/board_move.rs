@@ -0,0 +1,90 @@
+use crate::model::{Preset, SysExMessage, PEDALS_PER_BANK};
+use crate::project::{PresetMetadata, ProjectMetadata};
+use std::collections::BTreeSet;
+
+/// What becomes of the presets already on the pedals a group lands on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collision {
+    /// They are replaced and the moved pedals are left blank
+    Overwrite,
+    /// They go to the pedals the group came from
+    Swap,
+}
+
+/// Pedals selected on the board view and dragged to another bank; every
+/// preset keeps its pedal number
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupMove {
+    pub pedals: BTreeSet<usize>,
+    pub from_bank: usize,
+    pub to_bank: usize,
+}
+
+impl GroupMove {
+    /// Source and target preset index of every moved pedal
+    fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pedals.iter().map(|&pedal| {
+            (
+                self.from_bank * PEDALS_PER_BANK + pedal,
+                self.to_bank * PEDALS_PER_BANK + pedal,
+            )
+        })
+    }
+
+    /// The target pedals that already hold a preset or a name
+    pub fn conflicts(&self, message: &SysExMessage, metadata: &ProjectMetadata) -> Vec<usize> {
+        if self.from_bank == self.to_bank {
+            return Vec::new();
+        }
+        self.pedals
+            .iter()
+            .copied()
+            .filter(|&pedal| {
+                let index = self.to_bank * PEDALS_PER_BANK + pedal;
+                *message.preset(index) != Preset::new()
+                    || metadata
+                        .presets
+                        .get(index)
+                        .is_some_and(|preset| !preset.name.is_empty() || !preset.notes.is_empty())
+            })
+            .collect()
+    }
+
+    /// The new preset and metadata of every index the move touches
+    pub fn apply(
+        &self,
+        message: &SysExMessage,
+        metadata: &ProjectMetadata,
+        collision: Collision,
+    ) -> Vec<(usize, Preset, PresetMetadata)> {
+        if self.from_bank == self.to_bank {
+            return Vec::new();
+        }
+        let entry = |index: usize| {
+            (
+                *message.preset(index),
+                metadata.presets.get(index).cloned().unwrap_or_default(),
+            )
+        };
+        let mut changes = Vec::new();
+        for (from, to) in self.pairs() {
+            let (preset, moved) = entry(from);
+            let (left, left_metadata) = match collision {
+                Collision::Overwrite => (Preset::new(), PresetMetadata::default()),
+                Collision::Swap => entry(to),
+            };
+            changes.push((to, preset, moved));
+            changes.push((from, left, left_metadata));
+        }
+        changes
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} presets from bank {} to bank {}",
+            self.pedals.len(),
+            self.from_bank + 1,
+            self.to_bank + 1
+        )
+    }
+}
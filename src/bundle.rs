@@ -0,0 +1,181 @@
+use crate::project::{Project, ProjectMetadata};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, ZipArchive, ZipWriter};
+
+pub const BUNDLE_EXTENSION: &str = "fcbz";
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const PROJECT_ENTRY: &str = "project.json";
+const NAMES_ENTRY: &str = "names.json";
+const RAW_DUMP_ENTRY: &str = "dump.syx";
+
+/// Describes where a bundle came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub app_version: String,
+    pub firmware: Option<String>,
+    pub created: String,
+}
+
+impl BundleManifest {
+    fn new(project: &Project) -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            firmware: Some(project.dump.firmware().label().to_string()),
+            created: chrono::Local::now().to_rfc3339(),
+        }
+    }
+}
+
+/// The complete content of a .fcbz bundle
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub project: Project,
+    /// The last dump received from the device, exactly as it arrived
+    pub raw_dump: Option<Vec<u8>>,
+}
+
+impl Bundle {
+    pub fn new(project: Project, raw_dump: Option<Vec<u8>>) -> Self {
+        Self {
+            manifest: BundleManifest::new(&project),
+            project,
+            raw_dump,
+        }
+    }
+
+    /// Writes the bundle; with a passphrase every entry is AES-256 encrypted,
+    /// so it also opens in other zip tools that support WinZip AES
+    pub fn write(&self, path: &Path, passphrase: Option<&str>) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut zip = ZipWriter::new(file);
+        let mut options = SimpleFileOptions::default();
+        if let Some(passphrase) = passphrase {
+            options = options.with_aes_encryption(AesMode::Aes256, passphrase);
+        }
+
+        let manifest = serde_json::to_vec_pretty(&self.manifest).map_err(|e| e.to_string())?;
+        let project = self.project.to_json().map_err(|e| e.to_string())?;
+        let names = serde_json::to_vec_pretty(&self.project.metadata).map_err(|e| e.to_string())?;
+
+        let mut entries: Vec<(&str, &[u8])> = vec![
+            (MANIFEST_ENTRY, &manifest),
+            (PROJECT_ENTRY, project.as_bytes()),
+            (NAMES_ENTRY, &names),
+        ];
+        if let Some(raw_dump) = &self.raw_dump {
+            entries.push((RAW_DUMP_ENTRY, raw_dump));
+        }
+
+        for (name, data) in entries {
+            zip.start_file(name, options).map_err(|e| e.to_string())?;
+            zip.write_all(data).map_err(|e| e.to_string())?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Whether reading the bundle needs a passphrase
+    pub fn is_encrypted(path: &Path) -> Result<bool, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+        for index in 0..zip.len() {
+            if zip
+                .by_index_raw(index)
+                .map_err(|e| e.to_string())?
+                .encrypted()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn read(path: &Path, passphrase: Option<&str>) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+        let mut read_entry = |name: &str| read_entry(&mut zip, name, passphrase);
+
+        let manifest = read_entry(MANIFEST_ENTRY)?
+            .ok_or("Bundle has no manifest")
+            .and_then(|data| serde_json::from_slice(&data).map_err(|_| "Invalid manifest"))?;
+
+        let project_json = read_entry(PROJECT_ENTRY)?.ok_or("Bundle has no project")?;
+        let mut project = Project::from_json(&String::from_utf8_lossy(&project_json))
+            .map_err(|e| e.to_string())?;
+
+        // Names written by other tools take precedence over the copy in the project
+        if let Some(names) = read_entry(NAMES_ENTRY)? {
+            if let Ok(metadata) = serde_json::from_slice::<ProjectMetadata>(&names) {
+                project.metadata = metadata;
+            }
+        }
+
+        let raw_dump = read_entry(RAW_DUMP_ENTRY)?;
+
+        Ok(Self {
+            manifest,
+            project,
+            raw_dump,
+        })
+    }
+}
+
+fn read_entry(
+    zip: &mut ZipArchive<File>,
+    name: &str,
+    passphrase: Option<&str>,
+) -> Result<Option<Vec<u8>>, String> {
+    let entry = match passphrase {
+        Some(passphrase) => zip.by_name_decrypt(name, passphrase.as_bytes()),
+        None => zip.by_name(name),
+    };
+    let mut entry = match entry {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(ZipError::InvalidPassword) => return Err("Wrong passphrase".to_string()),
+        Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)) => {
+            return Err("The bundle is encrypted; enter its passphrase".to_string())
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_bundles_need_their_passphrase() {
+        let dir = std::env::temp_dir().join(format!("fcb1010-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rig.fcbz");
+        let mut project = Project::default();
+        project.metadata.preset_mut(0).name = "Clean".to_string();
+        let raw_dump = project.dump.encode();
+        Bundle::new(project.clone(), Some(raw_dump.clone()))
+            .write(&path, Some("stage left"))
+            .unwrap();
+
+        let encrypted = Bundle::is_encrypted(&path);
+        let read = Bundle::read(&path, Some("stage left"));
+        let wrong = Bundle::read(&path, Some("stage right")).err();
+        let missing = Bundle::read(&path, None).err();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(encrypted, Ok(true));
+        let read = read.unwrap();
+        assert_eq!(read.project, project);
+        assert_eq!(read.raw_dump, Some(raw_dump));
+        assert_eq!(wrong.as_deref(), Some("Wrong passphrase"));
+        assert!(missing.unwrap().ends_with("enter its passphrase"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::model::{Preset, PresetField};
+use crate::values::Value7;
+use eframe::egui;
+use midir::{MidiInput, MidiInputConnection};
+use std::sync::{Arc, Mutex};
+
+/// A preset rebuilt from the messages in a MIDI monitor log
+#[derive(Debug, Clone)]
+pub struct CaptureImport {
+    pub preset: Preset,
+    /// What was taken from the log, for the preview
+    pub used: Vec<String>,
+    /// Messages that did not fit into a preset (extra CCs, other types…)
+    pub ignored: usize,
+}
+
+/// Pulls channel messages out of lines like `00:01.250  B0 50 7F  Control`.
+/// On every line the first run of hex bytes that starts with a status byte
+/// is taken as the message.
+pub fn parse_messages(text: &str) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    for line in text.lines() {
+        let tokens: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+            .collect();
+        let Some(start) = tokens
+            .iter()
+            .position(|t| hex_byte(t).is_some_and(|b| (0x80..0xf0).contains(&b)))
+        else {
+            continue;
+        };
+        let bytes: Vec<u8> = tokens[start..].iter().map_while(|t| hex_byte(t)).collect();
+
+        let length = match bytes[0] & 0xf0 {
+            0xc0 | 0xd0 => 2,
+            _ => 3,
+        };
+        if bytes.len() >= length {
+            messages.push(bytes[..length].to_vec());
+        }
+    }
+    messages
+}
+
+/// Fills PC 1-5, CC 1-2 and the note in the order they appear
+pub fn import(text: &str) -> Result<CaptureImport, String> {
+    let messages = parse_messages(text);
+    if messages.is_empty() {
+        return Err("No MIDI messages were found in the capture".to_string());
+    }
+    Ok(assemble(&messages))
+}
+
+/// Builds a preset from channel messages, taking them in order
+pub fn assemble(messages: &[Vec<u8>]) -> CaptureImport {
+    let mut preset = Preset::new();
+    let mut used = Vec::new();
+    let mut ignored = 0;
+    let (mut programs, mut controls, mut note) = (0, 0, None);
+
+    for message in messages {
+        let channel = (message[0] & 0x0f) + 1;
+        match (message[0] & 0xf0, message.as_slice()) {
+            (0xc0, &[_, program]) if programs < 5 => {
+                let field = PresetField::ProgramChange(programs);
+                preset.set(field, Value7::clamped(program));
+                preset.set_enabled(field.slot(), true);
+                used.push(format!(
+                    "PC {} = {} (channel {})",
+                    programs + 1,
+                    program,
+                    channel
+                ));
+                programs += 1;
+            }
+            (0xb0, &[_, number, value]) if controls < 2 => {
+                let field = PresetField::ControlNumber(controls);
+                preset.set(field, Value7::clamped(number));
+                preset.set(PresetField::ControlValue(controls), Value7::clamped(value));
+                preset.set_enabled(field.slot(), true);
+                used.push(format!(
+                    "CC {} = controller {} value {} (channel {})",
+                    controls + 1,
+                    number,
+                    value,
+                    channel
+                ));
+                controls += 1;
+            }
+            // A note on with velocity 0 is a note off
+            (0x90, &[_, number, velocity]) if note.is_none() && velocity > 0 => {
+                preset.set(PresetField::Note, Value7::clamped(number));
+                preset.set_enabled(PresetField::Note.slot(), true);
+                used.push(format!("Note = {} (channel {})", number, channel));
+                note = Some(number);
+            }
+            // Releasing the note taken is part of it
+            (0x80, &[_, number, _]) | (0x90, &[_, number, 0]) if note == Some(number) => {}
+            _ => ignored += 1,
+        }
+    }
+
+    CaptureImport {
+        preset,
+        used,
+        ignored,
+    }
+}
+
+/// Collects the channel messages arriving on an input port while armed, e.g.
+/// from buttons on another controller or a DAW, to build a preset from them
+#[derive(Default)]
+pub struct LiveCapture {
+    connection: Option<MidiInputConnection<()>>,
+    messages: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl LiveCapture {
+    /// Starts listening, forgetting what an earlier capture collected
+    pub fn start(&mut self, port_index: usize, ctx: &egui::Context) -> Result<(), String> {
+        self.stop();
+        self.messages.lock().unwrap().clear();
+
+        let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+        let port = midi_in
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+
+        let messages = Arc::clone(&self.messages);
+        let ctx = ctx.clone();
+        let connection = midi_in
+            .connect(
+                &port,
+                "midir-preset-capture",
+                move |_, message, _| {
+                    // Clock, active sensing and SysEx are not preset material
+                    if message.first().is_some_and(|&b| (0x80..0xf0).contains(&b)) {
+                        messages.lock().unwrap().push(message.to_vec());
+                        ctx.request_repaint();
+                    }
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    /// Stops listening; what was collected stays available
+    pub fn stop(&mut self) {
+        self.connection = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.lock().unwrap().clear();
+    }
+
+    pub fn messages(&self) -> Vec<Vec<u8>> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+fn hex_byte(token: &str) -> Option<u8> {
+    let token = token.trim_start_matches("0x");
+    if token.len() != 2 {
+        return None;
+    }
+    u8::from_str_radix(token, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_notes_are_ignored() {
+        let messages = [
+            vec![0x80, 40, 0],
+            vec![0x90, 60, 100],
+            vec![0x90, 62, 100],
+            vec![0x80, 60, 0],
+            vec![0x90, 62, 0],
+            vec![0x90, 60, 0],
+        ];
+        let capture = assemble(&messages);
+        assert_eq!(capture.preset.get(PresetField::Note), 60);
+        assert_eq!(capture.used.len(), 1);
+        assert_eq!(capture.ignored, 3);
+    }
+}
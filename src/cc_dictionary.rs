@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Which device's controller assignments CC numbers are named after
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum CcProfile {
+    #[default]
+    GenericMidi,
+    Helix,
+    HxStomp,
+    AxeFx,
+    Kemper,
+    /// The user's own dictionary from the config file
+    Custom,
+}
+
+const GENERIC_MIDI: &[(u8, &str)] = &[
+    (0, "Bank Select"),
+    (1, "Modulation"),
+    (2, "Breath"),
+    (4, "Foot Controller"),
+    (5, "Portamento Time"),
+    (7, "Volume"),
+    (8, "Balance"),
+    (10, "Pan"),
+    (11, "Expression"),
+    (64, "Sustain"),
+    (65, "Portamento"),
+    (66, "Sostenuto"),
+    (67, "Soft Pedal"),
+    (71, "Resonance"),
+    (74, "Cutoff"),
+    (91, "Reverb Send"),
+    (93, "Chorus Send"),
+];
+
+const HELIX: &[(u8, &str)] = &[
+    (1, "EXP 1"),
+    (2, "EXP 2"),
+    (3, "EXP 3"),
+    (49, "FS1"),
+    (50, "FS2"),
+    (51, "FS3"),
+    (52, "FS4"),
+    (53, "FS5"),
+    (54, "FS7"),
+    (55, "FS8"),
+    (56, "FS9"),
+    (57, "FS10"),
+    (58, "FS11"),
+    (59, "EXP Toe Switch"),
+    (60, "Looper Record/Overdub"),
+    (61, "Looper Play/Stop"),
+    (62, "Looper Play Once"),
+    (63, "Looper Undo"),
+    (64, "Tap Tempo"),
+    (65, "Looper Forward/Reverse"),
+    (66, "Looper Full/Half Speed"),
+    (67, "Looper On/Off"),
+    (68, "Tuner"),
+    (69, "Snapshot Select"),
+    (71, "Footswitch Mode"),
+];
+
+const HX_STOMP: &[(u8, &str)] = &[
+    (1, "EXP 1"),
+    (2, "EXP 2"),
+    (49, "FS1"),
+    (50, "FS2"),
+    (51, "FS3"),
+    (52, "FS4"),
+    (53, "FS5"),
+    (60, "Looper Record/Overdub"),
+    (61, "Looper Play/Stop"),
+    (62, "Looper Play Once"),
+    (63, "Looper Undo"),
+    (64, "Tap Tempo"),
+    (65, "Looper Forward/Reverse"),
+    (66, "Looper Full/Half Speed"),
+    (67, "Looper On/Off"),
+    (68, "Tuner"),
+    (69, "Snapshot Select"),
+    (71, "Footswitch Mode"),
+];
+
+const AXE_FX: &[(u8, &str)] = &[
+    (10, "Input Volume"),
+    (11, "Output 1 Volume"),
+    (12, "Output 2 Volume"),
+    (13, "Bypass"),
+    (14, "Tap Tempo"),
+    (15, "Tuner"),
+    (28, "Looper Record"),
+    (29, "Looper Play"),
+    (30, "Looper Once"),
+    (31, "Looper Dub"),
+    (32, "Looper Reverse"),
+    (34, "Scene Select"),
+];
+
+const KEMPER: &[(u8, &str)] = &[
+    (1, "Wah Pedal"),
+    (4, "Pitch Pedal"),
+    (7, "Volume Pedal"),
+    (11, "Morph Pedal"),
+    (17, "Stomp A"),
+    (18, "Stomp B"),
+    (19, "Stomp C"),
+    (20, "Stomp D"),
+    (22, "Stomp X"),
+    (24, "Stomp MOD"),
+    (26, "Delay"),
+    (28, "Reverb"),
+    (30, "Tap Tempo"),
+    (31, "Tuner"),
+    (33, "Rotary Speed"),
+    (34, "Delay Infinity"),
+    (35, "Delay Hold"),
+    (48, "Performance Up"),
+    (49, "Performance Down"),
+    (50, "Slot 1"),
+    (51, "Slot 2"),
+    (52, "Slot 3"),
+    (53, "Slot 4"),
+    (54, "Slot 5"),
+];
+
+impl CcProfile {
+    pub const ALL: [CcProfile; 6] = [
+        CcProfile::GenericMidi,
+        CcProfile::Helix,
+        CcProfile::HxStomp,
+        CcProfile::AxeFx,
+        CcProfile::Kemper,
+        CcProfile::Custom,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CcProfile::GenericMidi => "Generic MIDI",
+            CcProfile::Helix => "Line 6 Helix",
+            CcProfile::HxStomp => "Line 6 HX Stomp",
+            CcProfile::AxeFx => "Fractal Axe-Fx",
+            CcProfile::Kemper => "Kemper Profiler",
+            CcProfile::Custom => "Custom",
+        }
+    }
+
+    fn builtin(self) -> &'static [(u8, &'static str)] {
+        match self {
+            CcProfile::GenericMidi => GENERIC_MIDI,
+            CcProfile::Helix => HELIX,
+            CcProfile::HxStomp => HX_STOMP,
+            CcProfile::AxeFx => AXE_FX,
+            CcProfile::Kemper => KEMPER,
+            CcProfile::Custom => &[],
+        }
+    }
+}
+
+/// Resolves CC numbers to names for the active profile
+pub struct CcDictionary<'a> {
+    pub profile: CcProfile,
+    pub custom: &'a BTreeMap<u8, String>,
+}
+
+impl<'a> CcDictionary<'a> {
+    pub fn new(profile: CcProfile, custom: &'a BTreeMap<u8, String>) -> Self {
+        Self { profile, custom }
+    }
+
+    pub fn name(&self, controller: u8) -> Option<&str> {
+        match self.profile {
+            CcProfile::Custom => self.custom.get(&controller).map(String::as_str),
+            profile => profile
+                .builtin()
+                .iter()
+                .find(|(number, _)| *number == controller)
+                .map(|(_, name)| *name),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<(u8, &str)> {
+        match self.profile {
+            CcProfile::Custom => self
+                .custom
+                .iter()
+                .map(|(number, name)| (*number, name.as_str()))
+                .collect(),
+            profile => profile.builtin().to_vec(),
+        }
+    }
+
+    /// "CC 68 – Tuner", or just "CC 68" when the number has no name
+    pub fn describe(&self, controller: u8) -> String {
+        match self.name(controller) {
+            Some(name) => format!("CC {} – {}", controller, name),
+            None => format!("CC {}", controller),
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::model::{PresetField, SysExMessage, PEDALS_PER_BANK};
+use crate::spec::field_title;
+use crate::values::{Channel, Value7};
+use std::collections::BTreeSet;
+
+/// The FCB1010 has one channel per function, not per preset, so each bank
+/// is routed through its own program change function instead.
+pub const PROGRAM_CHANGE_FUNCTIONS: usize = 5;
+
+/// One value the channel-per-bank tool would rewrite
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelChange {
+    GlobalChannel {
+        function: usize,
+        from: Channel,
+        to: Channel,
+    },
+    PresetField {
+        index: usize,
+        field: PresetField,
+        from: Value7,
+        to: Value7,
+    },
+    /// A program change slot switched on or off
+    Slot {
+        index: usize,
+        slot: usize,
+        enabled: bool,
+    },
+}
+
+impl ChannelChange {
+    pub fn describe(&self) -> String {
+        match self {
+            ChannelChange::GlobalChannel { function, from, to } => {
+                format!(
+                    "PC{} channel: {} → {}",
+                    function + 1,
+                    from.number(),
+                    to.number()
+                )
+            }
+            ChannelChange::PresetField {
+                index,
+                field,
+                from,
+                to,
+            } => format!(
+                "Preset {} {}: {} → {}",
+                index + 1,
+                field_title(*field),
+                from,
+                to
+            ),
+            ChannelChange::Slot {
+                index,
+                slot,
+                enabled,
+            } => format!(
+                "Preset {} {}: switched {}",
+                index + 1,
+                field_title(PresetField::ProgramChange(*slot)),
+                if *enabled { "on" } else { "off" }
+            ),
+        }
+    }
+}
+
+/// Everything needed to preview and apply the channel-per-bank assignment
+#[derive(Debug, Clone, Default)]
+pub struct ChannelPlan {
+    pub changes: Vec<ChannelChange>,
+    pub warnings: Vec<String>,
+}
+
+impl ChannelPlan {
+    /// Bank N is moved onto program change function N, whose channel becomes
+    /// N + 1: PC1 is switched off and its program sent through the function's
+    /// slot instead. Banks past the fifth share functions with the earlier
+    /// ones. Presets whose slot is already in use are left alone, as are the
+    /// presets of locked banks; the warnings list both, and the presets that
+    /// already send on a function whose channel changes.
+    ///
+    /// A channel is shared by every preset, so one that a locked bank sends
+    /// on is kept, and the banks meant for it are not moved, unless
+    /// `change_locked` allows it.
+    pub fn channel_per_bank(
+        message: &SysExMessage,
+        locked_banks: &BTreeSet<usize>,
+        change_locked: bool,
+    ) -> Self {
+        let mut plan = ChannelPlan::default();
+        let banks = message.presets().len() / PEDALS_PER_BANK;
+        let locked = |index: usize| locked_banks.contains(&(index / PEDALS_PER_BANK));
+
+        let mut held = BTreeSet::new();
+        for function in 0..PROGRAM_CHANGE_FUNCTIONS.min(banks) {
+            let from = message.global_channels()[function];
+            let to = Channel::clamped(function as u8);
+            if from == to {
+                continue;
+            }
+            let mut locked_senders: Vec<String> = (0..message.presets().len())
+                .filter(|&index| {
+                    locked(index) && message.preset(index).program_change(function).is_some()
+                })
+                .map(|index| (index / PEDALS_PER_BANK + 1).to_string())
+                .collect();
+            locked_senders.dedup();
+            if !locked_senders.is_empty() {
+                if !change_locked {
+                    plan.warnings.push(format!(
+                        "PC {} keeps channel {} because locked banks {} send on it",
+                        function + 1,
+                        from.number(),
+                        locked_senders.join(", ")
+                    ));
+                    held.insert(function);
+                    continue;
+                }
+                plan.warnings.push(format!(
+                    "Locked banks {} send PC {} on channel {}, and on channel {} after this",
+                    locked_senders.join(", "),
+                    function + 1,
+                    from.number(),
+                    to.number()
+                ));
+            }
+            plan.changes
+                .push(ChannelChange::GlobalChannel { function, from, to });
+        }
+
+        if banks > PROGRAM_CHANGE_FUNCTIONS {
+            plan.warnings.push(format!(
+                "Only {} program change channels exist; banks {}–{} reuse the channels of banks 1–{}",
+                PROGRAM_CHANGE_FUNCTIONS,
+                PROGRAM_CHANGE_FUNCTIONS + 1,
+                banks,
+                banks - PROGRAM_CHANGE_FUNCTIONS
+            ));
+        }
+
+        for index in 0..message.presets().len() {
+            let function = bank_function(index);
+            if function == 0 || locked(index) || held.contains(&function) {
+                continue;
+            }
+
+            // Move the main program change into the bank's own function:
+            // the value goes over and PC1 is switched off, keeping its value
+            let preset = message.preset(index);
+            let Some(program) = preset.program_change(0) else {
+                continue;
+            };
+            if let Some(used) = preset.program_change(function) {
+                plan.warnings.push(format!(
+                    "Preset {} already sends {} (program {}), so its PC 1 is not moved",
+                    index + 1,
+                    field_title(PresetField::ProgramChange(function)),
+                    used
+                ));
+                continue;
+            }
+            let target = PresetField::ProgramChange(function);
+            if preset.value(target) != program.into() {
+                plan.changes.push(ChannelChange::PresetField {
+                    index,
+                    field: target,
+                    from: preset.value(target),
+                    to: program.into(),
+                });
+            }
+            plan.changes.push(ChannelChange::Slot {
+                index,
+                slot: function,
+                enabled: true,
+            });
+            plan.changes.push(ChannelChange::Slot {
+                index,
+                slot: 0,
+                enabled: false,
+            });
+        }
+
+        // Presets of other banks that send on a function whose channel
+        // changes send elsewhere afterwards, unless their PC1 is moved away
+        let moved: BTreeSet<usize> = plan
+            .changes
+            .iter()
+            .filter_map(|change| match *change {
+                ChannelChange::Slot {
+                    index,
+                    slot: 0,
+                    enabled: false,
+                } => Some(index),
+                _ => None,
+            })
+            .collect();
+        for change in &plan.changes {
+            let &ChannelChange::GlobalChannel { function, from, to } = change else {
+                continue;
+            };
+            let affected: Vec<String> = (0..message.presets().len())
+                .filter(|&index| {
+                    message.preset(index).program_change(function).is_some()
+                        && !locked(index)
+                        && bank_function(index) != function
+                        && !(function == 0 && moved.contains(&index))
+                })
+                .map(|index| (index + 1).to_string())
+                .collect();
+            if !affected.is_empty() {
+                plan.warnings.push(format!(
+                    "Presets {} send PC {} on channel {}, and on channel {} after this",
+                    affected.join(", "),
+                    function + 1,
+                    from.number(),
+                    to.number()
+                ));
+            }
+        }
+
+        plan
+    }
+}
+
+/// The program change function the bank of the preset at `index` is moved to
+fn bank_function(index: usize) -> usize {
+    (index / PEDALS_PER_BANK) % PROGRAM_CHANGE_FUNCTIONS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb1010_core::builder::DumpBuilder;
+
+    #[test]
+    fn moves_pc1_into_the_bank_function() {
+        let message = DumpBuilder::new()
+            .bank(1)
+            .pedal(1)
+            .pc(1, 12)
+            .pedal(2)
+            .pc(1, 13)
+            .pc(2, 40)
+            .build()
+            .unwrap();
+        let plan = ChannelPlan::channel_per_bank(&message, &BTreeSet::new(), false);
+
+        let moved: Vec<&ChannelChange> = plan
+            .changes
+            .iter()
+            .filter(|change| !matches!(change, ChannelChange::GlobalChannel { .. }))
+            .collect();
+        assert_eq!(moved.len(), 3);
+        assert_eq!(moved[0].describe(), "Preset 11 PC 2: 0 → 12");
+        assert_eq!(moved[1].describe(), "Preset 11 PC 2: switched on");
+        assert_eq!(moved[2].describe(), "Preset 11 PC 1: switched off");
+        assert!(plan
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with("Preset 12 already sends PC 2")));
+    }
+
+    #[test]
+    fn keeps_channels_locked_banks_send_on() {
+        let message = DumpBuilder::new()
+            .bank(2)
+            .pedal(1)
+            .pc(1, 5)
+            .bank(7)
+            .pedal(1)
+            .pc(3, 9)
+            .build()
+            .unwrap();
+        let locked = BTreeSet::from([7]);
+
+        let plan = ChannelPlan::channel_per_bank(&message, &locked, false);
+        assert!(!plan
+            .changes
+            .iter()
+            .any(|change| matches!(change, ChannelChange::GlobalChannel { function: 2, .. })));
+        assert!(!plan
+            .changes
+            .iter()
+            .any(|change| matches!(change, ChannelChange::Slot { index: 20, .. })));
+        assert!(plan.warnings[0].starts_with("PC 3 keeps channel 1 because locked banks 8"));
+
+        let plan = ChannelPlan::channel_per_bank(&message, &locked, true);
+        assert!(plan
+            .changes
+            .iter()
+            .any(|change| matches!(change, ChannelChange::GlobalChannel { function: 2, .. })));
+        assert!(plan
+            .changes
+            .iter()
+            .all(|change| !matches!(change, ChannelChange::Slot { index, .. } if *index >= 70 && *index < 80)));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "Usage: rust-fcb1010 [--project <file.json>] [--port <name>] [--no-midi] [--portable] [<file>]
+       rust-fcb1010 --daemon [--portable] [--port <name>] [--backup-dir <dir>]
+                    [--interval <minutes>] [--keep <count>] [--request <hex bytes>]";
+
+/// Options given on the command line when launching the editor
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// Project file to load at startup and to save back to
+    pub project: Option<PathBuf>,
+    /// Name (or part of the name) of the MIDI output port to connect to
+    pub port: Option<String>,
+    /// Start without opening the MIDI backend, for offline editing
+    pub no_midi: bool,
+    /// Keep the config, backups and projects next to the executable
+    pub portable: bool,
+    /// A project, bundle or `.syx` file to open, e.g. from a file manager
+    pub open: Option<PathBuf>,
+    /// Run without a window, storing backups of received dumps
+    pub daemon: bool,
+    pub backup_dir: Option<PathBuf>,
+    pub interval_minutes: Option<u64>,
+    pub keep: Option<usize>,
+    /// SysEx sent to ask the device for a dump in daemon mode
+    pub request: Option<Vec<u8>>,
+}
+
+impl CliArgs {
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--project" => {
+                    let value = args.next().ok_or("--project requires a file name")?;
+                    cli.project = Some(PathBuf::from(value));
+                }
+                "--port" => {
+                    let value = args.next().ok_or("--port requires a port name")?;
+                    cli.port = Some(value);
+                }
+                "--no-midi" | "--safe-mode" => cli.no_midi = true,
+                "--portable" => cli.portable = true,
+                "--daemon" => cli.daemon = true,
+                "--backup-dir" => {
+                    let value = args.next().ok_or("--backup-dir requires a directory")?;
+                    cli.backup_dir = Some(PathBuf::from(value));
+                }
+                "--interval" => {
+                    let value = args
+                        .next()
+                        .ok_or("--interval requires a number of minutes")?;
+                    let minutes = value
+                        .parse()
+                        .ok()
+                        .filter(|&m| m > 0)
+                        .ok_or_else(|| format!("Invalid interval '{}'", value))?;
+                    cli.interval_minutes = Some(minutes);
+                }
+                "--keep" => {
+                    let value = args.next().ok_or("--keep requires a count")?;
+                    let keep = value
+                        .parse()
+                        .map_err(|_| format!("Invalid backup count '{}'", value))?;
+                    cli.keep = Some(keep);
+                }
+                "--request" => {
+                    let value = args.next().ok_or("--request requires hex bytes")?;
+                    cli.request = Some(parse_hex(&value)?);
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other if !other.starts_with("--") && cli.open.is_none() => {
+                    cli.open = Some(PathBuf::from(other));
+                }
+                other => return Err(format!("Unknown argument '{}'\n{}", other, USAGE)),
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+/// Parses bytes written as hex pairs, optionally separated by spaces
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not valid hex", text));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("'{}' is not a whole number of hex bytes", text));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
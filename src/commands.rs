@@ -0,0 +1,183 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Every action that can be bound to a keyboard shortcut
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Copy)]
+pub enum Command {
+    SaveProject,
+    LoadProject,
+    NewFromFactoryDefaults,
+    SendDump,
+    ReceiveDump,
+    ExportBundle,
+    ImportBundle,
+    ShowEditor,
+    ShowDeviceVsEditor,
+    ToggleLock,
+    FindReplace,
+}
+
+impl Command {
+    pub const ALL: [Command; 11] = [
+        Command::SaveProject,
+        Command::LoadProject,
+        Command::NewFromFactoryDefaults,
+        Command::SendDump,
+        Command::ReceiveDump,
+        Command::ExportBundle,
+        Command::ImportBundle,
+        Command::ShowEditor,
+        Command::ShowDeviceVsEditor,
+        Command::ToggleLock,
+        Command::FindReplace,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::SaveProject => "Save project",
+            Command::LoadProject => "Load project",
+            Command::NewFromFactoryDefaults => "New from factory defaults",
+            Command::SendDump => "Send dump to device",
+            Command::ReceiveDump => "Receive dump from device",
+            Command::ExportBundle => "Export bundle",
+            Command::ImportBundle => "Import bundle",
+            Command::ShowEditor => "Show editor",
+            Command::ShowDeviceVsEditor => "Show device vs. editor",
+            Command::ToggleLock => "Lock or unlock the editor",
+            Command::FindReplace => "Find and replace messages",
+        }
+    }
+
+    /// Whether the command can run while the editor is locked: it neither
+    /// changes the edited dump nor sends anything to the device
+    pub fn allowed_while_locked(self) -> bool {
+        match self {
+            Command::SaveProject
+            | Command::ExportBundle
+            | Command::ShowEditor
+            | Command::ShowDeviceVsEditor
+            | Command::ToggleLock => true,
+            Command::LoadProject
+            | Command::NewFromFactoryDefaults
+            | Command::SendDump
+            | Command::ReceiveDump
+            | Command::ImportBundle
+            | Command::FindReplace => false,
+        }
+    }
+
+    fn default_shortcut(self) -> Option<Shortcut> {
+        let command = |key| Some(Shortcut::new(egui::Modifiers::COMMAND, key));
+        match self {
+            Command::SaveProject => command(egui::Key::S),
+            Command::LoadProject => command(egui::Key::O),
+            Command::SendDump => command(egui::Key::T),
+            Command::ReceiveDump => command(egui::Key::R),
+            Command::ShowEditor => command(egui::Key::Num1),
+            Command::ShowDeviceVsEditor => command(egui::Key::Num2),
+            Command::ToggleLock => command(egui::Key::L),
+            Command::FindReplace => command(egui::Key::H),
+            Command::NewFromFactoryDefaults | Command::ExportBundle | Command::ImportBundle => None,
+        }
+    }
+}
+
+/// A key plus modifiers, stored by key name so the config stays readable
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Shortcut {
+    pub key: String,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self {
+            key: key.name().to_string(),
+            ctrl: modifiers.command || modifiers.ctrl,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    pub fn to_egui(&self) -> Option<egui::KeyboardShortcut> {
+        let key = egui::Key::from_name(&self.key)?;
+        let mut modifiers = egui::Modifiers::NONE;
+        if self.ctrl {
+            modifiers = modifiers | egui::Modifiers::COMMAND;
+        }
+        if self.shift {
+            modifiers = modifiers | egui::Modifiers::SHIFT;
+        }
+        if self.alt {
+            modifiers = modifiers | egui::Modifiers::ALT;
+        }
+        Some(egui::KeyboardShortcut::new(modifiers, key))
+    }
+
+    pub fn describe(&self, ctx: &egui::Context) -> String {
+        match self.to_egui() {
+            Some(shortcut) => ctx.format_shortcut(&shortcut),
+            None => format!("Unknown key '{}'", self.key),
+        }
+    }
+}
+
+/// The user's shortcut table; commands missing from it are unbound
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Keybindings {
+    bindings: BTreeMap<Command, Shortcut>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            bindings: Command::ALL
+                .iter()
+                .filter_map(|&command| Some((command, command.default_shortcut()?)))
+                .collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    pub fn get(&self, command: Command) -> Option<&Shortcut> {
+        self.bindings.get(&command)
+    }
+
+    /// Binds the shortcut, taking it away from any other command using it
+    pub fn bind(&mut self, command: Command, shortcut: Shortcut) {
+        self.bindings.retain(|_, existing| *existing != shortcut);
+        self.bindings.insert(command, shortcut);
+    }
+
+    pub fn unbind(&mut self, command: Command) {
+        self.bindings.remove(&command);
+    }
+
+    /// Returns the commands whose shortcuts were pressed this frame
+    pub fn triggered(&self, ctx: &egui::Context) -> Vec<Command> {
+        // Shortcuts with more modifiers go first so Ctrl+Shift+S is not taken by Ctrl+S
+        let mut shortcuts: Vec<(Command, egui::KeyboardShortcut)> = self
+            .bindings
+            .iter()
+            .filter_map(|(&command, shortcut)| Some((command, shortcut.to_egui()?)))
+            .collect();
+        shortcuts.sort_by_key(|(_, s)| {
+            std::cmp::Reverse(s.modifiers.shift as u8 + s.modifiers.alt as u8)
+        });
+
+        ctx.input_mut(|input| {
+            shortcuts
+                .iter()
+                .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(command, _)| *command)
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use crate::firmware::FirmwareSetting;
+use crate::model::{PresetField, SysExMessage};
+use crate::project::{Project, ProjectMetadata};
+use crate::spec::{field_title, GLOBAL_FUNCTIONS};
+use std::path::Path;
+
+/// A dump loaded for comparison, from a `.syx` file or a project
+pub struct ComparedFile {
+    pub name: String,
+    pub dump: SysExMessage,
+    pub metadata: ProjectMetadata,
+}
+
+impl ComparedFile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path.display().to_string();
+        let is_syx = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("syx"));
+        if is_syx {
+            let data = std::fs::read(path).map_err(|e| e.to_string())?;
+            let dump = SysExMessage::decode(&data, FirmwareSetting::AutoDetect)
+                .map_err(|e| e.to_string())?;
+            Ok(Self {
+                name,
+                dump,
+                metadata: ProjectMetadata::default(),
+            })
+        } else {
+            let project = Project::load(path)?;
+            Ok(Self {
+                name,
+                dump: project.dump,
+                metadata: project.metadata,
+            })
+        }
+    }
+
+    fn preset_name(&self, index: usize) -> &str {
+        self.metadata
+            .presets
+            .get(index)
+            .map_or("", |preset| preset.name.as_str())
+    }
+}
+
+/// Everything that differs between two dumps, preset by preset
+pub struct CompareReport {
+    pub before: String,
+    pub after: String,
+    /// Global settings that differ, as (setting, before, after)
+    pub globals: Vec<(String, String, String)>,
+    pub presets: Vec<PresetChanges>,
+}
+
+pub struct PresetChanges {
+    pub index: usize,
+    /// Name in either file, the later one preferred
+    pub name: String,
+    pub fields: Vec<(PresetField, u8, u8)>,
+}
+
+impl CompareReport {
+    pub fn new(before: &ComparedFile, after: &ComparedFile) -> Self {
+        let (a, b) = (&before.dump, &after.dump);
+        let mut globals = Vec::new();
+        if a.firmware() != b.firmware() {
+            globals.push((
+                "Firmware layout".to_string(),
+                a.firmware().label().to_string(),
+                b.firmware().label().to_string(),
+            ));
+        }
+        for (function, (x, y)) in a
+            .global_channels()
+            .iter()
+            .zip(b.global_channels())
+            .enumerate()
+        {
+            if x != y {
+                globals.push((
+                    format!("{} channel", GLOBAL_FUNCTIONS[function]),
+                    x.number().to_string(),
+                    y.number().to_string(),
+                ));
+            }
+        }
+        for (switch, (x, y)) in a.switch_modes().iter().zip(b.switch_modes()).enumerate() {
+            if x != y {
+                globals.push((
+                    format!("Switch {} mode", switch + 1),
+                    x.label().to_string(),
+                    y.label().to_string(),
+                ));
+            }
+        }
+
+        let mut presets: Vec<PresetChanges> = Vec::new();
+        for (index, field) in a.changed_fields(b) {
+            let change = (
+                field,
+                a.preset(index).get(field),
+                b.preset(index).get(field),
+            );
+            match presets.last_mut() {
+                Some(last) if last.index == index => last.fields.push(change),
+                _ => {
+                    let name = [after.preset_name(index), before.preset_name(index)]
+                        .into_iter()
+                        .find(|name| !name.is_empty())
+                        .unwrap_or_default()
+                        .to_string();
+                    presets.push(PresetChanges {
+                        index,
+                        name,
+                        fields: vec![change],
+                    });
+                }
+            }
+        }
+
+        Self {
+            before: before.name.clone(),
+            after: after.name.clone(),
+            globals,
+            presets,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globals.is_empty() && self.presets.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let values: usize = self.presets.iter().map(|p| p.fields.len()).sum();
+        format!(
+            "{} global settings and {} values in {} presets differ",
+            self.globals.len(),
+            values,
+            self.presets.len()
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# FCB1010 comparison\n\n- Before: `{}`\n- After: `{}`\n\n{}.\n",
+            self.before,
+            self.after,
+            self.summary()
+        );
+
+        if !self.globals.is_empty() {
+            out.push_str("\n## Global settings\n\n| Setting | Before | After |\n|---|---|---|\n");
+            for (setting, before, after) in &self.globals {
+                out.push_str(&format!("| {} | {} | {} |\n", setting, before, after));
+            }
+        }
+
+        for preset in &self.presets {
+            out.push_str(&format!("\n## Preset {}", preset.index + 1));
+            if !preset.name.is_empty() {
+                out.push_str(&format!(" – {}", preset.name));
+            }
+            out.push_str("\n\n| Field | Before | After |\n|---|---|---|\n");
+            for (field, before, after) in &preset.fields {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    field_title(*field),
+                    before,
+                    after
+                ));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::SwitchMode;
+    use fcb1010_core::builder::DumpBuilder;
+
+    fn file(name: &str, dump: SysExMessage) -> ComparedFile {
+        ComparedFile {
+            name: name.to_string(),
+            dump,
+            metadata: ProjectMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn reports_what_differs() {
+        let before = file("before.syx", DumpBuilder::new().build().unwrap());
+        let mut after = file(
+            "after.syx",
+            DumpBuilder::new()
+                .pedal(2)
+                .pc(1, 9)
+                .global_channel(8, 3)
+                .switch_mode(1, SwitchMode::Toggle)
+                .build()
+                .unwrap(),
+        );
+        after.metadata.preset_mut(1).name = "Lead".to_string();
+
+        let report = CompareReport::new(&before, &after);
+        assert_eq!(report.globals.len(), 2);
+        assert_eq!(report.globals[0].0, "EXP A channel");
+        assert_eq!(report.globals[0].2, "3");
+        assert_eq!(report.presets.len(), 1);
+        assert_eq!(report.presets[0].index, 1);
+        assert_eq!(report.presets[0].name, "Lead");
+        assert_eq!(
+            report.presets[0].fields,
+            [(PresetField::ProgramChange(0), 0, 9)]
+        );
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("## Preset 2 – Lead"));
+        assert!(markdown.contains("| Switch 1 mode | Momentary | Toggle |"));
+
+        assert!(CompareReport::new(&before, &before).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::cli::parse_hex;
+use crate::widgets::NumberBase;
+use eframe::egui;
+use midir::{MidiInput, MidiInputConnection};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How many sent lines the console remembers
+pub const HISTORY_LENGTH: usize = 30;
+// Longer messages are shortened in the response list
+const SHOWN_BYTES: usize = 48;
+
+/// Bytes as spaced upper-case hex pairs; messages longer than a line are
+/// cut short with their length
+pub fn format_hex(bytes: &[u8]) -> String {
+    format_bytes(bytes, NumberBase::Hex)
+}
+
+/// Like [`format_hex`], with decimal numbers instead of hex if asked for
+pub fn format_bytes(bytes: &[u8], base: NumberBase) -> String {
+    let mut text = spell_bytes(&bytes[..bytes.len().min(SHOWN_BYTES)], base);
+    if bytes.len() > SHOWN_BYTES {
+        text.push_str(&format!(" … ({} bytes)", bytes.len()));
+    }
+    text
+}
+
+/// Every byte, as hex pairs ("F0 00 20") or decimal numbers ("240 0 32")
+pub fn spell_bytes(bytes: &[u8], base: NumberBase) -> String {
+    bytes
+        .iter()
+        .map(|byte| match base {
+            NumberBase::Decimal => byte.to_string(),
+            NumberBase::Hex => format!("{:02X}", byte),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads bytes typed in `base`: hex digits in pairs, or decimal numbers
+/// separated by spaces or commas, where "0x40" is taken as hex too
+pub fn parse_bytes(text: &str, base: NumberBase) -> Result<Vec<u8>, String> {
+    match base {
+        NumberBase::Hex => parse_hex(text),
+        NumberBase::Decimal => text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                base.parse(word)
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| format!("'{}' is not a byte (0-255)", word))
+            })
+            .collect(),
+    }
+}
+
+/// A line kept in the history or a snippet, which are stored as hex, as
+/// typed in `base`
+pub fn stored_as(line: &str, base: NumberBase) -> String {
+    match parse_hex(line) {
+        Ok(bytes) => spell_bytes(&bytes, base),
+        Err(_) => line.to_string(),
+    }
+}
+
+/// Puts `line` first in the history, dropping an older copy of it and
+/// whatever no longer fits
+pub fn remember(history: &mut Vec<String>, line: &str) {
+    history.retain(|old| old != line);
+    history.insert(0, line.to_string());
+    history.truncate(HISTORY_LENGTH);
+}
+
+/// A message that arrived while the console was listening
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// How long after listening started
+    pub at: Duration,
+    pub bytes: Vec<u8>,
+}
+
+/// Records every message arriving on an input port, so the answers to
+/// what the console sends can be read
+#[derive(Default)]
+pub struct ConsoleListener {
+    connection: Option<MidiInputConnection<()>>,
+    responses: Arc<Mutex<Vec<Response>>>,
+}
+
+impl ConsoleListener {
+    pub fn start(&mut self, port_index: usize, ctx: &egui::Context) -> Result<(), String> {
+        self.stop();
+
+        let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+        let port = midi_in
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+
+        let responses = Arc::clone(&self.responses);
+        let started = Instant::now();
+        let ctx = ctx.clone();
+        let connection = midi_in
+            .connect(
+                &port,
+                "midir-sysex-console",
+                move |_, message, _| {
+                    // Clock and active sensing would bury everything else
+                    if matches!(message, [0xf8] | [0xfe]) {
+                        return;
+                    }
+                    responses.lock().unwrap().push(Response {
+                        at: started.elapsed(),
+                        bytes: message.to_vec(),
+                    });
+                    ctx.request_repaint();
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.connection = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.responses.lock().unwrap().clear();
+    }
+
+    pub fn responses(&self) -> Vec<Response> {
+        self.responses.lock().unwrap().clone()
+    }
+}
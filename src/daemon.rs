@@ -0,0 +1,146 @@
+use crate::firmware::FirmwareSetting;
+use crate::log::Log;
+use crate::midi::{self, MidiOutputs};
+use crate::model::SysExMessage;
+use midir::MidiInput;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Where backups go unless `--backup-dir` says otherwise
+pub const DEFAULT_BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "fcb1010-";
+const BACKUP_EXTENSION: &str = "syx";
+
+/// How the resident backup mode runs
+#[derive(Debug, Clone)]
+pub struct DaemonOptions {
+    pub port: usize,
+    pub backup_dir: PathBuf,
+    pub interval: Duration,
+    /// Number of backups kept; older ones are deleted
+    pub keep: usize,
+    /// Sent at every interval to ask for a dump. The stock firmware cannot be
+    /// polled, so without it the daemon only stores dumps sent from the pedal.
+    pub request: Option<Vec<u8>>,
+    pub firmware: FirmwareSetting,
+}
+
+/// Runs until the MIDI input goes away, storing every valid dump received
+pub fn run(options: &DaemonOptions, log: &Log) -> Result<(), String> {
+    fs::create_dir_all(&options.backup_dir).map_err(|e| e.to_string())?;
+
+    let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+    let port = midi_in
+        .ports()
+        .get(options.port)
+        .cloned()
+        .ok_or("MIDI input port is not available")?;
+    log.push(format!(
+        "Backing up dumps from {} into {}",
+        midi_in.port_name(&port).unwrap_or_default(),
+        options.backup_dir.display()
+    ));
+
+    let (sender, receiver) = channel();
+    let _connection = midi_in
+        .connect(
+            &port,
+            "midir-read-input",
+            move |_, message, _| {
+                if message.first() == Some(&0xf0) {
+                    sender.send(message.to_vec()).ok();
+                }
+            },
+            (),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut outputs = MidiOutputs::default();
+    if options.request.is_some() {
+        let output = midi::matching_output(
+            &midi::input_port_names()?,
+            &midi::output_port_names()?,
+            options.port,
+        )
+        .ok_or("The MIDI port has no output to send the request to")?;
+        outputs.connect(output)?;
+    }
+
+    let mut next_request = Instant::now();
+    let mut received_since_request = true;
+    loop {
+        if Instant::now() >= next_request {
+            if !received_since_request {
+                log.push("No dump was received during the last interval");
+            }
+            if let Some(request) = &options.request {
+                match outputs.send_all(request) {
+                    Ok(_) => log.push("Requested a dump"),
+                    Err(e) => log.push(format!("Could not request a dump: {}", e)),
+                }
+            }
+            received_since_request = false;
+            next_request += options.interval;
+        }
+
+        let message = match receiver.recv_timeout(next_request - Instant::now()) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Err("MIDI input closed".to_string()),
+        };
+
+        if let Err(e) = SysExMessage::decode(&message, options.firmware.clone()) {
+            log.push(format!("Ignored invalid SysEx: {}", e));
+            continue;
+        }
+        received_since_request = true;
+
+        match store_backup(&options.backup_dir, &message) {
+            Ok(path) => log.push(format!("Stored backup {}", path.display())),
+            Err(e) => log.push(format!("Could not store backup: {}", e)),
+        }
+        if let Err(e) = rotate_backups(&options.backup_dir, options.keep, log) {
+            log.push(format!("Could not rotate backups: {}", e));
+        }
+    }
+}
+
+fn store_backup(dir: &Path, dump: &[u8]) -> std::io::Result<PathBuf> {
+    let name = format!(
+        "{}{}.{}",
+        BACKUP_PREFIX,
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        BACKUP_EXTENSION
+    );
+    let path = dir.join(name);
+    fs::write(&path, dump)?;
+    Ok(path)
+}
+
+/// The backups stored in `dir`, oldest first; timestamped names sort
+/// chronologically
+pub fn list_backups(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+fn rotate_backups(dir: &Path, keep: usize, log: &Log) -> std::io::Result<()> {
+    let mut backups = list_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    for old in backups.drain(..excess) {
+        fs::remove_file(&old)?;
+        log.push(format!("Removed old backup {}", old.display()));
+    }
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use eframe::egui;
+use midir::{MidiInput, MidiInputConnection};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// One control of the FCB1010 the test asks the user to operate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    /// Footswitch 1-10, numbered from 0
+    Switch(usize),
+    PedalA,
+    PedalB,
+}
+
+pub const CONTROLS: [Control; 12] = [
+    Control::Switch(0),
+    Control::Switch(1),
+    Control::Switch(2),
+    Control::Switch(3),
+    Control::Switch(4),
+    Control::Switch(5),
+    Control::Switch(6),
+    Control::Switch(7),
+    Control::Switch(8),
+    Control::Switch(9),
+    Control::PedalA,
+    Control::PedalB,
+];
+
+/// How long a footswitch step keeps collecting after its first message, so
+/// every message of the preset lands in it
+const SETTLE: Duration = Duration::from_millis(600);
+/// A pedal whose controller moved at least this far swept its whole travel
+const FULL_TRAVEL: u8 = 100;
+
+impl Control {
+    pub fn label(self) -> String {
+        match self {
+            Control::Switch(switch) => format!("Footswitch {}", switch + 1),
+            Control::PedalA => "Expression pedal A".to_string(),
+            Control::PedalB => "Expression pedal B".to_string(),
+        }
+    }
+
+    pub fn instruction(self) -> String {
+        match self {
+            Control::Switch(_) => format!("Press {} once.", self.label().to_lowercase()),
+            _ => format!(
+                "Move {} from heel to toe and back, then click Next.",
+                self.label().to_lowercase()
+            ),
+        }
+    }
+}
+
+/// What arrived while one control was being tested
+#[derive(Debug, Clone, Default)]
+pub struct ControlResult {
+    pub tested: bool,
+    pub messages: Vec<Vec<u8>>,
+    first: Option<Instant>,
+    /// Lowest and highest value seen for every controller number
+    controllers: Vec<(u8, u8, u8)>,
+}
+
+impl ControlResult {
+    pub fn has_output(&self) -> bool {
+        !self.messages.is_empty()
+    }
+
+    pub fn verdict(&self, control: Control) -> String {
+        if !self.tested {
+            return "not tested".to_string();
+        }
+        if !self.has_output() {
+            return "no output".to_string();
+        }
+        match control {
+            Control::Switch(_) => self
+                .messages
+                .iter()
+                .map(|message| describe(message))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Control::PedalA | Control::PedalB => {
+                let Some(&(number, low, high)) = self
+                    .controllers
+                    .iter()
+                    .max_by_key(|(_, low, high)| high - low)
+                else {
+                    return format!("{} messages but no control change", self.messages.len());
+                };
+                let travel = if high - low >= FULL_TRAVEL {
+                    "full travel"
+                } else {
+                    "partial travel"
+                };
+                format!("CC {} from {} to {} ({})", number, low, high, travel)
+            }
+        }
+    }
+
+    fn record(&mut self, message: &[u8]) {
+        self.first.get_or_insert_with(Instant::now);
+        if let [status, number, value] = *message {
+            if status & 0xf0 == 0xb0 {
+                match self.controllers.iter_mut().find(|(n, _, _)| *n == number) {
+                    Some((_, low, high)) => {
+                        *low = (*low).min(value);
+                        *high = (*high).max(value);
+                    }
+                    None => self.controllers.push((number, value, value)),
+                }
+            }
+        }
+        self.messages.push(message.to_vec());
+    }
+}
+
+fn describe(message: &[u8]) -> String {
+    let channel = (message[0] & 0x0f) + 1;
+    match (message[0] & 0xf0, message) {
+        (0xc0, &[_, program]) => format!("PC {} (ch {})", program, channel),
+        (0xb0, &[_, number, value]) => format!("CC {} = {} (ch {})", number, value, channel),
+        (0x90, &[_, note, _]) => format!("note {} (ch {})", note, channel),
+        _ => message
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+#[derive(Default)]
+struct TestState {
+    step: Option<usize>,
+    results: Vec<ControlResult>,
+}
+
+/// Walks the user through every footswitch and pedal while listening to an
+/// input port, recording which controls produced output
+#[derive(Default)]
+pub struct DeviceTest {
+    connection: Option<MidiInputConnection<()>>,
+    state: Arc<Mutex<TestState>>,
+}
+
+impl DeviceTest {
+    pub fn start(&mut self, port_index: usize, ctx: &egui::Context) -> Result<(), String> {
+        self.stop();
+
+        let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+        let port = midi_in
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+
+        *self.state.lock().unwrap() = TestState {
+            step: Some(0),
+            results: vec![ControlResult::default(); CONTROLS.len()],
+        };
+        let state = Arc::clone(&self.state);
+        let ctx = ctx.clone();
+        let connection = midi_in
+            .connect(
+                &port,
+                "midir-device-test",
+                move |_, message, _| {
+                    // Clock, active sensing and SysEx say nothing about the controls
+                    if message.is_empty() || message[0] >= 0xf0 {
+                        return;
+                    }
+                    let mut state = state.lock().unwrap();
+                    if let Some(step) = state.step {
+                        state.results[step].record(message);
+                        ctx.request_repaint();
+                    }
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.connection = None;
+        self.state.lock().unwrap().step = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// The control being tested
+    pub fn current(&self) -> Option<(usize, Control)> {
+        let step = self.state.lock().unwrap().step?;
+        Some((step, CONTROLS[step]))
+    }
+
+    /// Finishes the current control, with or without output, and moves on;
+    /// the test stops after the last one
+    pub fn next(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        let Some(step) = state.step else {
+            return;
+        };
+        state.results[step].tested = true;
+        state.step = Some(step + 1).filter(|&next| next < CONTROLS.len());
+        let finished = state.step.is_none();
+        drop(state);
+        if finished {
+            self.connection = None;
+        }
+    }
+
+    /// Moves past a footswitch once its messages have settled; returns how
+    /// long to wait before checking again
+    pub fn poll(&mut self) -> Option<Duration> {
+        let (step, first) = {
+            let state = self.state.lock().unwrap();
+            let step = state.step?;
+            (step, state.results[step].first)
+        };
+        if !matches!(CONTROLS[step], Control::Switch(_)) {
+            return None;
+        }
+        let elapsed = first?.elapsed();
+        if elapsed >= SETTLE {
+            self.next();
+            None
+        } else {
+            Some(SETTLE - elapsed)
+        }
+    }
+
+    pub fn results(&self) -> Vec<ControlResult> {
+        self.state.lock().unwrap().results.clone()
+    }
+}
@@ -0,0 +1,136 @@
+use midir::{MidiInput, MidiOutput};
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+// 0x7d is the manufacturer ID reserved for non-commercial use, so no real
+// device should react to the probe
+const PROBE_MANUFACTURER: u8 = 0x7d;
+const ROUND_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Round trip times measured through a MIDI loopback or thru connection
+#[derive(Debug, Clone)]
+pub struct LatencyReport {
+    pub samples: Vec<Duration>,
+    pub lost: usize,
+}
+
+impl LatencyReport {
+    pub fn min(&self) -> Option<Duration> {
+        self.samples.iter().min().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+    }
+
+    /// Standard deviation of the round trip times
+    pub fn jitter(&self) -> Option<Duration> {
+        let mean = self.mean()?.as_secs_f64();
+        let variance = self
+            .samples
+            .iter()
+            .map(|s| (s.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / self.samples.len() as f64;
+        Some(Duration::from_secs_f64(variance.sqrt()))
+    }
+
+    pub fn summary(&self) -> String {
+        match (self.min(), self.mean(), self.max(), self.jitter()) {
+            (Some(min), Some(mean), Some(max), Some(jitter)) => format!(
+                "{} of {} probes returned: min {:.2} ms, avg {:.2} ms, max {:.2} ms, jitter {:.2} ms",
+                self.samples.len(),
+                self.samples.len() + self.lost,
+                millis(min),
+                millis(mean),
+                millis(max),
+                millis(jitter)
+            ),
+            _ => format!(
+                "None of the {} probes came back; check the loopback or MIDI thru cable",
+                self.lost
+            ),
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Sends `rounds` short SysEx probes to the output port and times how long
+/// each takes to arrive back on the input port. Blocks until done.
+pub fn measure_latency(
+    output_port: usize,
+    input_port: usize,
+    rounds: u8,
+) -> Result<LatencyReport, String> {
+    let midi_in = MidiInput::new("Latency Input").map_err(|e| e.to_string())?;
+    let in_port = midi_in
+        .ports()
+        .get(input_port)
+        .cloned()
+        .ok_or("Input port is no longer available")?;
+
+    let midi_out = MidiOutput::new("Latency Output").map_err(|e| e.to_string())?;
+    let out_port = midi_out
+        .ports()
+        .get(output_port)
+        .cloned()
+        .ok_or("Output port is no longer available")?;
+
+    let (sender, receiver) = channel();
+    let _input = midi_in
+        .connect(
+            &in_port,
+            "latency-probe-in",
+            move |_, message, _| {
+                if message.len() == 4 && message[0] == 0xf0 && message[1] == PROBE_MANUFACTURER {
+                    sender.send((message[2], Instant::now())).ok();
+                }
+            },
+            (),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut output = midi_out
+        .connect(&out_port, "latency-probe-out")
+        .map_err(|e| e.to_string())?;
+
+    let mut report = LatencyReport {
+        samples: Vec::new(),
+        lost: 0,
+    };
+
+    for sequence in 0..rounds.min(0x7f) {
+        let sent = Instant::now();
+        output
+            .send(&[0xf0, PROBE_MANUFACTURER, sequence, 0xf7])
+            .map_err(|e| e.to_string())?;
+
+        let deadline = sent + ROUND_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(remaining) {
+                Ok((seq, received)) if seq == sequence => {
+                    report.samples.push(received - sent);
+                    break;
+                }
+                // A late echo of an earlier probe
+                Ok(_) => continue,
+                Err(_) => {
+                    report.lost += 1;
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,123 @@
+use crate::model::SysExMessage;
+use crate::project::{PresetMetadata, ProjectMetadata};
+use std::collections::BTreeSet;
+
+/// Which presets differ from the project as it was last saved and from the
+/// dump the device was last sent or sent us
+pub struct DirtyState {
+    saved: SysExMessage,
+    saved_labels: Vec<PresetMetadata>,
+    /// `None` until a dump went to or came from the device this session,
+    /// since what the device holds is not known before
+    sent: Option<SysExMessage>,
+    /// The last received dump seen, to notice a new one arriving
+    received: Option<SysExMessage>,
+    /// Preset indexes whose values, name or notes changed since the save
+    pub unsaved: BTreeSet<usize>,
+    pub unsent: BTreeSet<usize>,
+    /// The global channels or switch modes changed since the save
+    pub globals_unsaved: bool,
+    pub globals_unsent: bool,
+}
+
+impl DirtyState {
+    pub fn new(saved: &SysExMessage, metadata: &ProjectMetadata) -> Self {
+        Self {
+            saved: saved.clone(),
+            saved_labels: metadata.presets.clone(),
+            sent: None,
+            received: None,
+            unsaved: BTreeSet::new(),
+            unsent: BTreeSet::new(),
+            globals_unsaved: false,
+            globals_unsent: false,
+        }
+    }
+
+    /// Makes the project as it is now the saved state
+    pub fn mark_saved(&mut self, message: &SysExMessage, metadata: &ProjectMetadata) {
+        self.saved = message.clone();
+        self.saved_labels = metadata.presets.clone();
+    }
+
+    /// Makes `message` what the device holds
+    pub fn mark_sent(&mut self, message: &SysExMessage) {
+        self.sent = Some(message.clone());
+    }
+
+    /// Whether a dump went to or came from the device this session
+    pub fn device_known(&self) -> bool {
+        self.sent.is_some()
+    }
+
+    /// Whether the dump differs from what the device holds; unknown counts
+    /// as different
+    pub fn needs_send(&self) -> bool {
+        self.sent.is_none() || self.globals_unsent || !self.unsent.is_empty()
+    }
+
+    pub fn needs_save(&self) -> bool {
+        self.globals_unsaved || !self.unsaved.is_empty()
+    }
+
+    /// Compares the edited project with both states again. A dump newly
+    /// received from the device becomes what it holds.
+    pub fn refresh(
+        &mut self,
+        message: &SysExMessage,
+        metadata: &ProjectMetadata,
+        received: Option<&SysExMessage>,
+    ) {
+        if received != self.received.as_ref() {
+            self.received = received.cloned();
+            if let Some(received) = received {
+                self.sent = Some(received.clone());
+            }
+        }
+
+        let label = |labels: &[PresetMetadata], index: usize| {
+            labels
+                .get(index)
+                .map(|labels| (labels.name.clone(), labels.notes.clone()))
+                .unwrap_or_default()
+        };
+        self.unsaved = (0..message.presets().len())
+            .filter(|&index| {
+                message.preset(index) != self.saved.preset(index)
+                    || label(&metadata.presets, index) != label(&self.saved_labels, index)
+            })
+            .collect();
+        self.globals_unsaved = !same_globals(message, &self.saved);
+
+        (self.unsent, self.globals_unsent) = match &self.sent {
+            Some(sent) => (
+                (0..message.presets().len())
+                    .filter(|&index| message.preset(index) != sent.preset(index))
+                    .collect(),
+                !same_globals(message, sent),
+            ),
+            None => (BTreeSet::new(), false),
+        };
+    }
+
+    /// "7 presets unsaved, 12 not yet sent", or `None` when everything is
+    /// saved and sent
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if !self.unsaved.is_empty() {
+            parts.push(format!("{} presets unsaved", self.unsaved.len()));
+        } else if self.globals_unsaved {
+            parts.push("global settings unsaved".to_string());
+        }
+        if !self.unsent.is_empty() {
+            parts.push(format!("{} not yet sent", self.unsent.len()));
+        } else if self.globals_unsent {
+            parts.push("global settings not yet sent".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+fn same_globals(a: &SysExMessage, b: &SysExMessage) -> bool {
+    a.global_channels() == b.global_channels() && a.switch_modes() == b.switch_modes()
+}
@@ -0,0 +1,58 @@
+use crate::model::{bank_and_pedal, PresetField};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A change made to the edited model
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelEvent {
+    /// A single value inside a preset was edited
+    PresetChanged {
+        bank: usize,
+        pedal: usize,
+        field: PresetField,
+    },
+    /// A whole preset was overwritten, e.g. copied from another model
+    PresetReplaced { bank: usize, pedal: usize },
+    /// A global channel or switch mode was edited
+    GlobalsChanged,
+    /// The whole dump was replaced by a received or loaded one
+    DumpReplaced,
+}
+
+impl ModelEvent {
+    pub fn preset_changed(index: usize, field: PresetField) -> Self {
+        let (bank, pedal) = bank_and_pedal(index);
+        ModelEvent::PresetChanged { bank, pedal, field }
+    }
+
+    pub fn preset_replaced(index: usize) -> Self {
+        let (bank, pedal) = bank_and_pedal(index);
+        ModelEvent::PresetReplaced { bank, pedal }
+    }
+}
+
+/// Broadcasts model changes to any number of subscribers.
+///
+/// Cloning the handle shares the subscriber list, so it can be moved into
+/// background threads that replace the model.
+#[derive(Clone, Default)]
+pub struct ModelEvents {
+    subscribers: Arc<Mutex<Vec<Sender<ModelEvent>>>>,
+}
+
+impl ModelEvents {
+    /// Returns a receiver that gets every event emitted from now on
+    pub fn subscribe(&self) -> Receiver<ModelEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn emit(&self, event: ModelEvent) {
+        // Subscribers that dropped their receiver are forgotten
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+use crate::model::{bank_and_pedal, ExpressionPart, Preset, PresetField, SysExMessage};
+use crate::values::Value7;
+use std::collections::BTreeSet;
+
+/// The controller fields of expression pedals A and B
+const CONTROLLERS: [PresetField; 2] = [
+    PresetField::ExpressionA(ExpressionPart::Controller),
+    PresetField::ExpressionB(ExpressionPart::Controller),
+];
+
+/// One controller number for each expression pedal across many presets,
+/// the most common bulk change
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionDefaults {
+    /// For pedal A and B; `None` leaves that pedal alone
+    pub controllers: [Option<Value7>; 2],
+    /// The banks to change; empty means all of them
+    pub banks: BTreeSet<usize>,
+    /// Also switch the pedal on in presets where it is off
+    pub switch_on: bool,
+}
+
+impl Default for ExpressionDefaults {
+    fn default() -> Self {
+        Self {
+            controllers: [Some(Value7::clamped(7)), None],
+            banks: BTreeSet::new(),
+            switch_on: false,
+        }
+    }
+}
+
+impl ExpressionDefaults {
+    /// The presets that change, by index. Presets in `locked_banks` are
+    /// skipped.
+    pub fn plan(
+        &self,
+        message: &SysExMessage,
+        locked_banks: &BTreeSet<usize>,
+    ) -> Vec<(usize, Preset)> {
+        message
+            .presets()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                let (bank, _) = bank_and_pedal(*index);
+                (self.banks.is_empty() || self.banks.contains(&bank))
+                    && !locked_banks.contains(&bank)
+            })
+            .filter_map(|(index, preset)| {
+                let mut changed = *preset;
+                for (field, controller) in CONTROLLERS.into_iter().zip(self.controllers) {
+                    if let Some(controller) = controller {
+                        changed.set(field, controller);
+                        if self.switch_on {
+                            changed.set_enabled(field.slot(), true);
+                        }
+                    }
+                }
+                (changed != *preset).then_some((index, changed))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,27 @@
+use crate::model::{SysExMessage, PEDALS_PER_BANK};
+use fcb1010_core::builder::DumpBuilder;
+
+// Controllers the expression pedals send after a factory reset
+const EXPRESSION_A_CONTROLLER: u8 = 27;
+const EXPRESSION_B_CONTROLLER: u8 = 7;
+
+/// The dump of a freshly reset FCB1010, as the manual lists it: the pedals
+/// of banks 00-09 send program changes 1-100 in order, expression pedal A
+/// sweeps CC 27 and B the volume (CC 7), everything on channel 1 and every
+/// other slot switched off
+pub fn factory_defaults() -> SysExMessage {
+    let mut builder = DumpBuilder::new();
+    for bank in 0..10 {
+        builder = builder.bank(bank);
+        for pedal in 1..=PEDALS_PER_BANK {
+            builder = builder
+                .pedal(pedal)
+                .pc(1, (bank * PEDALS_PER_BANK + pedal - 1) as u8)
+                .expression_a(EXPRESSION_A_CONTROLLER, 0, 127)
+                .expression_b(EXPRESSION_B_CONTROLLER, 0, 127);
+        }
+    }
+    builder
+        .build()
+        .expect("the factory layout fits the FCB1010")
+}
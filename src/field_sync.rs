@@ -0,0 +1,112 @@
+use crate::model::{Preset, PresetField, SwitchMode, SysExMessage, PEDALS_PER_BANK, SLOTS};
+use crate::values::Channel;
+use std::ops::Range;
+
+/// A group of settings that can be taken from a reference dump on its own,
+/// e.g. to give several old dumps the same channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncCategory {
+    /// The global channel of every function
+    #[default]
+    Channels,
+    SwitchModes,
+    ProgramChanges,
+    ControlChanges,
+    /// Controller, sweep and on/off of both expression pedals
+    Expression,
+    Note,
+}
+
+impl SyncCategory {
+    pub const ALL: [SyncCategory; 6] = [
+        SyncCategory::Channels,
+        SyncCategory::SwitchModes,
+        SyncCategory::ProgramChanges,
+        SyncCategory::ControlChanges,
+        SyncCategory::Expression,
+        SyncCategory::Note,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncCategory::Channels => "Channel assignments",
+            SyncCategory::SwitchModes => "Relay switch modes",
+            SyncCategory::ProgramChanges => "Program changes",
+            SyncCategory::ControlChanges => "Control changes",
+            SyncCategory::Expression => "Expression pedals",
+            SyncCategory::Note => "Notes",
+        }
+    }
+
+    /// The preset slots the category covers; none for the global settings
+    fn slots(self) -> Range<usize> {
+        match self {
+            SyncCategory::Channels | SyncCategory::SwitchModes => 0..0,
+            SyncCategory::ProgramChanges => 0..5,
+            SyncCategory::ControlChanges => 5..7,
+            SyncCategory::Expression => 7..9,
+            SyncCategory::Note => 9..SLOTS,
+        }
+    }
+}
+
+/// What adopting a category from a reference dump changes
+#[derive(Debug, Clone, Default)]
+pub struct FieldSync {
+    /// The presets that change, by index, with only the category's slots
+    /// taken from the reference
+    pub presets: Vec<(usize, Preset)>,
+    pub channels: Option<[Channel; 10]>,
+    pub switch_modes: Option<[SwitchMode; 2]>,
+    /// The preset fields whose value changes, for highlighting them
+    pub changed: Vec<(usize, PresetField)>,
+}
+
+impl FieldSync {
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty() && self.channels.is_none() && self.switch_modes.is_none()
+    }
+}
+
+/// Takes `category` from `reference` into a copy of `current`, leaving every
+/// other field alone. Presets in banks `skip` rejects keep theirs.
+pub fn adopt(
+    category: SyncCategory,
+    reference: &SysExMessage,
+    current: &SysExMessage,
+    skip: impl Fn(usize) -> bool,
+) -> FieldSync {
+    let mut sync = FieldSync::default();
+    match category {
+        SyncCategory::Channels => {
+            sync.channels = (reference.global_channels() != current.global_channels())
+                .then(|| *reference.global_channels());
+        }
+        SyncCategory::SwitchModes => {
+            sync.switch_modes = (reference.switch_modes() != current.switch_modes())
+                .then(|| *reference.switch_modes());
+        }
+        _ => {}
+    }
+
+    let slots = category.slots();
+    for (index, preset) in current.presets().iter().enumerate() {
+        if slots.is_empty() || skip(index / PEDALS_PER_BANK) {
+            continue;
+        }
+        let source = reference.preset(index);
+        let mut adopted = *preset;
+        for field in PresetField::ALL {
+            if slots.contains(&field.slot()) && adopted.set(field, source.value(field)) {
+                sync.changed.push((index, field));
+            }
+        }
+        for slot in slots.clone() {
+            adopted.set_enabled(slot, source.is_enabled(slot));
+        }
+        if adopted != *preset {
+            sync.presets.push((index, adopted));
+        }
+    }
+    sync
+}
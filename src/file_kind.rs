@@ -0,0 +1,47 @@
+use crate::inspector::is_fcb1010_dump;
+
+/// What a file passed on the command line or dropped onto the window holds,
+/// judged by its content rather than its extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    /// A project (or an older bare dump) saved as JSON
+    Project,
+    /// A raw FCB1010 SysEx dump
+    Dump,
+    /// Any other SysEx, opened in the inspector
+    ForeignSysEx,
+    /// A `.fcbz` bundle, which is a zip archive
+    Bundle,
+    /// A Standard MIDI File, which may hold dumps recorded from the pedal
+    MidiFile,
+}
+
+impl FileKind {
+    /// Whether opening the file replaces the edited project
+    pub fn replaces_project(self) -> bool {
+        matches!(self, FileKind::Project | FileKind::Dump | FileKind::Bundle)
+    }
+}
+
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+
+pub fn sniff(data: &[u8]) -> Option<FileKind> {
+    if data.first() == Some(&0xf0) {
+        return Some(if is_fcb1010_dump(data) {
+            FileKind::Dump
+        } else {
+            FileKind::ForeignSysEx
+        });
+    }
+    if data.starts_with(&ZIP_MAGIC) {
+        return Some(FileKind::Bundle);
+    }
+    if data.starts_with(b"MThd") {
+        return Some(FileKind::MidiFile);
+    }
+    let text = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    match text.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => Some(FileKind::Project),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use crate::inspector::{is_fcb1010_dump, ForeignSysEx};
+use crate::midi::OutputConnection;
+use crate::windows_midi::HighResolutionTimer;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A firmware update file, split into the SysEx messages it is sent as
+pub struct FirmwareImage {
+    pub name: String,
+    pub size: usize,
+    pub messages: Vec<Vec<u8>>,
+    /// SHA-256 of the whole file, to compare with the published checksum
+    pub sha256: String,
+}
+
+impl FirmwareImage {
+    /// Reads the file and refuses anything that is not cleanly framed SysEx
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        if data.is_empty() {
+            return Err("The file is empty".to_string());
+        }
+        if is_fcb1010_dump(&data) {
+            return Err("This is a preset dump, not a firmware image".to_string());
+        }
+
+        let file = ForeignSysEx::new(path, data);
+        let messages: Vec<Vec<u8>> = file.messages().iter().map(|m| m.to_vec()).collect();
+        if messages.iter().any(|m| m.last() != Some(&0xf7)) {
+            return Err("The last SysEx message is not terminated; the file is truncated".into());
+        }
+        let framed: usize = messages.iter().map(Vec::len).sum();
+        if framed != file.data.len() {
+            return Err(format!(
+                "{} bytes are outside SysEx messages; this is not a SysEx firmware file",
+                file.data.len() - framed
+            ));
+        }
+
+        Ok(Self {
+            sha256: Sha256::digest(&file.data)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            name: file.name,
+            size: file.data.len(),
+            messages,
+        })
+    }
+
+    /// Compares with a checksum as published, ignoring case and spaces
+    pub fn matches_checksum(&self, expected: &str) -> bool {
+        let expected: String = expected
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        expected == self.sha256
+    }
+}
+
+/// How far a running update has got
+#[derive(Debug, Clone, Default)]
+pub struct UpdateProgress {
+    pub sent: usize,
+    pub total: usize,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+/// Sends the messages one by one, pausing after each so the bootloader
+/// has time to write it. Runs on the calling thread.
+pub fn send_image(
+    port_index: usize,
+    messages: &[Vec<u8>],
+    pause: Duration,
+    progress: &Arc<Mutex<UpdateProgress>>,
+) {
+    // Without it Windows stretches every pause to its 15.6 ms timer tick
+    let _timer = HighResolutionTimer::request();
+    let result = OutputConnection::open(port_index).and_then(|mut output| {
+        for (i, message) in messages.iter().enumerate() {
+            output
+                .send(message)
+                .map_err(|e| format!("Message {} failed: {}", i + 1, e))?;
+            progress.lock().unwrap().sent = i + 1;
+            std::thread::sleep(pause);
+        }
+        Ok(())
+    });
+
+    let mut progress = progress.lock().unwrap();
+    progress.error = result.err();
+    progress.finished = true;
+}
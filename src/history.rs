@@ -0,0 +1,210 @@
+use crate::events::{ModelEvent, ModelEvents};
+use crate::model::SLOTS;
+use crate::model::{PresetField, SysExMessage, PEDALS_PER_BANK};
+use crate::project::ProjectMetadata;
+use crate::spec::{field_title, GLOBAL_FUNCTIONS};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::mpsc::Receiver;
+
+// Older entries are dropped so the project file does not grow without bound
+const MAX_ENTRIES: usize = 10_000;
+
+// Edits of the same value this close together (e.g. a drag) make one entry
+const COALESCE_SECONDS: i64 = 3;
+
+/// One change to the project, as saved in the project file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// RFC 3339 local time
+    pub time: String,
+    pub author: String,
+    pub what: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Turns model events into history entries by comparing the model with
+/// what it looked like before the event
+pub struct AuditLog {
+    events: Receiver<ModelEvent>,
+    snapshot: SysExMessage,
+}
+
+impl AuditLog {
+    pub fn new(events: &ModelEvents, model: &SysExMessage) -> Self {
+        Self {
+            events: events.subscribe(),
+            snapshot: model.clone(),
+        }
+    }
+
+    /// Forgets pending events, e.g. after a project was loaded
+    pub fn reset(&mut self, model: &SysExMessage) {
+        while self.events.try_recv().is_ok() {}
+        self.snapshot = model.clone();
+    }
+
+    /// Appends entries for the events since the last call and stamps the
+    /// presets whose values changed
+    pub fn record(&mut self, model: &SysExMessage, author: &str, metadata: &mut ProjectMetadata) {
+        let mut changes = Vec::new();
+        let mut touched = BTreeSet::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ModelEvent::PresetChanged { bank, pedal, field } => {
+                    let index = bank * PEDALS_PER_BANK + pedal;
+                    let preset = self.preset_changes(model, index, &[field]);
+                    if !preset.is_empty() {
+                        touched.insert(index);
+                    }
+                    changes.extend(preset);
+                }
+                ModelEvent::PresetReplaced { bank, pedal } => {
+                    let index = bank * PEDALS_PER_BANK + pedal;
+                    let mut preset = self.preset_changes(model, index, &PresetField::ALL);
+                    preset.extend(self.slot_changes(model, index));
+                    if !preset.is_empty() {
+                        touched.insert(index);
+                    }
+                    changes.extend(preset);
+                }
+                ModelEvent::GlobalsChanged => changes.extend(self.global_changes(model)),
+                ModelEvent::DumpReplaced => {
+                    let fields = self.snapshot.changed_fields(model);
+                    let changed = fields.len();
+                    touched.extend(fields.into_iter().map(|(index, _)| index));
+                    changes.push((
+                        "Whole dump replaced".to_string(),
+                        String::new(),
+                        format!("{} preset values changed", changed),
+                    ));
+                }
+            }
+            self.snapshot = model.clone();
+        }
+
+        let now = Local::now();
+        for index in touched {
+            metadata.preset_mut(index).modified = Some(now.to_rfc3339());
+        }
+        let history = &mut metadata.history;
+        for (what, old, new) in changes {
+            if let Some(last) = history.last_mut() {
+                let recent = DateTime::parse_from_rfc3339(&last.time)
+                    .is_ok_and(|time| (now.fixed_offset() - time).num_seconds() < COALESCE_SECONDS);
+                if recent && last.what == what && last.author == author {
+                    last.new = new;
+                    last.time = now.to_rfc3339();
+                    continue;
+                }
+            }
+            history.push(HistoryEntry {
+                time: now.to_rfc3339(),
+                author: author.to_string(),
+                what,
+                old,
+                new,
+            });
+        }
+        if history.len() > MAX_ENTRIES {
+            history.drain(..history.len() - MAX_ENTRIES);
+        }
+    }
+
+    fn preset_changes(
+        &self,
+        model: &SysExMessage,
+        index: usize,
+        fields: &[PresetField],
+    ) -> Vec<(String, String, String)> {
+        let (before, after) = (self.snapshot.preset(index), model.preset(index));
+        fields
+            .iter()
+            .filter(|&&field| before.get(field) != after.get(field))
+            .map(|&field| {
+                (
+                    format!("Preset {} {}", index + 1, field_title(field)),
+                    before.get(field).to_string(),
+                    after.get(field).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn slot_changes(&self, model: &SysExMessage, index: usize) -> Vec<(String, String, String)> {
+        let (before, after) = (self.snapshot.preset(index), model.preset(index));
+        let state = |enabled: bool| if enabled { "on" } else { "off" }.to_string();
+        (0..SLOTS)
+            .filter(|&slot| before.is_enabled(slot) != after.is_enabled(slot))
+            .map(|slot| {
+                (
+                    format!("Preset {} {}", index + 1, GLOBAL_FUNCTIONS[slot]),
+                    state(before.is_enabled(slot)),
+                    state(after.is_enabled(slot)),
+                )
+            })
+            .collect()
+    }
+
+    fn global_changes(&self, model: &SysExMessage) -> Vec<(String, String, String)> {
+        let mut changes = Vec::new();
+        let channels = self
+            .snapshot
+            .global_channels()
+            .iter()
+            .zip(model.global_channels());
+        for (function, (before, after)) in channels.enumerate() {
+            if before != after {
+                changes.push((
+                    format!("{} channel", GLOBAL_FUNCTIONS[function]),
+                    before.number().to_string(),
+                    after.number().to_string(),
+                ));
+            }
+        }
+        let modes = self
+            .snapshot
+            .switch_modes()
+            .iter()
+            .zip(model.switch_modes());
+        for (switch, (before, after)) in modes.enumerate() {
+            if before != after {
+                changes.push((
+                    format!("Switch {} mode", switch + 1),
+                    before.label().to_string(),
+                    after.label().to_string(),
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// An RFC 3339 time as it is shown in the editor, or the text itself when
+/// it does not parse
+pub fn short_time(time: &str) -> String {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| time.to_string())
+}
+
+/// The history as CSV, oldest first
+pub fn to_csv(history: &[HistoryEntry]) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    let mut csv = String::from("time,author,what,old,new\n");
+    for entry in history {
+        let fields = [
+            &entry.time,
+            &entry.author,
+            &entry.what,
+            &entry.old,
+            &entry.new,
+        ];
+        let row: Vec<String> = fields.iter().map(|field| quote(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,208 @@
+use crate::model::{packed_offset, PresetField, SysExMessage, ValueLocation};
+use crate::spec::{self, GLOBAL_CHANNEL, GLOBAL_FUNCTIONS};
+use crate::values::{Channel, Value7};
+use serde::{Deserialize, Serialize};
+
+/// What an import does with values outside the range the FCB1010 accepts
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum RangePolicy {
+    /// Replace the value with the nearest valid one
+    #[default]
+    Clamp,
+    /// Keep the value the editor had before the import
+    SkipField,
+    /// Refuse the whole import
+    Abort,
+}
+
+impl RangePolicy {
+    pub const ALL: [RangePolicy; 3] = [
+        RangePolicy::Clamp,
+        RangePolicy::SkipField,
+        RangePolicy::Abort,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RangePolicy::Clamp => "Clamp to the valid range",
+            RangePolicy::SkipField => "Keep the current value",
+            RangePolicy::Abort => "Refuse the import",
+        }
+    }
+}
+
+/// One value of an import that was out of range
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub location: String,
+    /// Where the value's bits are in the raw dump, if it came from one
+    pub offset: Option<usize>,
+    pub found: u8,
+    pub max: u8,
+    /// What the value became; `None` when the import was refused
+    pub result: Option<u8>,
+}
+
+/// Every adjustment made to one import
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub source: String,
+    pub policy: RangePolicy,
+    pub adjustments: Vec<Adjustment>,
+    /// The dump as read, to look at the bytes of an adjustment
+    pub raw: Option<Vec<u8>>,
+}
+
+impl ImportReport {
+    pub fn aborted(&self) -> bool {
+        self.policy == RangePolicy::Abort && !self.adjustments.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let count = self.adjustments.len();
+        if self.aborted() {
+            format!("Refused {}: {} value(s) out of range", self.source, count)
+        } else {
+            format!("Imported {} with {} value(s) adjusted", self.source, count)
+        }
+    }
+}
+
+/// Checks every preset field and global channel of `imported` against the
+/// ranges in the spec table and applies `policy` to the ones outside them.
+/// Values too big for their type were clamped when the dump was decoded or
+/// the file loaded; the message keeps what was found, and that is checked.
+/// `current` is the editor content the import replaces. `raw` is the dump
+/// `imported` was just decoded from, if it was, to point at the bytes.
+/// Returns `None` when every value was in range.
+pub fn check_import(
+    source: &str,
+    imported: &mut SysExMessage,
+    raw: Option<&[u8]>,
+    current: &SysExMessage,
+    policy: RangePolicy,
+) -> Option<ImportReport> {
+    let map = imported.firmware().memory_map();
+    let clamped = imported.take_out_of_range();
+    let found_at = |location: ValueLocation| {
+        clamped
+            .iter()
+            .find(|(at, _)| *at == location)
+            .map(|(_, error)| error.value)
+    };
+    let raw_offset = |offset: usize| raw.map(|_| packed_offset(offset));
+    let mut adjustments = Vec::new();
+    let mut adjust =
+        |location: String, offset: Option<usize>, found: u8, max: u8, current: u8| -> Option<u8> {
+            let result = match policy {
+                RangePolicy::Clamp => Some(max),
+                RangePolicy::SkipField => Some(current.min(max)),
+                RangePolicy::Abort => None,
+            };
+            adjustments.push(Adjustment {
+                location,
+                offset,
+                found,
+                max,
+                result,
+            });
+            result
+        };
+
+    for index in 0..imported.presets().len() {
+        for field in PresetField::ALL {
+            let offset = index * map.preset_size + map.offset_of(field);
+            let found = found_at(ValueLocation::Preset(index, field))
+                .unwrap_or_else(|| imported.preset(index).get(field));
+            let max = spec::preset_field(field).max;
+            if found <= max {
+                continue;
+            }
+            let location = format!("Preset {} {}", index + 1, spec::field_title(field));
+            let kept = current.preset(index).get(field);
+            if let Some(value) = adjust(location, raw_offset(offset), found, max, kept) {
+                imported.set_preset_field(index, field, Value7::clamped(value));
+            }
+        }
+    }
+    for (function, name) in GLOBAL_FUNCTIONS.iter().enumerate() {
+        let offset = map.global_channels + function;
+        let found = found_at(ValueLocation::GlobalChannel(function))
+            .unwrap_or_else(|| imported.global_channels()[function].get());
+        let max = GLOBAL_CHANNEL.max;
+        if found <= max {
+            continue;
+        }
+        let location = format!("Global channel {}", name);
+        let kept = current.global_channels()[function].get();
+        if let Some(value) = adjust(location, raw_offset(offset), found, max, kept) {
+            imported.set_global_channel(function, Channel::clamped(value));
+        }
+    }
+
+    if adjustments.is_empty() {
+        return None;
+    }
+    Some(ImportReport {
+        source: source.to_string(),
+        policy,
+        adjustments,
+        raw: raw.map(<[u8]>::to_vec),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb1010_core::builder::DumpBuilder;
+
+    fn file_with_bad_values() -> SysExMessage {
+        let dump = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(1, 9)
+            .build()
+            .unwrap();
+        let mut json = serde_json::to_value(&dump).unwrap();
+        json["presets"][0]["program_changes"][0] = 200.into();
+        json["global_channels"][4] = 16.into();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn files_go_through_the_policy() {
+        let current = DumpBuilder::new()
+            .bank(0)
+            .pedal(1)
+            .pc(1, 3)
+            .build()
+            .unwrap();
+
+        let mut imported = file_with_bad_values();
+        let report =
+            check_import("a file", &mut imported, None, &current, RangePolicy::Abort).unwrap();
+        assert!(report.aborted());
+        let found: Vec<_> = report.adjustments.iter().map(|a| a.found).collect();
+        assert_eq!(found, [200, 16]);
+
+        let mut imported = file_with_bad_values();
+        check_import(
+            "a file",
+            &mut imported,
+            None,
+            &current,
+            RangePolicy::SkipField,
+        )
+        .unwrap();
+        assert_eq!(imported.preset(0).get(PresetField::ProgramChange(0)), 3);
+
+        let mut imported = file_with_bad_values();
+        check_import("a file", &mut imported, None, &current, RangePolicy::Clamp).unwrap();
+        assert_eq!(imported.preset(0).get(PresetField::ProgramChange(0)), 127);
+        assert_eq!(imported.global_channels()[4].get(), 15);
+        assert_eq!(
+            check_import("a file", &mut imported, None, &current, RangePolicy::Abort),
+            None
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+/// Manufacturer IDs of FCB1010 dumps: Behringer, then the FCB1010 model byte
+const FCB1010_MANUFACTURER: [u8; 3] = [0x00, 0x20, 0x32];
+const FCB1010_DEVICE: u8 = 0x0c;
+
+const MANUFACTURERS: &[(&[u8], &str)] = &[
+    (&[0x01], "Sequential Circuits"),
+    (&[0x04], "Moog"),
+    (&[0x06], "Lexicon"),
+    (&[0x07], "Kurzweil"),
+    (&[0x0f], "Ensoniq"),
+    (&[0x10], "Oberheim"),
+    (&[0x18], "E-mu"),
+    (&[0x40], "Kawai"),
+    (&[0x41], "Roland"),
+    (&[0x42], "Korg"),
+    (&[0x43], "Yamaha"),
+    (&[0x44], "Casio"),
+    (&[0x47], "Akai"),
+    (&[0x7d], "Non-commercial"),
+    (&[0x7e], "Universal non-real time"),
+    (&[0x7f], "Universal real time"),
+    (&[0x00, 0x01, 0x0c], "Line 6"),
+    (&[0x00, 0x01, 0x74], "Fractal Audio"),
+    (&[0x00, 0x20, 0x29], "Focusrite/Novation"),
+    (&[0x00, 0x20, 0x32], "Behringer"),
+    (&[0x00, 0x20, 0x3c], "Elektron"),
+    (&[0x00, 0x20, 0x6b], "Arturia"),
+];
+
+/// Whether raw bytes look like a dump this app can edit
+pub fn is_fcb1010_dump(data: &[u8]) -> bool {
+    data.len() > 6
+        && data[0] == 0xf0
+        && data[1..4] == FCB1010_MANUFACTURER
+        && data[5] == FCB1010_DEVICE
+}
+
+/// The manufacturer ID of a single SysEx message: one byte, or three when
+/// the first is zero
+pub fn manufacturer_id(message: &[u8]) -> Option<&[u8]> {
+    match message.get(1)? {
+        0x00 => message.get(1..4),
+        _ => message.get(1..2),
+    }
+}
+
+pub fn manufacturer_name(id: &[u8]) -> &'static str {
+    MANUFACTURERS
+        .iter()
+        .find(|(known, _)| *known == id)
+        .map_or("Unknown manufacturer", |(_, name)| name)
+}
+
+/// A SysEx file that is not an FCB1010 dump, opened read-only
+pub struct ForeignSysEx {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl ForeignSysEx {
+    pub fn new(path: &Path, data: Vec<u8>) -> Self {
+        Self {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            data,
+        }
+    }
+
+    /// The F0 … F7 messages in the file; an unterminated tail is included as is
+    pub fn messages(&self) -> Vec<&[u8]> {
+        let mut messages = Vec::new();
+        let mut start = None;
+        for (i, &byte) in self.data.iter().enumerate() {
+            match byte {
+                0xf0 => start = Some(i),
+                0xf7 => {
+                    if let Some(s) = start.take() {
+                        messages.push(&self.data[s..=i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            messages.push(&self.data[s..]);
+        }
+        messages
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum WorkspaceView {
+    #[default]
+    Editor,
+    DeviceVsEditor,
+    History,
+    Board,
+    Overview,
+    Scratchpad,
+}
+
+/// The editing context of a project, restored when it is opened again. It
+/// is kept in a file next to the project rather than in it, so looking
+/// around never makes the project itself change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceLayout {
+    #[serde(default)]
+    pub view: WorkspaceView,
+    /// Inner size of the window in points
+    #[serde(default)]
+    pub window_size: Option<[f32; 2]>,
+    #[serde(default = "shown")]
+    pub reference_panel: bool,
+    /// Reference projects open in the side panel
+    #[serde(default)]
+    pub reference_projects: Vec<PathBuf>,
+    /// The bank scrolled to in the preset grid
+    #[serde(default)]
+    pub bank: usize,
+    /// The bank shown on the pedalboard photo
+    #[serde(default)]
+    pub board_bank: usize,
+    #[serde(default)]
+    pub collapsed_cards: BTreeSet<usize>,
+    /// Global functions (PC 1 … Note) whose fields are left off every card
+    #[serde(default)]
+    pub hidden_functions: BTreeSet<usize>,
+}
+
+fn shown() -> bool {
+    true
+}
+
+impl Default for WorkspaceLayout {
+    fn default() -> Self {
+        Self {
+            view: WorkspaceView::default(),
+            window_size: None,
+            reference_panel: shown(),
+            reference_projects: Vec::new(),
+            bank: 0,
+            board_bank: 0,
+            collapsed_cards: BTreeSet::new(),
+            hidden_functions: BTreeSet::new(),
+        }
+    }
+}
+
+/// `song.json` keeps its layout in `song.layout.json`
+pub fn layout_path(project: &Path) -> PathBuf {
+    project.with_extension("layout.json")
+}
+
+impl WorkspaceLayout {
+    /// The layout last saved for `project`, or the default one
+    pub fn load(project: &Path) -> Self {
+        fs::read_to_string(layout_path(project))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, project: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(layout_path(project), text).map_err(|e| e.to_string())
+    }
+}
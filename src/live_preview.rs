@@ -0,0 +1,136 @@
+use crate::events::{ModelEvent, ModelEvents};
+use crate::model::{ExpressionPart, PresetField, SysExMessage, PEDALS_PER_BANK};
+use crate::spec::global_function;
+use crate::values::Channel;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+/// A status byte and, for control changes, the controller number
+type Address = (u8, u8);
+
+/// Keeps each address from being sent more often than every `interval`.
+/// What is offered in between replaces what is waiting, and the newest is
+/// sent once the interval is up, so the receiver always ends on the final
+/// value.
+#[derive(Default)]
+pub struct RateLimiter {
+    last_sent: HashMap<Address, Instant>,
+    waiting: BTreeMap<Address, Vec<Vec<u8>>>,
+}
+
+impl RateLimiter {
+    /// Queues `messages`, which all set what the first one sets
+    pub fn offer(&mut self, messages: Vec<Vec<u8>>) {
+        let Some(first) = messages.first() else {
+            return;
+        };
+        let address = match first[0] & 0xf0 {
+            0xb0 => (first[0], first.get(1).copied().unwrap_or(0)),
+            _ => (first[0], 0),
+        };
+        self.waiting.insert(address, messages);
+    }
+
+    /// The messages that may go out now and how long until the next waiting
+    /// ones may; `None` when nothing is waiting
+    pub fn due(&mut self, interval: Duration) -> (Vec<Vec<u8>>, Option<Duration>) {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        let mut wait: Option<Duration> = None;
+        self.waiting.retain(|address, messages| {
+            let since = self
+                .last_sent
+                .get(address)
+                .map(|sent| now.duration_since(*sent));
+            match since {
+                Some(since) if since < interval => {
+                    let left = interval - since;
+                    wait = Some(wait.map_or(left, |wait| wait.min(left)));
+                    true
+                }
+                _ => {
+                    self.last_sent.insert(*address, now);
+                    due.append(messages);
+                    false
+                }
+            }
+        });
+        (due, wait)
+    }
+}
+
+/// Turns edits into the messages the edited values now send, so a synth
+/// follows a value while it is dragged
+pub struct LivePreview {
+    events: Receiver<ModelEvent>,
+    pub limiter: RateLimiter,
+}
+
+impl LivePreview {
+    pub fn new(events: &ModelEvents) -> Self {
+        Self {
+            events: events.subscribe(),
+            limiter: RateLimiter::default(),
+        }
+    }
+
+    /// Queues the messages for the values edited since the last call. The
+    /// events are taken either way, so none pile up while `enabled` is off.
+    pub fn collect(&mut self, model: &SysExMessage, channels: &[Channel; 10], enabled: bool) {
+        while let Ok(event) = self.events.try_recv() {
+            if let (true, ModelEvent::PresetChanged { bank, pedal, field }) = (enabled, event) {
+                let messages =
+                    edit_messages(model, channels, bank * PEDALS_PER_BANK + pedal, field);
+                self.limiter.offer(messages);
+            }
+        }
+    }
+}
+
+/// What the edited field of a preset sends; nothing while its slot is off.
+/// An expression pedal sends the edited end of its sweep, or the toe end
+/// when its controller changed.
+fn edit_messages(
+    model: &SysExMessage,
+    channels: &[Channel; 10],
+    index: usize,
+    field: PresetField,
+) -> Vec<Vec<u8>> {
+    let preset = model.preset(index);
+    let channel = channels[global_function(field)].get();
+    match field {
+        PresetField::ProgramChange(slot) => preset
+            .program_change(slot)
+            .map(|program| vec![vec![0xc0 | channel, program.get()]])
+            .unwrap_or_default(),
+        PresetField::ControlNumber(slot) | PresetField::ControlValue(slot) => preset
+            .control_change(slot)
+            .map(|(number, value)| vec![vec![0xb0 | channel, number.get(), value.get()]])
+            .unwrap_or_default(),
+        PresetField::ExpressionA(part) | PresetField::ExpressionB(part) => {
+            let pedal = match field {
+                PresetField::ExpressionA(_) => preset.expression_pedal_a(),
+                _ => preset.expression_pedal_b(),
+            };
+            pedal
+                .map(|pedal| {
+                    let value = match part {
+                        ExpressionPart::Min => pedal.min,
+                        ExpressionPart::Controller | ExpressionPart::Max => pedal.max,
+                    };
+                    vec![vec![0xb0 | channel, pedal.controller.get(), value.get()]]
+                })
+                .unwrap_or_default()
+        }
+        PresetField::Note => preset
+            .note()
+            .map(|note| {
+                vec![
+                    vec![0x90 | channel, note.get(), 0x7f],
+                    vec![0x80 | channel, note.get(), 0x00],
+                ]
+            })
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::{Arc, Mutex};
+
+// Oldest entries are dropped beyond this many
+const MAX_ENTRIES: usize = 500;
+
+/// A line in the application log
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub message: String,
+}
+
+/// Shared, append-only application log; clones write to the same log
+#[derive(Clone, Default)]
+pub struct Log {
+    entries: Arc<Mutex<Vec<LogEntry>>>,
+    /// Also print every entry, for running without a window
+    stderr: bool,
+}
+
+impl Log {
+    /// A log that also prints its entries to stderr, for the daemon
+    pub fn printing() -> Self {
+        Self {
+            stderr: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&self, message: impl Into<String>) {
+        let entry = LogEntry {
+            time: chrono::Local::now(),
+            message: message.into(),
+        };
+        if self.stderr {
+            eprintln!("{} {}", entry.time.format("%H:%M:%S"), entry.message);
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(entry);
+        if entries.len() > MAX_ENTRIES {
+            let excess = entries.len() - MAX_ENTRIES;
+            entries.drain(..excess);
+        }
+    }
+
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
@@ -1,524 +1,8384 @@
+mod annotate;
+mod api;
+mod bank_copy;
+mod bank_file;
+mod bank_merge;
+mod board;
+mod board_move;
+mod bundle;
+mod capture;
+mod cc_dictionary;
+mod channel_plan;
+mod cli;
+mod commands;
+mod compare;
+mod console;
+mod daemon;
+mod device_test;
+mod diagnostics;
+mod dirty;
+mod events;
+mod expression_defaults;
+mod factory;
+mod field_sync;
+mod file_kind;
+mod firmware_update;
+mod history;
+mod import_check;
+mod inspector;
+mod layout;
+mod live_preview;
+mod log;
+mod map_file;
+mod mapping_export;
+mod midi;
+mod monitor;
+mod name_suggest;
+mod numbering;
+mod paste;
+mod patch_names;
+mod portable;
+mod preset_clip;
+mod preset_grid;
+mod preset_timeline;
+mod preview;
+mod project;
+mod recovery;
+mod rehearsal;
+mod replace;
+mod rig_profile;
+mod scratchpad;
+mod send_check;
+mod simulator;
+mod smf;
+mod spec;
+mod stats;
+mod tour;
+mod uno2_export;
+mod usage;
+mod web_export;
+mod widgets;
+mod windows_midi;
+mod wizard;
+
+use api::{ApiRequest, ApiServer, Route};
+use bank_copy::BankCopy;
+use bank_file::{BankFile, BANK_EXTENSION};
+use bank_merge::{differences, BankMerge, BANKS};
+use board::BoardLabel;
+use board_move::{Collision, GroupMove};
+use bundle::{Bundle, BUNDLE_EXTENSION};
+use capture::{CaptureImport, LiveCapture};
+use cc_dictionary::{CcDictionary, CcProfile};
+use channel_plan::{ChannelChange, ChannelPlan};
+use cli::CliArgs;
+use commands::{Command, Keybindings, Shortcut};
+use compare::{CompareReport, ComparedFile};
+use console::{
+    format_bytes, format_hex, parse_bytes, remember, spell_bytes, stored_as, ConsoleListener,
+};
+use daemon::DaemonOptions;
+use device_test::{DeviceTest, CONTROLS};
+use diagnostics::{measure_latency, LatencyReport};
+use dirty::DirtyState;
 use eframe::egui;
-use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use events::{ModelEvent, ModelEvents};
+use expression_defaults::ExpressionDefaults;
+use fcb1010_core::{firmware, model, receive, values};
+use field_sync::SyncCategory;
+use file_kind::{sniff, FileKind};
+use firmware::{Firmware, FirmwareSetting, MemoryMap};
+use firmware_update::{FirmwareImage, UpdateProgress};
+use history::AuditLog;
+use import_check::{check_import, ImportReport, RangePolicy};
+use inspector::{is_fcb1010_dump, manufacturer_id, manufacturer_name, ForeignSysEx};
+use layout::{WorkspaceLayout, WorkspaceView};
+use live_preview::LivePreview;
+use log::Log;
+use midi::{
+    MidiOutputs, PreviewOutput, PreviewTarget, Transfer, TransferArbiter, VIRTUAL_PORT_NAME,
+};
+use midir::{MidiInput, MidiInputConnection};
+use model::{
+    bank_and_pedal, hexdump, ExpressionPart, ExpressionPedal, MidiError, Preset, PresetField,
+    SwitchMode, SysExMessage, PEDALS_PER_BANK, SLOTS, SLOT_KEYS,
+};
+use monitor::CcMonitor;
+use numbering::Numbering;
+use preset_clip::PresetClip;
+use preset_grid::{grid_rows, GridRow, RowHeights};
+use preview::{expression_messages, preview_messages, FIRMWARE_SEND_ORDER};
+use project::{file_fingerprint, Integrity, PresetMetadata, Project, ProjectMetadata};
+use receive::{ReceiveProgress, STALL_TIMEOUT};
+use recovery::Recovery;
+use rehearsal::{RehearsalRecorder, Replay};
+use replace::{Hit, MessageType, Query, Replacement};
+use rig_profile::{RigProfile, RIG_EXTENSION};
+use scratchpad::{ScratchPreset, SCRATCHPAD_SLOTS};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use simulator::{Simulator, EXPRESSION_DOWN, EXPRESSION_UP, PEDAL_KEYS, TOUR_STEP};
+use smf::SmfSysEx;
+use stats::TransferStats;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
-use std::sync::mpsc::channel;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tour::{Tour, TourAction};
+use usage::{functions_on, ChannelUsage};
+use values::{Channel, Value7};
+use widgets::{
+    controller_picker, describe_program, optional_value, program_picker, value_editor,
+    vertical_meter, NumberBase, ValueWidget,
+};
+use windows_midi::MidiBackend;
+use wizard::{Wizard, WizardResult, WIZARD_DIR};
 
 const CONFIG_FILE: &str = "config.json";
 const SYSEX_FILE: &str = "preset_data.syx";
+const STATS_FILE: &str = "transfer_stats.json";
+
+// Some interfaces send the dump twice; repeats within this window are ignored
+const RECEIVE_WINDOW: Duration = Duration::from_secs(5);
+
+// How often the port list is checked for ports that went away or came back,
+// unless Settings say otherwise
+const PORT_SCAN_INTERVAL: Duration = Duration::from_secs(3);
+const LIVE_PREVIEW_INTERVAL: Duration = Duration::from_millis(40);
+const WEAR_WARNING_WRITES: u64 = 1000;
+/// What has to be typed to let a previewed write through
+const WRITE_CONFIRMATION: &str = "WRITE";
+
+// Column width assumed before the first card has been laid out
+const DEFAULT_CARD_WIDTH: f32 = 260.0;
+
+// How far beyond the viewport grid rows are still built, so scrolling a
+// little does not show empty space
+const GRID_OVERSCAN: f32 = 200.0;
+
+// How long fields changed by a received dump stay highlighted
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(30);
+// The layout is written once it has stayed the same this long, not while a
+// window is being resized
+const LAYOUT_SAVE_DELAY: Duration = Duration::from_secs(1);
+// Tour targets taller than this get the callout inside them instead of below
+const TOUR_INSIDE_HEIGHT: f32 = 200.0;
 
 #[derive(Serialize, Deserialize, Default)]
 struct AppConfig {
     selected_port: Option<usize>,
+    #[serde(default)]
+    value_widget: ValueWidget,
+    /// Whether MIDI values are shown and typed in decimal or hex
+    #[serde(default)]
+    number_base: NumberBase,
+    #[serde(default)]
+    custom_cc_names: BTreeMap<u8, String>,
+    #[serde(default)]
+    keybindings: Keybindings,
+    #[serde(default)]
+    firmware: FirmwareSetting,
+    /// Create our own output port for preset previews
+    #[serde(default)]
+    virtual_port: bool,
+    /// Name written into the project history with every change
+    #[serde(default)]
+    author: String,
+    /// Draw fields holding their default value like any other instead of dimmed
+    #[serde(default)]
+    undimmed_defaults: bool,
+    #[serde(default)]
+    preview_target: PreviewTarget,
+    /// Where the project file and other editor files go unless a path is given
+    #[serde(default)]
+    project_dir: String,
+    /// Where `.syx` files are looked for unless a path is given
+    #[serde(default)]
+    syx_dir: String,
+    /// Where backup daemon dumps are written and recovery looks for them;
+    /// empty means `daemon::DEFAULT_BACKUP_DIR`
+    #[serde(default)]
+    backup_dir: String,
+    /// Memory map file loaded as the custom firmware layout at startup
+    #[serde(default)]
+    memory_map_path: String,
+    #[serde(default)]
+    send_on_save: SendOnSave,
+    /// Ask before the dump goes out after saving
+    #[serde(default)]
+    confirm_send_on_save: bool,
+    /// Serve the HTTP API for home automation and show control
+    #[serde(default)]
+    api_enabled: bool,
+    /// Where the API listens; empty means `api::DEFAULT_ADDRESS`
+    #[serde(default)]
+    api_address: String,
+    /// What API requests have to send as "Authorization: Bearer <token>";
+    /// empty lets any program on this computer in
+    #[serde(default)]
+    api_token: String,
+    /// What imports do with values the pedal would not accept
+    #[serde(default)]
+    import_policy: RangePolicy,
+    /// Ask which banks of a received dump to apply instead of taking it whole
+    #[serde(default)]
+    choose_received_banks: bool,
+    /// Lines sent from the SysEx console, newest first
+    #[serde(default)]
+    console_history: Vec<String>,
+    /// Named hex lines saved in the SysEx console
+    #[serde(default)]
+    console_snippets: BTreeMap<String, String>,
+    #[serde(default)]
+    port_scan: PortScan,
+    /// Show every port's position in the backend's list after its name
+    #[serde(default)]
+    show_port_ids: bool,
+    /// Keys 1-0 and the up and down arrows play the simulator
+    #[serde(default)]
+    simulator_keys: bool,
+    /// The guided tour was finished or skipped, so it is not shown at startup
+    #[serde(default)]
+    tour_seen: bool,
+    /// How presets and banks are numbered on screen and in exports
+    #[serde(default)]
+    numbering: Numbering,
+    /// Send values to the preview output while they are edited
+    #[serde(default)]
+    live_preview: bool,
+    #[serde(default)]
+    live_preview_rate: PreviewRate,
+    /// The Windows MIDI API the user wants; only the one the editor was
+    /// built with can be used
+    #[serde(default)]
+    midi_backend: MidiBackend,
+    /// Full dumps written to the device from this installation
+    #[serde(default)]
+    dumps_written: u64,
+    #[serde(default)]
+    wear_guard: WearGuard,
+    /// Every send waits for a preview of the write and a typed confirmation
+    #[serde(default)]
+    preview_writes: bool,
+    /// Named rig setups any project can take its global settings from
+    #[serde(default)]
+    rig_profiles: Vec<RigProfile>,
 }
 
+/// How often the list of MIDI ports is read again
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
-pub struct Preset {
-    program_changes: [u8; 5],
-    control_changes: [(u8, u8); 2],
-    expression_pedal_a: (u8, u8, u8),
-    expression_pedal_b: (u8, u8, u8),
-    note: u8,
+enum PortScan {
+    /// Every so many seconds
+    Every(u64),
+    /// Only when Refresh is clicked; lost ports are not noticed by themselves
+    Manual,
 }
 
-impl Preset {
-    pub fn new() -> Self {
-        Self {
-            program_changes: [0; 5],
-            control_changes: [(0, 0); 2],
-            expression_pedal_a: (0, 0, 0),
-            expression_pedal_b: (0, 0, 0),
-            note: 0,
-        }
+impl Default for PortScan {
+    fn default() -> Self {
+        PortScan::Every(PORT_SCAN_INTERVAL.as_secs())
     }
+}
 
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        Self {
-            program_changes: [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4]],
-            control_changes: [(bytes[5], bytes[6]), (bytes[7], bytes[8])],
-            expression_pedal_a: (bytes[9], bytes[10], bytes[11]),
-            expression_pedal_b: (bytes[12], bytes[13], bytes[14]),
-            note: bytes[15],
-        }
-    }
-
-    pub fn to_bytes(&self) -> [u8; 16] {
-        [
-            self.program_changes[0],
-            self.program_changes[1],
-            self.program_changes[2],
-            self.program_changes[3],
-            self.program_changes[4],
-            self.control_changes[0].0,
-            self.control_changes[0].1,
-            self.control_changes[1].0,
-            self.control_changes[1].1,
-            self.expression_pedal_a.0,
-            self.expression_pedal_a.1,
-            self.expression_pedal_a.2,
-            self.expression_pedal_b.0,
-            self.expression_pedal_b.1,
-            self.expression_pedal_b.2,
-            self.note,
-        ]
-    }
-}
-
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
-pub struct SysExMessage {
-    start_byte: u8,
-    manufacturer_id: [u8; 3],
-    global_channel: u8,
-    device_id: u8,
-    #[serde(with = "serde_arrays")]
-    presets: [Preset; 100],
-    #[serde(with = "serde_arrays")]
-    global_channels: [u8; 10],
-    end_byte: u8,
-    original_data: Option<Vec<u8>>,
-}
-
-impl Default for SysExMessage {
-    fn default() -> Self {
-        Self {
-            start_byte: 0xf0,
-            manufacturer_id: [0x00, 0x20, 0x32],
-            global_channel: 0x00,
-            device_id: 0x0c,
-            presets: [Preset::new(); 100],
-            global_channels: [0; 10],
-            end_byte: 0xf7,
-            original_data: None,
+impl PortScan {
+    fn interval(self) -> Option<Duration> {
+        match self {
+            PortScan::Every(seconds) => Some(Duration::from_secs(seconds.max(1))),
+            PortScan::Manual => None,
         }
     }
 }
 
-impl SysExMessage {
-    pub fn encode(&self) -> Vec<u8> {
-        let mut encoded: Vec<u8> = Vec::new();
-        encoded.push(self.start_byte);
-        encoded.extend_from_slice(&self.manufacturer_id);
-        encoded.push(self.global_channel);
-        encoded.push(self.device_id);
-        encoded.push(0x0f); // Hacked patch
-
-        let mut patched_data: Vec<u8> = if let Some(ref data) = self.original_data {
-            data[7..data.len() - 1].to_vec()
-        } else {
-            vec![0u8; 0x7ea] // Size to cover the entire data area including global channels
-        };
+/// How often live preview may send to the same controller
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+enum PreviewRate {
+    /// At most once every so many milliseconds; the final value always goes out
+    Every(u64),
+    /// Every edit, however fast a value is dragged
+    Unlimited,
+}
 
-        // Encode the presets and global channels into the patched data
-        for (i, preset) in self.presets.iter().enumerate() {
-            let bytes = preset.to_bytes();
-            for (j, &byte) in bytes.iter().enumerate() {
-                patched_data[i * 16 + j] = byte;
-            }
-        }
+impl Default for PreviewRate {
+    fn default() -> Self {
+        PreviewRate::Every(LIVE_PREVIEW_INTERVAL.as_millis() as u64)
+    }
+}
 
-        for (i, &channel) in self.global_channels.iter().enumerate() {
-            patched_data[0x7e0 + i] = channel;
+impl PreviewRate {
+    fn interval(self) -> Duration {
+        match self {
+            PreviewRate::Every(milliseconds) => Duration::from_millis(milliseconds),
+            PreviewRate::Unlimited => Duration::ZERO,
         }
+    }
+}
 
-        // Perform 8-bit to 7-bit encoding
-        let mut index = 0;
-        while index < patched_data.len() {
-            let chunk = &patched_data[index..index + 7.min(patched_data.len() - index)];
-            let mut data: [u8; 8] = [0; 8];
-            let mut msb_byte = 0u8;
-            for (i, &byte) in chunk.iter().enumerate() {
-                msb_byte |= (byte >> 7) << i;
-                data[i] = byte & 0x7F;
-            }
-            data[7] = msb_byte;
-            encoded.extend_from_slice(&data);
-            index += 7;
-        }
+/// When to warn that another full write wears the device's memory
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+enum WearGuard {
+    /// Before every write once this many were made
+    WarnAfter(u64),
+    Off,
+}
 
-        encoded.push(self.end_byte);
-        encoded
+impl Default for WearGuard {
+    fn default() -> Self {
+        WearGuard::WarnAfter(WEAR_WARNING_WRITES)
     }
+}
 
-    pub fn decode(data: &[u8]) -> Result<Self, MidiError> {
-        if data.len() < 6 {
-            return Err(MidiError::InvalidDataLength);
-        }
+/// Whether saving the project also sends the dump to the device
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+enum SendOnSave {
+    #[default]
+    Off,
+    Always,
+    /// The FCB1010 only takes whole dumps, so this skips the send when no
+    /// preset changed since the last one instead of sending just the changes
+    WhenChanged,
+}
 
-        if data[0] != 0xf0 {
-            return Err(MidiError::InvalidSysExStart);
-        }
+impl SendOnSave {
+    const ALL: [SendOnSave; 3] = [SendOnSave::Off, SendOnSave::Always, SendOnSave::WhenChanged];
 
-        if data[data.len() - 1] != 0xf7 {
-            return Err(MidiError::InvalidSysExEnd);
+    fn label(self) -> &'static str {
+        match self {
+            SendOnSave::Off => "Never",
+            SendOnSave::Always => "Always",
+            SendOnSave::WhenChanged => "When changed since the last send",
         }
+    }
+}
 
-        let manufacturer_id = [data[1], data[2], data[3]];
-        let global_channel = data[4];
-        let device_id = data[5];
-
-        let mut fixed_data: Vec<u8> = Vec::new();
-        let mut index = 7;
-
-        while index + 8 <= data.len() - 1 {
-            let chunk = &data[index..index + 8];
-            let msb_byte = chunk[7];
-            for i in 0..7 {
-                let byte = chunk[i] | ((msb_byte >> i) & 0x01) << 7;
-                fixed_data.push(byte);
+impl AppConfig {
+    /// The saved settings, or the defaults and what went wrong when the file
+    /// cannot be read. A damaged file is set aside first, so the wear counter
+    /// and rig profiles in it are not saved over.
+    fn load() -> (Self, Option<String>) {
+        let Ok(config_str) = fs::read_to_string(CONFIG_FILE) else {
+            return (AppConfig::default(), None);
+        };
+        match serde_json::from_str(&config_str) {
+            Ok(config) => (config, None),
+            Err(e) => {
+                let problem = match recovery::set_aside(Path::new(CONFIG_FILE)) {
+                    Ok(aside) => format!(
+                        "The settings in {} could not be read ({}); the file was kept as {} \
+                         and the defaults are used",
+                        CONFIG_FILE,
+                        e,
+                        aside.display()
+                    ),
+                    Err(rename) => format!(
+                        "The settings in {} could not be read ({}) nor moved aside ({}); \
+                         the defaults are used and saving them replaces the file",
+                        CONFIG_FILE, e, rename
+                    ),
+                };
+                (AppConfig::default(), Some(problem))
             }
-            index += 8;
         }
+    }
 
-        // Let's hexdump the fixed_data for debugging
-        eprintln!("{}", hexdump(&fixed_data));
-
-        let mut presets: [Preset; 100] = unsafe { std::mem::zeroed() };
-        let mut preset_bytes: Vec<u8> = Vec::new();
-        let mut preset_index = 0;
-
-        for byte in &fixed_data[0..0x640] {
-            preset_bytes.push(*byte);
-            if preset_bytes.len() == 16 {
-                presets[preset_index] = Preset::from_bytes(&preset_bytes);
-                preset_index += 1;
-                preset_bytes.clear();
-            }
+    fn save(&self) {
+        if let Ok(config_str) = serde_json::to_string(self) {
+            fs::write(CONFIG_FILE, config_str).ok();
         }
+    }
 
-        // Assuming the global MIDI channel data starts at address 0x7e0
-        let mut global_channels: [u8; 10] = [0; 10];
-        global_channels.copy_from_slice(&fixed_data[0x7e0..0x7ea]);
+    fn project_file(&self, path: impl AsRef<Path>) -> PathBuf {
+        in_folder(&self.project_dir, path.as_ref())
+    }
+
+    fn syx_file(&self, path: impl AsRef<Path>) -> PathBuf {
+        in_folder(&self.syx_dir, path.as_ref())
+    }
 
-        Ok(Self {
-            start_byte: 0xf0,
-            manufacturer_id,
-            global_channel,
-            device_id,
-            presets,
-            global_channels,
-            original_data: Some(data.to_vec()), // Save the original data
-            end_byte: 0xf7,
-        })
+    fn backup_dir(&self) -> PathBuf {
+        match self.backup_dir.trim() {
+            "" => PathBuf::from(daemon::DEFAULT_BACKUP_DIR),
+            folder => PathBuf::from(folder),
+        }
     }
 }
 
-#[derive(Debug)]
-pub enum MidiError {
-    InvalidSysExStart,
-    InvalidSysExEnd,
-    InvalidDataLength,
+/// Relative paths are taken from the folder when one is configured
+fn in_folder(folder: &str, path: &Path) -> PathBuf {
+    let folder = folder.trim();
+    if folder.is_empty() || path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(folder).join(path)
+    }
 }
 
 struct MidiApp {
     available_ports: Vec<String>,
+    /// What the port selectors show for `available_ports`
+    port_labels: Vec<String>,
+    /// The output ports, which dumps and previews are sent to; the selected
+    /// interface's output is found by name (see [`midi::matching_output`])
+    available_outputs: Vec<String>,
+    output_labels: Vec<String>,
+    /// False until the MIDI backend has been opened
+    midi_started: bool,
+    last_port_scan: Instant,
+    /// The selected port while it is missing, e.g. during sleep, to reselect it
+    lost_port: Option<String>,
     selected_port: Option<usize>,
-    midi_out_connection: Option<MidiOutputConnection>,
+    outputs: MidiOutputs,
+    transfers: TransferArbiter,
     midi_in_connection: Option<MidiInputConnection<()>>,
     config: AppConfig,
     sysex_message: Arc<Mutex<SysExMessage>>,
+    device_message: Arc<Mutex<Option<SysExMessage>>>,
     receiving_sysex: Arc<Mutex<bool>>,
+    /// The dump being received; shared with the input callback
+    receive_progress: Arc<Mutex<ReceiveProgress>>,
+    log: Log,
+    workspace_view: WorkspaceView,
+    reference_panel: bool,
+    /// The first bank in view in the preset grid
+    grid_bank: usize,
+    scroll_to_bank: Option<usize>,
+    window_size: Option<[f32; 2]>,
+    resize_window: Option<[f32; 2]>,
+    /// The layout as last written next to the project
+    saved_layout: WorkspaceLayout,
+    layout_changed: Option<Instant>,
+    events: ModelEvents,
+    metadata: ProjectMetadata,
+    project_path: PathBuf,
+    disk_fingerprint: Option<String>,
+    /// The project file could not be read at startup; saving waits until
+    /// the user decides what replaces it
+    recovery: Option<Recovery>,
+    confirm_overwrite: bool,
+    confirm_factory_defaults: bool,
+    confirm_send_after_save: bool,
+    /// Problems found in the dump about to be sent, until the user decides
+    send_problems: Option<Vec<String>>,
+    /// Which presets changed since the last save and the last send
+    dirty: DirtyState,
+    /// A send waiting for its typed confirmation
+    write_preview: Option<WritePreview>,
+    /// A send waits for the user to accept another full write
+    confirm_wear: bool,
+    /// The wear warning was waved through for the rest of the session
+    wear_quiet: bool,
+    /// Closing was held back to ask about unsaved changes
+    confirm_quit: bool,
+    /// The user chose to close without saving, so the next close goes through
+    quit_confirmed: bool,
+    /// A dropped file that would replace unsaved changes, until the user
+    /// decides
+    held_drop: Option<(PathBuf, Vec<u8>)>,
+    api: Option<ApiServer>,
+    /// Why the API could not be started; cleared when the settings change
+    api_error: Option<String>,
+    /// Adjustments made by the last import; shared with the receiving thread
+    import_report: Arc<Mutex<Option<ImportReport>>>,
+    /// The last data that would not decode; shared with the receiving thread
+    decode_failure: Arc<Mutex<Option<DecodeFailure>>>,
+    hex_view: Option<HexView>,
+    /// A received dump whose banks are being picked; set by the receiving thread
+    bank_merge: Arc<Mutex<Option<BankMerge>>>,
+    bundle_path: String,
+    /// Passphrase bundles are exported with; kept in memory only
+    bundle_passphrase: String,
+    bundle_encrypt: bool,
+    /// The passphrase being typed for an encrypted bundle that is opened
+    bundle_unlock: Option<String>,
+    annotated_dump_path: String,
+    web_export_path: String,
+    uno2_export_path: String,
+    mapping_export_path: String,
+    reference_documents: Vec<ReferenceDocument>,
+    selected_reference: usize,
+    reference_path: String,
+    /// What "Adopt" takes from the selected reference project
+    adopt_category: SyncCategory,
+    warning: Option<String>,
+    status: Option<String>,
+    new_cc_number: u8,
+    program_names_path: String,
+    recording_shortcut: Option<Command>,
+    latency_input_port: Option<usize>,
+    latency_result: Arc<Mutex<Option<Result<LatencyReport, String>>>>,
+    measuring_latency: Arc<Mutex<bool>>,
+    channel_plan: Option<ChannelPlan>,
+    /// Let the channel plan change channels that locked banks send on
+    channel_plan_locked: bool,
+    bank_copy_from: usize,
+    bank_copy_to: usize,
+    bank_copy_offset: i16,
+    bank_copy: Option<BankCopy>,
+    /// The presets a bank copy overwrote, to put back on undo
+    bank_copy_undo: Option<Vec<(usize, Preset)>>,
+    /// The bank exported to or imported into a bank file
+    bank_file_bank: usize,
+    bank_file_name: String,
+    rig_name: String,
+    rig_path: String,
+    bank_file_path: String,
+    /// The real preset each scratchpad slot copies to and from
+    scratch_targets: [usize; SCRATCHPAD_SLOTS],
+    expression_defaults: ExpressionDefaults,
+    /// The presets the expression pedal tool would change, by index
+    expression_plan: Option<Vec<(usize, Preset)>>,
+    /// A MIDI file that was opened and the dumps found in it
+    smf_dumps: Option<(PathBuf, Vec<SmfSysEx>)>,
+    /// The preset whose past states the history scrubber shows
+    timeline_preset: usize,
+    /// How many versions back the scrubber stands; 0 is the current state
+    timeline_position: usize,
+    /// Names offered for unnamed presets, by index, until applied or discarded
+    name_suggestions: Option<Vec<(usize, String)>>,
+    capture_path: String,
+    capture_target: usize,
+    capture_import: Option<CaptureImport>,
+    firmware_image_path: String,
+    firmware_image: Option<FirmwareImage>,
+    firmware_checksum: String,
+    firmware_confirmed: bool,
+    firmware_pause_ms: u64,
+    firmware_progress: Arc<Mutex<UpdateProgress>>,
+    compare_before: String,
+    compare_after: String,
+    compare_report_path: String,
+    compare_report: Option<CompareReport>,
+    audit: AuditLog,
+    live_preview: LivePreview,
+    history_export_path: String,
+    /// Whether the overview lists the most recently changed presets first
+    overview_recent_first: bool,
+    /// The loaded photo of the pedalboard and the path it came from
+    board_texture: Option<(String, egui::TextureHandle)>,
+    /// The photo path that could not be opened and why, not retried until
+    /// the path changes
+    board_image_error: Option<(String, String)>,
+    board_bank: usize,
+    board_sheet_path: String,
+    /// Pedals picked on the board view, to be dragged to another bank together
+    board_selection: BTreeSet<usize>,
+    /// Where the rubber band being dragged across the photo started
+    board_band: Option<egui::Pos2>,
+    /// A group move waiting for the user to settle what it would replace
+    board_move: Option<GroupMove>,
+    /// The map loaded as the custom layout, offered in the layout pickers
+    custom_map: Option<MemoryMap>,
+    /// The memory map being edited, not yet in use
+    map_draft: MemoryMap,
+    map_path: String,
+    replace_open: bool,
+    replace_query: Query,
+    replacement: Replacement,
+    replace_hits: Vec<Hit>,
+    /// The preset the paste window fills, while it is open
+    paste_target: Option<usize>,
+    paste_text: String,
+    /// The built-in wizards and those in `WIZARD_DIR`
+    wizards: Vec<Wizard>,
+    /// Wizard files that could not be read
+    wizard_problems: Vec<String>,
+    wizard: usize,
+    wizard_answers: Vec<u8>,
+    wizard_bank: usize,
+    wizard_result: Option<WizardResult>,
+    inspected: Vec<ForeignSysEx>,
+    locked: bool,
+    preview: PreviewOutput,
+    highlights: Arc<Mutex<Option<Highlights>>>,
+    transfer_stats: Arc<Mutex<TransferStats>>,
+    /// Presets shown as a one-line summary in the grid
+    collapsed_cards: HashSet<usize>,
+    /// Global functions whose fields the preset cards leave out
+    hidden_functions: BTreeSet<usize>,
+    /// Width of the widest preset card last frame, to pick the column count
+    card_width: f32,
+    row_heights: RowHeights,
+    /// The same for the rows of the device and editor panes
+    compare_heights: RowHeights,
+    /// Cards built for the last drawn preset grid and how long it took
+    grid_frame: (usize, Duration),
+    cc_monitor: CcMonitor,
+    monitor_input_port: Option<usize>,
+    device_test: DeviceTest,
+    device_test_port: Option<usize>,
+    live_capture: LiveCapture,
+    live_capture_port: Option<usize>,
+    rehearsal_recorder: RehearsalRecorder,
+    rehearsal_port: Option<usize>,
+    replay: Option<Replay>,
+    console_text: String,
+    console_snippet_name: String,
+    console_listener: ConsoleListener,
+    console_port: Option<usize>,
+    monitored_preset: usize,
+    simulator: Simulator,
+    simulator_export_path: String,
+    tour: Tour,
 }
 
-impl Default for MidiApp {
-    fn default() -> Self {
-        let midi_in = MidiInput::new("MIDI Input").unwrap();
-        let available_ports = midi_in
-            .ports()
-            .iter()
-            .map(|p| midi_in.port_name(p).unwrap())
-            .collect();
+/// Fields that a received dump changed, highlighted until `until`
+struct Highlights {
+    fields: HashSet<(usize, PresetField)>,
+    until: Instant,
+}
 
-        let config: AppConfig = if let Ok(config_str) = fs::read_to_string(CONFIG_FILE) {
-            serde_json::from_str(&config_str).unwrap_or_default()
-        } else {
-            AppConfig::default()
-        };
+/// A project opened read-only next to the edited one, to copy presets from
+struct ReferenceDocument {
+    path: PathBuf,
+    project: Project,
+}
 
-        let selected_port = config.selected_port;
-        let midi_out_connection = if let Some(port_index) = selected_port {
-            let midi_out = MidiOutput::new("MIDI Output").unwrap();
-            let port = midi_out.ports().get(port_index).cloned();
-            port.and_then(|p| midi_out.connect(&p, "midir-test").ok())
-        } else {
-            None
+/// What is carried while dragging presets out of a reference document
+#[derive(Clone)]
+enum PresetDrag {
+    Preset(Preset, PresetMetadata),
+    Bank(Vec<(Preset, PresetMetadata)>),
+}
+
+impl MidiApp {
+    fn new(args: CliArgs) -> Self {
+        let (config, config_problem) = AppConfig::load();
+        let mut warning = config_problem.clone();
+        let mut custom_map = None;
+        if !config.memory_map_path.is_empty() {
+            match map_file::load(Path::new(&config.memory_map_path)) {
+                Ok(map) => custom_map = Some(map),
+                Err(e) => {
+                    warning = Some(format!(
+                        "The custom memory map {} was not loaded ({}); it is not offered as a \
+                         layout",
+                        config.memory_map_path, e
+                    ))
+                }
+            }
+        }
+        let map_path = match config.memory_map_path.as_str() {
+            "" => "memory_map.toml".to_string(),
+            path => path.to_string(),
         };
 
-        let sysex_message: SysExMessage = if let Ok(sysex_str) = fs::read_to_string(SYSEX_FILE) {
-            serde_json::from_str(&sysex_str).unwrap_or_default()
-        } else {
-            SysExMessage::default()
+        let project_path = args
+            .project
+            .unwrap_or_else(|| config.project_file(SYSEX_FILE));
+        let mut recovery = None;
+        let project = match Project::load_checked(&project_path) {
+            Ok((project, integrity)) => {
+                if integrity == Integrity::Modified {
+                    warning = Some(modified_warning(&project_path));
+                }
+                project
+            }
+            Err(e) => {
+                // Never quietly start over a file that holds someone's work
+                if project_path.exists() {
+                    let backup_dir = args
+                        .backup_dir
+                        .clone()
+                        .unwrap_or_else(|| config.backup_dir());
+                    recovery = Some(Recovery::new(&project_path, e, &backup_dir));
+                }
+                Project::default()
+            }
         };
+        let disk_fingerprint = file_fingerprint(&project_path);
+        let events = ModelEvents::default();
+        let audit = AuditLog::new(&events, &project.dump);
+        let dirty = DirtyState::new(&project.dump, &project.metadata);
+        let live_preview = LivePreview::new(&events);
+        let annotated_dump_path = project_path
+            .with_extension("annotated.txt")
+            .to_string_lossy()
+            .into_owned();
+        let web_export_path = project_path
+            .with_extension("html")
+            .to_string_lossy()
+            .into_owned();
+        let uno2_export_path = project_path
+            .with_extension("uno2.txt")
+            .to_string_lossy()
+            .into_owned();
+        let mapping_export_path = project_path
+            .with_extension("mapping.txt")
+            .to_string_lossy()
+            .into_owned();
+        let bundle_path = project_path
+            .with_extension(BUNDLE_EXTENSION)
+            .to_string_lossy()
+            .into_owned();
 
-        Self {
-            available_ports,
-            selected_port,
-            midi_out_connection,
+        let mut app = Self {
+            available_ports: Vec::new(),
+            port_labels: Vec::new(),
+            available_outputs: Vec::new(),
+            output_labels: Vec::new(),
+            midi_started: false,
+            last_port_scan: Instant::now(),
+            lost_port: None,
+            selected_port: None,
+            outputs: MidiOutputs::default(),
+            transfers: TransferArbiter::default(),
             midi_in_connection: None,
             config,
-            sysex_message: Arc::new(Mutex::new(sysex_message)),
+            sysex_message: Arc::new(Mutex::new(project.dump)),
+            device_message: Arc::new(Mutex::new(None)),
             receiving_sysex: Arc::new(Mutex::new(false)),
+            receive_progress: Arc::new(Mutex::new(ReceiveProgress::default())),
+            log: Log::default(),
+            workspace_view: WorkspaceView::Editor,
+            reference_panel: true,
+            grid_bank: 0,
+            scroll_to_bank: None,
+            window_size: None,
+            resize_window: None,
+            saved_layout: WorkspaceLayout::default(),
+            layout_changed: None,
+            events,
+            audit,
+            live_preview,
+            history_export_path: "history.csv".to_string(),
+            overview_recent_first: false,
+            board_texture: None,
+            board_image_error: None,
+            board_bank: 0,
+            board_sheet_path: "cheat_sheet.html".to_string(),
+            board_selection: BTreeSet::new(),
+            board_band: None,
+            board_move: None,
+            map_draft: custom_map
+                .clone()
+                .unwrap_or_else(|| Firmware::V2_5.memory_map()),
+            custom_map,
+            map_path,
+            metadata: project.metadata,
+            project_path,
+            disk_fingerprint,
+            confirm_overwrite: false,
+            recovery,
+            confirm_factory_defaults: false,
+            confirm_send_after_save: false,
+            send_problems: None,
+            dirty,
+            write_preview: None,
+            confirm_wear: false,
+            wear_quiet: false,
+            confirm_quit: false,
+            held_drop: None,
+            quit_confirmed: false,
+            api: None,
+            api_error: None,
+            import_report: Arc::new(Mutex::new(None)),
+            decode_failure: Arc::new(Mutex::new(None)),
+            hex_view: None,
+            bank_merge: Arc::new(Mutex::new(None)),
+            bundle_path,
+            bundle_passphrase: String::new(),
+            bundle_encrypt: false,
+            bundle_unlock: None,
+            annotated_dump_path,
+            web_export_path,
+            uno2_export_path,
+            mapping_export_path,
+            reference_documents: Vec::new(),
+            selected_reference: 0,
+            reference_path: String::new(),
+            adopt_category: SyncCategory::default(),
+            warning,
+            status: None,
+            new_cc_number: 0,
+            program_names_path: String::new(),
+            recording_shortcut: None,
+            latency_input_port: None,
+            latency_result: Arc::new(Mutex::new(None)),
+            measuring_latency: Arc::new(Mutex::new(false)),
+            channel_plan: None,
+            channel_plan_locked: false,
+            bank_copy_from: 0,
+            bank_copy_to: 1,
+            bank_copy_offset: 0,
+            bank_copy: None,
+            bank_copy_undo: None,
+            bank_file_bank: 0,
+            bank_file_name: String::new(),
+            rig_name: String::new(),
+            rig_path: format!("rig.{}", RIG_EXTENSION),
+            bank_file_path: format!("bank.{}", BANK_EXTENSION),
+            scratch_targets: [0; SCRATCHPAD_SLOTS],
+            expression_defaults: ExpressionDefaults::default(),
+            expression_plan: None,
+            name_suggestions: None,
+            timeline_preset: 0,
+            timeline_position: 0,
+            smf_dumps: None,
+            capture_path: String::new(),
+            capture_target: 0,
+            capture_import: None,
+            firmware_image_path: String::new(),
+            firmware_image: None,
+            firmware_checksum: String::new(),
+            firmware_confirmed: false,
+            firmware_pause_ms: 200,
+            firmware_progress: Arc::new(Mutex::new(UpdateProgress::default())),
+            compare_before: String::new(),
+            compare_after: String::new(),
+            compare_report_path: "comparison.md".to_string(),
+            compare_report: None,
+            replace_open: false,
+            replace_query: Query::default(),
+            replacement: Replacement::default(),
+            replace_hits: Vec::new(),
+            paste_target: None,
+            paste_text: String::new(),
+            wizards: Vec::new(),
+            wizard_problems: Vec::new(),
+            wizard: 0,
+            wizard_answers: Vec::new(),
+            wizard_bank: 0,
+            wizard_result: None,
+            inspected: Vec::new(),
+            locked: false,
+            preview: PreviewOutput::default(),
+            highlights: Arc::new(Mutex::new(None)),
+            transfer_stats: Arc::new(Mutex::new(TransferStats::load(Path::new(STATS_FILE)))),
+            collapsed_cards: HashSet::new(),
+            hidden_functions: BTreeSet::new(),
+            card_width: DEFAULT_CARD_WIDTH,
+            row_heights: RowHeights::default(),
+            compare_heights: RowHeights::default(),
+            grid_frame: (0, Duration::ZERO),
+            cc_monitor: CcMonitor::default(),
+            monitor_input_port: None,
+            device_test: DeviceTest::default(),
+            device_test_port: None,
+            live_capture: LiveCapture::default(),
+            live_capture_port: None,
+            rehearsal_recorder: RehearsalRecorder::default(),
+            rehearsal_port: None,
+            replay: None,
+            console_text: String::new(),
+            console_snippet_name: String::new(),
+            console_listener: ConsoleListener::default(),
+            console_port: None,
+            monitored_preset: 0,
+            simulator: Simulator::default(),
+            simulator_export_path: "simulator.mid".to_string(),
+            tour: Tour::default(),
+        };
+
+        app.tour.register(TOUR_STEP);
+        if !app.config.tour_seen {
+            app.tour.start();
+        }
+
+        if let Some(problem) = config_problem {
+            app.log.push(problem);
+        }
+        app.restore_layout();
+        if !args.no_midi {
+            app.start_midi(args.port.as_deref());
+            app.check_midi_backend();
+        }
+        if let Some(path) = &args.open {
+            match fs::read(path) {
+                Ok(data) => app.open_file(path, data),
+                Err(e) => app.warning = Some(format!("Could not open {}: {}", path.display(), e)),
+            }
+        }
+        app
+    }
+
+    /// Opens the MIDI backend and the remembered ports. Skipped at startup with
+    /// `--no-midi`, so files can be edited where no backend is available.
+    fn start_midi(&mut self, requested_port: Option<&str>) {
+        let (available_ports, available_outputs) = match midi::input_port_names()
+            .and_then(|ports| Ok((ports, midi::output_port_names()?)))
+        {
+            Ok(ports) => ports,
+            Err(e) => {
+                self.warning = Some(format!("MIDI is not available: {}", e));
+                return;
+            }
+        };
+
+        // A port requested on the command line wins over the remembered one
+        if let Some(port_name) = requested_port {
+            match find_port(&available_ports, port_name) {
+                Some(index) => self.config.selected_port = Some(index),
+                None => {
+                    self.warning = Some(format!(
+                        "MIDI port \"{}\" was not found; no output is connected",
+                        port_name
+                    ));
+                    self.config.selected_port = None;
+                }
+            }
+        }
+
+        // Remembered indexes may be stale when the ports changed since
+        let selected_port = self
+            .config
+            .selected_port
+            .filter(|&index| index < available_ports.len());
+        let selected_output = selected_port
+            .and_then(|index| midi::matching_output(&available_ports, &available_outputs, index));
+        if let Some(output) = selected_output {
+            self.outputs.connect(output).ok();
+        }
+        if requested_port.is_some() && selected_port.is_some() && self.outputs.is_empty() {
+            self.warning = Some("Could not connect to the requested MIDI port".to_string());
+        }
+
+        if self.config.virtual_port {
+            if let Err(e) = self.preview.open_virtual(VIRTUAL_PORT_NAME) {
+                self.warning = Some(format!("Could not create the virtual port: {}", e));
+            }
+        }
+        if let Err(e) = self.preview.set_target(self.config.preview_target) {
+            self.warning = Some(format!("Could not connect the preview output: {}", e));
+        }
+
+        self.available_ports = available_ports;
+        self.available_outputs = available_outputs;
+        self.update_port_labels();
+        self.selected_port = selected_port;
+        self.latency_input_port = selected_port;
+        self.monitor_input_port = selected_port;
+        self.device_test_port = selected_port;
+        self.live_capture_port = selected_port;
+        self.rehearsal_port = selected_port;
+        self.console_port = selected_port;
+        self.midi_started = true;
+    }
+
+    /// Closes every MIDI connection and opens them again, e.g. after the
+    /// backend setting changed
+    fn restart_midi(&mut self) {
+        for index in 0..self.available_outputs.len() {
+            self.outputs.disconnect(index);
+        }
+        self.midi_in_connection = None;
+        self.preview.close_virtual();
+        self.start_midi(None);
+        self.status = Some("MIDI restarted".to_string());
+    }
+
+    /// Warns when the chosen Windows MIDI API is not the one this build has
+    fn check_midi_backend(&mut self) {
+        let chosen = self.config.midi_backend;
+        if cfg!(windows) && chosen != MidiBackend::BUILT {
+            self.warning = Some(format!(
+                "MIDI backend {} is selected, but this editor talks through {}; {} is needed for {}",
+                chosen.label(),
+                MidiBackend::BUILT.label(),
+                chosen.build_hint(),
+                chosen.label()
+            ));
+        }
+    }
+
+    fn update_port_labels(&mut self) {
+        self.port_labels = midi::port_labels(&self.available_ports, self.config.show_port_ids);
+        self.output_labels = midi::port_labels(&self.available_outputs, self.config.show_port_ids);
+    }
+
+    /// The output port of the interface at input index `input`
+    fn output_for(&self, input: usize) -> Option<usize> {
+        midi::matching_output(&self.available_ports, &self.available_outputs, input)
+    }
+
+    /// The output of the selected interface, where dumps are sent
+    fn selected_output(&self) -> Option<usize> {
+        self.selected_port.and_then(|input| self.output_for(input))
+    }
+
+    /// Re-reads the port list and follows the selected ports to their new
+    /// indexes. A selected port that disappears is reconnected when it returns.
+    fn refresh_ports(&mut self) {
+        self.last_port_scan = Instant::now();
+        let Ok((ports, outputs)) =
+            midi::input_port_names().and_then(|ports| Ok((ports, midi::output_port_names()?)))
+        else {
+            return;
+        };
+        if ports == self.available_ports && outputs == self.available_outputs {
+            return;
+        }
+
+        let remap = |index: Option<usize>| {
+            let name = index.and_then(|i| self.available_ports.get(i))?;
+            ports.iter().position(|port| port == name)
+        };
+        let selected_name = self
+            .selected_port
+            .and_then(|i| self.available_ports.get(i))
+            .cloned();
+        let selected = remap(self.selected_port);
+        self.latency_input_port = remap(self.latency_input_port);
+        self.monitor_input_port = remap(self.monitor_input_port);
+        self.device_test_port = remap(self.device_test_port);
+        self.live_capture_port = remap(self.live_capture_port);
+        self.rehearsal_port = remap(self.rehearsal_port);
+        self.console_port = remap(self.console_port);
+        self.outputs.reindex(&outputs);
+        if let Some(gone) = self.preview.reindex(&outputs) {
+            self.log.push(format!(
+                "{} disappeared; previews go to the dump outputs until another port is chosen",
+                gone
+            ));
+        }
+        if self.preview.target() != self.config.preview_target {
+            self.config.preview_target = self.preview.target();
+            self.config.save();
+        }
+
+        if selected.is_none() && selected_name.is_some() {
+            self.lost_port = selected_name;
+            self.log.push(format!(
+                "{} disappeared; it is reconnected when it comes back",
+                self.lost_port.as_deref().unwrap_or_default()
+            ));
+        }
+        self.selected_port = selected;
+        self.available_ports = ports;
+        self.available_outputs = outputs;
+        self.update_port_labels();
+
+        let returned = self
+            .lost_port
+            .as_ref()
+            .and_then(|name| self.available_ports.iter().position(|port| port == name));
+        if let Some(index) = returned {
+            let name = self.lost_port.take().unwrap_or_default();
+            self.selected_port = Some(index);
+            let connected = match self.output_for(index) {
+                Some(output) => self.outputs.connect(output),
+                None => Err("it has no output port".to_string()),
+            };
+            self.status = Some(match connected {
+                Ok(()) => format!("Reconnected to {}", name),
+                Err(e) => format!("{} is back but could not be reconnected: {}", name, e),
+            });
+        }
+        if self.selected_port.is_some() && self.selected_port != self.config.selected_port {
+            self.config.selected_port = self.selected_port;
+            self.config.save();
+        }
+    }
+
+    /// Notes ports that were reopened because a send on them failed
+    fn report_reconnects(&mut self) {
+        let mut reconnected = self.outputs.take_reconnected();
+        reconnected.extend(self.preview.take_reconnected());
+        if reconnected.is_empty() {
+            return;
+        }
+        for name in &reconnected {
+            self.log
+                .push(format!("Reopened {} after a failed send", name));
         }
+        self.refresh_ports();
     }
 }
 
+fn modified_warning(path: &Path) -> String {
+    format!(
+        "{} was modified outside the editor since it was last saved; check its content",
+        path.display()
+    )
+}
+
+/// Finds a port by exact name first, then by a case-insensitive substring
+fn find_port(ports: &[String], name: &str) -> Option<usize> {
+    ports.iter().position(|p| p == name).or_else(|| {
+        let needle = name.to_lowercase();
+        ports
+            .iter()
+            .position(|p| p.to_lowercase().contains(&needle))
+    })
+}
+
 impl eframe::App for MidiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_shortcuts(ctx);
+        self.handle_simulator_keys(ctx);
+        self.advance_replay(ctx);
+        self.serve_api(ctx);
+        self.record_history();
+        self.refresh_dirty();
+        self.hold_unsaved_close(ctx);
+        self.send_live_preview(ctx);
+        if let Some(interval) = self.config.port_scan.interval() {
+            if self.midi_started && self.last_port_scan.elapsed() >= interval {
+                self.refresh_ports();
+            }
+            ctx.request_repaint_after(interval);
+        }
+        self.persist_layout(ctx);
+        if self.reference_panel {
+            self.show_reference_documents(ctx);
+        }
+        self.show_recovery(ctx);
+        self.show_overwrite_confirmation(ctx);
+        self.show_send_after_save_confirmation(ctx);
+        self.show_quit_confirmation(ctx);
+        self.show_drop_confirmation(ctx);
+        self.show_wear_warning(ctx);
+        self.show_write_preview(ctx);
+        self.show_send_problems(ctx);
+        self.show_smf_dumps(ctx);
+        self.show_factory_confirmation(ctx);
+        self.show_bundle_unlock(ctx);
+        self.show_import_report(ctx);
+        self.show_decode_failure(ctx);
+        self.show_hex_view(ctx);
+        self.show_bank_merge(ctx);
+        self.show_board_move(ctx);
+        self.handle_dropped_files(ctx);
+        self.show_inspectors(ctx);
+        self.show_replace_window(ctx);
+        self.show_paste_window(ctx);
+        self.show_tour(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("MIDI Interface Selector");
 
-            egui::ComboBox::from_label("Select MIDI Interface")
-                .selected_text(self.selected_port.map_or("None".to_string(), |index| {
-                    self.available_ports[index].clone()
-                }))
-                .show_ui(ui, |ui| {
-                    for (index, port) in self.available_ports.iter().enumerate() {
-                        if ui
-                            .selectable_value(&mut self.selected_port, Some(index), port)
-                            .clicked()
-                        {
-                            if let Some(port_index) = self.selected_port {
-                                let midi_out = MidiOutput::new("MIDI Output").unwrap();
-                                let port = midi_out.ports().get(port_index).cloned();
-                                self.midi_out_connection =
-                                    port.and_then(|p| midi_out.connect(&p, "midir-test").ok());
-
-                                self.config.selected_port = Some(port_index);
-                                if let Ok(config_str) = serde_json::to_string(&self.config) {
-                                    fs::write(CONFIG_FILE, config_str).ok();
-                                }
-                            }
-                        }
-                    }
+            if let Some(warning) = &self.warning {
+                let mut dismissed = false;
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, format!("⚠ {}", warning));
+                    dismissed = ui.button("Dismiss").clicked();
                 });
-
-            if let Some(selected_index) = self.selected_port {
-                ui.label(format!(
-                    "Selected: {}",
-                    self.available_ports[selected_index]
-                ));
-            } else {
-                ui.label("No MIDI interface selected");
-            }
-
-            ui.separator();
-
-            if ui.button("Save to SysEx").clicked() {
-                if let Ok(sysex_str) = serde_json::to_string(&*self.sysex_message.lock().unwrap()) {
-                    fs::write(SYSEX_FILE, sysex_str).ok();
-                    ui.label("SysEx data saved");
-                } else {
-                    ui.label("Failed to save SysEx data");
+                if dismissed {
+                    self.warning = None;
                 }
             }
 
-            if ui.button("Load from SysEx").clicked() {
-                if let Ok(sysex_str) = fs::read_to_string(SYSEX_FILE) {
-                    *self.sysex_message.lock().unwrap() =
-                        serde_json::from_str(&sysex_str).unwrap_or_default();
-                    ui.label("SysEx data loaded");
-                } else {
-                    ui.label("Failed to load SysEx data");
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut self.locked, "🔒 Lock editor")
+                    .on_hover_text("Makes the editor read-only, e.g. on stage");
+                if self.locked {
+                    ui.label("Read-only: editing, loading and sending are disabled");
                 }
-            }
+                if ui
+                    .button("Take the tour")
+                    .on_hover_text("Show the guided tour of the editor again")
+                    .clicked()
+                {
+                    self.tour.start();
+                }
+            });
 
-            if ui.button("Send SysEx Message").clicked() {
-                if let Some(connection) = &mut self.midi_out_connection {
-                    let message = self.sysex_message.lock().unwrap().encode();
-                    connection.send(&message).unwrap();
-                    ui.label("SysEx message sent");
-                } else {
-                    ui.label("No MIDI connection available");
-                }
-            }
-
-            if ui.button("Receive SysEx Message").clicked() {
-                if let Some(port_index) = self.selected_port {
-                    let midi_in = MidiInput::new("MIDI Input").unwrap();
-                    let port = midi_in.ports().get(port_index).cloned();
-                    if let Some(port) = port {
-                        let (sender, receiver) = channel();
-                        let connection = midi_in
-                            .connect(
-                                &port,
-                                "midir-read-input",
-                                move |_, message, _| {
-                                    eprintln!("Received:\n{}", hexdump(message));
-                                    if message[0] == 0xF0 && message[message.len() - 1] == 0xF7 {
-                                        sender.send(message.to_vec()).unwrap();
-                                    }
-                                },
-                                (),
-                            )
-                            .unwrap();
+            let ports = ui
+                .scope(|ui| self.mutating(ui, |app, ui| app.show_port_selector(ui)))
+                .response
+                .rect;
+            self.tour.mark("ports", ports);
 
-                        self.midi_in_connection = Some(connection);
-                        *self.receiving_sysex.lock().unwrap() = true;
+            ui.separator();
 
-                        let ctx_clone = ctx.clone();
-                        let sysex_message_clone = Arc::clone(&self.sysex_message);
-                        let receiving_sysex_clone = Arc::clone(&self.receiving_sysex);
+            if ui.button("Save to SysEx").clicked() {
+                self.run_command(Command::SaveProject, ctx);
+            }
 
-                        std::thread::spawn(move || {
-                            if let Ok(message) = receiver.recv() {
-                                if let Ok(sysex_message) = SysExMessage::decode(&message) {
-                                    *sysex_message_clone.lock().unwrap() = sysex_message;
-                                    *receiving_sysex_clone.lock().unwrap() = false;
-                                    ctx_clone.request_repaint();
-                                }
-                            }
-                        });
-                    }
+            self.mutating(ui, |app, ui| {
+                if ui.button("Load from SysEx").clicked() {
+                    app.run_command(Command::LoadProject, ctx);
+                }
+                if ui
+                    .button("New from factory defaults")
+                    .on_hover_text("Start from the presets of a freshly reset FCB1010")
+                    .clicked()
+                {
+                    app.run_command(Command::NewFromFactoryDefaults, ctx);
                 }
-            }
 
-            ui.separator();
-            ui.heading("Presets");
+                app.show_bundle_controls(ui);
+            });
 
-            let columns = 5; // Number of presets per row
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+            if let Some(summary) = self.dirty.summary() {
+                ui.weak(summary);
+            }
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                egui::Grid::new("preset_grid").show(ui, |ui| {
-                    for (i, preset) in self
-                        .sysex_message
-                        .lock()
-                        .unwrap()
-                        .presets
-                        .iter_mut()
-                        .enumerate()
-                    {
-                        if i % columns == 0 && i != 0 {
-                            ui.end_row();
-                        }
+            let (unknown, regions) = {
+                let sysex_message = self.sysex_message.lock().unwrap();
+                let regions: Vec<String> = sysex_message
+                    .unknown_regions()
+                    .iter()
+                    .filter(|region| region.bytes.iter().any(|&b| b != 0))
+                    .map(|region| format!("0x{:03x}: {} bytes", region.offset, region.bytes.len()))
+                    .collect();
+                (sysex_message.unknown_nonzero_bytes(), regions)
+            };
+            if unknown > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "⚠ {} bytes outside the known memory map are set; they are kept as is",
+                        unknown
+                    ),
+                )
+                .on_hover_text(regions.join("\n"));
+            }
 
-                        ui.group(|ui| {
-                            ui.label(format!("Preset {}", i + 1));
+            self.mutating(ui, |app, ui| {
+                app.show_broadcast_ports(ui);
 
-                            for (j, program_change) in preset.program_changes.iter_mut().enumerate()
-                            {
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("PC {}:", j + 1));
-                                    ui.add(
-                                        egui::DragValue::new(program_change)
-                                            .speed(0.1)
-                                            .clamp_range(0..=127),
-                                    );
-                                });
-                            }
+                let send = ui.button("Send SysEx Message");
+                app.tour.mark("send", send.rect);
+                if send.clicked() {
+                    app.run_command(Command::SendDump, ctx);
+                }
 
-                            for (j, (control_change, value)) in
-                                preset.control_changes.iter_mut().enumerate()
-                            {
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("CC {}:", j + 1));
-                                    ui.add(
-                                        egui::DragValue::new(control_change)
-                                            .speed(0.1)
-                                            .clamp_range(0..=127),
-                                    );
-                                    ui.label("Value:");
-                                    ui.add(
-                                        egui::DragValue::new(value).speed(0.1).clamp_range(0..=127),
-                                    );
-                                });
-                            }
+                let receive = ui.button("Receive SysEx Message");
+                app.tour.mark("receive", receive.rect);
+                if receive.clicked() {
+                    app.run_command(Command::ReceiveDump, ctx);
+                }
+            });
 
-                            ui.horizontal(|ui| {
-                                ui.label("EP A:");
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_a.0)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_a.1)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_a.2)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                            });
+            ui.separator();
 
-                            ui.horizontal(|ui| {
-                                ui.label("EP B:");
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_b.0)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_b.1)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.expression_pedal_b.2)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                            });
+            self.mutating(ui, |app, ui| {
+                app.show_settings(ui);
+                app.show_diagnostics(ui);
+                app.show_firmware_update(ui);
+                app.show_memory_map_editor(ui);
+                app.show_sysex_console(ui);
+            });
+            self.show_simulator(ui);
+            self.show_pedal_monitor(ui);
+            self.show_rehearsal(ui);
+            self.show_device_test(ui);
+            self.show_log(ui);
+            self.show_compare_tool(ui);
+            self.show_channel_usage(ui);
+            self.mutating(ui, |app, ui| {
+                app.show_global_settings(ui);
+                app.show_channel_tool(ui);
+                app.show_expression_defaults_tool(ui);
+                app.show_name_suggestions_tool(ui);
+                app.show_preset_timeline(ui);
+                app.show_bank_copy_tool(ui);
+                app.show_bank_file_tool(ui);
+                app.show_rig_profiles(ui);
+                app.show_capture_import(ui);
+                app.show_live_capture(ui);
+                app.show_wizards(ui);
+                if ui.button("Find and replace messages…").clicked() {
+                    app.replace_open = true;
+                }
+            });
 
-                            ui.horizontal(|ui| {
-                                ui.label("Note:");
-                                ui.add(
-                                    egui::DragValue::new(&mut preset.note)
-                                        .speed(0.1)
-                                        .clamp_range(0..=127),
-                                );
-                            });
-                        });
-                    }
-                });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.workspace_view, WorkspaceView::Editor, "Editor");
+                ui.selectable_value(
+                    &mut self.workspace_view,
+                    WorkspaceView::DeviceVsEditor,
+                    "Device vs. Editor",
+                );
+                ui.selectable_value(&mut self.workspace_view, WorkspaceView::History, "History");
+                ui.selectable_value(&mut self.workspace_view, WorkspaceView::Board, "Pedalboard");
+                ui.selectable_value(
+                    &mut self.workspace_view,
+                    WorkspaceView::Overview,
+                    "Overview",
+                );
+                ui.selectable_value(
+                    &mut self.workspace_view,
+                    WorkspaceView::Scratchpad,
+                    "Scratchpad",
+                );
+                ui.separator();
+                ui.toggle_value(&mut self.reference_panel, "Reference projects");
+                ui.menu_button("View", |ui| self.show_view_menu(ui));
             });
 
+            match self.workspace_view {
+                WorkspaceView::Editor => {
+                    let presets = ui.scope(|ui| self.mutating(ui, Self::show_presets));
+                    self.tour.mark("presets", presets.response.rect);
+                }
+                WorkspaceView::DeviceVsEditor => self.mutating(ui, Self::show_device_vs_editor),
+                WorkspaceView::History => self.show_history(ui),
+                WorkspaceView::Board => self.mutating(ui, Self::show_board),
+                WorkspaceView::Overview => self.show_overview(ui),
+                WorkspaceView::Scratchpad => self.mutating(ui, Self::show_scratchpad),
+            }
+
             if *self.receiving_sysex.lock().unwrap() {
                 egui::Window::new("Receiving SysEx")
                     .collapsible(false)
                     .resizable(false)
                     .show(ctx, |ui| {
-                        ui.label("Receiving SysEx message...");
-                        if ui.button("Cancel").clicked() {
-                            *self.receiving_sysex.lock().unwrap() = false;
-                            self.midi_in_connection = None;
+                        let received = self.receive_progress.lock().unwrap().received();
+                        // Every built-in layout has the same size, so detection cannot change it
+                        let expected = match &self.config.firmware {
+                            FirmwareSetting::Fixed(firmware) => model::encoded_len(firmware),
+                            FirmwareSetting::AutoDetect => model::encoded_len(&Firmware::default()),
+                        };
+                        if received == 0 {
+                            ui.label("Waiting for the dump; start it on the FCB1010...");
+                        } else {
+                            ui.label(format!("Receiving: {} of {} bytes", received, expected));
                         }
+                        ui.add(
+                            egui::ProgressBar::new(received as f32 / expected as f32)
+                                .show_percentage(),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button("Cancel").clicked() {
+                                *self.receiving_sysex.lock().unwrap() = false;
+                                self.midi_in_connection = None;
+                            }
+                            if self.workspace_view != WorkspaceView::Overview
+                                && ui
+                                    .button("Watch in Overview")
+                                    .on_hover_text("See the presets fill in as they arrive")
+                                    .clicked()
+                            {
+                                self.workspace_view = WorkspaceView::Overview;
+                            }
+                        });
                     });
+                ctx.request_repaint_after(Duration::from_millis(250));
+                self.abort_stalled_receive();
             }
         });
     }
 }
 
-fn hexdump(data: &[u8]) -> String {
-    let mut result = String::new();
-    for (i, chunk) in data.chunks(16).enumerate() {
-        result.push_str(&format!("{:08x}: ", i * 16));
-        for byte in chunk {
-            result.push_str(&format!("{:02x} ", byte));
+impl MidiApp {
+    fn current_project(&self) -> Project {
+        Project {
+            dump: self.sysex_message.lock().unwrap().clone(),
+            metadata: self.metadata.clone(),
         }
-        for _ in 0..(16 - chunk.len()) {
-            result.push_str("   ");
+    }
+
+    fn replace_project(&mut self, project: Project) {
+        *self.sysex_message.lock().unwrap() = project.dump;
+        self.metadata = project.metadata;
+        self.events.emit(ModelEvent::DumpReplaced);
+        // Loading is not an edit of the loaded project
+        self.audit.reset(&self.sysex_message.lock().unwrap());
+    }
+
+    fn current_layout(&self) -> WorkspaceLayout {
+        WorkspaceLayout {
+            view: self.workspace_view,
+            window_size: self.window_size,
+            reference_panel: self.reference_panel,
+            reference_projects: self
+                .reference_documents
+                .iter()
+                .map(|document| document.path.clone())
+                .collect(),
+            bank: self.grid_bank,
+            board_bank: self.board_bank,
+            collapsed_cards: self.collapsed_cards.iter().copied().collect(),
+            hidden_functions: self.hidden_functions.clone(),
         }
-        result.push_str("  ");
-        for byte in chunk {
-            let ch = if byte.is_ascii_graphic() {
-                *byte as char
-            } else {
-                '.'
-            };
-            result.push(ch);
+    }
+
+    /// Brings back the editing context last saved for the project
+    fn restore_layout(&mut self) {
+        let layout = WorkspaceLayout::load(&self.project_path);
+        self.workspace_view = layout.view;
+        self.resize_window = layout.window_size;
+        self.reference_panel = layout.reference_panel;
+        self.reference_documents.clear();
+        for path in &layout.reference_projects {
+            match Project::load(path) {
+                Ok(project) => self.reference_documents.push(ReferenceDocument {
+                    path: path.clone(),
+                    project,
+                }),
+                Err(e) => self.log.push(format!(
+                    "Could not reopen the reference project {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
         }
-        result.push('\n');
+        self.selected_reference = 0;
+        self.grid_bank = layout.bank;
+        self.scroll_to_bank = Some(layout.bank);
+        self.board_bank = layout.board_bank;
+        self.collapsed_cards = layout.collapsed_cards.iter().copied().collect();
+        self.hidden_functions = layout.hidden_functions.clone();
+        self.row_heights.clear();
+        self.saved_layout = layout;
+        self.layout_changed = None;
+    }
+
+    /// Writes the layout next to the project once it has settled
+    fn persist_layout(&mut self, ctx: &egui::Context) {
+        if let Some(size) = self.resize_window.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size.into()));
+        }
+        self.window_size = ctx
+            .input(|i| i.viewport().inner_rect)
+            .map(|rect| [rect.width().round(), rect.height().round()]);
+
+        let layout = self.current_layout();
+        if layout == self.saved_layout {
+            self.layout_changed = None;
+            return;
+        }
+        let since = *self.layout_changed.get_or_insert_with(Instant::now);
+        if since.elapsed() >= LAYOUT_SAVE_DELAY {
+            self.save_layout(layout);
+        } else {
+            ctx.request_repaint_after(LAYOUT_SAVE_DELAY);
+        }
+    }
+
+    fn save_layout(&mut self, layout: WorkspaceLayout) {
+        if layout == self.saved_layout {
+            return;
+        }
+        if let Err(e) = layout.save(&self.project_path) {
+            self.log
+                .push(format!("Could not save the workspace layout: {}", e));
+        }
+        self.saved_layout = layout;
+        self.layout_changed = None;
+    }
+
+    fn author(&self) -> String {
+        if !self.config.author.trim().is_empty() {
+            return self.config.author.trim().to_string();
+        }
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    fn record_history(&mut self) {
+        let author = self.author();
+        let model = self.sysex_message.lock().unwrap();
+        self.audit.record(&model, &author, &mut self.metadata);
+    }
+
+    /// Makes the project as it is now the state saved on disk
+    fn mark_saved(&mut self) {
+        self.dirty
+            .mark_saved(&self.sysex_message.lock().unwrap(), &self.metadata);
+    }
+
+    fn refresh_dirty(&mut self) {
+        let device = self.device_message.lock().unwrap();
+        let model = self.sysex_message.lock().unwrap();
+        self.dirty.refresh(&model, &self.metadata, device.as_ref());
+    }
+
+    /// Keeps the window open when it is closed with unsaved changes, to ask
+    /// whether to save them first
+    fn hold_unsaved_close(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.dirty.needs_save()
+            && !self.quit_confirmed
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_quit = true;
+        }
+    }
+
+    fn show_quit_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_quit {
+            return;
+        }
+
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} has changes that were not saved.",
+                    self.project_path.display()
+                ));
+                if let Some(summary) = self.dirty.summary() {
+                    ui.weak(summary);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Save and quit").clicked() {
+                        self.confirm_quit = false;
+                        self.save_project();
+                        self.refresh_dirty();
+                        if !self.dirty.needs_save() {
+                            self.quit_confirmed = true;
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    }
+                    if ui.button("Quit without saving").clicked() {
+                        self.confirm_quit = false;
+                        self.quit_confirmed = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_quit = false;
+                    }
+                });
+            });
+    }
+
+    fn show_board(&mut self, ui: &mut egui::Ui) {
+        let mut typing = false;
+        ui.horizontal(|ui| {
+            ui.heading("Pedalboard");
+            ui.label("Photo (PNG):");
+            let edit = ui.text_edit_singleline(&mut self.metadata.board.image_path);
+            if edit.lost_focus() {
+                portable::make_relative(&mut self.metadata.board.image_path);
+            }
+            typing = edit.has_focus();
+            let mut bank = self.board_bank + 1;
+            ui.label("Bank");
+            ui.add(egui::DragValue::new(&mut bank).clamp_range(1..=10));
+            self.board_bank = bank - 1;
+        });
+
+        // Opened once the path is typed in, and again only when it changes
+        let path = self.metadata.board.image_path.clone();
+        let failed = self.board_image_error.as_ref().map(|(p, _)| p) == Some(&path);
+        if !typing
+            && !failed
+            && !path.is_empty()
+            && self.board_texture.as_ref().map(|(p, _)| p) != Some(&path)
+        {
+            let image = image::open(self.config.project_file(&path)).map(|image| image.to_rgba8());
+            match image {
+                Ok(image) => {
+                    let size = [image.width() as usize, image.height() as usize];
+                    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, &image);
+                    let texture = ui
+                        .ctx()
+                        .load_texture("pedalboard", pixels, Default::default());
+                    self.board_texture = Some((path, texture));
+                    self.board_image_error = None;
+                }
+                Err(e) => self.board_image_error = Some((path, e.to_string())),
+            }
+        }
+        if let Some((path, e)) = &self.board_image_error {
+            if *path == self.metadata.board.image_path {
+                ui.colored_label(egui::Color32::RED, format!("Cannot show {}: {}", path, e));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.menu_button("Add label", |ui| {
+                let mut added = None;
+                for pedal in 0..PEDALS_PER_BANK {
+                    if ui.button(format!("Pedal {}", pedal + 1)).clicked() {
+                        added = Some(Some(pedal));
+                    }
+                }
+                if ui.button("Text only").clicked() {
+                    added = Some(None);
+                }
+                if let Some(pedal) = added {
+                    self.metadata.board.labels.push(BoardLabel {
+                        pedal,
+                        text: String::new(),
+                        x: 0.5,
+                        y: 0.5,
+                    });
+                    ui.close_menu();
+                }
+            });
+            ui.label(
+                "Drag labels onto the switches; right-click one to edit or remove it. \
+                 Click labels or drag a box around them to select their pedals.",
+            );
+        });
+        if !self.board_selection.is_empty() {
+            let mut dropped = None;
+            ui.horizontal(|ui| {
+                let group = GroupMove {
+                    pedals: self.board_selection.clone(),
+                    from_bank: self.board_bank,
+                    to_bank: self.board_bank,
+                };
+                ui.dnd_drag_source(egui::Id::new("board_group"), group, |ui| {
+                    ui.strong(format!("✋ {} selected", self.board_selection.len()));
+                })
+                .response
+                .on_hover_text("Drag onto a bank to move these presets there");
+                ui.label("Drop on bank:");
+                for bank in 0..BANKS {
+                    let frame = egui::Frame::group(ui.style());
+                    let (_, payload) = ui.dnd_drop_zone::<GroupMove, _>(frame, |ui| {
+                        ui.label((bank + 1).to_string());
+                    });
+                    if let Some(group) = payload {
+                        dropped = Some(GroupMove {
+                            to_bank: bank,
+                            ..(*group).clone()
+                        });
+                    }
+                }
+                if ui.button("Clear selection").clicked() {
+                    self.board_selection.clear();
+                }
+            });
+            if let Some(group) = dropped {
+                self.start_group_move(group);
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.board_sheet_path);
+            if ui
+                .button("Export cheat sheet")
+                .on_hover_text("An HTML page with the labelled photo for every bank")
+                .clicked()
+            {
+                let page = self.config.project_file(&self.board_sheet_path);
+                let folder = page.parent().unwrap_or(Path::new(""));
+                let html = board::cheat_sheet_html(&self.metadata, folder, self.config.numbering);
+                self.status = Some(match fs::write(&page, html) {
+                    Ok(()) => format!("Cheat sheet written to {}", self.board_sheet_path),
+                    Err(e) => format!("Failed to write the cheat sheet: {}", e),
+                });
+            }
+        });
+
+        let Some((_, texture)) = &self.board_texture else {
+            return;
+        };
+        egui::ScrollArea::both().show(ui, |ui| {
+            let size = texture.size_vec2();
+            let size = size * (ui.available_width() / size.x).min(1.0);
+            let photo = ui.image((texture.id(), size));
+            let rect = photo.rect;
+            // Registered before the labels so they stay on top of it
+            let band = ui.interact(
+                rect,
+                ui.id().with("board_band"),
+                egui::Sense::click_and_drag(),
+            );
+            let adding = ui.input(|input| input.modifiers.shift || input.modifiers.command);
+
+            let mut removed = None;
+            let mut frames = Vec::new();
+            let presets = &self.metadata.presets;
+            for (i, label) in self.metadata.board.labels.iter_mut().enumerate() {
+                let caption = label.caption(self.board_bank, presets, self.config.numbering);
+                let center = rect.min + egui::vec2(label.x * rect.width(), label.y * rect.height());
+                let galley = ui.painter().layout_no_wrap(
+                    caption,
+                    egui::FontId::proportional(13.0),
+                    egui::Color32::BLACK,
+                );
+                let frame =
+                    egui::Rect::from_center_size(center, galley.size() + egui::vec2(8.0, 4.0));
+                let response = ui.interact(
+                    frame,
+                    ui.id().with(("board_label", i)),
+                    egui::Sense::click_and_drag(),
+                );
+                if let Some(pedal) = label.pedal {
+                    frames.push((pedal, frame));
+                    if response.clicked() {
+                        if !adding {
+                            self.board_selection.clear();
+                            self.board_selection.insert(pedal);
+                        } else if !self.board_selection.remove(&pedal) {
+                            self.board_selection.insert(pedal);
+                        }
+                    }
+                }
+                let selected = label
+                    .pedal
+                    .is_some_and(|pedal| self.board_selection.contains(&pedal));
+                if response.dragged() {
+                    let moved = response.drag_delta() / rect.size();
+                    label.x = (label.x + moved.x).clamp(0.0, 1.0);
+                    label.y = (label.y + moved.y).clamp(0.0, 1.0);
+                }
+                let stroke = if selected {
+                    egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 140, 0))
+                } else {
+                    egui::Stroke::new(1.0, egui::Color32::DARK_GRAY)
+                };
+                ui.painter()
+                    .rect(frame, 3.0, egui::Color32::from_white_alpha(230), stroke);
+                ui.painter().galley(
+                    frame.min + egui::vec2(4.0, 2.0),
+                    galley,
+                    egui::Color32::BLACK,
+                );
+                response.context_menu(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut label.text).hint_text("Note"));
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                self.metadata.board.labels.remove(i);
+            }
+
+            if band.drag_started() {
+                self.board_band = band.interact_pointer_pos();
+            }
+            let pointer = ui.input(|input| input.pointer.latest_pos());
+            if let (Some(start), Some(end)) = (self.board_band, pointer) {
+                let area = egui::Rect::from_two_pos(start, end);
+                ui.painter().rect(
+                    area,
+                    0.0,
+                    egui::Color32::from_rgba_unmultiplied(255, 140, 0, 40),
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 140, 0)),
+                );
+                if band.drag_stopped() {
+                    if !adding {
+                        self.board_selection.clear();
+                    }
+                    self.board_selection.extend(
+                        frames
+                            .iter()
+                            .filter(|(_, frame)| frame.intersects(area))
+                            .map(|&(pedal, _)| pedal),
+                    );
+                    self.board_band = None;
+                }
+            }
+            if band.clicked() && !adding {
+                self.board_selection.clear();
+            }
+        });
+    }
+
+    /// Moves a group dragged on the board view, asking first when it would
+    /// replace presets already in the target bank
+    fn start_group_move(&mut self, group: GroupMove) {
+        // Disabled drop zones still report drops
+        if self.locked || group.from_bank == group.to_bank {
+            return;
+        }
+        if self.metadata.is_bank_locked(group.from_bank)
+            || self.metadata.is_bank_locked(group.to_bank)
+        {
+            self.status = Some(format!("Cannot move {}: a bank is locked", group.summary()));
+            return;
+        }
+        let conflicts = group.conflicts(&self.sysex_message.lock().unwrap(), &self.metadata);
+        if conflicts.is_empty() {
+            self.move_group(&group, Collision::Overwrite);
+        } else {
+            self.board_move = Some(group);
+        }
+    }
+
+    fn move_group(&mut self, group: &GroupMove, collision: Collision) {
+        let mut sysex_message = self.sysex_message.lock().unwrap();
+        let mut presets = Vec::new();
+        for (index, preset, metadata) in group.apply(&sysex_message, &self.metadata, collision) {
+            *self.metadata.preset_mut(index) = metadata;
+            presets.push((index, preset));
+        }
+        replace_presets(&self.events, &mut sysex_message, presets);
+        drop(sysex_message);
+        // Follow the presets so the labels still name them
+        self.board_bank = group.to_bank;
+        self.status = Some(format!("Moved {}", group.summary()));
+    }
+
+    fn show_board_move(&mut self, ctx: &egui::Context) {
+        let Some(group) = self.board_move.clone() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut collision = None;
+        let mut cancel = false;
+        let conflicts = group.conflicts(&self.sysex_message.lock().unwrap(), &self.metadata);
+        egui::Window::new("Move presets")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Moving {} replaces what these pedals hold there:",
+                    group.summary()
+                ));
+                for &pedal in &conflicts {
+                    let index = group.to_bank * PEDALS_PER_BANK + pedal;
+                    let name = self
+                        .metadata
+                        .presets
+                        .get(index)
+                        .map_or("", |preset| preset.name.as_str());
+                    ui.label(match name {
+                        "" => format!(
+                            "Pedal {} (preset {})",
+                            pedal + 1,
+                            self.config.numbering.preset(index)
+                        ),
+                        name => format!("Pedal {}: {}", pedal + 1, name),
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Overwrite")
+                        .on_hover_text("Replace them and leave the moved pedals blank")
+                        .clicked()
+                    {
+                        collision = Some(Collision::Overwrite);
+                    }
+                    if ui
+                        .button("Swap")
+                        .on_hover_text("Put them on the pedals the group came from")
+                        .clicked()
+                    {
+                        collision = Some(Collision::Swap);
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if let Some(collision) = collision {
+            self.move_group(&group, collision);
+        }
+        if collision.is_some() || cancel || !open {
+            self.board_move = None;
+        }
+    }
+
+    fn show_history(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("History");
+            ui.label(format!("{} changes", self.metadata.history.len()));
+            ui.text_edit_singleline(&mut self.history_export_path);
+            if ui.button("Export CSV").clicked() {
+                let csv = history::to_csv(&self.metadata.history);
+                let path = self.config.project_file(&self.history_export_path);
+                self.status = Some(match fs::write(path, csv) {
+                    Ok(()) => format!("History written to {}", self.history_export_path),
+                    Err(e) => format!("Failed to write the history: {}", e),
+                });
+            }
+        });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("history_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for heading in ["When", "Who", "What", "Old", "New"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    // Newest first
+                    for entry in self.metadata.history.iter().rev() {
+                        let when = chrono::DateTime::parse_from_rfc3339(&entry.time).map_or_else(
+                            |_| entry.time.clone(),
+                            |time| time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        );
+                        ui.label(when);
+                        ui.label(&entry.author);
+                        ui.label(&entry.what);
+                        ui.label(&entry.old);
+                        ui.label(&entry.new);
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Every preset on one line with when it last changed; a row's button
+    /// goes to its bank in the editor
+    fn show_overview(&mut self, ui: &mut egui::Ui) {
+        let incoming = self.incoming_presets();
+        ui.horizontal(|ui| {
+            ui.heading("Overview");
+            ui.add_enabled_ui(incoming.is_none(), |ui| {
+                ui.selectable_value(&mut self.overview_recent_first, false, "Preset order");
+                ui.selectable_value(&mut self.overview_recent_first, true, "Recently changed");
+            });
+            if let Some(presets) = &incoming {
+                ui.label(format!("Receiving: {} of 100 presets", presets.len()));
+            }
+        });
+        if let Some(presets) = incoming {
+            self.show_incoming_overview(ui, &presets);
+            return;
+        }
+
+        let mut rows: Vec<(usize, String, Option<String>)> = {
+            let model = self.sysex_message.lock().unwrap();
+            model
+                .presets()
+                .iter()
+                .enumerate()
+                .map(|(index, preset)| {
+                    let metadata = self
+                        .metadata
+                        .presets
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_default();
+                    (
+                        index,
+                        preset_summary(self.config.numbering, index, preset, &metadata),
+                        metadata.modified,
+                    )
+                })
+                .collect()
+        };
+        if self.overview_recent_first {
+            // Never changed presets go last, in preset order
+            let time = |modified: &Option<String>| {
+                modified
+                    .as_deref()
+                    .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+            };
+            rows.sort_by_key(|row| std::cmp::Reverse(time(&row.2)));
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("overview_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for heading in ["Bank", "Pedal", "Preset", "Last changed", ""] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    for (index, summary, modified) in rows {
+                        let (bank, pedal) = bank_and_pedal(index);
+                        ui.label(self.config.numbering.bank(bank));
+                        ui.label((pedal + 1).to_string());
+                        ui.label(summary);
+                        match modified {
+                            Some(time) => ui.label(history::short_time(&time)),
+                            None => ui.weak("never"),
+                        };
+                        if ui.small_button("Show").clicked() {
+                            self.workspace_view = WorkspaceView::Editor;
+                            self.scroll_to_bank = Some(bank);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// The overview of a dump being received, filled in preset by preset;
+    /// presets still to come are greyed out
+    fn show_incoming_overview(&self, ui: &mut egui::Ui, presets: &[Preset]) {
+        let numbering = self.config.numbering;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("overview_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for heading in ["Bank", "Pedal", "Preset", ""] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    for index in 0..100 {
+                        let (bank, pedal) = bank_and_pedal(index);
+                        ui.label(numbering.bank(bank));
+                        ui.label((pedal + 1).to_string());
+                        match presets.get(index) {
+                            Some(preset) => {
+                                // The device sends no names
+                                let metadata = PresetMetadata::default();
+                                ui.label(preset_summary(numbering, index, preset, &metadata));
+                                ui.label("received");
+                            }
+                            None => {
+                                ui.weak(format!("{}.", numbering.preset(index)));
+                                ui.weak("waiting");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// The presets of the dump arriving right now that came in whole, or
+    /// `None` unless one is arriving
+    fn incoming_presets(&self) -> Option<Vec<Preset>> {
+        if !*self.receiving_sysex.lock().unwrap() {
+            return None;
+        }
+        // Auto-detection needs the whole dump; until then the project's
+        // layout is the best guess
+        let firmware = match &self.config.firmware {
+            FirmwareSetting::Fixed(firmware) => firmware.clone(),
+            FirmwareSetting::AutoDetect => self.sysex_message.lock().unwrap().firmware().clone(),
+        };
+        let progress = self.receive_progress.lock().unwrap();
+        (progress.received() > 0).then(|| progress.presets_so_far(&firmware))
+    }
+
+    /// Shows UI that edits the project or talks to the device. It is all
+    /// disabled at once while the editor is locked.
+    fn mutating(&mut self, ui: &mut egui::Ui, add_contents: impl FnOnce(&mut Self, &mut egui::Ui)) {
+        ui.add_enabled_ui(!self.locked, |ui| add_contents(self, ui));
+    }
+
+    fn show_port_selector(&mut self, ui: &mut egui::Ui) {
+        if !self.midi_started {
+            ui.horizontal(|ui| {
+                ui.label("MIDI is off; files can still be edited");
+                if ui.button("Start MIDI").clicked() {
+                    self.warning = None;
+                    self.start_midi(None);
+                }
+            });
+            return;
+        }
+
+        let mut refresh = false;
+        egui::ComboBox::from_label("Select MIDI Interface")
+            .selected_text(
+                self.selected_port
+                    .map_or("None".to_string(), |index| self.port_labels[index].clone()),
+            )
+            .show_ui(ui, |ui| {
+                refresh = ui
+                    .button("⟳ Refresh")
+                    .on_hover_text("Read the list of ports again")
+                    .clicked();
+                ui.separator();
+                for (index, port) in self.port_labels.iter().enumerate() {
+                    let previous = self.selected_port;
+                    if ui
+                        .selectable_value(&mut self.selected_port, Some(index), port)
+                        .clicked()
+                    {
+                        if let Some(port_index) = self.selected_port {
+                            let previous = previous
+                                .filter(|&p| p != port_index)
+                                .and_then(|p| self.output_for(p));
+                            if let Some(previous) = previous {
+                                self.outputs.disconnect(previous);
+                            }
+                            let connected = match self.output_for(port_index) {
+                                Some(output) => self.outputs.connect(output),
+                                None => Err(format!("{} has no output port", port)),
+                            };
+                            if let Err(e) = connected {
+                                self.status = Some(format!("Failed to connect: {}", e));
+                            }
+                            self.warning = None;
+                            self.lost_port = None;
+
+                            self.config.selected_port = Some(port_index);
+                            self.config.save();
+                        }
+                    }
+                }
+            });
+
+        if refresh {
+            self.refresh_ports();
+        }
+
+        if let Some(selected_index) = self.selected_port {
+            ui.label(format!("Selected: {}", self.port_labels[selected_index]));
+        } else {
+            ui.label("No MIDI interface selected");
+        }
+
+        let target = self.preview.target();
+        let mut selected = target;
+        egui::ComboBox::from_label("Preview output")
+            .selected_text(self.preview_target_label(target))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut selected,
+                    PreviewTarget::DumpOutputs,
+                    "Same as SysEx dumps",
+                );
+                for (index, port) in self.output_labels.iter().enumerate() {
+                    ui.selectable_value(&mut selected, PreviewTarget::Port(index), port);
+                }
+                if self.preview.virtual_name().is_some() {
+                    ui.selectable_value(&mut selected, PreviewTarget::Virtual, "Virtual port");
+                }
+            })
+            .response
+            .on_hover_text("Preset previews go here; SysEx dumps always go to the interface above");
+        if selected != target {
+            self.set_preview_target(selected);
+        }
+
+        ui.label(format!(
+            "SysEx dumps go to: {}\nPreviews go to: {}",
+            if self.outputs.is_empty() {
+                "nowhere".to_string()
+            } else {
+                self.outputs.names().join(", ")
+            },
+            self.preview.describe(&self.outputs)
+        ));
+    }
+
+    fn preview_target_label(&self, target: PreviewTarget) -> String {
+        match target {
+            PreviewTarget::DumpOutputs => "Same as SysEx dumps".to_string(),
+            PreviewTarget::Port(index) => self
+                .output_labels
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| format!("Port {}", index + 1)),
+            PreviewTarget::Virtual => "Virtual port".to_string(),
+        }
+    }
+
+    fn set_preview_target(&mut self, target: PreviewTarget) {
+        match self.preview.set_target(target) {
+            Ok(()) => {
+                self.config.preview_target = target;
+                self.config.save();
+            }
+            Err(e) => self.status = Some(format!("Failed to connect the preview output: {}", e)),
+        }
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if let Some(command) = self.recording_shortcut {
+            // The next key press becomes the shortcut; Escape cancels
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                if key != egui::Key::Escape {
+                    self.config
+                        .keybindings
+                        .bind(command, Shortcut::new(modifiers, key));
+                    self.config.save();
+                }
+                self.recording_shortcut = None;
+            }
+            return;
+        }
+
+        for command in self.config.keybindings.triggered(ctx) {
+            self.run_command(command, ctx);
+        }
+    }
+
+    /// Presses simulator footswitches and rocks its expression pedals from
+    /// the keyboard, unless a text field has the keys
+    fn handle_simulator_keys(&mut self, ctx: &egui::Context) {
+        if !self.config.simulator_keys {
+            return;
+        }
+        self.show_simulator_keys(ctx);
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        let (pedal, rock) = ctx.input(|i| {
+            // Leave modified keys to the editing shortcuts
+            if !i.modifiers.is_none() {
+                return (None, None);
+            }
+            let pedal = PEDAL_KEYS.iter().position(|&key| i.key_pressed(key));
+            let rock = if i.key_pressed(EXPRESSION_UP) {
+                Some(true)
+            } else if i.key_pressed(EXPRESSION_DOWN) {
+                Some(false)
+            } else {
+                None
+            };
+            (pedal, rock)
+        });
+        if let Some(pedal) = pedal {
+            self.press_simulator_pedal(pedal);
+        }
+        if let Some(toward_toe) = rock {
+            self.rock_simulator_expression(toward_toe);
+        }
+    }
+
+    /// The keys the simulator listens to, lit while they are held
+    fn show_simulator_keys(&self, ctx: &egui::Context) {
+        egui::Area::new(egui::Id::new("simulator_keys"))
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -8.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "Bank {}",
+                            self.config.numbering.bank(self.simulator.bank)
+                        ));
+                        for (pedal, key) in PEDAL_KEYS.into_iter().enumerate() {
+                            let index = self.simulator.preset_index(pedal);
+                            let held = ctx.input(|i| i.key_down(key))
+                                || self.simulator.selected == Some(index);
+                            ui.add(egui::SelectableLabel::new(held, key.name()));
+                        }
+                        ui.separator();
+                        for (key, symbol) in [(EXPRESSION_DOWN, "↓"), (EXPRESSION_UP, "↑")] {
+                            ui.add(egui::SelectableLabel::new(
+                                ctx.input(|i| i.key_down(key)),
+                                symbol,
+                            ));
+                        }
+                        ui.add(
+                            egui::ProgressBar::new(f32::from(self.simulator.expression) / 127.0)
+                                .desired_width(80.0)
+                                .text(format!("EXP {}", self.simulator.expression)),
+                        );
+                    });
+                });
+            });
+    }
+
+    fn press_simulator_pedal(&mut self, pedal: usize) {
+        let index = self.simulator.preset_index(pedal);
+        self.simulator.selected = Some(index);
+        self.preview_preset(index);
+    }
+
+    /// Sends where the selected preset's expression pedals now stand
+    fn rock_simulator_expression(&mut self, toward_toe: bool) {
+        let Some(index) = self.simulator.selected else {
+            self.status = Some("Press a simulator pedal first".to_string());
+            return;
+        };
+        if !self.simulator.rock(toward_toe) {
+            return;
+        }
+        let messages = {
+            let sysex_message = self.sysex_message.lock().unwrap();
+            expression_messages(
+                sysex_message.preset(index),
+                &self
+                    .metadata
+                    .preview_channels(sysex_message.global_channels()),
+                self.simulator.expression,
+            )
+        };
+        self.simulator.record(&messages);
+        let result = messages
+            .iter()
+            .try_for_each(|message| self.preview.send(&mut self.outputs, message));
+        if let Err(e) = result {
+            self.status = Some(format!("Failed to send the expression pedals: {}", e));
+        }
+        self.report_reconnects();
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if self.locked && !dropped.is_empty() {
+            self.status = Some("Unlock the editor to open dropped files".to_string());
+            return;
+        }
+        for file in dropped {
+            let (path, data) = match (file.path, file.bytes) {
+                (_, Some(bytes)) => (PathBuf::from(&file.name), bytes.to_vec()),
+                (Some(path), None) => match fs::read(&path) {
+                    Ok(data) => (path, data),
+                    Err(e) => {
+                        self.status = Some(format!("Could not read {}: {}", path.display(), e));
+                        continue;
+                    }
+                },
+                (None, None) => continue,
+            };
+
+            let replaces = sniff(&data).is_some_and(FileKind::replaces_project);
+            if replaces && self.dirty.needs_save() {
+                self.held_drop = Some((path, data));
+                continue;
+            }
+            self.open_file(&path, data);
+        }
+    }
+
+    /// Asks whether to save first when a dropped file would replace unsaved
+    /// changes
+    fn show_drop_confirmation(&mut self, ctx: &egui::Context) {
+        let Some((path, _)) = &self.held_drop else {
+            return;
+        };
+        let name = path.display().to_string();
+
+        egui::Window::new("Unsaved changes")
+            .id(egui::Id::new("drop_confirmation"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} has changes that were not saved. Opening {} replaces them.",
+                    self.project_path.display(),
+                    name
+                ));
+                if let Some(summary) = self.dirty.summary() {
+                    ui.weak(summary);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Save and open").clicked() {
+                        self.save_project();
+                        self.refresh_dirty();
+                        if !self.dirty.needs_save() {
+                            if let Some((path, data)) = self.held_drop.take() {
+                                self.open_file(&path, data);
+                            }
+                        }
+                    }
+                    if ui.button("Open without saving").clicked() {
+                        if let Some((path, data)) = self.held_drop.take() {
+                            self.open_file(&path, data);
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.held_drop = None;
+                    }
+                });
+            });
+    }
+
+    /// Opens a dropped file or one given on the command line as whatever its
+    /// content turns out to be
+    fn open_file(&mut self, path: &Path, data: Vec<u8>) {
+        let Some(kind) = sniff(&data) else {
+            self.status = Some(format!(
+                "{} is not a project, bundle, MIDI or SysEx file",
+                path.display()
+            ));
+            return;
+        };
+        self.status = Some(match kind {
+            FileKind::ForeignSysEx => {
+                self.inspected.push(ForeignSysEx::new(path, data));
+                return;
+            }
+            FileKind::Dump => match self.load_dump(&path.display().to_string(), &data) {
+                Some(status) => status,
+                None => return,
+            },
+            FileKind::Project => {
+                let json = String::from_utf8_lossy(&data);
+                match Project::from_json_checked(&json) {
+                    Ok((mut project, integrity)) => {
+                        if integrity == Integrity::Modified {
+                            self.warning = Some(modified_warning(path));
+                        }
+                        if !self.review_import(&path.display().to_string(), &mut project.dump, None)
+                        {
+                            return;
+                        }
+                        // Saving now goes back to the opened file
+                        self.save_layout(self.current_layout());
+                        self.project_path = portable::relative(path);
+                        self.disk_fingerprint = file_fingerprint(path);
+                        self.replace_project(project);
+                        self.mark_saved();
+                        self.restore_layout();
+                        format!("Opened project {}", path.display())
+                    }
+                    Err(e) => format!("Could not open {}: {}", path.display(), e),
+                }
+            }
+            FileKind::Bundle => {
+                self.bundle_path = portable::relative(path).to_string_lossy().into_owned();
+                self.import_bundle();
+                return;
+            }
+            FileKind::MidiFile => match smf::read_sysex(&data) {
+                Ok(messages) => {
+                    let dumps: Vec<SmfSysEx> = messages
+                        .into_iter()
+                        .filter(|message| is_fcb1010_dump(&message.bytes))
+                        .collect();
+                    if dumps.is_empty() {
+                        format!("{} holds no FCB1010 dumps", path.display())
+                    } else {
+                        let count = dumps.len();
+                        self.smf_dumps = Some((path.to_path_buf(), dumps));
+                        format!("Found {} dumps in {}", count, path.display())
+                    }
+                }
+                Err(e) => format!("Could not read {}: {}", path.display(), e),
+            },
+        });
+    }
+
+    /// Decodes a dump and makes it the edited one, as received from the
+    /// device. `None` when the import check stopped it and reported why.
+    fn load_dump(&mut self, source: &str, data: &[u8]) -> Option<String> {
+        match SysExMessage::decode(data, self.config.firmware.clone()) {
+            Ok(mut message) => {
+                if !self.review_import(source, &mut message, Some(data)) {
+                    return None;
+                }
+                message.keep_switches(&self.sysex_message.lock().unwrap());
+                *self.device_message.lock().unwrap() = Some(message.clone());
+                *self.sysex_message.lock().unwrap() = message;
+                self.events.emit(ModelEvent::DumpReplaced);
+                Some(format!("Loaded dump from {}", source))
+            }
+            Err(e) => {
+                let status = format!("Could not decode {}: {}", source, e);
+                *self.decode_failure.lock().unwrap() = Some(DecodeFailure {
+                    source: source.to_string(),
+                    data: data.to_vec(),
+                    error: e,
+                });
+                Some(status)
+            }
+        }
+    }
+
+    /// Lists the dumps found in a MIDI file to pick one to load
+    fn show_smf_dumps(&mut self, ctx: &egui::Context) {
+        let Some((path, dumps)) = &self.smf_dumps else {
+            return;
+        };
+        let mut open = true;
+        let mut load = None;
+        egui::Window::new("Dumps in MIDI file")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!("{}", path.display()));
+                egui::Grid::new("smf_dumps").striped(true).show(ui, |ui| {
+                    for (number, dump) in dumps.iter().enumerate() {
+                        ui.label(format!("Dump {}", number + 1));
+                        ui.label(format!("track {}, tick {}", dump.track + 1, dump.tick));
+                        ui.label(format!("{} bytes", dump.bytes.len()));
+                        if ui
+                            .add_enabled(!self.locked, egui::Button::new("Load"))
+                            .on_disabled_hover_text("Unlock the editor to load a dump")
+                            .clicked()
+                        {
+                            load = Some(number);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        if let Some(number) = load {
+            let source = format!("{} (dump {})", path.display(), number + 1);
+            let data = dumps[number].bytes.clone();
+            self.smf_dumps = None;
+            if let Some(status) = self.load_dump(&source, &data) {
+                self.status = Some(status);
+            }
+        } else if !open {
+            self.smf_dumps = None;
+        }
+    }
+
+    fn show_replace_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.replace_open;
+        egui::Window::new("Find and replace")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!self.locked, |ui| self.show_replace(ui));
+            });
+        self.replace_open = open;
+    }
+
+    fn show_replace(&mut self, ui: &mut egui::Ui) {
+        let query = &mut self.replace_query;
+        let before = *query;
+        egui::ComboBox::from_label("Message")
+            .selected_text(query.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in MessageType::ALL {
+                    ui.selectable_value(&mut query.kind, kind, kind.label());
+                }
+            });
+        ui.horizontal(|ui| {
+            optional_value(ui, "Number", &mut query.number, 0..=127, 0);
+            if query.kind == MessageType::ControlChange {
+                optional_value(ui, "Value", &mut query.value, 0..=127, 0);
+            }
+            optional_value(ui, "Channel", &mut query.channel, 0..=15, 1);
+        });
+        if *query != before {
+            self.replace_hits.clear();
+        }
+
+        if ui.button("Find").clicked() {
+            let sysex_message = self.sysex_message.lock().unwrap();
+            self.replace_hits = replace::find(
+                &sysex_message,
+                &self.replace_query,
+                &self.metadata.locked_banks,
+                self.config.numbering,
+            );
+            if self.replace_hits.is_empty() {
+                self.status = Some("No matching messages".to_string());
+            }
+        }
+        if self.replace_hits.is_empty() {
+            return;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("{} matches", self.replace_hits.len()));
+            if ui.button("Select all").clicked() {
+                self.replace_hits
+                    .iter_mut()
+                    .for_each(|hit| hit.accepted = true);
+            }
+            if ui.button("Select none").clicked() {
+                self.replace_hits
+                    .iter_mut()
+                    .for_each(|hit| hit.accepted = false);
+            }
+        });
+        egui::ScrollArea::vertical()
+            .id_source("replace_hits")
+            .max_height(240.0)
+            .show(ui, |ui| {
+                for hit in &mut self.replace_hits {
+                    ui.checkbox(&mut hit.accepted, &hit.description);
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.label("Replace with:");
+            optional_value(ui, "Number", &mut self.replacement.number, 0..=127, 0);
+            if self.replace_query.kind == MessageType::ControlChange {
+                optional_value(ui, "Value", &mut self.replacement.value, 0..=127, 0);
+            }
+        });
+        ui.label(
+            "Channels belong to functions, not presets, on the FCB1010; \
+             change them in Global Settings.",
+        );
+
+        let accepted = self.replace_hits.iter().filter(|hit| hit.accepted).count();
+        let replaces = self.replacement != Replacement::default();
+        if ui
+            .add_enabled(
+                accepted > 0 && replaces,
+                egui::Button::new(format!("Replace {} selected", accepted)),
+            )
+            .clicked()
+        {
+            let mut sysex_message = self.sysex_message.lock().unwrap();
+            for hit in self.replace_hits.iter().filter(|hit| hit.accepted) {
+                apply_edits(
+                    &self.events,
+                    &mut sysex_message,
+                    hit.index,
+                    hit.edits(&self.replacement),
+                );
+            }
+            self.status = Some(format!("Replaced {} messages", accepted));
+            self.replace_hits.clear();
+        }
+    }
+
+    /// The callout of the current tour step, next to the widget it explains
+    /// with a frame around it. The widgets are marked while the central panel
+    /// is drawn, so this points at where they were in the previous frame.
+    fn show_tour(&mut self, ctx: &egui::Context) {
+        let target = self.tour.take_target();
+        let Some((step, position, steps)) = self.tour.current().map(|(s, p, n)| (*s, p, n)) else {
+            return;
+        };
+
+        let mut area = egui::Area::new(egui::Id::new("tour")).order(egui::Order::Foreground);
+        match target {
+            Some(rect) => {
+                ctx.layer_painter(egui::LayerId::new(
+                    egui::Order::Foreground,
+                    egui::Id::new("tour_highlight"),
+                ))
+                .rect_stroke(
+                    rect.expand(4.0),
+                    4.0,
+                    egui::Stroke::new(2.0, ctx.style().visuals.selection.bg_fill),
+                );
+                // Large targets such as the preset grid get the callout inside
+                area = if rect.height() > TOUR_INSIDE_HEIGHT {
+                    area.fixed_pos(rect.left_top() + egui::vec2(16.0, 16.0))
+                } else {
+                    area.fixed_pos(rect.left_bottom() + egui::vec2(0.0, 8.0))
+                };
+            }
+            // Not on screen, e.g. the preset grid while another tab is open,
+            // or not drawn yet right after the tour started
+            None => {
+                area = area.anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0]);
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+        }
+
+        let mut action = None;
+        area.show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(320.0);
+                ui.strong(step.title);
+                ui.label(step.text);
+                ui.horizontal(|ui| {
+                    ui.weak(format!("{} of {}", position + 1, steps));
+                    if ui
+                        .add_enabled(position > 0, egui::Button::new("Back"))
+                        .clicked()
+                    {
+                        action = Some(TourAction::Back);
+                    }
+                    let next = if position + 1 == steps {
+                        "Done"
+                    } else {
+                        "Next"
+                    };
+                    if ui.button(next).clicked() {
+                        action = Some(TourAction::Next);
+                    }
+                    if ui.button("Skip tour").clicked() {
+                        action = Some(TourAction::Skip);
+                    }
+                });
+            });
+        });
+
+        if action.is_some_and(|action| self.tour.apply(action)) && !self.config.tour_seen {
+            self.config.tour_seen = true;
+            self.config.save();
+        }
+    }
+
+    fn show_paste_window(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.paste_target else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(format!("Paste into preset {}", index + 1))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!self.locked, |ui| self.show_paste(ui, index));
+            });
+        if !open {
+            self.paste_target = None;
+        }
+    }
+
+    fn show_paste(&mut self, ui: &mut egui::Ui, index: usize) {
+        ui.label(
+            "Presets copied from an editor, or one message per line or separated by \";\", \
+             e.g. \"PC 12 ch1; CC 80 127 ch1\".",
+        );
+        ui.add(
+            egui::TextEdit::multiline(&mut self.paste_text)
+                .hint_text("Paste here (Ctrl+V)")
+                .desired_rows(4),
+        );
+        if let Some(clip) = preset_clip::read(&self.paste_text) {
+            if self.show_clip_paste(ui, index, clip) {
+                self.paste_target = None;
+                self.paste_text.clear();
+            }
+            return;
+        }
+
+        let pasted = paste::parse(&self.paste_text);
+        for line in &pasted.used {
+            ui.label(line);
+        }
+        for problem in &pasted.problems {
+            ui.colored_label(egui::Color32::RED, problem);
+        }
+
+        let mut sysex_message = self.sysex_message.lock().unwrap();
+        let globals = *sysex_message.global_channels();
+        for &(function, channel) in &pasted.channels {
+            if globals[function] != channel {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "{} uses channel {} in Global Settings, not {}",
+                        spec::GLOBAL_FUNCTIONS[function],
+                        globals[function].number(),
+                        channel.number()
+                    ),
+                );
+            }
+        }
+
+        let locked = self.metadata.is_preset_locked(index);
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !locked && !pasted.edits.is_empty(),
+                    egui::Button::new("Apply"),
+                )
+                .on_disabled_hover_text("Nothing to apply, or the preset is in a locked bank")
+                .clicked()
+            {
+                // Pasted messages are meant to be sent
+                let slots = pasted.edits.iter().map(|(field, _)| (field.slot(), true));
+                let edits = CardEdits {
+                    slots: slots.collect(),
+                    values: pasted.edits,
+                };
+                apply_card_edits(&self.events, &mut sysex_message, index, edits);
+                close = true;
+            }
+            if ui.button("Cancel").clicked() {
+                close = true;
+            }
+        });
+        if close {
+            self.paste_target = None;
+            self.paste_text.clear();
+        }
+    }
+
+    /// Previews copied presets going in from preset `index` on; true once the
+    /// window can close
+    fn show_clip_paste(
+        &mut self,
+        ui: &mut egui::Ui,
+        index: usize,
+        clip: Result<PresetClip, String>,
+    ) -> bool {
+        let clip = match clip {
+            Ok(clip) => clip,
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+                return ui.button("Cancel").clicked();
+            }
+        };
+
+        let mut sysex_message = self.sysex_message.lock().unwrap();
+        let numbering = self.config.numbering;
+        let count = sysex_message.presets().len();
+        let mut placed = Vec::new();
+        ui.label(format!(
+            "{} copied presets, going into preset {} on:",
+            clip.presets.len(),
+            numbering.preset(index)
+        ));
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                for (offset, preset) in clip.presets.iter().enumerate() {
+                    let target = index + offset;
+                    if target >= count {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!(
+                                "{} more do not fit after the last preset",
+                                clip.presets.len() - offset
+                            ),
+                        );
+                        break;
+                    }
+                    let label = clip.label(offset);
+                    let summary = preset_summary(numbering, target, preset, &label);
+                    if self.metadata.is_preset_locked(target) {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("{} – skipped, its bank is locked", summary),
+                        );
+                        continue;
+                    }
+                    if sysex_message.preset(target) == preset {
+                        ui.weak(format!("{} (values unchanged)", summary));
+                    } else {
+                        ui.label(summary);
+                    }
+                    placed.push((target, *preset, label));
+                }
+            });
+
+        let globals = *sysex_message.global_channels();
+        if let Some(channels) = clip.channels {
+            let differing: Vec<&str> = (0..channels.len())
+                .filter(|&function| channels[function] != globals[function])
+                .map(|function| spec::GLOBAL_FUNCTIONS[function])
+                .collect();
+            if !differing.is_empty() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "Made with other channels for {}; Global Settings keeps this dump's",
+                        differing.join(", ")
+                    ),
+                );
+            }
+        }
+
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!placed.is_empty(), egui::Button::new("Apply"))
+                .on_disabled_hover_text("Every target preset is in a locked bank")
+                .clicked()
+            {
+                let pasted = placed.len();
+                let mut presets = Vec::new();
+                for (target, preset, label) in placed.drain(..) {
+                    *self.metadata.preset_mut(target) = label;
+                    presets.push((target, preset));
+                }
+                replace_presets(&self.events, &mut sysex_message, presets);
+                self.status = Some(format!("Pasted {} presets", pasted));
+                close = true;
+            }
+            if ui.button("Cancel").clicked() {
+                close = true;
+            }
+        });
+        close
+    }
+
+    fn show_inspectors(&mut self, ctx: &egui::Context) {
+        self.inspected.retain(|foreign| {
+            let mut open = true;
+            egui::Window::new(format!("SysEx inspector – {}", foreign.name))
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label("Not an FCB1010 dump; shown read-only.");
+                    let messages = foreign.messages();
+                    ui.label(format!(
+                        "{} bytes, {} SysEx messages",
+                        foreign.data.len(),
+                        messages.len()
+                    ));
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (i, message) in messages.iter().enumerate() {
+                            let manufacturer =
+                                manufacturer_id(message).map_or("None", manufacturer_name);
+                            egui::CollapsingHeader::new(format!(
+                                "Message {}: {} bytes, {}",
+                                i + 1,
+                                message.len(),
+                                manufacturer
+                            ))
+                            .id_source(i)
+                            .show(ui, |ui| {
+                                ui.monospace(hexdump(message));
+                            });
+                        }
+                    });
+                });
+            open
+        });
+    }
+
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        if self.locked && !command.allowed_while_locked() {
+            self.status = Some(format!(
+                "\"{}\" is disabled while the editor is locked",
+                command.label()
+            ));
+            return;
+        }
+
+        match command {
+            Command::SaveProject => self.save_project(),
+            Command::LoadProject => self.load_project(),
+            Command::NewFromFactoryDefaults => self.confirm_factory_defaults = true,
+            Command::SendDump => self.send_dump(),
+            Command::ReceiveDump => self.receive_dump(ctx),
+            Command::ExportBundle => self.export_bundle(),
+            Command::ImportBundle => self.import_bundle(),
+            Command::ShowEditor => self.workspace_view = WorkspaceView::Editor,
+            Command::ShowDeviceVsEditor => self.workspace_view = WorkspaceView::DeviceVsEditor,
+            Command::ToggleLock => self.locked = !self.locked,
+            Command::FindReplace => self.replace_open = true,
+        }
+    }
+
+    fn show_factory_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_factory_defaults {
+            return;
+        }
+
+        let mut replace = false;
+        let mut cancel = false;
+        egui::Window::new("New from factory defaults")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Replace the presets, channels and names with the factory defaults? \
+                     The project file only changes when it is saved.",
+                );
+                ui.horizontal(|ui| {
+                    replace = ui.button("Replace").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if replace || cancel {
+            self.confirm_factory_defaults = false;
+        }
+        if replace {
+            self.replace_project(Project {
+                dump: factory::factory_defaults(),
+                metadata: ProjectMetadata::default(),
+            });
+            self.status = Some("Started from the factory defaults".to_string());
+        }
+    }
+
+    fn save_project(&mut self) {
+        if self.recovery.is_some() {
+            self.warning = Some(
+                "The project file could not be read; choose how to recover it before saving"
+                    .to_string(),
+            );
+            return;
+        }
+        // Someone else (e.g. a sync client) wrote the file since we read it
+        let on_disk = file_fingerprint(&self.project_path);
+        if on_disk.is_some() && on_disk != self.disk_fingerprint {
+            self.confirm_overwrite = true;
+            return;
+        }
+        self.write_project();
+    }
+
+    fn write_project(&mut self) {
+        match self
+            .current_project()
+            .save(&self.project_path, self.disk_fingerprint.as_deref())
+        {
+            Ok(()) => {
+                self.disk_fingerprint = file_fingerprint(&self.project_path);
+                self.mark_saved();
+                self.status = Some("SysEx data saved".to_string());
+                self.send_after_save();
+            }
+            Err(e) => self.status = Some(format!("Failed to save SysEx data: {}", e)),
+        }
+    }
+
+    fn api_address(&self) -> String {
+        match self.config.api_address.trim() {
+            "" => api::DEFAULT_ADDRESS.to_string(),
+            address => address.to_string(),
+        }
+    }
+
+    /// Starts or stops the HTTP API to match the settings and answers what
+    /// came in since the last frame
+    fn serve_api(&mut self, ctx: &egui::Context) {
+        if !self.config.api_enabled {
+            self.api = None;
+            return;
+        }
+        if self.api.is_none() && self.api_error.is_none() {
+            let ctx = ctx.clone();
+            let token = Some(self.config.api_token.clone()).filter(|token| !token.is_empty());
+            match ApiServer::start(&self.api_address(), token, move || ctx.request_repaint()) {
+                Ok(server) => {
+                    self.log
+                        .push(format!("HTTP API listening on {}", server.address));
+                    self.api = Some(server);
+                }
+                Err(e) => self.api_error = Some(e),
+            }
+        }
+        while let Some(request) = self.api.as_ref().and_then(ApiServer::try_recv) {
+            self.answer_api(request);
+        }
+    }
+
+    fn answer_api(&mut self, request: ApiRequest) {
+        let preset_index = |n: usize| (1..=100).contains(&n).then(|| n - 1);
+        let not_found = || json!({ "error": "No such preset" });
+        match request.route.clone() {
+            Route::ListPresets => {
+                let presets: Vec<_> = (0..100).map(|i| self.preset_json(i)).collect();
+                request.respond(200, json!(presets));
+            }
+            Route::GetPreset(n) => match preset_index(n) {
+                Some(i) => {
+                    let preset = self.preset_json(i);
+                    request.respond(200, preset);
+                }
+                None => request.respond(404, not_found()),
+            },
+            Route::SetPreset(n, changes) => {
+                let Some(i) = preset_index(n) else {
+                    return request.respond(404, not_found());
+                };
+                if self.locked || self.metadata.is_preset_locked(i) {
+                    return request.respond(423, json!({ "error": "The preset is locked" }));
+                }
+                let Some(changes) = changes.as_object() else {
+                    return request.respond(400, json!({ "error": "Expected a JSON object" }));
+                };
+                let mut edits = Vec::new();
+                let mut slots = Vec::new();
+                let mut name = None;
+                for (key, value) in changes {
+                    // Slots to switch on or off; the unlisted ones stay as they are
+                    if key == "enabled" || key == "disabled" {
+                        let listed: Option<Vec<usize>> = value.as_array().and_then(|keys| {
+                            keys.iter()
+                                .map(|key| SLOT_KEYS.iter().position(|&k| Some(k) == key.as_str()))
+                                .collect()
+                        });
+                        let Some(listed) = listed else {
+                            return request.respond(
+                                400,
+                                json!({ "error": format!("{} must be a list of slot keys", key) }),
+                            );
+                        };
+                        let enabled = key == "enabled";
+                        if listed.iter().any(|slot| slots.contains(&(*slot, !enabled))) {
+                            return request.respond(
+                                400,
+                                json!({ "error": "A slot is both enabled and disabled" }),
+                            );
+                        }
+                        slots.extend(listed.into_iter().map(|slot| (slot, enabled)));
+                        continue;
+                    }
+                    if key == "name" {
+                        match value.as_str() {
+                            Some(text) => name = Some(text.to_string()),
+                            None => {
+                                return request
+                                    .respond(400, json!({ "error": "name must be a string" }))
+                            }
+                        }
+                        continue;
+                    }
+                    let field = PresetField::from_key(key);
+                    let value = value
+                        .as_u64()
+                        .and_then(|v| u8::try_from(v).ok())
+                        .and_then(|v| Value7::new(v).ok());
+                    match (field, value) {
+                        (Some(field), Some(value)) => edits.push((field, value)),
+                        _ => {
+                            return request.respond(
+                                400,
+                                json!({ "error": format!("{} must be a field set to 0-127", key) }),
+                            )
+                        }
+                    }
+                }
+                apply_card_edits(
+                    &self.events,
+                    &mut self.sysex_message.lock().unwrap(),
+                    i,
+                    CardEdits {
+                        values: edits,
+                        slots,
+                    },
+                );
+                if let Some(name) = name {
+                    self.metadata.preset_mut(i).name = name;
+                }
+                let preset = self.preset_json(i);
+                request.respond(200, preset);
+            }
+            Route::SendDump => {
+                if self.locked {
+                    return request.respond(423, json!({ "error": "The editor is locked" }));
+                }
+                if self.outputs.is_empty() {
+                    return request
+                        .respond(503, json!({ "error": "No MIDI connection available" }));
+                }
+                // Nobody is there to answer the warnings, so they block the send
+                let problems = send_check::check(&self.sysex_message.lock().unwrap());
+                if !problems.is_empty() {
+                    return request.respond(
+                        409,
+                        json!({ "error": "The dump looks broken", "problems": problems }),
+                    );
+                }
+                // The confirmations are for someone at the editor to answer
+                let worn = match self.config.wear_guard {
+                    WearGuard::WarnAfter(writes) => self.config.dumps_written >= writes,
+                    WearGuard::Off => false,
+                };
+                if (worn && !self.wear_quiet) || self.config.preview_writes {
+                    return request.respond(
+                        409,
+                        json!({ "error": "Writes have to be confirmed in the editor" }),
+                    );
+                }
+                let outcome = self.write_dump();
+                self.status = Some(outcome.clone().unwrap_or_else(|e| e));
+                match outcome {
+                    Ok(status) => request.respond(200, json!({ "status": status })),
+                    Err(error) => request.respond(503, json!({ "error": error })),
+                }
+            }
+        }
+    }
+
+    fn preset_json(&mut self, index: usize) -> serde_json::Value {
+        let (bank, pedal) = bank_and_pedal(index);
+        let mut preset = serde_json::Map::new();
+        preset.insert("preset".to_string(), json!(index + 1));
+        preset.insert("bank".to_string(), json!(bank + 1));
+        preset.insert("pedal".to_string(), json!(pedal + 1));
+        preset.insert(
+            "name".to_string(),
+            json!(self.metadata.preset_mut(index).name),
+        );
+        preset.insert(
+            "locked".to_string(),
+            json!(self.metadata.is_preset_locked(index)),
+        );
+        let sysex_message = self.sysex_message.lock().unwrap();
+        for field in PresetField::ALL {
+            preset.insert(
+                field.key().to_string(),
+                json!(sysex_message.preset(index).get(field)),
+            );
+        }
+        let disabled: Vec<&str> = SLOT_KEYS
+            .iter()
+            .enumerate()
+            .filter(|&(slot, _)| !sysex_message.preset(index).is_enabled(slot))
+            .map(|(_, &key)| key)
+            .collect();
+        preset.insert("disabled".to_string(), json!(disabled));
+        serde_json::Value::Object(preset)
+    }
+
+    fn send_after_save(&mut self) {
+        let wanted = match self.config.send_on_save {
+            SendOnSave::Off => false,
+            SendOnSave::Always => true,
+            SendOnSave::WhenChanged => self.dirty.needs_send(),
+        };
+        if !wanted {
+            return;
+        }
+        if self.outputs.is_empty() {
+            self.status = Some("SysEx data saved; not sent, no MIDI connection".to_string());
+        } else if self.config.confirm_send_on_save {
+            self.confirm_send_after_save = true;
+        } else {
+            self.send_saved_dump();
+        }
+    }
+
+    fn send_saved_dump(&mut self) {
+        self.send_dump();
+        if let Some(sent) = &self.status {
+            self.status = Some(format!("SysEx data saved; {}", sent));
+        }
+    }
+
+    fn show_send_after_save_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_send_after_save {
+            return;
+        }
+
+        egui::Window::new("Send to the device?")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("The project was saved. Send the dump to the FCB1010 as well?");
+                ui.horizontal(|ui| {
+                    if ui.button("Send").clicked() {
+                        self.confirm_send_after_save = false;
+                        self.send_saved_dump();
+                    }
+                    if ui.button("Not now").clicked() {
+                        self.confirm_send_after_save = false;
+                    }
+                });
+            });
+    }
+
+    fn load_project(&mut self) {
+        self.status = Some(match Project::load_checked(&self.project_path) {
+            Ok((mut project, integrity)) => {
+                if integrity == Integrity::Modified {
+                    self.warning = Some(modified_warning(&self.project_path));
+                }
+                let source = self.project_path.display().to_string();
+                if !self.review_import(&source, &mut project.dump, None) {
+                    return;
+                }
+                self.replace_project(project);
+                self.mark_saved();
+                self.disk_fingerprint = file_fingerprint(&self.project_path);
+                "SysEx data loaded".to_string()
+            }
+            Err(e) => format!("Failed to load SysEx data: {}", e),
+        });
+    }
+
+    /// Applies the import policy to a dump about to replace the editor's;
+    /// returns false when the import was refused
+    fn review_import(
+        &mut self,
+        source: &str,
+        imported: &mut SysExMessage,
+        raw: Option<&[u8]>,
+    ) -> bool {
+        let current = self.sysex_message.lock().unwrap().clone();
+        let Some(report) = check_import(source, imported, raw, &current, self.config.import_policy)
+        else {
+            return true;
+        };
+        let aborted = report.aborted();
+        self.log.push(report.summary());
+        self.status = Some(report.summary());
+        *self.import_report.lock().unwrap() = Some(report);
+        !aborted
+    }
+
+    fn show_import_report(&mut self, ctx: &egui::Context) {
+        let mut report = self.import_report.lock().unwrap();
+        let Some(shown) = report.as_ref() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut shown_byte = None;
+        egui::Window::new("Import report")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(shown.summary());
+                ui.label(format!("Out-of-range values: {}", shown.policy.label()));
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("import_report")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Value");
+                                ui.strong("Byte");
+                                ui.strong("Found");
+                                ui.strong("Maximum");
+                                ui.strong("Result");
+                                ui.end_row();
+                                for adjustment in &shown.adjustments {
+                                    ui.label(&adjustment.location);
+                                    match adjustment.offset {
+                                        Some(offset) => {
+                                            if ui
+                                                .small_button(format!("0x{:04X}", offset))
+                                                .on_hover_text("Show in the hex viewer")
+                                                .clicked()
+                                            {
+                                                shown_byte = Some(offset);
+                                            }
+                                        }
+                                        None => {
+                                            ui.weak("–");
+                                        }
+                                    }
+                                    ui.label(adjustment.found.to_string());
+                                    ui.label(adjustment.max.to_string());
+                                    ui.label(match adjustment.result {
+                                        Some(value) => value.to_string(),
+                                        None => "not imported".to_string(),
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        if let (Some(offset), Some(raw)) = (shown_byte, &shown.raw) {
+            self.hex_view = Some(HexView {
+                name: shown.source.clone(),
+                data: raw.clone(),
+                offset: Some(offset),
+                scroll: true,
+            });
+        }
+        if !open {
+            *report = None;
+        }
+    }
+
+    /// Says where data that would not decode broke, with a way to look
+    fn show_decode_failure(&mut self, ctx: &egui::Context) {
+        let mut failure = self.decode_failure.lock().unwrap();
+        let Some(shown) = failure.as_ref() else {
+            return;
+        };
+
+        let mut show = false;
+        let mut close = false;
+        egui::Window::new("Could not decode")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} is not a dump:", shown.source));
+                ui.colored_label(egui::Color32::YELLOW, shown.error.to_string());
+                ui.horizontal(|ui| {
+                    show = ui.button("Show in hex viewer").clicked();
+                    close = ui.button("Close").clicked();
+                });
+            });
+
+        if show {
+            self.hex_view = Some(HexView {
+                name: shown.source.clone(),
+                data: shown.data.clone(),
+                offset: Some(shown.error.offset()),
+                scroll: true,
+            });
+        }
+        if show || close {
+            *failure = None;
+        }
+    }
+
+    fn show_hex_view(&mut self, ctx: &egui::Context) {
+        let Some(view) = &mut self.hex_view else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Raw bytes – {}", view.name))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!("{} bytes", view.data.len()));
+                if let Some(offset) = view.offset {
+                    ui.label(format!("Byte {} (0x{:04X}) is marked", offset, offset));
+                }
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                let rows = view.data.len().div_ceil(16);
+                let mut area = egui::ScrollArea::vertical().max_height(400.0);
+                if let Some(offset) = view.offset.filter(|_| view.scroll) {
+                    let spacing = ui.spacing().item_spacing.y;
+                    // A few rows of context above the marked one
+                    let row = (offset / 16).saturating_sub(3);
+                    area = area.vertical_scroll_offset(row as f32 * (row_height + spacing));
+                    view.scroll = false;
+                }
+                area.show_rows(ui, row_height, rows, |ui, rows| {
+                    for row in rows {
+                        let start = row * 16;
+                        let bytes = &view.data[start..(start + 16).min(view.data.len())];
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = 0.0;
+                            ui.monospace(format!("{:08x}: ", start));
+                            for (i, byte) in bytes.iter().enumerate() {
+                                let text =
+                                    egui::RichText::new(format!("{:02x} ", byte)).monospace();
+                                if view.offset == Some(start + i) {
+                                    ui.label(
+                                        text.background_color(egui::Color32::YELLOW)
+                                            .color(egui::Color32::BLACK),
+                                    );
+                                } else {
+                                    ui.label(text);
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+        if !open {
+            self.hex_view = None;
+        }
+    }
+
+    fn show_overwrite_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_overwrite {
+            return;
+        }
+
+        egui::Window::new("Project changed on disk")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was changed by another program since it was opened.",
+                    self.project_path.display()
+                ));
+                ui.label("Saving now would overwrite those changes.");
+                ui.horizontal(|ui| {
+                    if ui.button("Overwrite").clicked() {
+                        self.confirm_overwrite = false;
+                        self.write_project();
+                    }
+                    if ui.button("Reload from disk").clicked() {
+                        self.confirm_overwrite = false;
+                        self.load_project();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_overwrite = false;
+                    }
+                });
+            });
+    }
+
+    /// Sends the dump unless it looks broken, in which case the user is
+    /// asked first
+    fn send_dump(&mut self) {
+        if self.outputs.is_empty() {
+            self.status = Some("No MIDI connection available".to_string());
+            return;
+        }
+        let worn = match self.config.wear_guard {
+            WearGuard::WarnAfter(writes) => self.config.dumps_written >= writes,
+            WearGuard::Off => false,
+        };
+        if worn && !self.wear_quiet {
+            self.status = Some("Not sent yet: confirm another full write".to_string());
+            self.confirm_wear = true;
+            return;
+        }
+        self.check_and_transmit();
+    }
+
+    fn check_and_transmit(&mut self) {
+        let problems = send_check::check(&self.sysex_message.lock().unwrap());
+        if problems.is_empty() {
+            self.preview_and_transmit();
+        } else {
+            self.status = Some(format!(
+                "Not sent yet: the dump has {} problems to review",
+                problems.len()
+            ));
+            self.send_problems = Some(problems);
+        }
+    }
+
+    fn show_wear_warning(&mut self, ctx: &egui::Context) {
+        if !self.confirm_wear {
+            return;
+        }
+
+        let mut send = false;
+        let mut cancel = false;
+        egui::Window::new("Another full write?")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} full dumps were written to the FCB1010 from this installation.",
+                    self.config.dumps_written
+                ));
+                ui.label(
+                    "Its memory takes a limited number of writes, and the FCB1010 only \
+                     accepts whole dumps, so every send rewrites all 100 presets. Collect \
+                     edits and send them once; live preview tries values out without writing \
+                     the device.",
+                );
+                ui.checkbox(
+                    &mut self.wear_quiet,
+                    "Don't ask again until the editor restarts",
+                );
+                ui.horizontal(|ui| {
+                    send = ui.button("Send anyway").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if send || cancel {
+            self.confirm_wear = false;
+        }
+        if send {
+            self.check_and_transmit();
+        } else if cancel {
+            self.wear_quiet = false;
+            self.status = Some("Sending cancelled".to_string());
+        }
+    }
+
+    fn show_send_problems(&mut self, ctx: &egui::Context) {
+        let Some(problems) = &self.send_problems else {
+            return;
+        };
+
+        let mut send = false;
+        let mut cancel = false;
+        egui::Window::new("Check before sending")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("The dump has settings that are probably mistakes:");
+                for problem in problems {
+                    ui.colored_label(egui::Color32::YELLOW, format!("• {}", problem));
+                }
+                ui.horizontal(|ui| {
+                    send = ui.button("Send anyway").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if send || cancel {
+            self.send_problems = None;
+        }
+        if send {
+            self.preview_and_transmit();
+        } else if cancel {
+            self.status = Some("Sending cancelled".to_string());
+        }
+    }
+
+    /// Sends the dump, or opens the write preview first if Settings asks for it
+    fn preview_and_transmit(&mut self) {
+        if !self.config.preview_writes {
+            self.transmit_dump();
+            return;
+        }
+        let message = self.sysex_message.lock().unwrap().clone();
+        // A dump of the wrong size is refused by transmit_dump
+        let Ok(bytes) = message.encode_checked() else {
+            self.transmit_dump();
+            return;
+        };
+        let changed = self.dirty.device_known().then(|| {
+            (
+                self.dirty.unsent.len(),
+                self.dirty.globals_unsent,
+                message.presets().len(),
+            )
+        });
+        let ports = self.outputs.names().into_iter().map(String::from).collect();
+        self.hold_write(
+            PendingWrite::Dump {
+                message: Box::new(message),
+                bytes,
+                changed,
+            },
+            ports,
+        );
+    }
+
+    fn hold_write(&mut self, write: PendingWrite, ports: Vec<String>) {
+        self.write_preview = Some(WritePreview {
+            write,
+            ports,
+            typed: String::new(),
+        });
+        self.status = Some("Not sent yet: confirm the write".to_string());
+    }
+
+    fn show_write_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = &mut self.write_preview else {
+            return;
+        };
+
+        let mut send = false;
+        let mut cancel = false;
+        egui::Window::new("Write to the device?")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let warning = match &preview.write {
+                    PendingWrite::Dump { bytes, changed, .. } => {
+                        ui.label(match *changed {
+                            Some((0, false, _)) => {
+                                "Nothing differs from what the FCB1010 holds".to_string()
+                            }
+                            Some((presets, globals, total)) => format!(
+                                "{} of {} presets{} differ from what the FCB1010 holds",
+                                presets,
+                                total,
+                                if globals {
+                                    " and the global settings"
+                                } else {
+                                    ""
+                                }
+                            ),
+                            None => "What the FCB1010 holds is not known this session".to_string(),
+                        });
+                        ui.label(format!("{} bytes go to:", bytes.len()));
+                        "The dump replaces the whole memory of every device on these ports."
+                    }
+                    PendingWrite::Firmware { messages, .. } => {
+                        ui.label(format!(
+                            "The firmware image, {} SysEx messages, goes to:",
+                            messages.len()
+                        ));
+                        "The bootloader rewrites the pedal's firmware with it; an interrupted \
+                         update can leave the pedal unusable."
+                    }
+                    PendingWrite::Console { bytes } => {
+                        ui.label(format!("{} bytes from the console go to:", bytes.len()));
+                        ui.monospace(format_hex(bytes));
+                        "Undocumented commands can change or erase what the device holds."
+                    }
+                };
+                for port in &preview.ports {
+                    ui.strong(format!("• {}", port));
+                }
+                ui.label(format!(
+                    "{} Type {} to send it.",
+                    warning, WRITE_CONFIRMATION
+                ));
+                let typed = ui.add(
+                    egui::TextEdit::singleline(&mut preview.typed)
+                        .hint_text(WRITE_CONFIRMATION)
+                        .desired_width(120.0),
+                );
+                typed.request_focus();
+                let confirmed = preview.typed.trim() == WRITE_CONFIRMATION;
+                ui.horizontal(|ui| {
+                    send = ui
+                        .add_enabled(confirmed, egui::Button::new("Write"))
+                        .clicked()
+                        || (confirmed
+                            && typed.lost_focus()
+                            && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.write_preview = None;
+            self.status = Some("Sending cancelled".to_string());
+        }
+        if !send {
+            return;
+        }
+        let Some(preview) = self.write_preview.take() else {
+            return;
+        };
+        // Exactly what was previewed, whatever was edited since
+        match preview.write {
+            PendingWrite::Dump { message, bytes, .. } => {
+                let outcome = self.write_encoded(&message, &bytes);
+                self.status = Some(outcome.unwrap_or_else(|e| e));
+            }
+            PendingWrite::Firmware {
+                port,
+                messages,
+                pause,
+            } => self.start_firmware_update(ctx, port, messages, pause),
+            PendingWrite::Console { bytes } => self.send_console_bytes(&bytes),
+        }
+    }
+
+    fn transmit_dump(&mut self) {
+        let outcome = self.write_dump();
+        self.status = Some(outcome.unwrap_or_else(|e| e));
+    }
+
+    /// Sends the dump to every open output and says how that went, in
+    /// words for the status bar
+    fn write_dump(&mut self) -> Result<String, String> {
+        let message = self.sysex_message.lock().unwrap().clone();
+        match message.encode_checked() {
+            Ok(bytes) => self.write_encoded(&message, &bytes),
+            Err(e) => {
+                self.warning = Some(format!("Nothing was sent: {}", e));
+                Err("Sending refused: the dump has the wrong size".to_string())
+            }
+        }
+    }
+
+    /// Sends `message`, the encoded `dump`, to every open output
+    fn write_encoded(&mut self, dump: &SysExMessage, message: &[u8]) -> Result<String, String> {
+        if self.outputs.is_empty() {
+            return Err("No MIDI connection available".to_string());
+        }
+
+        let _transfer = self.transfers.begin(Transfer::Send)?;
+
+        let results = self.outputs.send_each(message);
+        if results.iter().all(|sent| sent.result.is_ok()) {
+            self.dirty.mark_sent(dump);
+            // The device now holds what was sent, read back as a receive would
+            if let Ok(mut sent) = SysExMessage::decode_with(message, dump.firmware().clone()) {
+                sent.keep_switches(dump);
+                *self.device_message.lock().unwrap() = Some(sent);
+            }
+        }
+        if results.iter().any(|sent| sent.result.is_ok()) {
+            self.config.dumps_written += 1;
+            self.config.save();
+        }
+
+        let mut stats = self.transfer_stats.lock().unwrap();
+        for sent in &results {
+            let result = sent.result.as_ref().copied().map_err(|_| ());
+            stats.record_send(&sent.port, message.len(), result, sent.retried);
+        }
+        stats.save(Path::new(STATS_FILE));
+        drop(stats);
+
+        let failed: Vec<String> = results
+            .iter()
+            .filter_map(|sent| {
+                sent.result
+                    .as_ref()
+                    .err()
+                    .map(|e| format!("{}: {}", sent.port, e))
+            })
+            .collect();
+        let outcome = match (failed.is_empty(), results.len()) {
+            (true, 1) => Ok("SysEx message sent".to_string()),
+            (true, count) => Ok(format!("SysEx message sent to {} ports", count)),
+            (false, _) => Err(format!(
+                "Failed to send SysEx message to {}",
+                failed.join(", ")
+            )),
+        };
+        self.report_reconnects();
+        outcome
+    }
+
+    fn receive_dump(&mut self, ctx: &egui::Context) {
+        if let Some(port_index) = self.selected_port {
+            // Held by the receiving thread until the receive window closes
+            let transfer = match self.transfers.begin(Transfer::Receive) {
+                Ok(guard) => guard,
+                Err(e) => {
+                    self.status = Some(e);
+                    return;
+                }
+            };
+
+            let midi_in = match MidiInput::new("MIDI Input") {
+                Ok(midi_in) => midi_in,
+                Err(e) => {
+                    self.status = Some(format!("MIDI input is not available: {}", e));
+                    return;
+                }
+            };
+            let port = midi_in.ports().get(port_index).cloned();
+            if let Some(port) = port {
+                let port_name = midi_in.port_name(&port).unwrap_or_default();
+                let (sender, receiver) = channel();
+                let progress = Arc::clone(&self.receive_progress);
+                progress.lock().unwrap().reset();
+                let repaint = ctx.clone();
+                let connection = midi_in.connect(
+                    &port,
+                    "midir-read-input",
+                    move |_, chunk, _| {
+                        // Some backends hand a long dump over in several chunks
+                        let mut progress = progress.lock().unwrap();
+                        if let Some(message) = progress.push(chunk) {
+                            let took = progress.took().unwrap_or_default();
+                            // Nobody listens any more once the receive window has closed
+                            sender.send((message, took)).ok();
+                        }
+                        repaint.request_repaint();
+                    },
+                    (),
+                );
+                let connection = match connection {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        self.status = Some(format!("Could not open the MIDI input: {}", e));
+                        return;
+                    }
+                };
+
+                self.midi_in_connection = Some(connection);
+                *self.receiving_sysex.lock().unwrap() = true;
+
+                let ctx_clone = ctx.clone();
+                let sysex_message_clone = Arc::clone(&self.sysex_message);
+                let device_message_clone = Arc::clone(&self.device_message);
+                let events = self.events.clone();
+                let receiving_sysex_clone = Arc::clone(&self.receiving_sysex);
+                let log = self.log.clone();
+                let firmware = self.config.firmware.clone();
+                let policy = self.config.import_policy;
+                let import_report = Arc::clone(&self.import_report);
+                let decode_failure = Arc::clone(&self.decode_failure);
+                let choose_banks = self.config.choose_received_banks;
+                let bank_merge = Arc::clone(&self.bank_merge);
+                let highlights = Arc::clone(&self.highlights);
+                let stats = Arc::clone(&self.transfer_stats);
+                let record = move |bytes: usize, result: Result<Duration, ()>| {
+                    let mut stats = stats.lock().unwrap();
+                    stats.record_receive(&port_name, bytes, result);
+                    stats.save(Path::new(STATS_FILE));
+                };
+
+                std::thread::spawn(move || {
+                    let _transfer = transfer;
+                    let mut accepted: Option<(Vec<u8>, Instant)> = None;
+                    loop {
+                        // Wait indefinitely for the first dump, then only until the window closes
+                        let message = match &accepted {
+                            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                            Some((_, since)) => receiver
+                                .recv_timeout(RECEIVE_WINDOW.saturating_sub(since.elapsed())),
+                        };
+                        let Ok((message, took)) = message else {
+                            break;
+                        };
+
+                        if let Some((previous, _)) = &accepted {
+                            if *previous == message {
+                                log.push(format!(
+                                    "Ignored a repeated dump of {} bytes",
+                                    message.len()
+                                ));
+                                continue;
+                            }
+                        }
+
+                        match SysExMessage::decode(&message, firmware.clone()) {
+                            Ok(mut sysex_message) => {
+                                record(message.len(), Ok(took));
+                                log.push(format!(
+                                    "Received a dump of {} bytes ({} layout)",
+                                    message.len(),
+                                    sysex_message.firmware().label()
+                                ));
+                                let current = sysex_message_clone.lock().unwrap().clone();
+                                let report = check_import(
+                                    "the received dump",
+                                    &mut sysex_message,
+                                    Some(&message),
+                                    &current,
+                                    policy,
+                                );
+                                if let Some(report) = report {
+                                    log.push(report.summary());
+                                    let aborted = report.aborted();
+                                    *import_report.lock().unwrap() = Some(report);
+                                    ctx_clone.request_repaint();
+                                    if aborted {
+                                        continue;
+                                    }
+                                }
+                                sysex_message.keep_switches(&current);
+                                *device_message_clone.lock().unwrap() = Some(sysex_message.clone());
+                                let mut editor = sysex_message_clone.lock().unwrap();
+                                if choose_banks {
+                                    // The UI thread applies the banks the user picks
+                                    let merge = BankMerge::new(sysex_message, &editor);
+                                    // Never hold the editor while taking the merge lock
+                                    drop(editor);
+                                    *bank_merge.lock().unwrap() = Some(merge);
+                                } else {
+                                    *highlights.lock().unwrap() = Some(Highlights {
+                                        fields: editor
+                                            .changed_fields(&sysex_message)
+                                            .into_iter()
+                                            .collect(),
+                                        until: Instant::now() + HIGHLIGHT_DURATION,
+                                    });
+                                    *editor = sysex_message;
+                                    drop(editor);
+                                    events.emit(ModelEvent::DumpReplaced);
+                                }
+                                *receiving_sysex_clone.lock().unwrap() = false;
+                                ctx_clone.request_repaint();
+                                accepted = Some((message, Instant::now()));
+                            }
+                            Err(e) => {
+                                record(message.len(), Err(()));
+                                log.push(format!("Could not decode received SysEx: {}", e));
+                                *decode_failure.lock().unwrap() = Some(DecodeFailure {
+                                    source: "the received dump".to_string(),
+                                    data: message,
+                                    error: e,
+                                });
+                                ctx_clone.request_repaint();
+                            }
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    /// Gives up on a dump that stopped arriving part of the way through
+    fn abort_stalled_receive(&mut self) {
+        let mut progress = self.receive_progress.lock().unwrap();
+        if !progress.stalled(STALL_TIMEOUT) {
+            return;
+        }
+        let received = progress.received();
+        progress.reset();
+        drop(progress);
+
+        *self.receiving_sysex.lock().unwrap() = false;
+        self.midi_in_connection = None;
+        let message = format!(
+            "The dump stopped after {} bytes and nothing arrived for {} seconds, so receiving \
+             was aborted. Check the MIDI cable and that the interface passes SysEx (some drop \
+             long messages), then receive again and restart the dump on the FCB1010.",
+            received,
+            STALL_TIMEOUT.as_secs()
+        );
+        self.log.push(message.clone());
+        self.warning = Some(message);
+    }
+
+    fn show_bank_merge(&mut self, ctx: &egui::Context) {
+        let mut pending = self.bank_merge.lock().unwrap();
+        let Some(merge) = pending.as_mut() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+        let mut discard = false;
+        let mut sysex_message = self.sysex_message.lock().unwrap();
+        let counts = differences(&sysex_message, &merge.incoming);
+        egui::Window::new("Apply received banks")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Choose the banks to take from the device; the others keep their edits.");
+                ui.horizontal(|ui| {
+                    if ui.button("Select changed").clicked() {
+                        merge.banks = (0..BANKS).filter(|&bank| counts[bank] > 0).collect();
+                    }
+                    if ui.button("Select none").clicked() {
+                        merge.banks.clear();
+                    }
+                });
+                egui::Grid::new("bank_merge").striped(true).show(ui, |ui| {
+                    for (bank, &count) in counts.iter().enumerate() {
+                        let mut selected = merge.banks.contains(&bank);
+                        let locked = self.metadata.is_bank_locked(bank);
+                        let checkbox = ui
+                            .add_enabled(
+                                !locked,
+                                egui::Checkbox::new(&mut selected, format!("Bank {}", bank + 1)),
+                            )
+                            .on_disabled_hover_text("The bank is locked");
+                        if checkbox.changed() {
+                            if selected {
+                                merge.banks.insert(bank);
+                            } else {
+                                merge.banks.remove(&bank);
+                            }
+                        }
+                        ui.label(match count {
+                            0 => "unchanged".to_string(),
+                            count => format!("{} values differ", count),
+                        });
+                        ui.end_row();
+                    }
+                });
+                ui.add_enabled(
+                    merge.globals_differ(&sysex_message),
+                    egui::Checkbox::new(
+                        &mut merge.globals,
+                        "Also take the global channels and switch modes",
+                    ),
+                );
+                ui.horizontal(|ui| {
+                    apply = ui.button("Apply").clicked();
+                    discard = ui.button("Discard").clicked();
+                });
+            });
+
+        if apply {
+            let locked = |bank| self.metadata.is_bank_locked(bank);
+            *self.highlights.lock().unwrap() = Some(Highlights {
+                fields: merge
+                    .changed_fields(&sysex_message, locked)
+                    .into_iter()
+                    .collect(),
+                until: Instant::now() + HIGHLIGHT_DURATION,
+            });
+            replace_presets(&self.events, &mut sysex_message, merge.presets(locked));
+            if merge.globals {
+                let mut changed = false;
+                for (function, &channel) in merge.incoming.global_channels().iter().enumerate() {
+                    changed |= sysex_message.set_global_channel(function, channel);
+                }
+                for (switch, &mode) in merge.incoming.switch_modes().iter().enumerate() {
+                    changed |= sysex_message.set_switch_mode(switch, mode);
+                }
+                if changed {
+                    self.events.emit(ModelEvent::GlobalsChanged);
+                }
+            }
+            self.status = Some(format!(
+                "Applied {} received banks",
+                merge.banks.iter().filter(|&&bank| !locked(bank)).count()
+            ));
+        }
+        if apply || discard || !open {
+            *pending = None;
+        }
+    }
+
+    fn show_reference_documents(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("reference_documents")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("Reference projects");
+                ui.label("Drag a bank or preset onto the editor to copy it");
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.reference_path);
+                    if ui.button("Open").clicked() {
+                        let path = PathBuf::from(&self.reference_path);
+                        match Project::load(&path) {
+                            Ok(project) => {
+                                self.reference_documents
+                                    .push(ReferenceDocument { path, project });
+                                self.selected_reference = self.reference_documents.len() - 1;
+                            }
+                            Err(e) => self.status = Some(format!("Failed to open project: {}", e)),
+                        }
+                    }
+                });
+
+                let mut close = None;
+                ui.horizontal_wrapped(|ui| {
+                    for (i, document) in self.reference_documents.iter().enumerate() {
+                        let title = document
+                            .path
+                            .file_name()
+                            .map_or_else(|| "Untitled".into(), |n| n.to_string_lossy());
+                        ui.selectable_value(&mut self.selected_reference, i, title);
+                        if ui.small_button("x").clicked() {
+                            close = Some(i);
+                        }
+                    }
+                });
+                if let Some(i) = close {
+                    self.reference_documents.remove(i);
+                    self.selected_reference = self
+                        .selected_reference
+                        .min(self.reference_documents.len().saturating_sub(1));
+                }
+
+                let Some(document) = self.reference_documents.get(self.selected_reference) else {
+                    return;
+                };
+                let project = &document.project;
+                let numbering = self.config.numbering;
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("adopt_category")
+                        .selected_text(self.adopt_category.label())
+                        .show_ui(ui, |ui| {
+                            for category in SyncCategory::ALL {
+                                ui.selectable_value(
+                                    &mut self.adopt_category,
+                                    category,
+                                    category.label(),
+                                );
+                            }
+                        });
+                    if ui
+                        .add_enabled(!self.locked, egui::Button::new("Adopt"))
+                        .on_hover_text(
+                            "Take only these settings from the reference; everything else \
+                             keeps its values",
+                        )
+                        .on_disabled_hover_text("Unlock the editor to change the project")
+                        .clicked()
+                    {
+                        let mut sysex_message = self.sysex_message.lock().unwrap();
+                        let sync = field_sync::adopt(
+                            self.adopt_category,
+                            &project.dump,
+                            &sysex_message,
+                            |bank| self.metadata.is_bank_locked(bank),
+                        );
+                        self.status = Some(if sync.is_empty() {
+                            format!(
+                                "{} already match the reference",
+                                self.adopt_category.label()
+                            )
+                        } else {
+                            format!(
+                                "Adopted {} from {}: {} presets changed",
+                                self.adopt_category.label().to_lowercase(),
+                                document.path.display(),
+                                sync.presets.len()
+                            )
+                        });
+                        *self.highlights.lock().unwrap() = Some(Highlights {
+                            fields: sync.changed.into_iter().collect(),
+                            until: Instant::now() + HIGHLIGHT_DURATION,
+                        });
+                        replace_presets(&self.events, &mut sysex_message, sync.presets);
+                        let mut changed = false;
+                        if let Some(channels) = sync.channels {
+                            for (function, channel) in channels.into_iter().enumerate() {
+                                changed |= sysex_message.set_global_channel(function, channel);
+                            }
+                        }
+                        if let Some(modes) = sync.switch_modes {
+                            for (switch, mode) in modes.into_iter().enumerate() {
+                                changed |= sysex_message.set_switch_mode(switch, mode);
+                            }
+                        }
+                        if changed {
+                            self.events.emit(ModelEvent::GlobalsChanged);
+                        }
+                    }
+                });
+                if !self.metadata.locked_banks.is_empty() {
+                    ui.weak("Adopting leaves presets in locked banks alone");
+                }
+                let metadata_of = |index: usize| {
+                    project
+                        .metadata
+                        .presets
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_default()
+                };
+
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for bank in 0..project.dump.presets().len() / PEDALS_PER_BANK {
+                        let first = bank * PEDALS_PER_BANK;
+                        let bank_presets: Vec<(Preset, PresetMetadata)> = (first
+                            ..first + PEDALS_PER_BANK)
+                            .map(|index| (*project.dump.preset(index), metadata_of(index)))
+                            .collect();
+
+                        let id = egui::Id::new(("reference_bank", self.selected_reference, bank));
+                        ui.dnd_drag_source(id, PresetDrag::Bank(bank_presets), |ui| {
+                            ui.strong(format!("Bank {}", numbering.bank(bank)));
+                        });
+
+                        ui.indent(id, |ui| {
+                            for index in first..first + PEDALS_PER_BANK {
+                                let metadata = metadata_of(index);
+                                let label = if metadata.name.is_empty() {
+                                    format!("Preset {}", numbering.preset(index))
+                                } else {
+                                    format!(
+                                        "Preset {} – {}",
+                                        numbering.preset(index),
+                                        metadata.name
+                                    )
+                                };
+                                let id = egui::Id::new((
+                                    "reference_preset",
+                                    self.selected_reference,
+                                    index,
+                                ));
+                                let drag =
+                                    PresetDrag::Preset(*project.dump.preset(index), metadata);
+                                ui.dnd_drag_source(id, drag, |ui| {
+                                    ui.label(label);
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+    }
+
+    fn show_broadcast_ports(&mut self, ui: &mut egui::Ui) {
+        if self.available_outputs.len() < 2 {
+            return;
+        }
+
+        let primary_output = self.selected_output();
+        ui.collapsing("Also send to", |ui| {
+            for (index, port) in self.output_labels.iter().enumerate() {
+                let primary = primary_output == Some(index);
+                let mut enabled = self.outputs.contains(index);
+                let checkbox = ui.add_enabled(!primary, egui::Checkbox::new(&mut enabled, port));
+                if checkbox.changed() {
+                    if enabled {
+                        if let Err(e) = self.outputs.connect(index) {
+                            self.status = Some(format!("Failed to connect to {}: {}", port, e));
+                        }
+                    } else {
+                        self.outputs.disconnect(index);
+                    }
+                }
+            }
+        });
+    }
+
+    fn export_bundle(&mut self) {
+        // The raw dump is the last one received or sent, exactly as it went
+        // over the cable
+        let raw_dump = self
+            .device_message
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|device| device.original_data().map(<[u8]>::to_vec));
+        if self.bundle_encrypt && self.bundle_passphrase.is_empty() {
+            self.status = Some("Enter a passphrase to encrypt the bundle".to_string());
+            return;
+        }
+        let passphrase = self
+            .bundle_encrypt
+            .then_some(self.bundle_passphrase.as_str());
+        let bundle = Bundle::new(self.current_project(), raw_dump);
+        self.status = Some(
+            match bundle.write(&self.config.project_file(&self.bundle_path), passphrase) {
+                Ok(()) if passphrase.is_some() => {
+                    format!("Encrypted bundle exported to {}", self.bundle_path)
+                }
+                Ok(()) => format!("Bundle exported to {}", self.bundle_path),
+                Err(e) => format!("Failed to export bundle: {}", e),
+            },
+        );
+    }
+
+    /// Opens the bundle, first asking for its passphrase if it is encrypted
+    fn import_bundle(&mut self) {
+        match Bundle::is_encrypted(&self.config.project_file(&self.bundle_path)) {
+            Ok(true) => self.bundle_unlock = Some(String::new()),
+            Ok(false) => self.read_bundle(None),
+            Err(e) => self.status = Some(format!("Failed to import bundle: {}", e)),
+        }
+    }
+
+    fn show_recovery(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = &mut self.recovery else {
+            return;
+        };
+
+        let mut restore = None;
+        let mut fresh = false;
+        egui::Window::new("Project could not be read")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{}: {}", recovery.path.display(), recovery.error),
+                );
+                ui.label(
+                    "Nothing has been written to the file. Whatever you choose, it is kept \
+                     under a new name next to where it is.",
+                );
+
+                ui.checkbox(&mut recovery.show_raw, "Show the file as it is");
+                if recovery.show_raw {
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            ui.add(
+                                egui::TextEdit::multiline(&mut recovery.raw.as_str())
+                                    .code_editor()
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                    if ui.button("Copy to clipboard").clicked() {
+                        ui.output_mut(|o| o.copied_text = recovery.raw.clone());
+                    }
+                }
+
+                ui.separator();
+                if recovery.candidates.is_empty() {
+                    ui.label("No backup or stored device dump was found to restore from.");
+                }
+                for (index, candidate) in recovery.candidates.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore").clicked() {
+                            restore = Some(index);
+                        }
+                        ui.label(&candidate.label);
+                    });
+                }
+                fresh = ui
+                    .button("Start fresh")
+                    .on_hover_text("Open an empty project")
+                    .clicked();
+            });
+
+        if restore.is_none() && !fresh {
+            return;
+        }
+        let aside = match recovery.set_aside() {
+            Ok(aside) => aside,
+            Err(e) => {
+                self.warning = Some(format!("Could not move the damaged file aside: {}", e));
+                return;
+            }
+        };
+        let recovery = self.recovery.take().unwrap();
+        let project = match restore {
+            Some(index) => recovery.candidates.into_iter().nth(index).unwrap().project,
+            None => Project::default(),
+        };
+        self.replace_project(project);
+        self.disk_fingerprint = None;
+        self.log.push(format!(
+            "Kept the damaged {} as {}",
+            recovery.path.display(),
+            aside.display()
+        ));
+        self.status = Some(match restore {
+            Some(_) => "Project restored; save it to keep it".to_string(),
+            None => "Started a new project".to_string(),
+        });
+    }
+
+    fn show_bundle_unlock(&mut self, ctx: &egui::Context) {
+        let Some(passphrase) = &mut self.bundle_unlock else {
+            return;
+        };
+
+        let mut open = false;
+        let mut cancel = false;
+        egui::Window::new("Encrypted bundle")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} is encrypted. Passphrase:", self.bundle_path));
+                let response = ui.add(egui::TextEdit::singleline(passphrase).password(true));
+                response.request_focus();
+                let entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    open = ui.button("Open").clicked() || entered;
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if open {
+            if let Some(passphrase) = self.bundle_unlock.take() {
+                self.read_bundle(Some(&passphrase));
+            }
+        } else if cancel {
+            self.bundle_unlock = None;
+            self.status = Some("Bundle import cancelled".to_string());
+        }
+    }
+
+    fn read_bundle(&mut self, passphrase: Option<&str>) {
+        self.status = Some(
+            match Bundle::read(&self.config.project_file(&self.bundle_path), passphrase) {
+                Ok(mut bundle) => {
+                    let source = self.bundle_path.clone();
+                    if !self.review_import(&source, &mut bundle.project.dump, None) {
+                        return;
+                    }
+                    let firmware = FirmwareSetting::Fixed(bundle.project.dump.firmware().clone());
+                    let device = bundle
+                        .raw_dump
+                        .as_deref()
+                        .and_then(|raw| SysExMessage::decode(raw, firmware).ok());
+                    *self.device_message.lock().unwrap() = device;
+                    self.replace_project(bundle.project);
+                    format!(
+                        "Bundle imported (created {} by version {})",
+                        bundle.manifest.created, bundle.manifest.app_version
+                    )
+                }
+                Err(e) => format!("Failed to import bundle: {}", e),
+            },
+        );
+    }
+
+    fn show_bundle_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Bundle:");
+            ui.text_edit_singleline(&mut self.bundle_path);
+
+            if ui
+                .button(format!("Export bundle (.{})", BUNDLE_EXTENSION))
+                .clicked()
+            {
+                self.export_bundle();
+            }
+
+            if ui.button("Import bundle").clicked() {
+                self.import_bundle();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.bundle_encrypt, "Encrypt exported bundles")
+                .on_hover_text("AES-256; the passphrase is never saved, so keep it somewhere safe");
+            ui.add_enabled(
+                self.bundle_encrypt,
+                egui::TextEdit::singleline(&mut self.bundle_passphrase)
+                    .password(true)
+                    .hint_text("Passphrase"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Annotated dump:");
+            ui.text_edit_singleline(&mut self.annotated_dump_path);
+            if ui
+                .button("Export annotated dump")
+                .on_hover_text("A hexdump of the encoded dump naming the field of every byte")
+                .clicked()
+            {
+                let text = annotate::annotated_dump(
+                    &self.sysex_message.lock().unwrap(),
+                    self.config.numbering,
+                );
+                let path = self.config.project_file(&self.annotated_dump_path);
+                self.status = Some(match fs::write(path, text) {
+                    Ok(()) => format!("Annotated dump written to {}", self.annotated_dump_path),
+                    Err(e) => format!("Failed to write the annotated dump: {}", e),
+                });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Web page:");
+            ui.text_edit_singleline(&mut self.web_export_path);
+            if ui
+                .button("Export interactive HTML")
+                .on_hover_text(
+                    "A searchable page of every bank and preset that opens in any browser, \
+                     for bandmates without the editor",
+                )
+                .clicked()
+            {
+                let dictionary =
+                    CcDictionary::new(self.metadata.cc_profile, &self.config.custom_cc_names);
+                let html = web_export::interactive_html(
+                    &self.sysex_message.lock().unwrap(),
+                    &self.metadata,
+                    &dictionary,
+                    self.config.numbering,
+                );
+                self.status = Some(
+                    match fs::write(self.config.project_file(&self.web_export_path), html) {
+                        Ok(()) => format!("Web page written to {}", self.web_export_path),
+                        Err(e) => format!("Failed to write the web page: {}", e),
+                    },
+                );
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("UnO2 script:");
+            ui.text_edit_singleline(&mut self.uno2_export_path);
+            if ui
+                .button("Export UnO2 script")
+                .on_hover_text(
+                    "Translate the presets into a configuration script for the UnO2 firmware; \
+                     settings without an UnO2 equivalent are flagged in it",
+                )
+                .clicked()
+            {
+                let export =
+                    uno2_export::uno2_script(&self.sysex_message.lock().unwrap(), &self.metadata);
+                let path = self.config.project_file(&self.uno2_export_path);
+                match fs::write(path, export.script) {
+                    Ok(()) if export.unmapped.is_empty() => {
+                        self.status = Some(format!("UnO2 script written to {}", self.uno2_export_path));
+                    }
+                    Ok(()) => {
+                        self.warning = Some(format!(
+                            "UnO2 script written to {}, but {} settings could not be translated: {}",
+                            self.uno2_export_path,
+                            export.unmapped.len(),
+                            export.unmapped.join("; ")
+                        ));
+                    }
+                    Err(e) => self.status = Some(format!("Failed to write the UnO2 script: {}", e)),
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Mapping rules:");
+            ui.text_edit_singleline(&mut self.mapping_export_path);
+            if ui
+                .button("Export mapping rules")
+                .on_hover_text(
+                    "List what every pedal sends as incoming patterns for Bome MIDI Translator \
+                     or other mapping software, to fill in the outgoing side there",
+                )
+                .clicked()
+            {
+                let export = mapping_export::mapping_rules(
+                    &self.sysex_message.lock().unwrap(),
+                    &self.metadata,
+                    self.config.numbering,
+                );
+                let path = self.config.project_file(&self.mapping_export_path);
+                self.status = Some(match fs::write(path, export.text) {
+                    Ok(()) if export.ambiguous.is_empty() => {
+                        format!("Mapping rules written to {}", self.mapping_export_path)
+                    }
+                    Ok(()) => format!(
+                        "Mapping rules written to {}; {} groups of presets start with the \
+                         same message, listed at the end",
+                        self.mapping_export_path,
+                        export.ambiguous.len()
+                    ),
+                    Err(e) => format!("Failed to write the mapping rules: {}", e),
+                });
+            }
+        });
+    }
+
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Settings").show(ui, |ui| {
+            let before = self.config.value_widget;
+            egui::ComboBox::from_label("Value editor")
+                .selected_text(self.config.value_widget.label())
+                .show_ui(ui, |ui| {
+                    for widget in ValueWidget::ALL {
+                        ui.selectable_value(&mut self.config.value_widget, widget, widget.label());
+                    }
+                });
+            if self.config.value_widget != before {
+                self.config.save();
+            }
+
+            let before = self.config.number_base;
+            egui::ComboBox::from_label("MIDI values in")
+                .selected_text(self.config.number_base.label())
+                .show_ui(ui, |ui| {
+                    for base in NumberBase::ALL {
+                        ui.selectable_value(&mut self.config.number_base, base, base.label());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "For preset values, the pedal monitor and the SysEx console; tooltips \
+                     show both",
+                );
+            if self.config.number_base != before {
+                self.config.save();
+            }
+
+            let before = self.config.numbering;
+            egui::ComboBox::from_label("Preset numbering")
+                .selected_text(self.config.numbering.label())
+                .show_ui(ui, |ui| {
+                    for numbering in Numbering::ALL {
+                        ui.selectable_value(
+                            &mut self.config.numbering,
+                            numbering,
+                            numbering.label(),
+                        );
+                    }
+                });
+            if self.config.numbering != before {
+                self.config.save();
+            }
+
+            if ui
+                .checkbox(&mut self.config.live_preview, "Live preview")
+                .on_hover_text("Send values to the preview output while they are edited")
+                .changed()
+            {
+                self.config.save();
+            }
+            ui.add_enabled_ui(self.config.live_preview, |ui| {
+                ui.horizontal(|ui| {
+                    let before = self.config.live_preview_rate;
+                    let mut limited = before != PreviewRate::Unlimited;
+                    ui.checkbox(&mut limited, "Send each controller at most every")
+                        .on_hover_text("Dragging a value sends the newest one when the time is up");
+                    let mut milliseconds = match before {
+                        PreviewRate::Every(milliseconds) => milliseconds,
+                        PreviewRate::Unlimited => LIVE_PREVIEW_INTERVAL.as_millis() as u64,
+                    };
+                    ui.add_enabled(
+                        limited,
+                        egui::DragValue::new(&mut milliseconds)
+                            .clamp_range(5..=1000)
+                            .suffix(" ms"),
+                    );
+                    self.config.live_preview_rate = if limited {
+                        PreviewRate::Every(milliseconds)
+                    } else {
+                        PreviewRate::Unlimited
+                    };
+                    if self.config.live_preview_rate != before {
+                        self.config.save();
+                    }
+                });
+            });
+
+            ui.horizontal(|ui| {
+                let before = self.config.port_scan;
+                let mut automatic = before != PortScan::Manual;
+                ui.checkbox(&mut automatic, "Re-scan MIDI ports every");
+                let mut seconds = match before {
+                    PortScan::Every(seconds) => seconds,
+                    PortScan::Manual => PORT_SCAN_INTERVAL.as_secs(),
+                };
+                ui.add_enabled(
+                    automatic,
+                    egui::DragValue::new(&mut seconds)
+                        .clamp_range(1..=600)
+                        .suffix(" s"),
+                );
+                self.config.port_scan = if automatic {
+                    PortScan::Every(seconds)
+                } else {
+                    PortScan::Manual
+                };
+                if self.config.port_scan != before {
+                    self.config.save();
+                }
+            });
+            ui.horizontal(|ui| {
+                let before = self.config.wear_guard;
+                let mut guarded = before != WearGuard::Off;
+                ui.checkbox(&mut guarded, "Warn before full writes after")
+                    .on_hover_text("The FCB1010's memory takes a limited number of writes");
+                let mut writes = match before {
+                    WearGuard::WarnAfter(writes) => writes,
+                    WearGuard::Off => WEAR_WARNING_WRITES,
+                };
+                ui.add_enabled(
+                    guarded,
+                    egui::DragValue::new(&mut writes)
+                        .clamp_range(1..=1_000_000)
+                        .suffix(" writes"),
+                );
+                self.config.wear_guard = if guarded {
+                    WearGuard::WarnAfter(writes)
+                } else {
+                    WearGuard::Off
+                };
+                if self.config.wear_guard != before {
+                    self.config.save();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} full dumps written from this installation",
+                    self.config.dumps_written
+                ));
+                if ui
+                    .button("Reset")
+                    .on_hover_text("E.g. after moving to another FCB1010")
+                    .clicked()
+                {
+                    self.config.dumps_written = 0;
+                    self.config.save();
+                }
+            });
+
+            if cfg!(windows) {
+                let before = self.config.midi_backend;
+                egui::ComboBox::from_label("MIDI backend")
+                    .selected_text(self.config.midi_backend.label())
+                    .show_ui(ui, |ui| {
+                        for backend in MidiBackend::ALL {
+                            ui.selectable_value(
+                                &mut self.config.midi_backend,
+                                backend,
+                                backend.label(),
+                            )
+                            .on_hover_text(backend.description());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Some interfaces only deliver large SysEx reliably over one of them",
+                    );
+                if self.config.midi_backend != before {
+                    self.config.save();
+                    if self.config.midi_backend == MidiBackend::BUILT {
+                        self.warning = None;
+                        if self.midi_started {
+                            self.restart_midi();
+                        }
+                    } else {
+                        self.check_midi_backend();
+                    }
+                }
+            }
+            if ui
+                .checkbox(&mut self.config.show_port_ids, "Show port IDs")
+                .on_hover_text("Tell identical interfaces apart by their position in the port list")
+                .changed()
+            {
+                self.config.save();
+                self.update_port_labels();
+            }
+
+            if let Some(root) = portable::root() {
+                ui.label(format!(
+                    "Portable mode: settings, backups and projects are kept in {}",
+                    root.display()
+                ));
+            }
+            egui::Grid::new("default_folders").show(ui, |ui| {
+                let folders = [
+                    (
+                        "Project folder:",
+                        &mut self.config.project_dir,
+                        "Current directory",
+                        "Used for file names given without a folder",
+                    ),
+                    (
+                        "SysEx folder:",
+                        &mut self.config.syx_dir,
+                        "Current directory",
+                        "Used for file names given without a folder",
+                    ),
+                    (
+                        "Backup folder:",
+                        &mut self.config.backup_dir,
+                        daemon::DEFAULT_BACKUP_DIR,
+                        "Where the backup daemon writes its dumps and where recovery \
+                         looks for them, unless --backup-dir is given",
+                    ),
+                ];
+                let mut changed = false;
+                for (label, folder, hint, hover) in folders {
+                    ui.label(label);
+                    let edit = ui
+                        .add(
+                            egui::TextEdit::singleline(folder)
+                                .hint_text(hint)
+                                .desired_width(220.0),
+                        )
+                        .on_hover_text(hover);
+                    changed |=
+                        edit.changed() || (edit.lost_focus() && portable::make_relative(folder));
+                    ui.end_row();
+                }
+                if changed {
+                    self.config.save();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Your name in the project history:");
+                let fallback = self.author();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.config.author)
+                        .hint_text(fallback)
+                        .desired_width(140.0),
+                );
+                if response.changed() {
+                    self.config.save();
+                }
+            });
+
+            let before = (
+                self.config.api_enabled,
+                self.config.api_address.clone(),
+                self.config.api_token.clone(),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.config.api_enabled, "HTTP API on")
+                    .on_hover_text(
+                        "GET /presets, GET and PUT /presets/<n>, POST /dump/send, \
+                         for home automation and show control",
+                    );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.config.api_address)
+                        .hint_text(api::DEFAULT_ADDRESS)
+                        .desired_width(140.0),
+                );
+            });
+            if self.config.api_enabled {
+                ui.horizontal(|ui| {
+                    let mut required = !self.config.api_token.is_empty();
+                    let response = ui.checkbox(&mut required, "Require a token").on_hover_text(
+                        "Requests send it as \"Authorization: Bearer <token>\". \
+                         Without one only programs on this computer can connect.",
+                    );
+                    if response.changed() {
+                        self.config.api_token = if required {
+                            api::new_token()
+                        } else {
+                            String::new()
+                        };
+                    }
+                    if required {
+                        ui.monospace(&self.config.api_token);
+                        if ui
+                            .small_button("📋")
+                            .on_hover_text("Copy the token")
+                            .clicked()
+                        {
+                            ui.output_mut(|o| o.copied_text = self.config.api_token.clone());
+                        }
+                        if ui.small_button("New token").clicked() {
+                            self.config.api_token = api::new_token();
+                        }
+                    }
+                });
+            }
+            if self.config.api_enabled && !before.0 && self.config.api_token.is_empty() {
+                // Turned on without a token: start out with one
+                self.config.api_token = api::new_token();
+            }
+            if (
+                self.config.api_enabled,
+                &self.config.api_address,
+                &self.config.api_token,
+            ) != (before.0, &before.1, &before.2)
+            {
+                // Restarted with the new address on the next frame
+                self.api = None;
+                self.api_error = None;
+                self.config.save();
+            }
+            if let Some(error) = &self.api_error {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("The HTTP API could not start: {}", error),
+                );
+            } else if let Some(server) = &self.api {
+                ui.label(format!("Listening on http://{}", server.address));
+                if !server.address.ip().is_loopback() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "Anyone on the network with the token can change and send presets",
+                    );
+                }
+            }
+
+            let before = (self.config.send_on_save, self.config.confirm_send_on_save);
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Send to the device on save")
+                    .selected_text(self.config.send_on_save.label())
+                    .show_ui(ui, |ui| {
+                        for option in SendOnSave::ALL {
+                            ui.selectable_value(
+                                &mut self.config.send_on_save,
+                                option,
+                                option.label(),
+                            );
+                        }
+                    });
+                ui.add_enabled(
+                    self.config.send_on_save != SendOnSave::Off,
+                    egui::Checkbox::new(&mut self.config.confirm_send_on_save, "Ask first"),
+                );
+            });
+            if (self.config.send_on_save, self.config.confirm_send_on_save) != before {
+                self.config.save();
+            }
+            if ui
+                .checkbox(&mut self.config.preview_writes, "Preview every write")
+                .on_hover_text(format!(
+                    "Show what a send, firmware update or console line changes and where it \
+                     goes, and wait for {} to be typed",
+                    WRITE_CONFIRMATION
+                ))
+                .changed()
+            {
+                self.config.save();
+            }
+
+            let before = self.config.import_policy;
+            egui::ComboBox::from_label("Out-of-range values in imports")
+                .selected_text(self.config.import_policy.label())
+                .show_ui(ui, |ui| {
+                    for policy in RangePolicy::ALL {
+                        ui.selectable_value(&mut self.config.import_policy, policy, policy.label());
+                    }
+                });
+            if self.config.import_policy != before {
+                self.config.save();
+            }
+
+            let before = self.config.firmware.clone();
+            egui::ComboBox::from_label("Firmware of received dumps")
+                .selected_text(self.config.firmware.label())
+                .show_ui(ui, |ui| {
+                    let options = std::iter::once(FirmwareSetting::AutoDetect).chain(
+                        Firmware::selectable(self.custom_map.as_ref())
+                            .into_iter()
+                            .map(FirmwareSetting::Fixed),
+                    );
+                    for option in options {
+                        let label = option.label();
+                        ui.selectable_value(&mut self.config.firmware, option, label);
+                    }
+                });
+            if self.config.firmware != before {
+                self.config.save();
+            }
+            if ui
+                .checkbox(
+                    &mut self.config.choose_received_banks,
+                    "Choose which banks of a received dump to apply",
+                )
+                .changed()
+            {
+                self.config.save();
+            }
+
+            let mut dim = !self.config.undimmed_defaults;
+            if ui
+                .checkbox(&mut dim, "Dim values left at their default")
+                .on_hover_text("So a quick look at a preset shows only what it actually sends")
+                .changed()
+            {
+                self.config.undimmed_defaults = !dim;
+                self.config.save();
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .checkbox(
+                        &mut self.config.virtual_port,
+                        "Create a virtual MIDI output",
+                    )
+                    .on_hover_text("Preset previews go to this port so a DAW can receive them")
+                    .changed()
+                {
+                    self.preview.close_virtual();
+                    if self.config.virtual_port {
+                        match self.preview.open_virtual(VIRTUAL_PORT_NAME) {
+                            Ok(()) => self.set_preview_target(PreviewTarget::Virtual),
+                            Err(e) => {
+                                self.status =
+                                    Some(format!("Could not create the virtual port: {}", e));
+                                self.config.virtual_port = false;
+                            }
+                        }
+                    } else if self.preview.target() == PreviewTarget::Virtual {
+                        self.set_preview_target(PreviewTarget::DumpOutputs);
+                    }
+                    self.config.save();
+                }
+                if let Some(name) = self.preview.virtual_name() {
+                    ui.label(format!("Port \"{}\" is open", name));
+                }
+            });
+
+            ui.separator();
+
+            egui::ComboBox::from_label("CC names for this project")
+                .selected_text(self.metadata.cc_profile.label())
+                .show_ui(ui, |ui| {
+                    for profile in CcProfile::ALL {
+                        ui.selectable_value(
+                            &mut self.metadata.cc_profile,
+                            profile,
+                            profile.label(),
+                        );
+                    }
+                });
+
+            ui.collapsing("Preview send order for this project", |ui| {
+                ui.label(
+                    "The FCB1010 firmware has no setting for this order. Previews send program \
+                     changes, control changes, then the note unless reordered here to match \
+                     how your rig behaves.",
+                );
+                let mut swap = None;
+                let order = &self.metadata.send_order;
+                for (position, kind) in order.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. {}", position + 1, kind.label()));
+                        if ui
+                            .add_enabled(position > 0, egui::Button::new("▲"))
+                            .clicked()
+                        {
+                            swap = Some(position - 1);
+                        }
+                        if ui
+                            .add_enabled(position + 1 < order.len(), egui::Button::new("▼"))
+                            .clicked()
+                        {
+                            swap = Some(position);
+                        }
+                    });
+                }
+                if let Some(position) = swap {
+                    self.metadata.send_order.swap(position, position + 1);
+                }
+                if ui
+                    .add_enabled(
+                        self.metadata.send_order != FIRMWARE_SEND_ORDER,
+                        egui::Button::new("Use firmware order"),
+                    )
+                    .clicked()
+                {
+                    self.metadata.send_order = FIRMWARE_SEND_ORDER;
+                }
+            });
+
+            ui.collapsing("Preview channel for this project", |ui| {
+                ui.label(
+                    "Send previews and simulator presses on one channel instead of the ones \
+                     set for each function, e.g. to a scratch synth listening on channel 16. \
+                     The dump sent to the FCB1010 keeps its channels.",
+                );
+                ui.horizontal(|ui| {
+                    let mut overridden = self.metadata.preview_channel.is_some();
+                    if ui.checkbox(&mut overridden, "Preview on channel").changed() {
+                        self.metadata.preview_channel = overridden.then(|| Channel::clamped(15));
+                    }
+                    if let Some(channel) = &mut self.metadata.preview_channel {
+                        let mut number = channel.number();
+                        if ui
+                            .add(egui::DragValue::new(&mut number).clamp_range(1..=16))
+                            .changed()
+                        {
+                            *channel = Channel::clamped(number - 1);
+                        }
+                    }
+                });
+            });
+
+            ui.collapsing("Program names for this project", |ui| {
+                ui.label(
+                    "Import the target synth's patch names from a .midnam or number,name CSV file",
+                );
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.program_names_path);
+                    if ui.button("Import").clicked() {
+                        let path = PathBuf::from(&self.program_names_path);
+                        self.status = Some(match patch_names::load(&path) {
+                            Ok(names) => {
+                                let count = names.len();
+                                self.metadata.program_names = names;
+                                format!("Imported {} program names", count)
+                            }
+                            Err(e) => format!("Could not import {}: {}", path.display(), e),
+                        });
+                    }
+                });
+                if !self.metadata.program_names.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} names", self.metadata.program_names.len()));
+                        if ui.button("Clear").clicked() {
+                            self.metadata.program_names.clear();
+                        }
+                    });
+                }
+            });
+
+            ui.collapsing("Custom CC dictionary", |ui| {
+                let mut changed = false;
+                let mut remove = None;
+                egui::Grid::new("custom_cc_names").show(ui, |ui| {
+                    for (number, name) in self.config.custom_cc_names.iter_mut() {
+                        ui.label(format!("CC {}", number));
+                        changed |= ui.text_edit_singleline(name).changed();
+                        if ui.small_button("Remove").clicked() {
+                            remove = Some(*number);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(number) = remove {
+                    self.config.custom_cc_names.remove(&number);
+                    changed = true;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.new_cc_number)
+                            .speed(0.1)
+                            .clamp_range(0..=127),
+                    );
+                    if ui.button("Add").clicked() {
+                        self.config
+                            .custom_cc_names
+                            .entry(self.new_cc_number)
+                            .or_insert_with(|| format!("CC {}", self.new_cc_number));
+                        changed = true;
+                    }
+                });
+
+                if changed {
+                    self.config.save();
+                }
+            });
+
+            ui.collapsing("Keyboard shortcuts", |ui| {
+                let mut changed = false;
+                egui::Grid::new("keybindings").show(ui, |ui| {
+                    for command in Command::ALL {
+                        ui.label(command.label());
+                        if self.recording_shortcut == Some(command) {
+                            ui.label("Press a key (Esc to cancel)...");
+                        } else {
+                            ui.label(
+                                self.config
+                                    .keybindings
+                                    .get(command)
+                                    .map_or("Unbound".to_string(), |s| s.describe(ui.ctx())),
+                            );
+                        }
+                        if ui.small_button("Record").clicked() {
+                            self.recording_shortcut = Some(command);
+                        }
+                        if ui.small_button("Clear").clicked() {
+                            self.config.keybindings.unbind(command);
+                            changed = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+                if ui.button("Restore defaults").clicked() {
+                    self.config.keybindings = Keybindings::default();
+                    changed = true;
+                }
+                if changed {
+                    self.config.save();
+                }
+            });
+        });
+    }
+
+    fn show_simulator(&mut self, ui: &mut egui::Ui) {
+        let header = egui::CollapsingHeader::new("Simulator").show(ui, |ui| {
+            ui.label("Press virtual pedals to send what the board would; presses go to the preview output.");
+            if ui
+                .checkbox(
+                    &mut self.config.simulator_keys,
+                    "Play with the keyboard: 1-0 press the pedals, ↑ and ↓ rock the expression pedals",
+                )
+                .on_hover_text(
+                    "Keys are ignored while a text field has focus; switch this off when typing \
+                     numbers elsewhere",
+                )
+                .changed()
+            {
+                self.config.save();
+            }
+
+            let mut pressed = None;
+            ui.horizontal(|ui| {
+                if ui.button("Bank ▼").clicked() {
+                    self.simulator.bank_down();
+                }
+                ui.label(format!(
+                    "Bank {}",
+                    self.config.numbering.bank(self.simulator.bank)
+                ));
+                if ui.button("Bank ▲").clicked() {
+                    self.simulator.bank_up();
+                }
+                for pedal in 0..PEDALS_PER_BANK {
+                    let index = self.simulator.preset_index(pedal);
+                    let name = self
+                        .metadata
+                        .presets
+                        .get(index)
+                        .map_or("", |metadata| metadata.name.as_str());
+                    if ui
+                        .button(format!("{}", pedal + 1))
+                        .on_hover_text(format!(
+                            "Preset {} {}",
+                            self.config.numbering.preset(index),
+                            name
+                        ))
+                        .clicked()
+                    {
+                        pressed = Some(pedal);
+                    }
+                }
+            });
+            if let Some(pedal) = pressed {
+                self.press_simulator_pedal(pedal);
+            }
+
+            ui.horizontal(|ui| {
+                if self.simulator.is_recording() {
+                    if ui.button("⏹ Stop recording").clicked() {
+                        self.simulator.stop_recording();
+                    }
+                    ui.colored_label(egui::Color32::RED, "Recording");
+                } else if ui.button("⏺ Record").clicked() {
+                    self.simulator.start_recording();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.simulator_export_path);
+                let recorded = self.simulator.recorded();
+                if ui
+                    .add_enabled(!recorded.is_empty(), egui::Button::new("Export .mid"))
+                    .on_hover_text("Save the last recording as a Standard MIDI File")
+                    .clicked()
+                {
+                    let path = &self.simulator_export_path;
+                    self.status = Some(match fs::write(path, smf::write_smf(recorded)) {
+                        Ok(()) => format!("Exported {} events to {}", recorded.len(), path),
+                        Err(e) => format!("Failed to export {}: {}", path, e),
+                    });
+                }
+            });
+        });
+        self.tour.mark("simulator", header.header_response.rect);
+    }
+
+    fn show_rehearsal(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Rehearsal recorder").show(ui, |ui| {
+            ui.label(
+                "Record what the board sends during a rehearsal, with timestamps, to find out \
+                 later what was pressed; the recording is saved with the project and can be \
+                 replayed on the preview output.",
+            );
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Input")
+                    .selected_text(
+                        self.rehearsal_port
+                            .and_then(|i| self.port_labels.get(i))
+                            .map_or("None", String::as_str),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (index, port) in self.port_labels.iter().enumerate() {
+                            ui.selectable_value(&mut self.rehearsal_port, Some(index), port);
+                        }
+                    });
+
+                if self.rehearsal_recorder.is_running() {
+                    ui.label(format!(
+                        "● Recording… {} messages",
+                        self.rehearsal_recorder.count()
+                    ));
+                    if ui.button("Stop").clicked() {
+                        self.metadata.rehearsal = self.rehearsal_recorder.stop();
+                    }
+                } else if let Some(port) = self.rehearsal_port {
+                    if ui
+                        .button("● Record")
+                        .on_hover_text("Replaces the recording kept in the project")
+                        .clicked()
+                    {
+                        self.replay = None;
+                        if let Err(e) = self.rehearsal_recorder.start(port, ui.ctx()) {
+                            self.status = Some(format!("Could not start recording: {}", e));
+                        }
+                    }
+                }
+            });
+
+            let Some(rehearsal) = &self.metadata.rehearsal else {
+                return;
+            };
+            let recorded = history::short_time(&rehearsal.recorded);
+            let length = rehearsal.length().as_secs();
+            let count = rehearsal.events.len();
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Recorded {}: {} messages over {}:{:02}",
+                    recorded,
+                    count,
+                    length / 60,
+                    length % 60
+                ));
+                if let Some(replay) = &self.replay {
+                    let position = replay.position().as_secs();
+                    ui.label(format!("Replaying {}:{:02}", position / 60, position % 60));
+                    if ui.button("Stop replay").clicked() {
+                        self.replay = None;
+                    }
+                } else if ui
+                    .add_enabled(
+                        count > 0 && !self.rehearsal_recorder.is_running(),
+                        egui::Button::new("▶ Replay"),
+                    )
+                    .on_hover_text(format!(
+                        "Send the messages again in their timing to {}",
+                        self.preview.describe(&self.outputs)
+                    ))
+                    .clicked()
+                {
+                    self.replay = Some(Replay::start());
+                }
+                if ui.button("Discard").clicked() {
+                    self.metadata.rehearsal = None;
+                    self.replay = None;
+                }
+            });
+
+            let Some(rehearsal) = &self.metadata.rehearsal else {
+                return;
+            };
+            egui::ScrollArea::vertical()
+                .id_source("rehearsal_events")
+                .max_height(160.0)
+                .show_rows(
+                    ui,
+                    ui.text_style_height(&egui::TextStyle::Body),
+                    count,
+                    |ui, rows| {
+                        for event in &rehearsal.events[rows] {
+                            ui.monospace(format!(
+                                "{:>4}:{:06.3}  {}",
+                                event.at_ms / 60_000,
+                                (event.at_ms % 60_000) as f64 / 1000.0,
+                                rehearsal::describe(&event.bytes, self.config.number_base)
+                            ));
+                        }
+                    },
+                );
+        });
+    }
+
+    /// Sends what the values edited since the last frame now send, no more
+    /// often per controller than the configured rate allows
+    fn send_live_preview(&mut self, ctx: &egui::Context) {
+        {
+            let sysex_message = self.sysex_message.lock().unwrap();
+            let channels = self
+                .metadata
+                .preview_channels(sysex_message.global_channels());
+            self.live_preview
+                .collect(&sysex_message, &channels, self.config.live_preview);
+        }
+        let (due, next) = self
+            .live_preview
+            .limiter
+            .due(self.config.live_preview_rate.interval());
+        let result = due
+            .iter()
+            .try_for_each(|message| self.preview.send(&mut self.outputs, message));
+        if let Err(e) = result {
+            self.status = Some(format!("Live preview failed: {}", e));
+        }
+        if !due.is_empty() {
+            self.report_reconnects();
+        }
+        if let Some(wait) = next {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    /// Sends the messages of the running replay that became due
+    fn advance_replay(&mut self, ctx: &egui::Context) {
+        let (Some(replay), Some(rehearsal)) = (&mut self.replay, &self.metadata.rehearsal) else {
+            return;
+        };
+        let (due, next) = replay.due(rehearsal);
+        for message in due {
+            if let Err(e) = self.preview.send(&mut self.outputs, message) {
+                self.status = Some(format!("Replay stopped: {}", e));
+                self.replay = None;
+                return;
+            }
+        }
+        match next {
+            Some(wait) => ctx.request_repaint_after(wait),
+            None => {
+                self.replay = None;
+                self.status = Some("Replay finished".to_string());
+            }
+        }
+    }
+
+    fn show_pedal_monitor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Expression pedal monitor").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Input")
+                    .selected_text(
+                        self.monitor_input_port
+                            .and_then(|i| self.port_labels.get(i))
+                            .map_or("None", String::as_str),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (index, port) in self.port_labels.iter().enumerate() {
+                            ui.selectable_value(&mut self.monitor_input_port, Some(index), port);
+                        }
+                    });
+
+                if self.cc_monitor.is_running() {
+                    if ui.button("Stop").clicked() {
+                        self.cc_monitor.stop();
+                    }
+                } else if let Some(port) = self.monitor_input_port {
+                    if ui.button("Start").clicked() {
+                        if let Err(e) = self.cc_monitor.start(port, ui.ctx()) {
+                            self.status = Some(format!("Could not start the monitor: {}", e));
+                        }
+                    }
+                }
+            });
+
+            let mut number = self.monitored_preset + 1;
+            ui.horizontal(|ui| {
+                ui.label("Preset:");
+                ui.add(egui::DragValue::new(&mut number).clamp_range(1..=100));
+            });
+            self.monitored_preset = number - 1;
+
+            let preset = *self
+                .sysex_message
+                .lock()
+                .unwrap()
+                .preset(self.monitored_preset);
+            ui.horizontal(|ui| {
+                let pedals = [
+                    ("EP A", preset.expression_pedal_a()),
+                    ("EP B", preset.expression_pedal_b()),
+                ];
+                for (label, pedal) in pedals {
+                    ui.vertical(|ui| match pedal {
+                        Some(pedal) => {
+                            let base = self.config.number_base;
+                            let controller = pedal.controller.get();
+                            ui.label(format!("{} (CC {})", label, base.format(controller)));
+                            let value = self.cc_monitor.value(controller);
+                            vertical_meter(ui, value, pedal.min.get(), pedal.max.get(), base);
+                        }
+                        None => {
+                            ui.weak(format!("{} (off)", label));
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    fn show_device_test(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Device test").show(ui, |ui| {
+            ui.label(
+                "Checks every footswitch and pedal before a gig. Load presets that send \
+                 something into the current bank, then follow the instructions.",
+            );
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Input")
+                    .selected_text(
+                        self.device_test_port
+                            .and_then(|i| self.port_labels.get(i))
+                            .map_or("None", String::as_str),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (index, port) in self.port_labels.iter().enumerate() {
+                            ui.selectable_value(&mut self.device_test_port, Some(index), port);
+                        }
+                    });
+
+                if self.device_test.is_running() {
+                    if ui.button("Stop").clicked() {
+                        self.device_test.stop();
+                    }
+                } else if let Some(port) = self.device_test_port {
+                    if ui.button("Start").clicked() {
+                        if let Err(e) = self.device_test.start(port, ui.ctx()) {
+                            self.status = Some(format!("Could not start the device test: {}", e));
+                        }
+                    }
+                }
+            });
+
+            if let Some(wait) = self.device_test.poll() {
+                ui.ctx().request_repaint_after(wait);
+            }
+            if let Some((step, control)) = self.device_test.current() {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("{}/{}:", step + 1, CONTROLS.len()));
+                    ui.label(control.instruction());
+                    if ui.button("Next").clicked() {
+                        self.device_test.next();
+                    }
+                });
+            }
+
+            let results = self.device_test.results();
+            if results.is_empty() {
+                return;
+            }
+            egui::Grid::new("device_test").striped(true).show(ui, |ui| {
+                for (control, result) in CONTROLS.iter().zip(&results) {
+                    ui.label(control.label());
+                    let verdict = result.verdict(*control);
+                    if result.tested && !result.has_output() {
+                        ui.colored_label(egui::Color32::RED, verdict);
+                    } else {
+                        ui.label(verdict);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn show_log(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Log").show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_source("log")
+                .max_height(150.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in self.log.entries() {
+                        ui.label(format!(
+                            "{} {}",
+                            entry.time.format("%H:%M:%S"),
+                            entry.message
+                        ));
+                    }
+                });
+        });
+    }
+
+    fn show_diagnostics(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Diagnostics").show(ui, |ui| {
+            ui.label(
+                "Measures the round trip from the selected output back to an input. \
+                 Connect a loopback cable or enable MIDI thru first.",
+            );
+
+            egui::ComboBox::from_label("Return input")
+                .selected_text(
+                    self.latency_input_port
+                        .and_then(|i| self.port_labels.get(i))
+                        .map_or("None", String::as_str),
+                )
+                .show_ui(ui, |ui| {
+                    for (index, port) in self.port_labels.iter().enumerate() {
+                        ui.selectable_value(&mut self.latency_input_port, Some(index), port);
+                    }
+                });
+
+            let measuring = *self.measuring_latency.lock().unwrap();
+            let ready =
+                !measuring && self.selected_port.is_some() && self.latency_input_port.is_some();
+            if ui
+                .add_enabled(ready, egui::Button::new("Measure latency"))
+                .clicked()
+            {
+                let transfer = self.transfers.begin(Transfer::LatencyTest);
+                if let Err(e) = &transfer {
+                    self.status = Some(e.clone());
+                }
+                if let (Ok(transfer), Some(output), Some(input)) =
+                    (transfer, self.selected_output(), self.latency_input_port)
+                {
+                    // Our own input connection would swallow the probes
+                    self.midi_in_connection = None;
+                    *self.measuring_latency.lock().unwrap() = true;
+
+                    let ctx = ui.ctx().clone();
+                    let result = Arc::clone(&self.latency_result);
+                    let measuring = Arc::clone(&self.measuring_latency);
+                    std::thread::spawn(move || {
+                        let _transfer = transfer;
+                        *result.lock().unwrap() = Some(measure_latency(output, input, 20));
+                        *measuring.lock().unwrap() = false;
+                        ctx.request_repaint();
+                    });
+                }
+            }
+
+            if measuring {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Measuring...");
+                });
+            } else if let Some(result) = &*self.latency_result.lock().unwrap() {
+                match result {
+                    Ok(report) => ui.label(report.summary()),
+                    Err(e) => ui.colored_label(egui::Color32::RED, e),
+                };
+            }
+
+            ui.separator();
+            self.show_transfer_stats(ui);
+
+            ui.separator();
+            let (built, took) = self.grid_frame;
+            ui.label(format!(
+                "Preset grid: {} cards built in {:.1} ms the last time it was drawn",
+                built,
+                took.as_secs_f64() * 1000.0
+            ));
+        });
+    }
+
+    fn show_channel_usage(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Channel usage").show(ui, |ui| {
+            ui.label(
+                "How many presets send on each channel, and which controllers they use, \
+                 to spot collisions with other gear. Slots left at 0 count as unused.",
+            );
+            let sysex_message = self.sysex_message.lock().unwrap();
+            let usage = ChannelUsage::of(&sysex_message);
+
+            egui::Grid::new("channel_usage")
+                .striped(true)
+                .show(ui, |ui| {
+                    for heading in ["Channel", "Functions", "PCs", "CCs", "Notes", "Total"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    for (channel, count) in usage.channels.iter().enumerate() {
+                        let functions = functions_on(&sysex_message, channel as u8);
+                        if functions.is_empty() {
+                            continue;
+                        }
+                        ui.label((channel + 1).to_string());
+                        let names: Vec<&str> = functions
+                            .iter()
+                            .map(|&function| spec::GLOBAL_FUNCTIONS[function])
+                            .collect();
+                        ui.label(names.join(", "));
+                        for value in [count.programs, count.controls, count.notes, count.total()] {
+                            ui.label(value.to_string());
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.label("Controllers by channel (darker is used by more presets):");
+            let busiest = usage.busiest_controller().max(1);
+            let (cell_width, cell_height) = ((ui.available_width() - 30.0) / 128.0, 10.0);
+            let (response, painter) = ui.allocate_painter(
+                egui::vec2(30.0 + cell_width * 128.0, cell_height * 16.0),
+                egui::Sense::hover(),
+            );
+            let origin = response.rect.min;
+            for (channel, controllers) in usage.controllers.iter().enumerate() {
+                let y = origin.y + channel as f32 * cell_height;
+                painter.text(
+                    egui::pos2(origin.x, y + cell_height / 2.0),
+                    egui::Align2::LEFT_CENTER,
+                    (channel + 1).to_string(),
+                    egui::FontId::monospace(8.0),
+                    ui.visuals().text_color(),
+                );
+                for (number, &count) in controllers.iter().enumerate() {
+                    let cell = egui::Rect::from_min_size(
+                        egui::pos2(origin.x + 30.0 + number as f32 * cell_width, y),
+                        egui::vec2(cell_width, cell_height),
+                    );
+                    let color = if count == 0 {
+                        ui.visuals().faint_bg_color
+                    } else {
+                        let heat = 0.3 + 0.7 * count as f32 / busiest as f32;
+                        egui::Color32::from_rgb(255, 140, 0).gamma_multiply(heat)
+                    };
+                    painter.rect_filled(cell.shrink(0.5), 0.0, color);
+                }
+            }
+            if let Some(pointer) = response.hover_pos() {
+                let column = ((pointer.x - origin.x - 30.0) / cell_width).floor();
+                let row = ((pointer.y - origin.y) / cell_height).floor();
+                if (0.0..128.0).contains(&column) && (0.0..16.0).contains(&row) {
+                    let (channel, number) = (row as usize, column as usize);
+                    let count = usage.controllers[channel][number];
+                    let dictionary =
+                        CcDictionary::new(self.metadata.cc_profile, &self.config.custom_cc_names);
+                    let name = dictionary.name(number as u8);
+                    response.on_hover_text_at_pointer(format!(
+                        "Channel {}, CC {}{}: {} presets",
+                        channel + 1,
+                        number,
+                        name.map_or(String::new(), |name| format!(" ({})", name)),
+                        count
+                    ));
+                }
+            }
+        });
+    }
+
+    fn show_compare_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Compare two files").show(ui, |ui| {
+            ui.label("Compares any two .syx dumps or projects, e.g. an old backup with today's.");
+            egui::Grid::new("compare_paths").show(ui, |ui| {
+                ui.label("Before:");
+                ui.text_edit_singleline(&mut self.compare_before);
+                ui.end_row();
+                ui.label("After:");
+                ui.text_edit_singleline(&mut self.compare_after);
+                ui.end_row();
+            });
+
+            if ui.button("Compare").clicked() {
+                let (before, after) = (
+                    self.config.syx_file(&self.compare_before),
+                    self.config.syx_file(&self.compare_after),
+                );
+                let files = ComparedFile::load(&before)
+                    .and_then(|before| Ok((before, ComparedFile::load(&after)?)));
+                match files {
+                    Ok((before, after)) => {
+                        self.compare_report = Some(CompareReport::new(&before, &after));
+                    }
+                    Err(e) => {
+                        self.compare_report = None;
+                        self.status = Some(format!("Could not compare: {}", e));
+                    }
+                }
+            }
+
+            let Some(report) = &self.compare_report else {
+                return;
+            };
+            if report.is_empty() {
+                ui.label("The files hold the same settings");
+                return;
+            }
+            ui.label(report.summary());
+            egui::ScrollArea::vertical()
+                .id_source("compare_report")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    for (setting, before, after) in &report.globals {
+                        ui.label(format!("{}: {} → {}", setting, before, after));
+                    }
+                    for preset in &report.presets {
+                        let changes: Vec<String> = preset
+                            .fields
+                            .iter()
+                            .map(|(field, before, after)| {
+                                format!("{} {} → {}", spec::field_title(*field), before, after)
+                            })
+                            .collect();
+                        ui.label(format!(
+                            "Preset {} {}: {}",
+                            preset.index + 1,
+                            preset.name,
+                            changes.join(", ")
+                        ));
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.compare_report_path);
+                if ui.button("Export Markdown").clicked() {
+                    self.status = Some(
+                        match fs::write(
+                            self.config.project_file(&self.compare_report_path),
+                            report.to_markdown(),
+                        ) {
+                            Ok(()) => format!("Report written to {}", self.compare_report_path),
+                            Err(e) => format!("Failed to write the report: {}", e),
+                        },
+                    );
+                }
+            });
+        });
+    }
+
+    fn show_memory_map_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Custom memory map").show(ui, |ui| {
+            ui.label(
+                "Where every value lives in the unpacked dump, for clones and other firmware \
+                 variants. Once in use, pick \"custom\" as the firmware layout.",
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Start from v2.5").clicked() {
+                    self.map_draft = Firmware::V2_5.memory_map();
+                }
+                if ui.button("Start from v2.4").clicked() {
+                    self.map_draft = Firmware::V2_4.memory_map();
+                }
+            });
+
+            let map = &mut self.map_draft;
+            egui::Grid::new("memory_map_layout").show(ui, |ui| {
+                for (label, value) in [
+                    ("Preset size", &mut map.preset_size),
+                    ("Data size", &mut map.data_size),
+                    ("Global channels at", &mut map.global_channels),
+                    ("Switch modes at", &mut map.switch_modes),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(value).hexadecimal(3, false, false));
+                    ui.end_row();
+                }
+            });
+            ui.label("Offsets inside the preset record:");
+            egui::Grid::new("memory_map_fields").show(ui, |ui| {
+                for (i, field) in PresetField::ALL.into_iter().enumerate() {
+                    ui.label(spec::field_title(field));
+                    ui.add(egui::DragValue::new(&mut map.preset_fields[i]));
+                    if i % 4 == 3 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+            let valid = match map.validate() {
+                Ok(()) => {
+                    ui.label("The map is valid.");
+                    true
+                }
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                    false
+                }
+            };
+            if self.custom_map.as_ref() == Some(&self.map_draft) {
+                ui.label("This is the custom layout in use.");
+            }
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.map_path);
+                if ui.button("Load").clicked() {
+                    match map_file::load(Path::new(&self.map_path)) {
+                        Ok(map) => self.map_draft = map,
+                        Err(e) => {
+                            self.status = Some(format!("Could not load {}: {}", self.map_path, e))
+                        }
+                    }
+                }
+                if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                    self.status = Some(
+                        match map_file::save(&self.map_draft, Path::new(&self.map_path)) {
+                            Ok(()) => format!("Memory map saved to {}", self.map_path),
+                            Err(e) => format!("Failed to save the memory map: {}", e),
+                        },
+                    );
+                }
+                if ui
+                    .add_enabled(valid, egui::Button::new("Use as custom layout"))
+                    .on_hover_text("Saves the map and loads it at every start")
+                    .clicked()
+                {
+                    self.status = Some(
+                        match map_file::save(&self.map_draft, Path::new(&self.map_path)) {
+                            Ok(()) => {
+                                self.custom_map = Some(self.map_draft.clone());
+                                // Dumps received from now on use the new map
+                                if let FirmwareSetting::Fixed(Firmware::Custom(map)) =
+                                    &mut self.config.firmware
+                                {
+                                    **map = self.map_draft.clone();
+                                }
+                                portable::make_relative(&mut self.map_path);
+                                self.config.memory_map_path = self.map_path.clone();
+                                self.config.save();
+                                "Custom layout updated; reinterpret the dump in Global Settings \
+                                 if it was read with another map"
+                                    .to_string()
+                            }
+                            Err(e) => format!("Failed to save the memory map: {}", e),
+                        },
+                    );
+                }
+                if ui
+                    .add_enabled(self.custom_map.is_some(), egui::Button::new("Stop using"))
+                    .on_hover_text("Received dumps read with it fall back to v2.5")
+                    .clicked()
+                {
+                    self.custom_map = None;
+                    if let FirmwareSetting::Fixed(Firmware::Custom(_)) = self.config.firmware {
+                        self.config.firmware = FirmwareSetting::Fixed(Firmware::V2_5);
+                    }
+                    self.config.memory_map_path.clear();
+                    self.config.save();
+                }
+            });
+        });
+    }
+
+    /// Sends the image to the bootloader from a thread of its own
+    fn start_firmware_update(
+        &mut self,
+        ctx: &egui::Context,
+        port: usize,
+        messages: Vec<Vec<u8>>,
+        pause: Duration,
+    ) {
+        let transfer = match self.transfers.begin(Transfer::FirmwareUpdate) {
+            Ok(transfer) => transfer,
+            Err(e) => {
+                self.status = Some(e);
+                return;
+            }
+        };
+        let progress = Arc::clone(&self.firmware_progress);
+        *progress.lock().unwrap() = UpdateProgress {
+            total: messages.len(),
+            ..UpdateProgress::default()
+        };
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _transfer = transfer;
+            firmware_update::send_image(port, &messages, pause, &progress);
+            ctx.request_repaint();
+        });
+    }
+
+    fn show_firmware_update(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Firmware update (UnO)").show(ui, |ui| {
+            ui.colored_label(
+                egui::Color32::RED,
+                "⚠ A failed or interrupted update can leave the pedal unusable until it is \
+                 recovered. Only send images from the firmware's publisher, do not touch the \
+                 cable while it runs, and keep the pedal powered.",
+            );
+            ui.label(
+                "Put the pedal into its update mode first. The editor sends one SysEx \
+                 message at a time with a pause after each; it does not wait for replies.",
+            );
+
+            let progress = self.firmware_progress.lock().unwrap().clone();
+            let running = progress.total > 0 && !progress.finished;
+
+            ui.add_enabled_ui(!running, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Image:");
+                    ui.text_edit_singleline(&mut self.firmware_image_path);
+                    if ui.button("Load").clicked() {
+                        self.firmware_confirmed = false;
+                        match FirmwareImage::load(&self.config.syx_file(&self.firmware_image_path))
+                        {
+                            Ok(image) => self.firmware_image = Some(image),
+                            Err(e) => {
+                                self.firmware_image = None;
+                                self.status = Some(format!("Cannot use this image: {}", e));
+                            }
+                        }
+                    }
+                });
+            });
+
+            let selected_output = self.selected_output();
+            let mut send = None;
+            let Some(image) = &self.firmware_image else {
+                return;
+            };
+            ui.label(format!(
+                "{}: {} bytes in {} SysEx messages",
+                image.name,
+                image.size,
+                image.messages.len()
+            ));
+            ui.monospace(format!("SHA-256 {}", image.sha256));
+
+            ui.add_enabled_ui(!running, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Published checksum:");
+                    ui.text_edit_singleline(&mut self.firmware_checksum);
+                });
+                let verified = image.matches_checksum(&self.firmware_checksum);
+                if verified {
+                    ui.colored_label(egui::Color32::GREEN, "✔ Checksum matches");
+                } else {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "Paste the SHA-256 published with the image to enable sending",
+                    );
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Pause after each message:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.firmware_pause_ms)
+                            .clamp_range(20..=5000)
+                            .suffix(" ms"),
+                    );
+                });
+                ui.checkbox(
+                    &mut self.firmware_confirmed,
+                    "I understand the risk and the pedal is in update mode",
+                );
+
+                let ready = verified && self.firmware_confirmed && selected_output.is_some();
+                if ui
+                    .add_enabled(ready, egui::Button::new("Send firmware update"))
+                    .clicked()
+                {
+                    send = Some(PendingWrite::Firmware {
+                        port: selected_output.expect("checked above"),
+                        messages: image.messages.clone(),
+                        pause: Duration::from_millis(self.firmware_pause_ms),
+                    });
+                    self.firmware_confirmed = false;
+                }
+            });
+            match send {
+                Some(write) if self.config.preview_writes => {
+                    let ports = selected_output
+                        .and_then(|port| self.output_labels.get(port).cloned())
+                        .into_iter()
+                        .collect();
+                    self.hold_write(write, ports);
+                }
+                Some(PendingWrite::Firmware {
+                    port,
+                    messages,
+                    pause,
+                }) => self.start_firmware_update(ui.ctx(), port, messages, pause),
+                _ => {}
+            }
+
+            if progress.total == 0 {
+                return;
+            }
+            ui.add(
+                egui::ProgressBar::new(progress.sent as f32 / progress.total as f32)
+                    .text(format!("{} / {} messages", progress.sent, progress.total)),
+            );
+            if running {
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+            } else if let Some(error) = &progress.error {
+                ui.colored_label(egui::Color32::RED, format!("Update failed: {}", error));
+            } else {
+                ui.colored_label(egui::Color32::GREEN, "All messages sent");
+            }
+        });
+    }
+
+    fn show_transfer_stats(&mut self, ui: &mut egui::Ui) {
+        ui.label("Transfer statistics, kept across sessions:");
+        let mut stats = self.transfer_stats.lock().unwrap();
+        if stats.ports().next().is_none() {
+            ui.label("No transfers yet");
+            return;
+        }
+
+        let average = |duration: Option<Duration>| {
+            duration.map_or("–".to_string(), |d| {
+                format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+            })
+        };
+        egui::Grid::new("transfer_stats_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in [
+                    "Port",
+                    "Sent",
+                    "Received",
+                    "Failures",
+                    "Retries",
+                    "Avg send",
+                    "Avg receive",
+                ] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                for (port, port_stats) in stats.ports() {
+                    ui.label(port);
+                    ui.label(format!(
+                        "{} ({} B)",
+                        port_stats.sends, port_stats.bytes_sent
+                    ));
+                    ui.label(format!(
+                        "{} ({} B)",
+                        port_stats.receives, port_stats.bytes_received
+                    ));
+                    // More than one in ten going wrong points at the interface or cable
+                    let failures = format!("{}", port_stats.failures);
+                    if port_stats.failure_rate() > 0.1 {
+                        ui.colored_label(egui::Color32::RED, failures)
+                            .on_hover_text("Many transfers fail on this port; check the cable");
+                    } else {
+                        ui.label(failures);
+                    }
+                    ui.label(port_stats.retries.to_string()).on_hover_text(
+                        "Sends repeated after reopening the port, and transfers started again \
+                         right after the same kind of transfer failed",
+                    );
+                    ui.label(average(port_stats.average_send()));
+                    ui.label(average(port_stats.average_receive()))
+                        .on_hover_text("Time from the first byte of a dump until its last one");
+                    ui.end_row();
+                }
+            });
+
+        if ui.button("Reset statistics").clicked() {
+            stats.clear();
+            stats.save(Path::new(STATS_FILE));
+        }
+    }
+
+    fn show_global_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Global Settings").show(ui, |ui| {
+            let mut sysex_message = self.sysex_message.lock().unwrap();
+            let mut changed = false;
+
+            egui::Grid::new("global_channels_grid").show(ui, |ui| {
+                for i in 0..sysex_message.global_channels().len() {
+                    let mut channel = sysex_message.global_channels()[i].get();
+                    ui.label(format!("{} channel:", spec::GLOBAL_FUNCTIONS[i]));
+                    ui.add(
+                        egui::DragValue::new(&mut channel)
+                            .speed(0.1)
+                            .clamp_range(0..=15),
+                    )
+                    .on_hover_text(spec::global_channel_tooltip(i, sysex_message.firmware()));
+                    changed |= sysex_message.set_global_channel(i, Channel::clamped(channel));
+                    if i % 5 == 4 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+            ui.separator();
+
+            let mut firmware = sysex_message.firmware().clone();
+            egui::ComboBox::from_label("Firmware layout")
+                .selected_text(firmware.label())
+                .show_ui(ui, |ui| {
+                    for option in Firmware::selectable(self.custom_map.as_ref()) {
+                        let label = option.label();
+                        ui.selectable_value(&mut firmware, option, label);
+                    }
+                })
+                .response
+                .on_hover_text("Changing the layout reinterprets the received bytes");
+            if firmware != *sysex_message.firmware() {
+                // Re-read the raw dump with the other memory map when we still have it
+                let reinterpreted = sysex_message
+                    .original_data()
+                    .map(|raw| SysExMessage::decode_with(raw, firmware.clone()));
+                match reinterpreted {
+                    Some(Ok(message)) => {
+                        *sysex_message = message;
+                        self.events.emit(ModelEvent::DumpReplaced);
+                    }
+                    Some(Err(e)) => {
+                        self.log
+                            .push(format!("Could not reinterpret the dump: {}", e));
+                    }
+                    None => {
+                        sysex_message.set_firmware(firmware);
+                        changed = true;
+                    }
+                }
+            }
+
+            ui.separator();
+
+            for i in 0..sysex_message.switch_modes().len() {
+                let mut mode = sysex_message.switch_modes()[i];
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label(format!("Switch {} mode", i + 1))
+                        .selected_text(mode.label())
+                        .show_ui(ui, |ui| {
+                            for option in [SwitchMode::Momentary, SwitchMode::Toggle] {
+                                ui.selectable_value(&mut mode, option, option.label())
+                                    .on_hover_text(option.description());
+                            }
+                        })
+                        .response
+                        .on_hover_text(spec::switch_mode_tooltip(i, sysex_message.firmware()));
+                    ui.label(mode.description());
+                });
+                changed |= sysex_message.set_switch_mode(i, mode);
+            }
+
+            if changed {
+                self.events.emit(ModelEvent::GlobalsChanged);
+            }
+        });
+    }
+
+    fn show_channel_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Channel per bank").show(ui, |ui| {
+            ui.label(
+                "Gives every bank its own MIDI channel by sending its program changes \
+                 through a separate PC function.",
+            );
+
+            let mut preview = ui.button("Preview").clicked();
+            if !self.metadata.locked_banks.is_empty() {
+                preview |= ui
+                    .checkbox(
+                        &mut self.channel_plan_locked,
+                        "Also change channels that locked banks send on",
+                    )
+                    .on_hover_text(
+                        "Channels belong to PC functions, not banks, so this changes what \
+                         the locked banks send; their presets still stay as they are",
+                    )
+                    .changed()
+                    && self.channel_plan.is_some();
+            }
+            if preview {
+                let sysex_message = self.sysex_message.lock().unwrap();
+                self.channel_plan = Some(ChannelPlan::channel_per_bank(
+                    &sysex_message,
+                    &self.metadata.locked_banks,
+                    self.channel_plan_locked,
+                ));
+            }
+
+            let Some(plan) = &self.channel_plan else {
+                return;
+            };
+
+            for warning in &plan.warnings {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+
+            if plan.changes.is_empty() {
+                ui.label("Nothing to change");
+            } else {
+                ui.label(format!("{} values will change:", plan.changes.len()));
+                egui::ScrollArea::vertical()
+                    .id_source("channel_plan_changes")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for change in &plan.changes {
+                            ui.label(change.describe());
+                        }
+                    });
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!plan.changes.is_empty(), egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    let mut sysex_message = self.sysex_message.lock().unwrap();
+                    apply_channel_plan(&self.events, &mut sysex_message, plan);
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                self.channel_plan = None;
+            }
+        });
+    }
+
+    fn show_expression_defaults_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Expression pedal controllers").show(ui, |ui| {
+            ui.label("Sets one controller number for an expression pedal in every preset.");
+            let before = self.expression_defaults.clone();
+            let dictionary =
+                CcDictionary::new(self.metadata.cc_profile, &self.config.custom_cc_names);
+            for (pedal, name) in ["Expression A", "Expression B"].into_iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let controller = &mut self.expression_defaults.controllers[pedal];
+                    let mut set = controller.is_some();
+                    ui.checkbox(&mut set, name);
+                    match (set, *controller) {
+                        (true, None) => *controller = Some(Value7::default()),
+                        (false, Some(_)) => *controller = None,
+                        _ => {}
+                    }
+                    if let Some(value) = controller {
+                        let mut number = value.get();
+                        ui.label("CC");
+                        if ui
+                            .add(egui::DragValue::new(&mut number).clamp_range(0..=127))
+                            .changed()
+                        {
+                            *value = Value7::clamped(number);
+                        }
+                        ui.weak(dictionary.describe(number));
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Banks:");
+                for bank in 0..BANKS {
+                    let mut selected = self.expression_defaults.banks.contains(&bank);
+                    if ui
+                        .toggle_value(&mut selected, (bank + 1).to_string())
+                        .changed()
+                    {
+                        if selected {
+                            self.expression_defaults.banks.insert(bank);
+                        } else {
+                            self.expression_defaults.banks.remove(&bank);
+                        }
+                    }
+                }
+                if self.expression_defaults.banks.is_empty() {
+                    ui.weak("all");
+                }
+            });
+            ui.checkbox(
+                &mut self.expression_defaults.switch_on,
+                "Also switch the pedal on where it is off",
+            );
+            if self.expression_defaults != before {
+                self.expression_plan = None;
+            }
+
+            if ui.button("Preview").clicked() {
+                let sysex_message = self.sysex_message.lock().unwrap();
+                self.expression_plan = Some(
+                    self.expression_defaults
+                        .plan(&sysex_message, &self.metadata.locked_banks),
+                );
+            }
+
+            let Some(plan) = &self.expression_plan else {
+                return;
+            };
+            if plan.is_empty() {
+                ui.label("Nothing to change");
+            } else {
+                let presets: Vec<String> = plan
+                    .iter()
+                    .map(|(index, _)| (index + 1).to_string())
+                    .collect();
+                ui.label(format!(
+                    "{} presets change: {}",
+                    plan.len(),
+                    presets.join(", ")
+                ));
+            }
+            if !self.metadata.locked_banks.is_empty() {
+                ui.weak("Presets in locked banks are left alone");
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!plan.is_empty(), egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    let mut sysex_message = self.sysex_message.lock().unwrap();
+                    replace_presets(&self.events, &mut sysex_message, plan.clone());
+                    self.status = Some(format!("Expression pedals set in {} presets", plan.len()));
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                self.expression_plan = None;
+            }
+        });
+    }
+
+    fn show_name_suggestions_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Suggest names").show(ui, |ui| {
+            ui.label(
+                "Names unnamed presets after what they send, e.g. \"PC 12 + Tuner On\", \
+                 using the CC names and program names of this project.",
+            );
+            if ui.button("Suggest names").clicked() {
+                let sysex_message = self.sysex_message.lock().unwrap();
+                let dictionary =
+                    CcDictionary::new(self.metadata.cc_profile, &self.config.custom_cc_names);
+                self.name_suggestions = Some(name_suggest::suggestions(
+                    &sysex_message,
+                    &dictionary,
+                    &self.metadata,
+                ));
+            }
+
+            let Some(suggestions) = &self.name_suggestions else {
+                return;
+            };
+            if suggestions.is_empty() {
+                ui.label("Every preset that sends something already has a name");
+            } else {
+                egui::ScrollArea::vertical()
+                    .id_source("name_suggestions")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (index, name) in suggestions {
+                            ui.label(format!(
+                                "Preset {}: {}",
+                                self.config.numbering.preset(*index),
+                                name
+                            ));
+                        }
+                    });
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !suggestions.is_empty(),
+                        egui::Button::new("Fill empty names"),
+                    )
+                    .clicked()
+                {
+                    for (index, name) in suggestions {
+                        self.metadata.preset_mut(*index).name = name.clone();
+                    }
+                    self.status = Some(format!("Named {} presets", suggestions.len()));
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                self.name_suggestions = None;
+            }
+        });
+    }
+
+    fn show_preset_timeline(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Preset history").show(ui, |ui| {
+            ui.label(
+                "Scrub through the past states of one preset, rebuilt from the project \
+                 history, and restore one without touching the rest of the dump.",
+            );
+            ui.horizontal(|ui| {
+                let mut number = self.timeline_preset + 1;
+                ui.label("Preset");
+                ui.add(egui::DragValue::new(&mut number).clamp_range(1..=100));
+                if number - 1 != self.timeline_preset {
+                    self.timeline_preset = number - 1;
+                    self.timeline_position = 0;
+                }
+                ui.weak(self.config.numbering.preset(self.timeline_preset));
+            });
+
+            let index = self.timeline_preset;
+            let current = *self.sysex_message.lock().unwrap().preset(index);
+            let versions =
+                preset_timeline::preset_versions(&current, index, &self.metadata.history);
+            if versions.len() < 2 {
+                ui.label("The history has no earlier state of this preset");
+                return;
+            }
+            self.timeline_position = self.timeline_position.min(versions.len() - 1);
+            ui.add(
+                egui::Slider::new(&mut self.timeline_position, 0..=versions.len() - 1)
+                    .text("changes back"),
+            );
+
+            let version = &versions[self.timeline_position];
+            match &version.ended {
+                Some((time, author)) => {
+                    ui.label(format!(
+                        "Until {} by {}: {}",
+                        history::short_time(time),
+                        author,
+                        version.change
+                    ));
+                }
+                None => {
+                    ui.label("The current state");
+                }
+            }
+            let differences: Vec<String> = PresetField::ALL
+                .into_iter()
+                .filter(|&field| version.preset.get(field) != current.get(field))
+                .map(|field| {
+                    format!(
+                        "{} {} (now {})",
+                        spec::field_title(field),
+                        version.preset.get(field),
+                        current.get(field)
+                    )
+                })
+                .chain(
+                    (0..SLOTS)
+                        .filter(|&slot| version.preset.is_enabled(slot) != current.is_enabled(slot))
+                        .map(|slot| {
+                            format!(
+                                "{} {}",
+                                spec::GLOBAL_FUNCTIONS[slot],
+                                if version.preset.is_enabled(slot) {
+                                    "on"
+                                } else {
+                                    "off"
+                                }
+                            )
+                        }),
+                )
+                .collect();
+            if !differences.is_empty() {
+                ui.weak(format!("Differs from now: {}", differences.join(", ")));
+            }
+
+            let firmware = self.sysex_message.lock().unwrap().firmware().clone();
+            let mut options = CardOptions::new(
+                &self.config,
+                &self.metadata,
+                &self.highlights,
+                firmware,
+                &self.hidden_functions,
+                &self.dirty,
+            );
+            options.highlighted.clear();
+            let title = format!("Preset {}", options.numbering.preset(index));
+            ui.add_enabled_ui(false, |ui| {
+                preset_card(ui, index, &title, &version.preset, None, None, &options);
+            });
+
+            let locked = self.metadata.is_preset_locked(index);
+            if ui
+                .add_enabled(
+                    self.timeline_position > 0 && !locked,
+                    egui::Button::new("Restore this version"),
+                )
+                .on_disabled_hover_text(if locked {
+                    "The preset is in a locked bank"
+                } else {
+                    "Move the scrubber to an earlier state first"
+                })
+                .clicked()
+            {
+                let mut sysex_message = self.sysex_message.lock().unwrap();
+                replace_presets(
+                    &self.events,
+                    &mut sysex_message,
+                    vec![(index, version.preset)],
+                );
+                self.status = Some(format!(
+                    "Restored preset {} as it was {} changes back",
+                    self.config.numbering.preset(index),
+                    self.timeline_position
+                ));
+                self.timeline_position = 0;
+            }
+        });
+    }
+
+    fn show_bank_file_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Bank files").show(ui, |ui| {
+            ui.label(
+                "Share a complete bank: its ten presets with their names and notes go into \
+                 a file of their own, which can be imported into any bank of another project.",
+            );
+            ui.horizontal(|ui| {
+                let mut bank = self.bank_file_bank + 1;
+                ui.label("Bank");
+                ui.add(egui::DragValue::new(&mut bank).clamp_range(1..=BANKS));
+                self.bank_file_bank = bank - 1;
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.bank_file_path);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.bank_file_name)
+                        .hint_text("e.g. Helix snapshot bank"),
+                );
+                if ui.button("Export bank…").clicked() {
+                    self.export_bank();
+                }
+            });
+            let locked = self.metadata.is_bank_locked(self.bank_file_bank);
+            if ui
+                .add_enabled(!locked, egui::Button::new("Import bank into slot…"))
+                .on_hover_text("Replaces the ten presets of the bank with the file's")
+                .on_disabled_hover_text("The bank is locked")
+                .clicked()
+            {
+                self.import_bank();
+            }
+        });
+    }
+
+    fn show_rig_profiles(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Rig profiles").show(ui, |ui| {
+            ui.label(
+                "Keep how a rig is wired under a name: the global channels, relay switch modes \
+                 and CC names. Applying a profile gives any dump those settings and leaves its \
+                 presets alone.",
+            );
+
+            let (mut apply, mut update, mut export, mut delete) = (None, None, None, None);
+            let sysex_message = self.sysex_message.lock().unwrap();
+            for (i, profile) in self.config.rig_profiles.iter().enumerate() {
+                let differences = profile.differences(&sysex_message, &self.metadata);
+                ui.horizontal(|ui| {
+                    ui.strong(&profile.name);
+                    if ui
+                        .add_enabled(!differences.is_empty(), egui::Button::new("Apply"))
+                        .on_hover_text(differences.join("\n"))
+                        .on_disabled_hover_text("The project already has these settings")
+                        .clicked()
+                    {
+                        apply = Some(i);
+                    }
+                    if ui
+                        .button("Update")
+                        .on_hover_text("Replace the profile's settings with this project's")
+                        .clicked()
+                    {
+                        update = Some(i);
+                    }
+                    if ui
+                        .button("Export…")
+                        .on_hover_text("Write the profile to the file below")
+                        .clicked()
+                    {
+                        export = Some(i);
+                    }
+                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                        delete = Some(i);
+                    }
+                });
+            }
+            drop(sysex_message);
+
+            let mut save = false;
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.rig_name)
+                        .hint_text("e.g. Helix + Strymon"),
+                );
+                save = ui
+                    .add_enabled(
+                        !self.rig_name.trim().is_empty(),
+                        egui::Button::new("Save this project's settings"),
+                    )
+                    .clicked();
+            });
+            let mut import = false;
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.rig_path);
+                import = ui.button("Import…").clicked();
+            });
+
+            if let Some(i) = apply {
+                let profile = self.config.rig_profiles[i].clone();
+                let mut sysex_message = self.sysex_message.lock().unwrap();
+                if profile.apply(&mut sysex_message, &mut self.metadata) {
+                    self.events.emit(ModelEvent::GlobalsChanged);
+                }
+                self.status = Some(format!("Rig profile \"{}\" applied", profile.name));
+            }
+            if let Some(i) = update {
+                let name = self.config.rig_profiles[i].name.clone();
+                self.config.rig_profiles[i] = RigProfile::from_project(
+                    &self.sysex_message.lock().unwrap(),
+                    &self.metadata,
+                    &name,
+                );
+                self.config.save();
+                self.status = Some(format!("Rig profile \"{}\" updated", name));
+            }
+            if let Some(i) = export {
+                let profile = &self.config.rig_profiles[i];
+                let path = self.config.project_file(&self.rig_path);
+                self.status = Some(match profile.save(&path) {
+                    Ok(()) => format!(
+                        "Rig profile \"{}\" exported to {}",
+                        profile.name,
+                        path.display()
+                    ),
+                    Err(e) => format!("Failed to export the rig profile: {}", e),
+                });
+            }
+            if let Some(i) = delete {
+                let profile = self.config.rig_profiles.remove(i);
+                self.config.save();
+                self.status = Some(format!("Rig profile \"{}\" deleted", profile.name));
+            }
+            if save {
+                let profile = RigProfile::from_project(
+                    &self.sysex_message.lock().unwrap(),
+                    &self.metadata,
+                    self.rig_name.trim(),
+                );
+                self.status = Some(format!("Rig profile \"{}\" saved", profile.name));
+                self.keep_rig_profile(profile);
+                self.rig_name.clear();
+            }
+            if import {
+                let path = self.config.project_file(&self.rig_path);
+                match RigProfile::load(&path) {
+                    Ok(profile) => {
+                        self.status = Some(format!("Rig profile \"{}\" imported", profile.name));
+                        self.keep_rig_profile(profile);
+                    }
+                    Err(e) => {
+                        self.status = Some(format!("Failed to import {}: {}", path.display(), e))
+                    }
+                }
+            }
+        });
+    }
+
+    /// Adds a rig profile, replacing one of the same name
+    fn keep_rig_profile(&mut self, profile: RigProfile) {
+        let profiles = &mut self.config.rig_profiles;
+        match profiles.iter_mut().find(|kept| kept.name == profile.name) {
+            Some(kept) => *kept = profile,
+            None => profiles.push(profile),
+        }
+        self.config.save();
+    }
+
+    fn export_bank(&mut self) {
+        let file = BankFile::from_bank(
+            &self.sysex_message.lock().unwrap(),
+            &self.metadata,
+            self.bank_file_bank,
+            &self.bank_file_name,
+        );
+        let path = self.config.project_file(&self.bank_file_path);
+        self.status = Some(match file.save(&path) {
+            Ok(()) => format!(
+                "Bank {} exported to {}",
+                self.bank_file_bank + 1,
+                path.display()
+            ),
+            Err(e) => format!("Failed to export the bank: {}", e),
+        });
+    }
+
+    fn import_bank(&mut self) {
+        let path = self.config.project_file(&self.bank_file_path);
+        let file = match BankFile::load(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                self.status = Some(format!("Failed to import {}: {}", path.display(), e));
+                return;
+            }
+        };
+
+        let mut sysex_message = self.sysex_message.lock().unwrap();
+        let mut presets = Vec::new();
+        for (index, preset, labels) in file.placed(self.bank_file_bank) {
+            *self.metadata.preset_mut(index) = labels;
+            presets.push((index, preset));
+        }
+        replace_presets(&self.events, &mut sysex_message, presets);
+        let differences = file.channel_differences(&sysex_message);
+        drop(sysex_message);
+
+        let name = match file.name.as_str() {
+            "" => path.display().to_string(),
+            name => format!("\"{}\"", name),
+        };
+        self.status = Some(format!(
+            "Imported {} into bank {}",
+            name,
+            self.bank_file_bank + 1
+        ));
+        if !differences.is_empty() {
+            let functions: Vec<&str> = differences
+                .iter()
+                .map(|&function| spec::GLOBAL_FUNCTIONS[function])
+                .collect();
+            self.warning = Some(format!(
+                "The bank was made with other channels for {}; its presets send on this \
+                 project's channels",
+                functions.join(", ")
+            ));
+        }
+    }
+
+    fn show_bank_copy_tool(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Duplicate bank with offset").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let banks = 100 / PEDALS_PER_BANK;
+                let mut from = self.bank_copy_from + 1;
+                let mut to = self.bank_copy_to + 1;
+                ui.label("Copy bank");
+                ui.add(egui::DragValue::new(&mut from).clamp_range(1..=banks));
+                ui.label("to bank");
+                ui.add(egui::DragValue::new(&mut to).clamp_range(1..=banks));
+                ui.label("adding");
+                ui.add(egui::DragValue::new(&mut self.bank_copy_offset).clamp_range(-127..=127));
+                ui.label("to every PC");
+                if from - 1 != self.bank_copy_from || to - 1 != self.bank_copy_to {
+                    self.bank_copy_from = from - 1;
+                    self.bank_copy_to = to - 1;
+                    self.bank_copy = None;
+                }
+            });
+
+            let target_locked = self.metadata.is_bank_locked(self.bank_copy_to);
+            let undo_allowed = self.bank_copy_undo.as_ref().is_some_and(|previous| {
+                previous
+                    .iter()
+                    .all(|(index, _)| !self.metadata.is_preset_locked(*index))
+            });
+            if target_locked {
+                self.bank_copy = None;
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        self.bank_copy_from != self.bank_copy_to && !target_locked,
+                        egui::Button::new("Preview"),
+                    )
+                    .on_disabled_hover_text("The target bank is locked")
+                    .clicked()
+                {
+                    let sysex_message = self.sysex_message.lock().unwrap();
+                    self.bank_copy = Some(BankCopy::plan(
+                        &sysex_message,
+                        self.bank_copy_from,
+                        self.bank_copy_to,
+                        self.bank_copy_offset,
+                    ));
+                }
+                if ui
+                    .add_enabled(undo_allowed, egui::Button::new("Undo"))
+                    .on_hover_text("Restore the presets the last copy overwrote")
+                    .clicked()
+                {
+                    if let Some(previous) = self.bank_copy_undo.take() {
+                        let mut sysex_message = self.sysex_message.lock().unwrap();
+                        replace_presets(&self.events, &mut sysex_message, previous);
+                    }
+                }
+            });
+
+            let Some(copy) = &self.bank_copy else {
+                return;
+            };
+
+            let mut sysex_message = self.sysex_message.lock().unwrap();
+            ui.label(copy.summary());
+            if copy.clamped > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("{} program changes are clamped to 0–127", copy.clamped),
+                );
+            }
+            for line in copy.describe(&sysex_message) {
+                ui.label(line);
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                if ui.button("Apply").clicked() {
+                    let previous = copy
+                        .presets
+                        .iter()
+                        .map(|&(index, _)| (index, *sysex_message.preset(index)))
+                        .collect();
+                    replace_presets(&self.events, &mut sysex_message, copy.presets.clone());
+                    self.bank_copy_undo = Some(previous);
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            drop(sysex_message);
+            if close {
+                self.bank_copy = None;
+            }
+        });
+    }
+
+    /// Reads the wizards again, for files added or changed in `WIZARD_DIR`
+    fn load_wizards(&mut self) {
+        let (wizards, problems) = wizard::load_all(Path::new(WIZARD_DIR));
+        self.wizards = wizards;
+        self.wizard_problems = problems;
+        self.wizard = 0;
+        self.wizard_answers = self.wizards[0].default_answers();
+        self.wizard_result = None;
+    }
+
+    fn show_wizards(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Preset wizards").show(ui, |ui| {
+            if self.wizards.is_empty() {
+                self.load_wizards();
+            }
+            let before = self.wizard;
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Wizard")
+                    .selected_text(&self.wizards[self.wizard].name)
+                    .show_ui(ui, |ui| {
+                        for (index, wizard) in self.wizards.iter().enumerate() {
+                            ui.selectable_value(&mut self.wizard, index, &wizard.name);
+                        }
+                    });
+                if ui
+                    .button("Reload")
+                    .on_hover_text(format!(
+                        "Read the wizard files in the {} folder again",
+                        WIZARD_DIR
+                    ))
+                    .clicked()
+                {
+                    self.load_wizards();
+                }
+            });
+            for problem in &self.wizard_problems {
+                ui.colored_label(egui::Color32::YELLOW, problem);
+            }
+            let wizard = &self.wizards[self.wizard];
+            if self.wizard != before {
+                self.wizard_answers = wizard.default_answers();
+                self.wizard_result = None;
+            }
+            ui.label(&wizard.description);
+
+            egui::Grid::new("wizard_questions").show(ui, |ui| {
+                for (question, answer) in wizard.questions.iter().zip(&mut self.wizard_answers) {
+                    ui.label(&question.label);
+                    if ui
+                        .add(egui::DragValue::new(answer).clamp_range(0..=question.max))
+                        .changed()
+                    {
+                        self.wizard_result = None;
+                    }
+                    ui.end_row();
+                }
+                ui.label("Bank to fill");
+                let mut bank = self.wizard_bank + 1;
+                ui.add(egui::DragValue::new(&mut bank).clamp_range(1..=100 / PEDALS_PER_BANK));
+                if bank - 1 != self.wizard_bank {
+                    self.wizard_bank = bank - 1;
+                    self.wizard_result = None;
+                }
+                ui.end_row();
+            });
+
+            let locked = self.metadata.is_bank_locked(self.wizard_bank);
+            if ui
+                .add_enabled(!locked, egui::Button::new("Preview"))
+                .on_disabled_hover_text("The bank is locked")
+                .clicked()
+            {
+                match wizard.generate(self.wizard_bank, &self.wizard_answers) {
+                    Ok(result) => self.wizard_result = Some(result),
+                    Err(e) => self.status = Some(format!("Could not generate the bank: {}", e)),
+                }
+            }
+
+            let Some(result) = &self.wizard_result else {
+                return;
+            };
+            for (index, preset, name) in &result.presets {
+                ui.label(format!(
+                    "Preset {} {}: PC {} · CC {}={} · CC {}={}",
+                    index + 1,
+                    name,
+                    preset.get(PresetField::ProgramChange(0)),
+                    preset.get(PresetField::ControlNumber(0)),
+                    preset.get(PresetField::ControlValue(0)),
+                    preset.get(PresetField::ControlNumber(1)),
+                    preset.get(PresetField::ControlValue(1)),
+                ));
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!locked, egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    for (index, _, name) in &result.presets {
+                        self.metadata.preset_mut(*index).name = name.clone();
+                    }
+                    let presets = result
+                        .presets
+                        .iter()
+                        .map(|(index, preset, _)| (*index, *preset))
+                        .collect();
+                    let mut sysex_message = self.sysex_message.lock().unwrap();
+                    replace_presets(&self.events, &mut sysex_message, presets);
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                self.wizard_result = None;
+            }
+        });
+    }
+
+    fn show_capture_import(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Import from MIDI monitor capture").show(ui, |ui| {
+            ui.label(
+                "Builds a preset from a text log of captured messages, e.g. \"B0 50 7F\" lines.",
+            );
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.capture_path);
+                let mut number = self.capture_target + 1;
+                ui.label("into preset");
+                ui.add(egui::DragValue::new(&mut number).clamp_range(1..=100));
+                self.capture_target = number - 1;
+                if ui.button("Read").clicked() {
+                    let result = fs::read_to_string(&self.capture_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| capture::import(&text));
+                    match result {
+                        Ok(import) => self.capture_import = Some(import),
+                        Err(e) => {
+                            self.capture_import = None;
+                            self.status =
+                                Some(format!("Could not import {}: {}", self.capture_path, e));
+                        }
+                    }
+                }
+            });
+
+            let Some(import) = &self.capture_import else {
+                return;
+            };
+            for line in &import.used {
+                ui.label(line);
+            }
+            if import.ignored > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("{} messages did not fit into a preset", import.ignored),
+                );
+            }
+
+            let mut close = false;
+            ui.horizontal(|ui| {
+                let locked = self.metadata.is_preset_locked(self.capture_target);
+                if ui
+                    .add_enabled(!locked, egui::Button::new("Apply"))
+                    .on_disabled_hover_text("The target preset is in a locked bank")
+                    .clicked()
+                {
+                    let mut sysex_message = self.sysex_message.lock().unwrap();
+                    replace_presets(
+                        &self.events,
+                        &mut sysex_message,
+                        vec![(self.capture_target, import.preset)],
+                    );
+                    close = true;
+                }
+                if ui.button("Discard").clicked() {
+                    close = true;
+                }
+            });
+            if close {
+                self.capture_import = None;
+            }
+        });
+    }
+
+    fn show_sysex_console(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("SysEx console").show(ui, |ui| {
+            let base = self.config.number_base;
+            ui.label(format!(
+                "Sends any bytes typed as {} to the output ports, for experimenting with \
+                 undocumented commands. Nothing is checked, so know what you send.",
+                match base {
+                    NumberBase::Decimal => "decimal numbers",
+                    NumberBase::Hex => "hex",
+                }
+            ));
+            let mut send = false;
+            ui.horizontal(|ui| {
+                let line = ui.add(
+                    egui::TextEdit::singleline(&mut self.console_text)
+                        .hint_text(match base {
+                            NumberBase::Decimal => "240 0 32 50 … 247",
+                            NumberBase::Hex => "F0 00 20 32 … F7",
+                        })
+                        .desired_width(320.0),
+                );
+                send = line.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                send |= ui.button("Send").clicked();
+                ui.menu_button("History", |ui| {
+                    if self.config.console_history.is_empty() {
+                        ui.label("Nothing sent yet");
+                    }
+                    for line in &self.config.console_history {
+                        let line = stored_as(line, base);
+                        if ui.button(&line).clicked() {
+                            self.console_text = line;
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+            if send {
+                self.send_console_line();
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.console_snippet_name)
+                        .hint_text("Snippet name")
+                        .desired_width(160.0),
+                );
+                let name = self.console_snippet_name.trim().to_string();
+                if ui
+                    .add_enabled(
+                        !name.is_empty() && !self.console_text.trim().is_empty(),
+                        egui::Button::new("Save snippet"),
+                    )
+                    .clicked()
+                {
+                    match parse_bytes(&self.console_text, base) {
+                        Ok(bytes) => {
+                            // Stored as hex whatever the base, so they read back in either
+                            let stored = spell_bytes(&bytes, NumberBase::Hex);
+                            self.config.console_snippets.insert(name, stored);
+                            self.config.save();
+                        }
+                        Err(e) => self.status = Some(e),
+                    }
+                }
+            });
+            let mut removed = None;
+            egui::Grid::new("console_snippets").show(ui, |ui| {
+                for (name, hex) in &self.config.console_snippets {
+                    let line = stored_as(hex, base);
+                    ui.label(name);
+                    ui.monospace(&line);
+                    if ui.button("Use").clicked() {
+                        self.console_text = line;
+                        self.console_snippet_name = name.clone();
+                    }
+                    if ui.button("Remove").clicked() {
+                        removed = Some(name.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(name) = removed {
+                self.config.console_snippets.remove(&name);
+                self.config.save();
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Responses from")
+                    .selected_text(
+                        self.console_port
+                            .and_then(|i| self.port_labels.get(i))
+                            .map_or("None", String::as_str),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (index, port) in self.port_labels.iter().enumerate() {
+                            ui.selectable_value(&mut self.console_port, Some(index), port);
+                        }
+                    });
+                if self.console_listener.is_running() {
+                    if ui.button("Stop listening").clicked() {
+                        self.console_listener.stop();
+                    }
+                } else if let Some(port) = self.console_port {
+                    if ui.button("Listen").clicked() {
+                        if let Err(e) = self.console_listener.start(port, ui.ctx()) {
+                            self.status = Some(format!("Could not listen to the port: {}", e));
+                        }
+                    }
+                }
+                if ui.button("Clear").clicked() {
+                    self.console_listener.clear();
+                }
+            });
+            let responses = self.console_listener.responses();
+            egui::ScrollArea::vertical()
+                .id_source("console_responses")
+                .max_height(160.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for response in &responses {
+                        ui.monospace(format!(
+                            "+{:.3}s  {}",
+                            response.at.as_secs_f64(),
+                            format_bytes(&response.bytes, base)
+                        ));
+                    }
+                });
+        });
+    }
+
+    fn send_console_line(&mut self) {
+        let line = self.console_text.trim().to_string();
+        let bytes = match parse_bytes(&line, self.config.number_base) {
+            Ok(bytes) if !bytes.is_empty() => bytes,
+            Ok(_) => return,
+            Err(e) => {
+                self.status = Some(e);
+                return;
+            }
+        };
+        if self.outputs.is_empty() {
+            self.status = Some("No MIDI connection available".to_string());
+            return;
+        }
+        if self.config.preview_writes {
+            let ports = self.outputs.names().into_iter().map(String::from).collect();
+            self.hold_write(PendingWrite::Console { bytes }, ports);
+            return;
+        }
+        self.send_console_bytes(&bytes);
+    }
+
+    fn send_console_bytes(&mut self, bytes: &[u8]) {
+        let failed: Vec<String> = self
+            .outputs
+            .send_each(bytes)
+            .into_iter()
+            .filter_map(|sent| sent.result.err().map(|e| format!("{}: {}", sent.port, e)))
+            .collect();
+        self.log
+            .push(format!("Sent from the console: {}", format_hex(bytes)));
+        remember(
+            &mut self.config.console_history,
+            &spell_bytes(bytes, NumberBase::Hex),
+        );
+        self.config.save();
+        self.status = Some(if failed.is_empty() {
+            format!("Sent {} bytes", bytes.len())
+        } else {
+            format!("Failed to send to {}", failed.join(", "))
+        });
+    }
+
+    fn show_live_capture(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Capture preset").show(ui, |ui| {
+            ui.label(
+                "Arm the capture, then press buttons on another controller or send messages \
+                 from the DAW; the program changes, control changes and note that arrive \
+                 become the preset.",
+            );
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Input")
+                    .selected_text(
+                        self.live_capture_port
+                            .and_then(|i| self.port_labels.get(i))
+                            .map_or("None", String::as_str),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (index, port) in self.port_labels.iter().enumerate() {
+                            ui.selectable_value(&mut self.live_capture_port, Some(index), port);
+                        }
+                    });
+                let mut number = self.capture_target + 1;
+                ui.label("into preset");
+                ui.add(egui::DragValue::new(&mut number).clamp_range(1..=100));
+                self.capture_target = number - 1;
+
+                if self.live_capture.is_running() {
+                    if ui.button("Stop").clicked() {
+                        self.live_capture.stop();
+                    }
+                } else if let Some(port) = self.live_capture_port {
+                    if ui.button("Arm").clicked() {
+                        if let Err(e) = self.live_capture.start(port, ui.ctx()) {
+                            self.status = Some(format!("Could not start the capture: {}", e));
+                        }
+                    }
+                }
+            });
+
+            let messages = self.live_capture.messages();
+            if self.live_capture.is_running() {
+                ui.label(format!("Listening… {} messages so far", messages.len()));
+            }
+            if messages.is_empty() {
+                return;
+            }
+            let capture = capture::assemble(&messages);
+            for line in &capture.used {
+                ui.label(line);
+            }
+            if capture.ignored > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("{} messages did not fit into a preset", capture.ignored),
+                );
+            }
+
+            ui.horizontal(|ui| {
+                let locked = self.metadata.is_preset_locked(self.capture_target);
+                if ui
+                    .add_enabled(!locked, egui::Button::new("Apply"))
+                    .on_disabled_hover_text("The target preset is in a locked bank")
+                    .clicked()
+                {
+                    let mut sysex_message = self.sysex_message.lock().unwrap();
+                    replace_presets(
+                        &self.events,
+                        &mut sysex_message,
+                        vec![(self.capture_target, capture.preset)],
+                    );
+                    drop(sysex_message);
+                    self.live_capture.stop();
+                    self.live_capture.clear();
+                    self.status = Some(format!(
+                        "Preset {} built from {} captured messages",
+                        self.capture_target + 1,
+                        messages.len()
+                    ));
+                }
+                if ui.button("Clear").clicked() {
+                    self.live_capture.clear();
+                }
+            });
+        });
+    }
+
+    fn show_view_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label("Fields on preset cards:");
+        let mut changed = false;
+        for (function, name) in spec::GLOBAL_FUNCTIONS.iter().enumerate() {
+            let mut shown = !self.hidden_functions.contains(&function);
+            if ui.checkbox(&mut shown, *name).changed() {
+                changed = true;
+                if shown {
+                    self.hidden_functions.remove(&function);
+                } else {
+                    self.hidden_functions.insert(function);
+                }
+            }
+        }
+        if ui
+            .add_enabled(
+                !self.hidden_functions.is_empty(),
+                egui::Button::new("Show all"),
+            )
+            .clicked()
+        {
+            changed = true;
+            self.hidden_functions.clear();
+        }
+        // The cards change height
+        if changed {
+            self.row_heights.clear();
+        }
+    }
+
+    fn show_presets(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Presets");
+            if ui.button("Collapse all").clicked() {
+                self.collapsed_cards = (0..100).collect();
+                self.row_heights.clear();
+            }
+            if ui.button("Expand all").clicked() {
+                self.collapsed_cards.clear();
+                self.row_heights.clear();
+            }
+            self.show_highlight_controls(ui);
+        });
+
+        // As many cards per row as fit, but never more than a bank
+        let spacing = ui.spacing().item_spacing.x;
+        let columns = ((ui.available_width() / (self.card_width + spacing)) as usize)
+            .clamp(1, PEDALS_PER_BANK);
+        let mut widest: f32 = 0.0;
+        let firmware = self.sysex_message.lock().unwrap().firmware().clone();
+        let options = CardOptions::new(
+            &self.config,
+            &self.metadata,
+            &self.highlights,
+            firmware,
+            &self.hidden_functions,
+            &self.dirty,
+        );
+        let mut preview = None;
+        let mut paste = None;
+        let mut copy = None;
+
+        let rows = grid_rows(self.sysex_message.lock().unwrap().presets().len(), columns);
+        self.row_heights.prepare(columns, rows.len());
+        let started = Instant::now();
+        let mut built = 0;
+        let mut relayout = false;
+
+        // Only rows that intersect the viewport get widgets; the others just
+        // take up the height they had when last drawn
+        let mut area = egui::ScrollArea::vertical();
+        if let Some(bank) = self.scroll_to_bank.take() {
+            let row_spacing = ui.spacing().item_spacing.y;
+            let offset: f32 = rows
+                .iter()
+                .enumerate()
+                .take_while(|(_, row)| **row != GridRow::BankHeader(bank))
+                .map(|(i, row)| {
+                    self.row_heights.height(i, row, &self.collapsed_cards) + row_spacing
+                })
+                .sum();
+            area = area.vertical_scroll_offset(offset);
+        }
+        let mut top_bank = None;
+        area.show_viewport(ui, |ui, viewport| {
+            // Locked through clones so the cards can borrow the rest of self
+            let (sysex, device) = (self.sysex_message.clone(), self.device_message.clone());
+            let mut sysex_message = sysex.lock().unwrap();
+            let device = device.lock().unwrap();
+            let row_spacing = ui.spacing().item_spacing.y;
+            let (first_visible, last_visible) = (
+                viewport.min.y - GRID_OVERSCAN,
+                viewport.max.y + GRID_OVERSCAN,
+            );
+            let mut top = 0.0;
+            for (row_index, row) in rows.iter().enumerate() {
+                let height = self
+                    .row_heights
+                    .height(row_index, row, &self.collapsed_cards);
+                if top_bank.is_none() && top + height >= viewport.min.y {
+                    top_bank = Some(match row {
+                        GridRow::BankHeader(bank) => *bank,
+                        GridRow::Cards(range) => range.start / PEDALS_PER_BANK,
+                    });
+                }
+                if top + height < first_visible || top > last_visible {
+                    ui.allocate_space(egui::vec2(ui.available_width(), height));
+                    top += height + row_spacing;
+                    continue;
+                }
+
+                let drawn = match row {
+                    &GridRow::BankHeader(bank) => {
+                        let mut locked = self.metadata.is_bank_locked(bank);
+                        let response = ui
+                            .horizontal(|ui| {
+                                ui.strong(format!("Bank {}", options.numbering.bank(bank)));
+                                let icon = if locked { "🔒" } else { "🔓" };
+                                ui.toggle_value(&mut locked, icon).on_hover_text(
+                                    "Locked banks are read-only and skipped by batch tools",
+                                );
+                                if ui
+                                    .small_button("📄 Copy bank")
+                                    .on_hover_text(
+                                        "Copy the bank as text to paste into another editor, \
+                                         a chat or a forum post",
+                                    )
+                                    .clicked()
+                                {
+                                    copy =
+                                        Some(bank * PEDALS_PER_BANK..(bank + 1) * PEDALS_PER_BANK);
+                                }
+                            })
+                            .response;
+                        self.metadata.set_bank_locked(bank, locked);
+                        response
+                    }
+                    GridRow::Cards(range) => {
+                        ui.horizontal_top(|ui| {
+                            for i in range.clone() {
+                                built += 1;
+                                let device = device.as_ref().map(|device| device.preset(i));
+                                let card = self.show_grid_card(
+                                    ui,
+                                    i,
+                                    &mut sysex_message,
+                                    device,
+                                    &options,
+                                );
+                                widest = widest.max(card.width);
+                                if card.preview {
+                                    preview = Some(i);
+                                }
+                                if let Some(text) = card.paste {
+                                    paste = Some((i, text));
+                                }
+                                if card.copy {
+                                    copy = Some(i..i + 1);
+                                }
+                            }
+                        })
+                        .response
+                    }
+                };
+                let height = drawn.rect.height();
+                relayout |=
+                    self.row_heights
+                        .measured(row_index, row, &self.collapsed_cards, height);
+                top += height + row_spacing;
+            }
+        });
+        if let Some(bank) = top_bank {
+            self.grid_bank = bank;
+        }
+        self.grid_frame = (built, started.elapsed());
+        if relayout {
+            ui.ctx().request_repaint();
+        }
+
+        if widest > 0.0 && widest != self.card_width {
+            self.card_width = widest;
+            ui.ctx().request_repaint();
+        }
+        if let Some(index) = preview {
+            self.preview_preset(index);
+        }
+        if let Some((index, text)) = paste {
+            self.paste_target = Some(index);
+            self.paste_text = text;
+        }
+        if let Some(indexes) = copy {
+            let copied = indexes.len();
+            let clip =
+                PresetClip::copy(&self.sysex_message.lock().unwrap(), &self.metadata, indexes);
+            ui.output_mut(|o| o.copied_text = clip.to_text());
+            self.status = Some(format!("Copied {} presets to the clipboard", copied));
+        }
+    }
+
+    /// Draws one card of the preset grid and applies what was edited on it
+    fn show_grid_card(
+        &mut self,
+        ui: &mut egui::Ui,
+        i: usize,
+        sysex_message: &mut SysExMessage,
+        device: Option<&Preset>,
+        options: &CardOptions,
+    ) -> GridCard {
+        let mut preview = false;
+        let mut paste = None;
+        let mut copy = false;
+        let preset_locked = self.metadata.is_preset_locked(i);
+        let metadata = self.metadata.preset_mut(i);
+        let card = ui.vertical(|ui| {
+            // Rows are laid out on their own, so line the columns up
+            ui.set_min_width(self.card_width);
+            if self.collapsed_cards.contains(&i) {
+                let summary =
+                    preset_summary(options.numbering, i, sysex_message.preset(i), metadata);
+                if ui
+                    .button(summary)
+                    .on_hover_text("Click to expand")
+                    .clicked()
+                {
+                    self.collapsed_cards.remove(&i);
+                }
+                return CardEdits::default();
+            }
+            if ui
+                .small_button("▴")
+                .on_hover_text("Collapse to a one-line summary")
+                .clicked()
+            {
+                self.collapsed_cards.insert(i);
+            }
+            ui.add_enabled_ui(!preset_locked, |ui| {
+                let edits = preset_card(
+                    ui,
+                    i,
+                    &format!("Preset {}", options.numbering.preset(i)),
+                    sysex_message.preset(i),
+                    device,
+                    Some(metadata),
+                    options,
+                );
+                if ui
+                    .small_button("▶ Preview")
+                    .on_hover_text("Send what this preset sends when its pedal is pressed")
+                    .clicked()
+                {
+                    preview = true;
+                }
+                if ui
+                    .small_button("📋 Paste")
+                    .on_hover_text(
+                        "Fill this preset from text like \"PC 12 ch1; CC 80 127 ch1\" \
+                         (or press Ctrl+V over the card)",
+                    )
+                    .clicked()
+                {
+                    paste = Some(String::new());
+                }
+                edits
+            })
+            .inner
+        });
+        // Copying also works on presets of locked banks
+        if !self.collapsed_cards.contains(&i)
+            && ui
+                .small_button("📄 Copy")
+                .on_hover_text(
+                    "Copy as text to paste into another editor, a chat or a forum post \
+                     (or press Ctrl+C over the card)",
+                )
+                .clicked()
+        {
+            copy = true;
+        }
+        let pasted_text = ui.input(|input| {
+            input.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        let over_card = ui.rect_contains_pointer(card.response.rect)
+            && ui.memory(|memory| memory.focused().is_none());
+        if let Some(text) = pasted_text.filter(|_| !preset_locked && over_card) {
+            paste = Some(text);
+        }
+        if over_card && ui.input(|input| input.events.contains(&egui::Event::Copy)) {
+            copy = true;
+        }
+        apply_card_edits(&self.events, sysex_message, i, card.inner);
+
+        // Disabled cards still report drops, so check the lock here too
+        let drop = card.response.dnd_release_payload::<PresetDrag>();
+        if let Some(drag) = drop.filter(|_| !self.locked && !preset_locked) {
+            let drops = match &*drag {
+                PresetDrag::Preset(preset, metadata) => {
+                    vec![(i, *preset, metadata.clone())]
+                }
+                // A bank lands on the bank of the preset it was dropped onto
+                PresetDrag::Bank(presets) => {
+                    let first = i - bank_and_pedal(i).1;
+                    presets
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, (preset, metadata))| {
+                            (first + offset, *preset, metadata.clone())
+                        })
+                        .collect()
+                }
+            };
+            for (index, preset, metadata) in drops {
+                if self.metadata.is_preset_locked(index) {
+                    continue;
+                }
+                *self.metadata.preset_mut(index) = metadata;
+                if sysex_message.set_preset(index, preset) {
+                    self.events.emit(ModelEvent::preset_replaced(index));
+                }
+            }
+        }
+
+        GridCard {
+            width: card.response.rect.width(),
+            preview,
+            paste,
+            copy,
+        }
+    }
+
+    fn show_scratchpad(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            "Draft ideas in these slots. They are saved with the project but are not part of \
+             the dump, so they are never sent; copy a draft into a real preset when it is ready.",
+        );
+        let firmware = self.sysex_message.lock().unwrap().firmware().clone();
+        let mut options = CardOptions::new(
+            &self.config,
+            &self.metadata,
+            &self.highlights,
+            firmware,
+            &self.hidden_functions,
+            &self.dirty,
+        );
+        // Received dumps never touch the drafts, and they are neither sent
+        // nor compared with a saved dump
+        options.highlighted.clear();
+        options.unsaved.clear();
+        options.unsent.clear();
+
+        let (mut copy_to, mut copy_from) = (None, None);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (slot, scratch) in self.metadata.scratchpad.iter_mut().enumerate() {
+                    ui.vertical(|ui| {
+                        let edits = preset_card(
+                            ui,
+                            slot,
+                            &format!("Scratch {}", slot + 1),
+                            &scratch.preset,
+                            None,
+                            Some(&mut scratch.labels),
+                            &options,
+                        );
+                        for (field, value) in edits.values {
+                            scratch.preset.set(field, value);
+                        }
+                        for (slot, enabled) in edits.slots {
+                            scratch.preset.set_enabled(slot, enabled);
+                        }
+
+                        ui.horizontal(|ui| {
+                            let target = &mut self.scratch_targets[slot];
+                            let mut number = *target + 1;
+                            ui.label("Preset");
+                            ui.add(egui::DragValue::new(&mut number).clamp_range(1..=100));
+                            *target = number - 1;
+                            if options.numbering != Numbering::Linear {
+                                ui.weak(options.numbering.preset(*target));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(
+                                    !scratch.is_empty(),
+                                    egui::Button::new("Copy to preset"),
+                                )
+                                .clicked()
+                            {
+                                copy_to = Some(slot);
+                            }
+                            if ui
+                                .button("Copy from preset")
+                                .on_hover_text("Replace this draft with the preset")
+                                .clicked()
+                            {
+                                copy_from = Some(slot);
+                            }
+                        });
+                    });
+                }
+            });
+        });
+        drop(options);
+
+        if let Some(slot) = copy_to {
+            let index = self.scratch_targets[slot];
+            if self.metadata.is_preset_locked(index) {
+                self.status = Some(format!(
+                    "Preset {} is in a locked bank",
+                    self.config.numbering.preset(index)
+                ));
+                return;
+            }
+            let scratch = self.metadata.scratchpad[slot].clone();
+            *self.metadata.preset_mut(index) = scratch.labels;
+            let mut sysex_message = self.sysex_message.lock().unwrap();
+            replace_presets(
+                &self.events,
+                &mut sysex_message,
+                vec![(index, scratch.preset)],
+            );
+            drop(sysex_message);
+            self.status = Some(format!(
+                "Scratch {} copied to preset {}",
+                slot + 1,
+                self.config.numbering.preset(index)
+            ));
+        }
+        if let Some(slot) = copy_from {
+            let index = self.scratch_targets[slot];
+            self.metadata.scratchpad[slot] = ScratchPreset {
+                preset: *self.sysex_message.lock().unwrap().preset(index),
+                labels: self
+                    .metadata
+                    .presets
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+        }
+    }
+
+    fn show_highlight_controls(&mut self, ui: &mut egui::Ui) {
+        let mut highlights = self.highlights.lock().unwrap();
+        let Some(active) = highlights.as_ref() else {
+            return;
+        };
+
+        let remaining = active.until.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            *highlights = None;
+            return;
+        }
+        // Repaint once more when they expire so they disappear on time
+        ui.ctx().request_repaint_after(remaining);
+
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 140, 0),
+            format!(
+                "{} values changed by the received dump",
+                active.fields.len()
+            ),
+        );
+        if ui.button("Clear highlights").clicked() {
+            *highlights = None;
+        }
+    }
+
+    /// Plays a preset's messages on the preview output
+    fn preview_preset(&mut self, index: usize) {
+        let messages = {
+            let sysex_message = self.sysex_message.lock().unwrap();
+            preview_messages(
+                sysex_message.preset(index),
+                &self
+                    .metadata
+                    .preview_channels(sysex_message.global_channels()),
+                &self.metadata.send_order,
+            )
+        };
+
+        self.simulator.record(&messages);
+        let target = self.preview.describe(&self.outputs);
+        let result = messages
+            .iter()
+            .try_for_each(|message| self.preview.send(&mut self.outputs, message));
+        self.status = Some(match result {
+            Ok(()) => format!(
+                "Previewed preset {} on {}",
+                self.config.numbering.preset(index),
+                target
+            ),
+            Err(e) => format!(
+                "Failed to preview preset {}: {}",
+                self.config.numbering.preset(index),
+                e
+            ),
+        });
+        self.report_reconnects();
+    }
+
+    fn show_device_vs_editor(&mut self, ui: &mut egui::Ui) {
+        let device_message = self.device_message.lock().unwrap();
+        let Some(device) = device_message.as_ref() else {
+            ui.label("No dump has been received from the device yet");
+            return;
+        };
+        let mut editor = self.sysex_message.lock().unwrap();
+        let options = CardOptions::new(
+            &self.config,
+            &self.metadata,
+            &self.highlights,
+            editor.firmware().clone(),
+            &self.hidden_functions,
+            &self.dirty,
+        );
+        let title = |index: usize| format!("Preset {}", options.numbering.preset(index));
+
+        ui.label("The device holds the left preset of every row, the editor the right one.");
+        // One preset per row; rows outside the viewport only take their
+        // space, as in the preset grid
+        let rows: Vec<GridRow> = (0..device.presets().len())
+            .map(|i| GridRow::Cards(i..i + 1))
+            .collect();
+        self.compare_heights.prepare(1, rows.len());
+        let unfolded = HashSet::new();
+        let mut relayout = false;
+        // Both panes live in one scroll area so they always scroll together
+        egui::ScrollArea::vertical()
+            .id_source("device_vs_editor")
+            .show_viewport(ui, |ui, viewport| {
+                let row_spacing = ui.spacing().item_spacing.y;
+                let mut top = 0.0;
+                for (i, row) in rows.iter().enumerate() {
+                    let height = self.compare_heights.height(i, row, &unfolded);
+                    if top + height < viewport.min.y - GRID_OVERSCAN
+                        || top > viewport.max.y + GRID_OVERSCAN
+                    {
+                        ui.allocate_space(egui::vec2(ui.available_width(), height));
+                        top += height + row_spacing;
+                        continue;
+                    }
+
+                    let locked = self.metadata.is_preset_locked(i);
+                    let drawn = ui.horizontal_top(|ui| {
+                        ui.add_enabled_ui(false, |ui| {
+                            preset_card(ui, i, &title(i), device.preset(i), None, None, &options);
+                        });
+
+                        ui.vertical(|ui| {
+                            let differs = device.preset(i) != editor.preset(i);
+                            if ui
+                                .add_enabled(differs && !locked, egui::Button::new("→"))
+                                .on_hover_text("Copy device preset into the editor")
+                                .clicked()
+                                && editor.set_preset(i, *device.preset(i))
+                            {
+                                self.events.emit(ModelEvent::preset_replaced(i));
+                            }
+                            if differs {
+                                ui.colored_label(egui::Color32::YELLOW, "differs");
+                            }
+                        });
+
+                        let metadata = self.metadata.preset_mut(i);
+                        let edits = ui
+                            .add_enabled_ui(!locked, |ui| {
+                                preset_card(
+                                    ui,
+                                    i,
+                                    &title(i),
+                                    editor.preset(i),
+                                    Some(device.preset(i)),
+                                    Some(metadata),
+                                    &options,
+                                )
+                            })
+                            .inner;
+                        apply_card_edits(&self.events, &mut editor, i, edits);
+                    });
+                    let height = drawn.response.rect.height();
+                    relayout |= self.compare_heights.measured(i, row, &unfolded, height);
+                    top += height + row_spacing;
+                }
+            });
+        if relayout {
+            ui.ctx().request_repaint();
+        }
+    }
+}
+
+/// One line for a collapsed card: number, name and the messages it sends first
+fn preset_summary(
+    numbering: Numbering,
+    index: usize,
+    preset: &Preset,
+    metadata: &PresetMetadata,
+) -> String {
+    let mut summary = format!("{}.", numbering.preset(index));
+    if !metadata.name.is_empty() {
+        summary.push_str(&format!(" {}", metadata.name));
+    }
+    if let Some(program) = preset.program_change(0) {
+        summary.push_str(&format!(" · PC {}", program));
+    }
+    if let Some((number, value)) = preset.control_change(0) {
+        summary.push_str(&format!(" · CC {}={}", number, value));
+    }
+    summary
+}
+
+/// Writes field edits made in the UI into the model and notifies subscribers
+fn apply_edits(
+    events: &ModelEvents,
+    sysex_message: &mut SysExMessage,
+    index: usize,
+    edits: Vec<(PresetField, Value7)>,
+) {
+    for (field, value) in edits {
+        if sysex_message.set_preset_field(index, field, value) {
+            events.emit(ModelEvent::preset_changed(index, field));
+        }
+    }
+}
+
+/// Switches the slots toggled on a card, then writes its field edits
+fn apply_card_edits(
+    events: &ModelEvents,
+    sysex_message: &mut SysExMessage,
+    index: usize,
+    edits: CardEdits,
+) {
+    let mut switched = false;
+    for (slot, enabled) in edits.slots {
+        switched |= sysex_message.set_slot_enabled(index, slot, enabled);
+    }
+    if switched {
+        events.emit(ModelEvent::preset_replaced(index));
+    }
+    apply_edits(events, sysex_message, index, edits.values);
+}
+
+fn replace_presets(
+    events: &ModelEvents,
+    sysex_message: &mut SysExMessage,
+    presets: Vec<(usize, Preset)>,
+) {
+    for (index, preset) in presets {
+        if sysex_message.set_preset(index, preset) {
+            events.emit(ModelEvent::preset_replaced(index));
+        }
+    }
+}
+
+fn apply_channel_plan(events: &ModelEvents, sysex_message: &mut SysExMessage, plan: &ChannelPlan) {
+    let mut globals_changed = false;
+    for change in &plan.changes {
+        match *change {
+            ChannelChange::GlobalChannel { function, to, .. } => {
+                globals_changed |= sysex_message.set_global_channel(function, to);
+            }
+            ChannelChange::PresetField {
+                index, field, to, ..
+            } => apply_edits(events, sysex_message, index, vec![(field, to)]),
+            ChannelChange::Slot {
+                index,
+                slot,
+                enabled,
+            } => {
+                if sysex_message.set_slot_enabled(index, slot, enabled) {
+                    events.emit(ModelEvent::preset_replaced(index));
+                }
+            }
+        }
+    }
+    if globals_changed {
+        events.emit(ModelEvent::GlobalsChanged);
+    }
+}
+
+/// Data that would not decode as a dump, kept to show where it broke
+struct DecodeFailure {
+    /// What the data is, e.g. "the received dump"
+    source: String,
+    data: Vec<u8>,
+    error: MidiError,
+}
+
+/// Raw bytes shown in the hex viewer
+struct HexView {
+    name: String,
+    data: Vec<u8>,
+    /// The byte picked out, e.g. where decoding failed
+    offset: Option<usize>,
+    /// Scroll to the byte when the viewer is next drawn
+    scroll: bool,
+}
+
+/// A device write held back until the user typed the confirmation
+struct WritePreview {
+    write: PendingWrite,
+    ports: Vec<String>,
+    typed: String,
+}
+
+/// What a confirmed write sends, fixed when the preview opened so edits
+/// made while it is open do not go out unreviewed
+enum PendingWrite {
+    Dump {
+        /// What was encoded, to mark as sent
+        message: Box<SysExMessage>,
+        bytes: Vec<u8>,
+        /// Presets differing from the device, whether the global settings
+        /// do and the preset count; `None` when what the device holds is
+        /// unknown
+        changed: Option<(usize, bool, usize)>,
+    },
+    /// A firmware image for the bootloader on output `port`
+    Firmware {
+        port: usize,
+        messages: Vec<Vec<u8>>,
+        pause: Duration,
+    },
+    /// A line typed in the SysEx console
+    Console { bytes: Vec<u8> },
+}
+
+/// What the user asked for on one card of the preset grid
+struct GridCard {
+    width: f32,
+    preview: bool,
+    /// Text pasted with Ctrl+V over the card, or empty for the Paste button
+    paste: Option<String>,
+    /// Copy with the Copy button or Ctrl+C over the card
+    copy: bool,
+}
+
+/// What was changed on a preset card
+#[derive(Default)]
+struct CardEdits {
+    values: Vec<(PresetField, Value7)>,
+    /// Slots switched on or off
+    slots: Vec<(usize, bool)>,
+}
+
+/// View settings shared by every preset card
+struct CardOptions {
+    widget: ValueWidget,
+    base: NumberBase,
+    cc_profile: CcProfile,
+    custom_cc_names: BTreeMap<u8, String>,
+    /// Fields changed by the last received dump
+    highlighted: HashSet<(usize, PresetField)>,
+    program_names: BTreeMap<u8, String>,
+    /// Layout of the dump, for the field tooltips
+    firmware: Firmware,
+    dim_defaults: bool,
+    /// Global functions whose fields are left off
+    hidden: BTreeSet<usize>,
+    numbering: Numbering,
+    /// Presets changed since the last save and since the last send
+    unsaved: BTreeSet<usize>,
+    unsent: BTreeSet<usize>,
+}
+
+impl CardOptions {
+    fn new(
+        config: &AppConfig,
+        metadata: &ProjectMetadata,
+        highlights: &Mutex<Option<Highlights>>,
+        firmware: Firmware,
+        hidden: &BTreeSet<usize>,
+        dirty: &DirtyState,
+    ) -> Self {
+        let highlighted = highlights
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|h| Instant::now() < h.until)
+            .map(|h| h.fields.clone())
+            .unwrap_or_default();
+        Self {
+            widget: config.value_widget,
+            base: config.number_base,
+            cc_profile: metadata.cc_profile,
+            custom_cc_names: config.custom_cc_names.clone(),
+            highlighted,
+            program_names: metadata.program_names.clone(),
+            firmware,
+            dim_defaults: !config.undimmed_defaults,
+            hidden: hidden.clone(),
+            numbering: config.numbering,
+            unsaved: dirty.unsaved.clone(),
+            unsent: dirty.unsent.clone(),
+        }
+    }
+
+    fn dictionary(&self) -> CcDictionary<'_> {
+        CcDictionary::new(self.cc_profile, &self.custom_cc_names)
+    }
+}
+
+/// Draws the editor for one preset and returns the fields the user changed
+fn preset_card(
+    ui: &mut egui::Ui,
+    index: usize,
+    title: &str,
+    preset: &Preset,
+    device: Option<&Preset>,
+    metadata: Option<&mut PresetMetadata>,
+    options: &CardOptions,
+) -> CardEdits {
+    let widget = options.widget;
+    let modified = match &metadata {
+        Some(metadata) => metadata.modified.as_deref().map(history::short_time),
+        None => None,
+    };
+    let mut edits = Vec::new();
+    let mut picked = Vec::new();
+    let mut slots = Vec::new();
+    let mut switch = |ui: &mut egui::Ui, slot: usize| {
+        let mut enabled = preset.is_enabled(slot);
+        if ui
+            .checkbox(&mut enabled, "")
+            .on_hover_text(
+                "Send this message; switched off, its values are kept. The switch is saved \
+                 with the project: dumps hold no switches the editor can read, so a received \
+                 dump switches on the slots with values other than 0.",
+            )
+            .changed()
+        {
+            slots.push((slot, enabled));
+        }
+    };
+    let mut field = |ui: &mut egui::Ui, field: PresetField| {
+        let mut value = preset.get(field);
+        let dim = (options.dim_defaults && spec::is_default(field, value)) || !preset.sends(field);
+        let mut response = ui
+            .scope(|ui| {
+                if dim {
+                    ui.visuals_mut().override_text_color = Some(ui.visuals().weak_text_color());
+                }
+                value_editor(ui, &mut value, 0..=127, widget, options.base)
+            })
+            .inner
+            .on_hover_text(spec::preset_tooltip(field, &options.firmware));
+        if response.changed() {
+            edits.push((field, Value7::clamped(value)));
+        }
+        if let PresetField::ProgramChange(_) = field {
+            if !options.program_names.is_empty() {
+                response = response.on_hover_text(describe_program(value, &options.program_names));
+            }
+        }
+        if options.highlighted.contains(&(index, field)) {
+            ui.painter().rect_stroke(
+                response.rect.expand(1.0),
+                2.0,
+                egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 140, 0)),
+            );
+            response = response.on_hover_text("Changed by the last received dump");
+        }
+
+        // Pull just this value back from the last dump received from the device
+        if let Some(device) = device {
+            let device_value = device.value(field);
+            response.context_menu(|ui| {
+                let revert =
+                    egui::Button::new(format!("Revert to device value ({})", device_value));
+                if ui
+                    .add_enabled(device_value != preset.value(field), revert)
+                    .clicked()
+                {
+                    edits.push((field, device_value));
+                    ui.close_menu();
+                }
+            });
+        }
+    };
+
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(title).on_hover_text(match &modified {
+                Some(time) => format!("Last changed {}", time),
+                None => "Not changed since the project was started".to_string(),
+            });
+            let set = PresetField::ALL
+                .iter()
+                .filter(|&&field| !spec::is_default(field, preset.get(field)))
+                .count();
+            ui.weak(format!("{} set", set))
+                .on_hover_text("Fields that hold something other than their default value");
+            let hidden_set = PresetField::ALL
+                .iter()
+                .filter(|&&field| options.hidden.contains(&spec::global_function(field)))
+                .filter(|&&field| !spec::is_default(field, preset.get(field)))
+                .count();
+            if hidden_set > 0 {
+                ui.weak(format!("({} hidden)", hidden_set))
+                    .on_hover_text("Set fields that the View menu hides from the cards");
+            }
+            // Only the edited cards carry labels; the device's copy has no badges
+            if metadata.is_some() {
+                if options.unsaved.contains(&index) {
+                    ui.colored_label(egui::Color32::YELLOW, "unsaved")
+                        .on_hover_text("Changed since the project was last saved");
+                }
+                if options.unsent.contains(&index) {
+                    ui.colored_label(egui::Color32::LIGHT_BLUE, "unsent")
+                        .on_hover_text(
+                            "Differs from the dump last sent to or received from the device",
+                        );
+                }
+            }
+        });
+
+        if let Some(metadata) = metadata {
+            ui.add(
+                egui::TextEdit::singleline(&mut metadata.name)
+                    .hint_text("Name")
+                    .desired_width(120.0),
+            );
+            ui.collapsing("Notes", |ui| {
+                ui.add(egui::TextEdit::multiline(&mut metadata.notes).desired_width(120.0));
+            });
+        }
+
+        let shown = |function: usize| !options.hidden.contains(&function);
+        for j in (0..5).filter(|&j| shown(j)) {
+            ui.horizontal(|ui| {
+                switch(ui, j);
+                ui.label(format!("PC {}:", j + 1));
+                let program = PresetField::ProgramChange(j);
+                if !options.program_names.is_empty() {
+                    if let Some(picked_program) =
+                        program_picker(ui, preset.get(program), &options.program_names)
+                    {
+                        picked.push((program, picked_program));
+                    }
+                }
+                field(ui, program);
+            });
+        }
+
+        for j in (0..2).filter(|&j| shown(5 + j)) {
+            ui.horizontal(|ui| {
+                switch(ui, 5 + j);
+                ui.label(format!("CC {}:", j + 1));
+                let number = PresetField::ControlNumber(j);
+                if let Some(controller) =
+                    controller_picker(ui, preset.get(number), &options.dictionary())
+                {
+                    picked.push((number, controller));
+                }
+                field(ui, number);
+                ui.label("Value:");
+                field(ui, PresetField::ControlValue(j));
+            });
+        }
+
+        let pedals = [
+            (
+                "EP A",
+                preset.expression_pedal_a(),
+                PresetField::ExpressionA as fn(_) -> _,
+            ),
+            (
+                "EP B",
+                preset.expression_pedal_b(),
+                PresetField::ExpressionB,
+            ),
+        ];
+        for (label, pedal, pedal_field) in pedals {
+            let controller = pedal_field(ExpressionPart::Controller);
+            if !shown(spec::global_function(controller)) {
+                continue;
+            }
+            ui.horizontal(|ui| {
+                switch(ui, controller.slot());
+                ui.label(format!("{}:", label));
+                if let Some(picked_controller) =
+                    controller_picker(ui, preset.get(controller), &options.dictionary())
+                {
+                    picked.push((controller, picked_controller));
+                }
+                field(ui, controller);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Min:");
+                field(ui, pedal_field(ExpressionPart::Min));
+                ui.label("Max:");
+                field(ui, pedal_field(ExpressionPart::Max));
+            });
+            if let Some(problem) = pedal.and_then(ExpressionPedal::validate) {
+                ui.colored_label(egui::Color32::RED, problem);
+            }
+        }
+
+        if shown(9) {
+            ui.horizontal(|ui| {
+                switch(ui, 9);
+                ui.label("Note:");
+                field(ui, PresetField::Note);
+            });
+        }
+    });
+
+    edits.extend(
+        picked
+            .into_iter()
+            .map(|(field, value)| (field, Value7::clamped(value))),
+    );
+    CardEdits {
+        values: edits,
+        slots,
+    }
+}
+
+fn run_daemon(args: &CliArgs) -> Result<(), String> {
+    let log = Log::printing();
+    let (config, config_problem) = AppConfig::load();
+    if let Some(problem) = config_problem {
+        log.push(problem);
+    }
+    let port = match &args.port {
+        Some(name) => {
+            let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+            let ports: Vec<String> = midi_in
+                .ports()
+                .iter()
+                .map(|p| midi_in.port_name(p).unwrap_or_default())
+                .collect();
+            find_port(&ports, name)
+                .ok_or_else(|| format!("MIDI port \"{}\" was not found", name))?
+        }
+        None => config
+            .selected_port
+            .ok_or("No MIDI port configured; pass --port")?,
+    };
+
+    let options = DaemonOptions {
+        port,
+        backup_dir: args
+            .backup_dir
+            .clone()
+            .unwrap_or_else(|| config.backup_dir()),
+        interval: Duration::from_secs(60 * args.interval_minutes.unwrap_or(24 * 60)),
+        keep: args.keep.unwrap_or(14),
+        request: args.request.clone(),
+        firmware: config.firmware.clone(),
+    };
+    daemon::run(&options, &log)
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let mut args = match CliArgs::from_env() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let launch_dir = std::env::current_dir().unwrap_or_default();
+    match portable::enable(args.portable) {
+        Ok(Some(_)) => {
+            for path in [&mut args.project, &mut args.open, &mut args.backup_dir]
+                .into_iter()
+                .flatten()
+            {
+                *path = portable::from_launch_dir(&launch_dir, path);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Portable mode is not available: {}", e),
+    }
+
+    if args.daemon {
+        std::process::exit(match run_daemon(&args) {
+            Ok(()) => 0,
+            Err(message) => {
+                eprintln!("{}", message);
+                1
+            }
+        });
     }
-    result
-}
 
-fn main() -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "MIDI Interface Selector",
         options,
-        Box::new(|_cc| Box::<MidiApp>::default()),
+        Box::new(|_cc| Box::new(MidiApp::new(args))),
     )
 }
@@ -0,0 +1,142 @@
+use crate::firmware::{Firmware, MemoryMap};
+use crate::model::PresetField;
+use std::fs;
+use std::path::Path;
+use toml_edit::{Document, Item};
+
+/// Reads a memory map like
+///
+/// ```toml
+/// preset_size = 16
+/// data_size = 0x7ea
+/// global_channels = 0x7e0
+/// switch_modes = 0x7d0
+///
+/// [preset_fields]
+/// pc1 = 0
+/// # ... one offset for every key of PresetField::KEYS
+/// note = 15
+/// ```
+///
+/// Missing keys keep their v2.5 value. The map is validated before it is
+/// returned.
+pub fn parse(text: &str) -> Result<MemoryMap, String> {
+    let document: Document = text.parse().map_err(|e| format!("Not valid TOML: {}", e))?;
+    let mut map = Firmware::V2_5.memory_map();
+
+    let number = |item: &Item, key: &str| -> Result<Option<usize>, String> {
+        match item.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_integer()
+                .and_then(|n| usize::try_from(n).ok())
+                .map(Some)
+                .ok_or_else(|| format!("{} must be a positive whole number", key)),
+        }
+    };
+    let root = document.as_item();
+    if let Some((unknown, _)) = document.iter().find(|(key, _)| {
+        ![
+            "preset_size",
+            "data_size",
+            "global_channels",
+            "switch_modes",
+            "preset_fields",
+        ]
+        .contains(key)
+    }) {
+        return Err(format!("Unknown key \"{}\"", unknown));
+    }
+    for (key, target) in [
+        ("preset_size", &mut map.preset_size),
+        ("data_size", &mut map.data_size),
+        ("global_channels", &mut map.global_channels),
+        ("switch_modes", &mut map.switch_modes),
+    ] {
+        if let Some(value) = number(root, key)? {
+            *target = value;
+        }
+    }
+    if let Some(fields) = document.get("preset_fields") {
+        if !fields.is_table_like() {
+            return Err("preset_fields must be a table".to_string());
+        }
+        for (key, offset) in PresetField::KEYS.iter().zip(map.preset_fields.iter_mut()) {
+            if let Some(value) = number(fields, key)? {
+                *offset = value;
+            }
+        }
+        if let Some((unknown, _)) = fields.as_table_like().and_then(|table| {
+            table
+                .iter()
+                .find(|(key, _)| PresetField::from_key(key).is_none())
+        }) {
+            return Err(format!("Unknown preset field \"{}\"", unknown));
+        }
+    }
+
+    map.validate()?;
+    Ok(map)
+}
+
+pub fn to_toml(map: &MemoryMap) -> String {
+    let mut text = format!(
+        "preset_size = {}\ndata_size = 0x{:x}\nglobal_channels = 0x{:x}\nswitch_modes = 0x{:x}\n\n\
+         [preset_fields]\n",
+        map.preset_size, map.data_size, map.global_channels, map.switch_modes
+    );
+    for (key, offset) in PresetField::KEYS.iter().zip(map.preset_fields) {
+        text.push_str(&format!("{} = {}\n", key, offset));
+    }
+    text
+}
+
+pub fn load(path: &Path) -> Result<MemoryMap, String> {
+    parse(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+}
+
+pub fn save(map: &MemoryMap, path: &Path) -> Result<(), String> {
+    fs::write(path, to_toml(map)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_maps_read_back() {
+        let mut map = Firmware::V2_4.memory_map();
+        map.switch_modes = 0x7d4;
+        assert_eq!(parse(&to_toml(&map)), Ok(map));
+        // Keys left out keep their v2.5 value
+        assert_eq!(parse(""), Ok(Firmware::V2_5.memory_map()));
+    }
+
+    #[test]
+    fn rejects_broken_maps() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert!(error("preset_size = ").starts_with("Not valid TOML"));
+        assert_eq!(error("presets_size = 16"), "Unknown key \"presets_size\"");
+        assert_eq!(
+            error("[preset_fields]\nbpm = 3"),
+            "Unknown preset field \"bpm\""
+        );
+        assert_eq!(
+            error("preset_size = -1"),
+            "preset_size must be a positive whole number"
+        );
+        assert_eq!(
+            error("[preset_fields]\nnote = 16"),
+            "Field 16 is at 16 but presets are only 16 bytes"
+        );
+        assert_eq!(
+            error("[preset_fields]\npc2 = 0"),
+            "Two preset fields share offset 0"
+        );
+        // The switch modes moved into the last presets
+        assert_eq!(
+            error("switch_modes = 0x630"),
+            "0x0..0x640 overlaps 0x630..0x632"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::console::spell_bytes;
+use crate::model::{bank_and_pedal, ExpressionPart, PresetField, SysExMessage};
+use crate::numbering::Numbering;
+use crate::preview::{preview_messages, FIRMWARE_SEND_ORDER};
+use crate::project::ProjectMetadata;
+use crate::rehearsal::describe;
+use crate::spec::global_function;
+use crate::widgets::NumberBase;
+use std::collections::BTreeMap;
+
+/// A dump written out as translation rules for MIDI mapping software, with
+/// the presets a rule cannot tell apart
+pub struct MappingExport {
+    pub text: String,
+    /// One line per group of presets that start with the same message
+    pub ambiguous: Vec<String>,
+}
+
+/// Lists what every pedal sends as the incoming patterns of translation
+/// rules, to start a Bome MIDI Translator project (or any other mapping
+/// software) from instead of learning each pedal by pressing it.
+///
+/// Patterns are raw MIDI in hex as Bome's incoming field takes it; an
+/// expression pedal's value is the variable `pp`. The outgoing side is left
+/// for the user, since only they know what the FCB1010 is routed to.
+pub fn mapping_rules(
+    message: &SysExMessage,
+    metadata: &ProjectMetadata,
+    numbering: Numbering,
+) -> MappingExport {
+    let channels = message.global_channels();
+    let mut text = format!(
+        "# FCB1010 mapping rules, from a {} dump\n\
+         # One rule per pedal: paste each \"in\" line into the incoming Raw MIDI\n\
+         # field of a translator and set its outgoing action. A pedal that sends\n\
+         # several messages can be matched on its first one.\n",
+        message.firmware().label()
+    );
+
+    // Which presets each first message comes from, to point out the rules
+    // that would fire for several pedals
+    let mut first_messages: BTreeMap<Vec<u8>, Vec<usize>> = BTreeMap::new();
+    for (index, preset) in message.presets().iter().enumerate() {
+        let messages = preview_messages(preset, channels, &FIRMWARE_SEND_ORDER);
+        let pedals = [
+            (
+                "EXP A",
+                preset.expression_pedal_a(),
+                PresetField::ExpressionA as fn(_) -> _,
+            ),
+            (
+                "EXP B",
+                preset.expression_pedal_b(),
+                PresetField::ExpressionB,
+            ),
+        ];
+        if messages.is_empty() && pedals.iter().all(|(_, pedal, _)| pedal.is_none()) {
+            continue;
+        }
+
+        let (bank, pedal) = bank_and_pedal(index);
+        text.push_str(&format!(
+            "\n[Preset {}] bank {} pedal {}",
+            numbering.preset(index),
+            numbering.bank(bank),
+            pedal + 1
+        ));
+        match metadata.presets.get(index) {
+            Some(labels) if !labels.name.is_empty() => {
+                text.push_str(&format!(" – {}\n", labels.name))
+            }
+            _ => text.push('\n'),
+        }
+        for bytes in &messages {
+            text.push_str(&rule_line(
+                &spell_bytes(bytes, NumberBase::Hex),
+                &describe(bytes, NumberBase::Decimal),
+            ));
+        }
+        if let Some(first) = messages.first() {
+            first_messages.entry(first.clone()).or_default().push(index);
+        }
+        for (name, pedal, field) in pedals {
+            let Some(pedal) = pedal else {
+                continue;
+            };
+            let channel = channels[global_function(field(ExpressionPart::Controller))];
+            let status = 0xb0 | channel.get();
+            text.push_str(&rule_line(
+                &format!("{:02X} {:02X} pp", status, pedal.controller.get()),
+                &format!(
+                    "{} sweeps CC {} from {} to {} ch {}; pp is the position",
+                    name,
+                    pedal.controller,
+                    pedal.min,
+                    pedal.max,
+                    channel.number()
+                ),
+            ));
+        }
+    }
+
+    let ambiguous: Vec<String> = first_messages
+        .iter()
+        .filter(|(_, presets)| presets.len() > 1)
+        .map(|(bytes, presets)| {
+            let presets: Vec<String> = presets
+                .iter()
+                .map(|&index| numbering.preset(index))
+                .collect();
+            format!(
+                "Presets {} all start with {}",
+                presets.join(", "),
+                describe(bytes, NumberBase::Decimal)
+            )
+        })
+        .collect();
+    if !ambiguous.is_empty() {
+        text.push_str("\n# Rules on the first message alone fire for several pedals:\n");
+        for line in &ambiguous {
+            text.push_str(&format!("#   {}\n", line));
+        }
+    }
+
+    MappingExport { text, ambiguous }
+}
+
+/// "  in: C0 0C            # PC 12 ch 1"
+fn rule_line(pattern: &str, description: &str) -> String {
+    format!("  in: {:<16} # {}\n", pattern, description)
+}
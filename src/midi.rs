@@ -0,0 +1,463 @@
+use midir::{MidiInput, MidiOutput, MidiOutputConnection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Names of the input ports, which is also how the editor indexes the
+/// interfaces it offers. Fails when no MIDI backend is available.
+pub fn input_port_names() -> Result<Vec<String>, String> {
+    let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+    Ok(midi_in
+        .ports()
+        .iter()
+        .map(|port| midi_in.port_name(port).unwrap_or_default())
+        .collect())
+}
+
+/// Names of the output ports, which the backend lists apart from the
+/// inputs: an interface may have only one of them, and WinMM can give its
+/// output another index than its input
+pub fn output_port_names() -> Result<Vec<String>, String> {
+    let midi_out = MidiOutput::new("MIDI Output").map_err(|e| e.to_string())?;
+    Ok(midi_out
+        .ports()
+        .iter()
+        .map(|port| midi_out.port_name(port).unwrap_or_default())
+        .collect())
+}
+
+/// The output of the interface whose input is `inputs[input]`: the output
+/// with the same name, the second of that name for the second input of
+/// that name and so on
+pub fn matching_output(inputs: &[String], outputs: &[String], input: usize) -> Option<usize> {
+    let name = inputs.get(input)?;
+    let nth = inputs[..input]
+        .iter()
+        .filter(|other| *other == name)
+        .count();
+    outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, output)| *output == name)
+        .nth(nth)
+        .map(|(index, _)| index)
+}
+
+/// Port names as shown in the UI. Interfaces of the same model report the
+/// same name, so repeated names get `#1`, `#2`… in the order the backend
+/// lists them; with `ids` every port also shows that position.
+pub fn port_labels(names: &[String], ids: bool) -> Vec<String> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let mut label = name.clone();
+            if names.iter().filter(|other| *other == name).count() > 1 {
+                let nth = names[..index].iter().filter(|other| *other == name).count();
+                label.push_str(&format!(" #{}", nth + 1));
+            }
+            if ids {
+                label.push_str(&format!(" [id {}]", index));
+            }
+            label
+        })
+        .collect()
+}
+
+/// An open connection to one output port
+pub struct OutputConnection {
+    pub port_index: usize,
+    pub name: String,
+    connection: MidiOutputConnection,
+    /// Set when a failed send made us reopen the port
+    reconnected: bool,
+}
+
+/// How sending to one of the ports went
+pub struct PortSend {
+    pub port: String,
+    pub result: Result<Duration, String>,
+    /// The first attempt failed and the message was sent again
+    pub retried: bool,
+}
+
+/// The set of output ports that dumps are sent to
+#[derive(Default)]
+pub struct MidiOutputs {
+    connections: Vec<OutputConnection>,
+}
+
+impl OutputConnection {
+    pub fn open(port_index: usize) -> Result<Self, String> {
+        let midi_out = MidiOutput::new("MIDI Output").map_err(|e| e.to_string())?;
+        let port = midi_out
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+        let name = midi_out.port_name(&port).unwrap_or_default();
+        let connection = midi_out
+            .connect(&port, "midir-test")
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            port_index,
+            name,
+            connection,
+            reconnected: false,
+        })
+    }
+
+    /// Sends the message. Connections go stale when the computer sleeps, so
+    /// after a failure the port is reopened by name and the send tried again.
+    pub fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        self.send_retrying(message).0
+    }
+
+    // `send`, also telling whether the message had to be sent again
+    fn send_retrying(&mut self, message: &[u8]) -> (Result<(), String>, bool) {
+        if self.connection.send(message).is_ok() {
+            return (Ok(()), false);
+        }
+        if let Err(e) = self.reopen() {
+            return (Err(e), false);
+        }
+        self.reconnected = true;
+        (
+            self.connection.send(message).map_err(|e| e.to_string()),
+            true,
+        )
+    }
+
+    // The index may have changed while the port was gone, the name does not
+    fn reopen(&mut self) -> Result<(), String> {
+        let midi_out = MidiOutput::new("MIDI Output").map_err(|e| e.to_string())?;
+        let ports = midi_out.ports();
+        let (index, port) = ports
+            .iter()
+            .enumerate()
+            .find(|(_, port)| midi_out.port_name(port).is_ok_and(|name| name == self.name))
+            .ok_or_else(|| format!("{} is no longer available", self.name))?;
+        self.connection = midi_out
+            .connect(port, "midir-test")
+            .map_err(|e| e.to_string())?;
+        self.port_index = index;
+        Ok(())
+    }
+
+    /// Whether the port was reopened since the last call
+    pub fn take_reconnected(&mut self) -> bool {
+        std::mem::take(&mut self.reconnected)
+    }
+}
+
+impl MidiOutputs {
+    pub fn connect(&mut self, port_index: usize) -> Result<(), String> {
+        if self.contains(port_index) {
+            return Ok(());
+        }
+
+        self.connections.push(OutputConnection::open(port_index)?);
+        Ok(())
+    }
+
+    /// Names of the connected ports, for telling the user where dumps go
+    pub fn names(&self) -> Vec<&str> {
+        self.connections.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    pub fn disconnect(&mut self, port_index: usize) {
+        self.connections.retain(|c| c.port_index != port_index);
+    }
+
+    pub fn contains(&self, port_index: usize) -> bool {
+        self.connections.iter().any(|c| c.port_index == port_index)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Names of the ports reopened after a failed send since the last call
+    pub fn take_reconnected(&mut self) -> Vec<String> {
+        self.connections
+            .iter_mut()
+            .filter_map(|output| output.take_reconnected().then(|| output.name.clone()))
+            .collect()
+    }
+
+    /// Follows the ports to their new indexes after the output port list
+    /// changed, dropping connections to ports that are gone
+    pub fn reindex(&mut self, port_names: &[String]) {
+        self.connections.retain_mut(|output| {
+            match port_names.iter().position(|name| *name == output.name) {
+                Some(index) => {
+                    output.port_index = index;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    /// Sends to every connected port, reporting how long each send took
+    pub fn send_each(&mut self, message: &[u8]) -> Vec<PortSend> {
+        self.connections
+            .iter_mut()
+            .map(|output| {
+                let started = Instant::now();
+                let (result, retried) = output.send_retrying(message);
+                PortSend {
+                    port: output.name.clone(),
+                    result: result.map(|()| started.elapsed()),
+                    retried,
+                }
+            })
+            .collect()
+    }
+
+    /// Sends the message to every connected port, returning how many got it.
+    /// A failure on one port does not stop the others from being sent to.
+    pub fn send_all(&mut self, message: &[u8]) -> Result<usize, String> {
+        let failed: Vec<String> = self
+            .send_each(message)
+            .into_iter()
+            .filter_map(|sent| sent.result.err().map(|e| format!("{}: {}", sent.port, e)))
+            .collect();
+
+        if failed.is_empty() {
+            Ok(self.connections.len())
+        } else {
+            Err(failed.join(", "))
+        }
+    }
+}
+
+/// Name other applications see for the port the editor creates itself
+pub const VIRTUAL_PORT_NAME: &str = "FCB1010 Editor";
+
+/// An output port created by the editor, which DAWs can connect to directly
+pub struct VirtualOutput {
+    pub name: String,
+    connection: MidiOutputConnection,
+}
+
+impl VirtualOutput {
+    #[cfg(unix)]
+    pub fn create(name: &str) -> Result<Self, String> {
+        use midir::os::unix::VirtualOutput as _;
+
+        let midi_out = MidiOutput::new("MIDI Output").map_err(|e| e.to_string())?;
+        let connection = midi_out.create_virtual(name).map_err(|e| e.to_string())?;
+        Ok(Self {
+            name: name.to_string(),
+            connection,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn create(_name: &str) -> Result<Self, String> {
+        Err("Virtual MIDI ports are only available on Linux and macOS".to_string())
+    }
+
+    pub fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        self.connection.send(message).map_err(|e| e.to_string())
+    }
+}
+
+/// Where preset previews are sent
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum PreviewTarget {
+    /// The same ports the SysEx dumps go to
+    #[default]
+    DumpOutputs,
+    /// An output port of its own, e.g. the synth rather than the pedal
+    Port(usize),
+    /// The virtual port created by the editor
+    Virtual,
+}
+
+/// The connection used for previews, kept apart from the dump outputs
+#[derive(Default)]
+pub struct PreviewOutput {
+    target: PreviewTarget,
+    port: Option<OutputConnection>,
+    virtual_port: Option<VirtualOutput>,
+}
+
+impl PreviewOutput {
+    pub fn target(&self) -> PreviewTarget {
+        self.target
+    }
+
+    /// Switches previews to another target, opening its port if it has one
+    pub fn set_target(&mut self, target: PreviewTarget) -> Result<(), String> {
+        self.port = match target {
+            PreviewTarget::Port(index) => Some(OutputConnection::open(index)?),
+            PreviewTarget::DumpOutputs | PreviewTarget::Virtual => None,
+        };
+        self.target = target;
+        Ok(())
+    }
+
+    pub fn open_virtual(&mut self, name: &str) -> Result<(), String> {
+        self.virtual_port = Some(VirtualOutput::create(name)?);
+        Ok(())
+    }
+
+    pub fn close_virtual(&mut self) {
+        self.virtual_port = None;
+    }
+
+    /// Follows the preview port to its new index after the output port list
+    /// changed. When the port is gone previews go back to the dump outputs
+    /// and its name is returned.
+    pub fn reindex(&mut self, port_names: &[String]) -> Option<String> {
+        let port = self.port.as_mut()?;
+        match port_names.iter().position(|name| *name == port.name) {
+            Some(index) => {
+                port.port_index = index;
+                self.target = PreviewTarget::Port(index);
+                None
+            }
+            None => {
+                self.target = PreviewTarget::DumpOutputs;
+                self.port.take().map(|port| port.name)
+            }
+        }
+    }
+
+    /// The preview port, if it was reopened after a failed send since the last call
+    pub fn take_reconnected(&mut self) -> Option<String> {
+        let port = self.port.as_mut()?;
+        if !port.take_reconnected() {
+            return None;
+        }
+        self.target = PreviewTarget::Port(port.port_index);
+        Some(port.name.clone())
+    }
+
+    pub fn virtual_name(&self) -> Option<&str> {
+        self.virtual_port
+            .as_ref()
+            .map(|output| output.name.as_str())
+    }
+
+    /// Where the next preview will go, in words
+    pub fn describe(&self, outputs: &MidiOutputs) -> String {
+        match self.target {
+            PreviewTarget::DumpOutputs if outputs.is_empty() => "nowhere".to_string(),
+            PreviewTarget::DumpOutputs => outputs.names().join(", "),
+            PreviewTarget::Port(_) => self
+                .port
+                .as_ref()
+                .map_or("nowhere".to_string(), |port| port.name.clone()),
+            PreviewTarget::Virtual => self.virtual_name().map_or("nowhere".to_string(), |name| {
+                format!("virtual port \"{}\"", name)
+            }),
+        }
+    }
+
+    pub fn send(&mut self, outputs: &mut MidiOutputs, message: &[u8]) -> Result<(), String> {
+        match self.target {
+            PreviewTarget::DumpOutputs => outputs.send_all(message).map(|_| ()),
+            PreviewTarget::Port(_) => self
+                .port
+                .as_mut()
+                .ok_or("The preview port is not connected")?
+                .send(message),
+            PreviewTarget::Virtual => self
+                .virtual_port
+                .as_mut()
+                .ok_or("The virtual port is not open")?
+                .send(message),
+        }
+    }
+}
+
+/// A use of the ports that must not overlap with another one: interleaved
+/// SysEx from a send and a receive corrupts both
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transfer {
+    Send,
+    Receive,
+    LatencyTest,
+    FirmwareUpdate,
+}
+
+impl Transfer {
+    fn noun(self) -> &'static str {
+        match self {
+            Transfer::Send => "a send",
+            Transfer::Receive => "a receive",
+            Transfer::LatencyTest => "a latency test",
+            Transfer::FirmwareUpdate => "a firmware update",
+        }
+    }
+
+    fn in_progress(self) -> &'static str {
+        match self {
+            Transfer::Send => "a dump is being sent",
+            Transfer::Receive => "a receive is armed or in progress",
+            Transfer::LatencyTest => "the latency test is running",
+            Transfer::FirmwareUpdate => "a firmware update is being sent",
+        }
+    }
+}
+
+/// Lets only one transfer run at a time. Clones share the same state, so
+/// the guard can be moved into the thread doing the work.
+#[derive(Clone, Default)]
+pub struct TransferArbiter {
+    active: Arc<Mutex<Option<Transfer>>>,
+}
+
+impl TransferArbiter {
+    /// Claims the ports for `transfer` until the returned guard is dropped
+    pub fn begin(&self, transfer: Transfer) -> Result<TransferGuard, String> {
+        let mut active = self.active.lock().unwrap();
+        if let Some(current) = *active {
+            return Err(format!(
+                "Cannot start {} while {}",
+                transfer.noun(),
+                current.in_progress()
+            ));
+        }
+        *active = Some(transfer);
+        Ok(TransferGuard {
+            active: Arc::clone(&self.active),
+        })
+    }
+}
+
+/// Releases the ports when dropped
+pub struct TransferGuard {
+    active: Arc<Mutex<Option<Transfer>>>,
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        *self.active.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_are_matched_by_name() {
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let inputs = names(&["UM-ONE", "Keystation", "UM-ONE"]);
+        let outputs = names(&["Microsoft GS Wavetable Synth", "UM-ONE", "UM-ONE"]);
+        assert_eq!(matching_output(&inputs, &outputs, 0), Some(1));
+        assert_eq!(matching_output(&inputs, &outputs, 2), Some(2));
+        assert_eq!(matching_output(&inputs, &outputs, 1), None);
+        assert_eq!(matching_output(&inputs, &outputs, 3), None);
+    }
+}
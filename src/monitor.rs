@@ -0,0 +1,65 @@
+use eframe::egui;
+use midir::{MidiInput, MidiInputConnection};
+use std::sync::{Arc, Mutex};
+
+/// Listens to an input port and remembers the last value of every CC number,
+/// whatever channel it arrived on
+pub struct CcMonitor {
+    connection: Option<MidiInputConnection<()>>,
+    values: Arc<Mutex<[Option<u8>; 128]>>,
+}
+
+impl Default for CcMonitor {
+    fn default() -> Self {
+        Self {
+            connection: None,
+            values: Arc::new(Mutex::new([None; 128])),
+        }
+    }
+}
+
+impl CcMonitor {
+    pub fn start(&mut self, port_index: usize, ctx: &egui::Context) -> Result<(), String> {
+        self.stop();
+
+        let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+        let port = midi_in
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+
+        let values = Arc::clone(&self.values);
+        let ctx = ctx.clone();
+        let connection = midi_in
+            .connect(
+                &port,
+                "midir-cc-monitor",
+                move |_, message, _| {
+                    if let [status, number, value] = *message {
+                        if status & 0xf0 == 0xb0 {
+                            values.lock().unwrap()[(number & 0x7f) as usize] = Some(value);
+                            ctx.request_repaint();
+                        }
+                    }
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.connection = None;
+        *self.values.lock().unwrap() = [None; 128];
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn value(&self, controller: u8) -> Option<u8> {
+        self.values.lock().unwrap()[(controller & 0x7f) as usize]
+    }
+}
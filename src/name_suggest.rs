@@ -0,0 +1,62 @@
+use crate::cc_dictionary::CcDictionary;
+use crate::model::{Preset, SysExMessage};
+use crate::project::ProjectMetadata;
+
+/// A name made from what the preset sends, e.g. "PC 12 + Tuner On". Program
+/// changes use the project's patch names where there are some. `None` for a
+/// preset that sends nothing.
+pub fn suggest_name(
+    preset: &Preset,
+    dictionary: &CcDictionary,
+    metadata: &ProjectMetadata,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    for slot in 0..5 {
+        if let Some(program) = preset.program_change(slot) {
+            let part = match metadata.program_names.get(&program.get()) {
+                Some(name) => name.clone(),
+                None => format!("PC {}", program.get()),
+            };
+            if !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+    }
+    for slot in 0..2 {
+        if let Some((number, value)) = preset.control_change(slot) {
+            parts.push(match (dictionary.name(number.get()), value.get()) {
+                (Some(name), 127) => format!("{} On", name),
+                (Some(name), 0) => format!("{} Off", name),
+                (Some(name), value) => format!("{} {}", name, value),
+                (None, value) => format!("CC {}={}", number.get(), value),
+            });
+        }
+    }
+    if let Some(note) = preset.note() {
+        parts.push(format!("Note {}", note.get()));
+    }
+    (!parts.is_empty()).then(|| parts.join(" + "))
+}
+
+/// Suggested names for the presets whose name is empty, by index; named
+/// presets are never touched
+pub fn suggestions(
+    message: &SysExMessage,
+    dictionary: &CcDictionary,
+    metadata: &ProjectMetadata,
+) -> Vec<(usize, String)> {
+    message
+        .presets()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            metadata
+                .presets
+                .get(*index)
+                .is_none_or(|labels| labels.name.trim().is_empty())
+        })
+        .filter_map(|(index, preset)| {
+            suggest_name(preset, dictionary, metadata).map(|name| (index, name))
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use crate::model::bank_and_pedal;
+use serde::{Deserialize, Serialize};
+
+/// How presets and banks are numbered on screen and in exports
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum Numbering {
+    /// Presets 1-100 and banks 1-10
+    #[default]
+    Linear,
+    /// As the pedal's display and most manuals count: banks 00-09, each
+    /// with pedals 1-10
+    Device,
+}
+
+impl Numbering {
+    pub const ALL: [Numbering; 2] = [Numbering::Linear, Numbering::Device];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Numbering::Linear => "Presets 1-100, banks 1-10",
+            Numbering::Device => "As on the pedal: bank 00-09, pedal 1-10",
+        }
+    }
+
+    /// "12", or "01-2" for pedal 2 of bank 01
+    pub fn preset(self, index: usize) -> String {
+        match self {
+            Numbering::Linear => (index + 1).to_string(),
+            Numbering::Device => {
+                let (bank, pedal) = bank_and_pedal(index);
+                format!("{:02}-{}", bank, pedal + 1)
+            }
+        }
+    }
+
+    /// "2", or "01" as the pedal's display shows it
+    pub fn bank(self, bank: usize) -> String {
+        match self {
+            Numbering::Linear => (bank + 1).to_string(),
+            Numbering::Device => format!("{:02}", bank),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::model::{ExpressionPart, PresetField};
+use crate::values::{Channel, Value7};
+
+/// Messages read from pasted text such as `PC 12 ch1; CC 80 127 ch1`
+#[derive(Debug, Clone, Default)]
+pub struct PastedMessages {
+    pub edits: Vec<(PresetField, Value7)>,
+    /// What each understood message was put into, for the preview
+    pub used: Vec<String>,
+    /// Channels given in the text by global function index; the
+    /// FCB1010 only stores these in Global Settings
+    pub channels: Vec<(usize, Channel)>,
+    /// Messages that could not be read, with the reason
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Program,
+    Control,
+    Note,
+    Expression(bool),
+}
+
+/// Reads one message per `;`, `|` or line. Case, `#`, `=` and missing spaces
+/// (`PC12`, `ch1`) are all accepted; values are taken as sent (0-127) and
+/// channels as printed (1-16). PCs and CCs fill the preset's slots in order.
+pub fn parse(text: &str) -> PastedMessages {
+    let mut pasted = PastedMessages::default();
+    let (mut programs, mut controls, mut note) = (0, 0, false);
+
+    for message in text.split([';', '|', '\n']) {
+        let message = message.trim();
+        if message.is_empty() {
+            continue;
+        }
+        let words = words(message);
+        let (kind, rest) = match kind(&words) {
+            Some(found) => found,
+            None => {
+                pasted.problems.push(format!(
+                    "\"{}\": not a PC, CC, note or EXP message",
+                    message
+                ));
+                continue;
+            }
+        };
+
+        let mut numbers = Vec::new();
+        let mut channel = None;
+        let mut words = rest.iter();
+        let mut problem = None;
+        while let Some(word) = words.next() {
+            if matches!(word.as_str(), "ch" | "chan" | "channel" | "c") {
+                match words.next().and_then(|w| w.parse::<u8>().ok()) {
+                    Some(ch @ 1..=16) => channel = Some(Channel::clamped(ch - 1)),
+                    _ => problem = Some("the channel must be 1-16".to_string()),
+                }
+            } else if let Ok(number) = word.parse::<u32>() {
+                if number > 127 {
+                    problem = Some(format!("{} is above 127", number));
+                }
+                numbers.push(Value7::saturating(number.into()));
+            } else if !matches!(
+                word.as_str(),
+                "value"
+                    | "val"
+                    | "v"
+                    | "number"
+                    | "num"
+                    | "no"
+                    | "min"
+                    | "max"
+                    | "to"
+                    | "controller"
+            ) {
+                problem = Some(format!("\"{}\" is not understood", word));
+            }
+        }
+        if let Some(problem) = problem {
+            pasted
+                .problems
+                .push(format!("\"{}\": {}", message, problem));
+            continue;
+        }
+
+        let (function, fields): (usize, Vec<PresetField>) = match kind {
+            Kind::Program if programs < 5 => {
+                programs += 1;
+                (programs - 1, vec![PresetField::ProgramChange(programs - 1)])
+            }
+            Kind::Control if controls < 2 => {
+                controls += 1;
+                (
+                    4 + controls,
+                    vec![
+                        PresetField::ControlNumber(controls - 1),
+                        PresetField::ControlValue(controls - 1),
+                    ],
+                )
+            }
+            Kind::Note if !note => {
+                note = true;
+                (9, vec![PresetField::Note])
+            }
+            Kind::Expression(b) => {
+                let pedal = if b {
+                    PresetField::ExpressionB
+                } else {
+                    PresetField::ExpressionA
+                };
+                (
+                    if b { 8 } else { 7 },
+                    vec![
+                        pedal(ExpressionPart::Controller),
+                        pedal(ExpressionPart::Min),
+                        pedal(ExpressionPart::Max),
+                    ],
+                )
+            }
+            _ => {
+                pasted
+                    .problems
+                    .push(format!("\"{}\": the preset has no free slot left", message));
+                continue;
+            }
+        };
+
+        if numbers.is_empty() || numbers.len() > fields.len() {
+            pasted.problems.push(format!(
+                "\"{}\": expected {} number{}",
+                message,
+                if fields.len() == 1 {
+                    "one".to_string()
+                } else {
+                    format!("1-{}", fields.len())
+                },
+                if fields.len() == 1 { "" } else { "s" }
+            ));
+            continue;
+        }
+        let mut described = Vec::new();
+        for (&field, &value) in fields.iter().zip(&numbers) {
+            pasted.edits.push((field, value));
+            described.push(format!("{} = {}", crate::spec::field_title(field), value));
+        }
+        if let Some(channel) = channel {
+            pasted.channels.push((function, channel));
+            described.push(format!("channel {}", channel.number()));
+        }
+        pasted.used.push(described.join(", "));
+    }
+    pasted
+}
+
+/// Lowercase words with letters and digits split apart (`pc12` → `pc 12`)
+fn words(message: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut previous: Option<char> = None;
+    for c in message.chars().flat_map(char::to_lowercase) {
+        if !c.is_ascii_alphanumeric() {
+            previous = None;
+            continue;
+        }
+        match previous {
+            Some(p) if p.is_ascii_digit() == c.is_ascii_digit() => {
+                words.last_mut().unwrap().push(c)
+            }
+            _ => words.push(c.to_string()),
+        }
+        previous = Some(c);
+    }
+    words
+}
+
+fn kind(words: &[String]) -> Option<(Kind, &[String])> {
+    let first = words.first()?.as_str();
+    let kind = match first {
+        "pc" | "prog" | "program" | "patch" => Kind::Program,
+        "cc" | "ctrl" | "control" | "controller" => Kind::Control,
+        "note" | "n" => Kind::Note,
+        "exp" | "ep" | "expression" => {
+            // "EXP A", "expression pedal B"
+            let skip = if words.get(1).is_some_and(|w| w == "pedal") {
+                2
+            } else {
+                1
+            };
+            return match words.get(skip).map(String::as_str) {
+                Some("a") => Some((Kind::Expression(false), &words[skip + 1..])),
+                Some("b") => Some((Kind::Expression(true), &words[skip + 1..])),
+                _ => None,
+            };
+        }
+        "expa" | "epa" => Kind::Expression(false),
+        "expb" | "epb" => Kind::Expression(true),
+        _ => return None,
+    };
+    let mut rest = &words[1..];
+    // "program change", "control change"
+    if rest.first().is_some_and(|w| w == "change") {
+        rest = &rest[1..];
+    }
+    Some((kind, rest))
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Reads patch names for program change numbers from a MIDNAM file or a
+/// `number,name` CSV file, chosen by extension
+pub fn load(path: &Path) -> Result<BTreeMap<u8, String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let is_midnam = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("midnam") || ext.eq_ignore_ascii_case("xml"));
+    let names = if is_midnam {
+        parse_midnam(&text)
+    } else {
+        parse_csv(&text)
+    }?;
+
+    if names.is_empty() {
+        return Err("No patch names were found".to_string());
+    }
+    Ok(names)
+}
+
+/// Picks the `<Patch>` elements out of a MIDNAM document. The program number
+/// comes from `ProgramChange` when present, else from `Number`.
+pub fn parse_midnam(text: &str) -> Result<BTreeMap<u8, String>, String> {
+    let mut names = BTreeMap::new();
+    for element in text.split('<').filter(|e| e.starts_with("Patch ")) {
+        let tag = element.split('>').next().unwrap_or_default();
+        let Some(name) = attribute(tag, "Name") else {
+            continue;
+        };
+        let number = attribute(tag, "ProgramChange")
+            .or_else(|| attribute(tag, "Number"))
+            .and_then(|n| n.trim().parse::<u8>().ok())
+            .filter(|&n| n <= 127);
+        if let Some(number) = number {
+            names.insert(number, unescape(&name));
+        }
+    }
+    Ok(names)
+}
+
+/// Lines of `number,name`; a header line and blank lines are skipped
+pub fn parse_csv(text: &str) -> Result<BTreeMap<u8, String>, String> {
+    let mut names = BTreeMap::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (number, name) = line
+            .split_once(',')
+            .ok_or_else(|| format!("Line {} has no comma", line_number + 1))?;
+        match number.trim().parse::<u8>() {
+            Ok(number) if number <= 127 => {
+                names.insert(number, name.trim().trim_matches('"').to_string());
+            }
+            _ if line_number == 0 => continue, // header
+            _ => return Err(format!("Line {}: invalid program number", line_number + 1)),
+        }
+    }
+    Ok(names)
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(tag[start..end].to_string())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Placed next to the executable, turns portable mode on without `--portable`
+pub const FLAG_FILE: &str = "portable.flag";
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Switches to portable mode when asked for or when the flag file sits next
+/// to the executable: the working directory becomes the executable's folder,
+/// so the config, backups and projects, which are all opened by relative
+/// paths, are kept there. Returns the folder when portable mode is on.
+pub fn enable(requested: bool) -> Result<Option<&'static Path>, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let Some(folder) = exe.parent() else {
+        return Ok(None);
+    };
+    if !requested && !folder.join(FLAG_FILE).exists() {
+        return Ok(None);
+    }
+    std::env::set_current_dir(folder)
+        .map_err(|e| format!("Could not switch to {}: {}", folder.display(), e))?;
+    Ok(Some(ROOT.get_or_init(|| folder.to_path_buf())))
+}
+
+/// The folder everything is kept in, when running portable
+pub fn root() -> Option<&'static Path> {
+    ROOT.get().map(PathBuf::as_path)
+}
+
+/// In portable mode a path inside the portable folder is written relative to
+/// it, so it still resolves when the stick is mounted somewhere else
+pub fn relative(path: &Path) -> PathBuf {
+    match root().and_then(|root| path.strip_prefix(root).ok()) {
+        Some(inner) if !inner.as_os_str().is_empty() => inner.to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// `relative` for a path typed into a text field; returns whether it changed
+pub fn make_relative(text: &mut String) -> bool {
+    let shortened = relative(Path::new(text.as_str()))
+        .to_string_lossy()
+        .into_owned();
+    if shortened == *text {
+        return false;
+    }
+    *text = shortened;
+    true
+}
+
+/// A path given on the command line, where relative paths meant the folder
+/// the editor was started from rather than the portable one
+pub fn from_launch_dir(launch_dir: &Path, path: &Path) -> PathBuf {
+    relative(&launch_dir.join(path))
+}
@@ -0,0 +1,91 @@
+use crate::model::{Preset, SysExMessage};
+use crate::project::{PresetMetadata, ProjectMetadata};
+use crate::values::Channel;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Starts every copied text, so pasting can tell presets from typed messages
+pub const CLIP_PREFIX: &str = "FCB1010-PRESETS:";
+/// The clipboard format written by this version; older ones are read too
+pub const CLIP_VERSION: u32 = 1;
+
+/// Presets as plain text, to paste into a chat or forum post and back into
+/// anyone's editor: the prefix followed by one line of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetClip {
+    #[serde(rename = "v")]
+    pub version: u32,
+    pub presets: Vec<Preset>,
+    #[serde(default)]
+    pub labels: Vec<PresetMetadata>,
+    /// The global channels the presets were made with, to point out where
+    /// the pasting editor's differ
+    #[serde(default)]
+    pub channels: Option<[Channel; 10]>,
+}
+
+impl PresetClip {
+    pub fn copy(message: &SysExMessage, metadata: &ProjectMetadata, indexes: Range<usize>) -> Self {
+        Self {
+            version: CLIP_VERSION,
+            presets: indexes
+                .clone()
+                .map(|index| *message.preset(index))
+                .collect(),
+            labels: indexes
+                .map(|index| {
+                    let label = metadata.presets.get(index).cloned().unwrap_or_default();
+                    // When it changed here says nothing in another project
+                    PresetMetadata {
+                        modified: None,
+                        ..label
+                    }
+                })
+                .collect(),
+            channels: Some(*message.global_channels()),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let json = serde_json::to_string(self).expect("presets serialize");
+        format!("{}{}", CLIP_PREFIX, json)
+    }
+
+    /// The name and notes copied with the preset at `offset`, if any
+    pub fn label(&self, offset: usize) -> PresetMetadata {
+        self.labels.get(offset).cloned().unwrap_or_default()
+    }
+}
+
+/// Reads copied presets out of `text`, or `None` if it holds none. Text
+/// around them is ignored, as are the quote markers, code fences and line
+/// breaks a chat or forum adds when the text is quoted or wrapped. The
+/// prefix may be in any case.
+pub fn read(text: &str) -> Option<Result<PresetClip, String>> {
+    let start = text.to_ascii_uppercase().find(CLIP_PREFIX)? + CLIP_PREFIX.len();
+    let joined: String = text[start..]
+        .lines()
+        .map(|line| line.trim().trim_start_matches('>').trim().trim_matches('`'))
+        .collect();
+    let Some(json) = joined.find('{').map(|open| &joined[open..]) else {
+        return Some(Err("Nothing follows the preset marker".to_string()));
+    };
+
+    // Only the first JSON value counts; whatever follows it is chat text
+    let mut values = serde_json::Deserializer::from_str(json).into_iter::<PresetClip>();
+    let clip = match values.next() {
+        Some(Ok(clip)) => clip,
+        Some(Err(e)) => return Some(Err(format!("The copied presets are damaged: {}", e))),
+        None => return Some(Err("Nothing follows the preset marker".to_string())),
+    };
+    if clip.version > CLIP_VERSION {
+        return Some(Err(format!(
+            "The presets were copied in format version {}; this editor reads up to {}",
+            clip.version, CLIP_VERSION
+        )));
+    }
+    if clip.presets.is_empty() {
+        return Some(Err("The copied text holds no presets".to_string()));
+    }
+    Some(Ok(clip))
+}
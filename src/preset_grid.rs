@@ -0,0 +1,107 @@
+use crate::model::PEDALS_PER_BANK;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// One line of the preset grid
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridRow {
+    BankHeader(usize),
+    Cards(Range<usize>),
+}
+
+/// Splits the presets into rows of at most `columns` cards; rows restart at
+/// every bank so each bank gets its own header
+pub fn grid_rows(presets: usize, columns: usize) -> Vec<GridRow> {
+    let mut rows = Vec::new();
+    for first in (0..presets).step_by(PEDALS_PER_BANK) {
+        rows.push(GridRow::BankHeader(first / PEDALS_PER_BANK));
+        let end = (first + PEDALS_PER_BANK).min(presets);
+        for start in (first..end).step_by(columns) {
+            rows.push(GridRow::Cards(start..(start + columns).min(end)));
+        }
+    }
+    rows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowKind {
+    Header,
+    /// Every card of the row is collapsed to its summary
+    Summaries,
+    Cards,
+}
+
+impl RowKind {
+    fn of(row: &GridRow, collapsed: &HashSet<usize>) -> Self {
+        match row {
+            GridRow::BankHeader(_) => RowKind::Header,
+            GridRow::Cards(range) if range.clone().all(|i| collapsed.contains(&i)) => {
+                RowKind::Summaries
+            }
+            GridRow::Cards(_) => RowKind::Cards,
+        }
+    }
+}
+
+/// Heights of the grid rows from the frames they were drawn in, so rows
+/// outside the viewport can be skipped by allocating their space instead of
+/// building their widgets. Rows never drawn use the height last seen for a
+/// row of the same kind.
+#[derive(Debug, Clone, Default)]
+pub struct RowHeights {
+    columns: usize,
+    heights: Vec<Option<(RowKind, f32)>>,
+    typical: Vec<(RowKind, f32)>,
+}
+
+/// Before any row has been drawn
+const GUESSED_HEIGHT: f32 = 400.0;
+
+impl RowHeights {
+    /// Starts a frame; the cache is dropped when the number of columns changed
+    pub fn prepare(&mut self, columns: usize, rows: usize) {
+        if columns != self.columns {
+            self.clear();
+            self.columns = columns;
+        }
+        self.heights.resize(rows, None);
+    }
+
+    /// Forgets the measured rows, e.g. after every card was collapsed
+    pub fn clear(&mut self) {
+        self.heights.clear();
+    }
+
+    pub fn height(&self, index: usize, row: &GridRow, collapsed: &HashSet<usize>) -> f32 {
+        let kind = RowKind::of(row, collapsed);
+        match self.heights.get(index).copied().flatten() {
+            Some((measured_kind, height)) if measured_kind == kind => height,
+            _ => self
+                .typical
+                .iter()
+                .find(|(typical_kind, _)| *typical_kind == kind)
+                .map_or(GUESSED_HEIGHT, |&(_, height)| height),
+        }
+    }
+
+    /// Stores the height a row took when drawn; returns whether it differed
+    /// from what was assumed, in which case the frame should be redrawn
+    pub fn measured(
+        &mut self,
+        index: usize,
+        row: &GridRow,
+        collapsed: &HashSet<usize>,
+        height: f32,
+    ) -> bool {
+        let kind = RowKind::of(row, collapsed);
+        let changed = (self.height(index, row, collapsed) - height).abs() > 0.5;
+        if let Some(slot) = self.heights.get_mut(index) {
+            *slot = Some((kind, height));
+        }
+        match self.typical.iter_mut().find(|(k, _)| *k == kind) {
+            Some(typical) => typical.1 = height,
+            None => self.typical.push((kind, height)),
+        }
+        changed
+    }
+}
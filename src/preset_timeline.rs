@@ -0,0 +1,74 @@
+use crate::history::HistoryEntry;
+use crate::model::{Preset, PresetField};
+use crate::spec::{field_title, GLOBAL_FUNCTIONS};
+use crate::values::Value7;
+
+/// A state a preset was in, rebuilt from the project history
+#[derive(Debug, Clone)]
+pub struct PresetVersion {
+    pub preset: Preset,
+    /// When the state ended and who ended it, from the entry that changed it;
+    /// `None` for the current state
+    pub ended: Option<(String, String)>,
+    /// What changed when the state ended, e.g. "PC 1: 12 → 14"
+    pub change: String,
+}
+
+/// The states the preset at `index` went through, newest first and starting
+/// with the current one, found by undoing its history entries one by one.
+/// The walk stops at a whole dump replacement, which the history does not
+/// break down per preset.
+pub fn preset_versions(
+    current: &Preset,
+    index: usize,
+    history: &[HistoryEntry],
+) -> Vec<PresetVersion> {
+    let prefix = format!("Preset {} ", index + 1);
+    let mut versions = vec![PresetVersion {
+        preset: *current,
+        ended: None,
+        change: String::new(),
+    }];
+    let mut preset = *current;
+    for entry in history.iter().rev() {
+        if entry.what == "Whole dump replaced" {
+            break;
+        }
+        let Some(what) = entry.what.strip_prefix(&prefix) else {
+            continue;
+        };
+        if !undo(&mut preset, what, &entry.old) {
+            continue;
+        }
+        versions.push(PresetVersion {
+            preset,
+            ended: Some((entry.time.clone(), entry.author.clone())),
+            change: format!("{}: {} → {}", what, entry.old, entry.new),
+        });
+    }
+    versions
+}
+
+/// Puts `old` back into the field or slot switch named `what`. Switches are
+/// logged as "on" and "off", values as numbers.
+fn undo(preset: &mut Preset, what: &str, old: &str) -> bool {
+    if let Some(slot) = GLOBAL_FUNCTIONS.iter().position(|&name| name == what) {
+        if old == "on" || old == "off" {
+            preset.set_enabled(slot, old == "on");
+            return true;
+        }
+    }
+    let Ok(value) = old.parse::<u8>() else {
+        return false;
+    };
+    match PresetField::ALL
+        .into_iter()
+        .find(|&field| field_title(field) == what)
+    {
+        Some(field) => {
+            preset.set(field, Value7::clamped(value));
+            true
+        }
+        None => false,
+    }
+}
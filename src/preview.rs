@@ -0,0 +1,101 @@
+use crate::model::Preset;
+use crate::values::Channel;
+use serde::{Deserialize, Serialize};
+
+// Global channel slots, in the order the FCB1010 stores them
+const CC_CHANNELS: usize = 5;
+const EXPRESSION_CHANNELS: usize = 7;
+const NOTE_CHANNEL: usize = 9;
+
+/// The groups of messages a preset sends
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub enum MessageKind {
+    ProgramChange,
+    ControlChange,
+    Note,
+}
+
+impl MessageKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageKind::ProgramChange => "Program changes",
+            MessageKind::ControlChange => "Control changes",
+            MessageKind::Note => "Note",
+        }
+    }
+}
+
+/// The default order; the stock firmware has no setting to change it
+pub const FIRMWARE_SEND_ORDER: [MessageKind; 3] = [
+    MessageKind::ProgramChange,
+    MessageKind::ControlChange,
+    MessageKind::Note,
+];
+
+/// The messages a preset sends when its pedal is pressed, on the channels
+/// configured for each function, grouped in the given order. Slots that are
+/// switched off send nothing; the note is released straight away.
+pub fn preview_messages(
+    preset: &Preset,
+    global_channels: &[Channel; 10],
+    order: &[MessageKind; 3],
+) -> Vec<Vec<u8>> {
+    let channel = |slot: usize| global_channels[slot].get();
+    let mut messages = Vec::new();
+
+    for kind in order {
+        match kind {
+            MessageKind::ProgramChange => {
+                for slot in 0..5 {
+                    if let Some(program) = preset.program_change(slot) {
+                        messages.push(vec![0xc0 | channel(slot), program.get()]);
+                    }
+                }
+            }
+            MessageKind::ControlChange => {
+                for slot in 0..2 {
+                    if let Some((number, value)) = preset.control_change(slot) {
+                        messages.push(vec![
+                            0xb0 | channel(CC_CHANNELS + slot),
+                            number.get(),
+                            value.get(),
+                        ]);
+                    }
+                }
+            }
+            MessageKind::Note => {
+                if let Some(note) = preset.note() {
+                    let note = note.get();
+                    messages.push(vec![0x90 | channel(NOTE_CHANNEL), note, 0x7f]);
+                    messages.push(vec![0x80 | channel(NOTE_CHANNEL), note, 0x00]);
+                }
+            }
+        }
+    }
+
+    messages
+}
+
+/// What the preset's expression pedals send standing at `position`, from 0
+/// (heel) to 127 (toe), scaled into each pedal's sweep
+pub fn expression_messages(
+    preset: &Preset,
+    global_channels: &[Channel; 10],
+    position: u8,
+) -> Vec<Vec<u8>> {
+    [preset.expression_pedal_a(), preset.expression_pedal_b()]
+        .into_iter()
+        .enumerate()
+        .filter_map(|(offset, pedal)| {
+            let pedal = pedal?;
+            let (min, max) = (i32::from(pedal.min.get()), i32::from(pedal.max.get()));
+            // A max below the min sweeps backwards
+            let value = min + (max - min) * i32::from(position) / 127;
+            Some(vec![
+                0xb0 | global_channels[EXPRESSION_CHANNELS + offset].get(),
+                pedal.controller.get(),
+                value as u8,
+            ])
+        })
+        .collect()
+}
@@ -0,0 +1,351 @@
+use crate::board::BoardPicture;
+use crate::cc_dictionary::CcProfile;
+use crate::history::HistoryEntry;
+use crate::model::{SysExMessage, PEDALS_PER_BANK};
+use crate::preview::{MessageKind, FIRMWARE_SEND_ORDER};
+use crate::rehearsal::Rehearsal;
+use crate::scratchpad::{ScratchPreset, SCRATCHPAD_SLOTS};
+use crate::values::Channel;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Metadata beyond these is not from this editor
+const PRESETS: usize = 100;
+const BANKS: usize = PRESETS / PEDALS_PER_BANK;
+
+/// Free-form labels the user attaches to a preset; never sent to the device
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetMetadata {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub notes: String,
+    /// When a value of the preset last changed, RFC 3339 local time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+}
+
+/// Everything about a project that is not part of the SysEx dump itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectMetadata {
+    #[serde(default)]
+    pub presets: Vec<PresetMetadata>,
+    /// Which device the CC numbers in this project are meant for
+    #[serde(default)]
+    pub cc_profile: CcProfile,
+    /// Patch names of the target synth, by program change number
+    #[serde(default)]
+    pub program_names: BTreeMap<u8, String>,
+    /// The order previews send a preset's messages in
+    #[serde(default = "firmware_send_order")]
+    pub send_order: [MessageKind; 3],
+    /// Previews and test sends go out on this channel instead of the
+    /// preset's, e.g. to audition against a scratch synth; the dump keeps
+    /// its channels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_channel: Option<Channel>,
+    /// Banks whose presets are read-only and skipped by batch tools
+    #[serde(default)]
+    pub locked_banks: BTreeSet<usize>,
+    /// Who changed what and when, oldest first
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Photo of the rig with labels on its footswitches
+    #[serde(default)]
+    pub board: BoardPicture,
+    /// The last pedal activity recorded at a rehearsal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rehearsal: Option<Rehearsal>,
+    /// Drafts kept with the project and never sent to the device
+    #[serde(default)]
+    pub scratchpad: [ScratchPreset; SCRATCHPAD_SLOTS],
+}
+
+fn firmware_send_order() -> [MessageKind; 3] {
+    FIRMWARE_SEND_ORDER
+}
+
+impl Default for ProjectMetadata {
+    fn default() -> Self {
+        Self {
+            presets: Vec::new(),
+            cc_profile: CcProfile::default(),
+            program_names: BTreeMap::new(),
+            send_order: FIRMWARE_SEND_ORDER,
+            preview_channel: None,
+            locked_banks: BTreeSet::new(),
+            history: Vec::new(),
+            board: BoardPicture::default(),
+            rehearsal: None,
+            scratchpad: Default::default(),
+        }
+    }
+}
+
+impl ProjectMetadata {
+    /// The global channels previews use: the dump's own, or the preview
+    /// channel for every function when one is set
+    pub fn preview_channels(&self, global_channels: &[Channel; 10]) -> [Channel; 10] {
+        self.preview_channel
+            .map_or(*global_channels, |channel| [channel; 10])
+    }
+
+    pub fn preset_mut(&mut self, index: usize) -> &mut PresetMetadata {
+        if self.presets.len() <= index {
+            self.presets.resize_with(index + 1, PresetMetadata::default);
+        }
+        &mut self.presets[index]
+    }
+
+    pub fn is_bank_locked(&self, bank: usize) -> bool {
+        self.locked_banks.contains(&bank)
+    }
+
+    pub fn is_preset_locked(&self, index: usize) -> bool {
+        self.is_bank_locked(index / PEDALS_PER_BANK)
+    }
+
+    pub fn set_bank_locked(&mut self, bank: usize, locked: bool) {
+        if locked {
+            self.locked_banks.insert(bank);
+        } else {
+            self.locked_banks.remove(&bank);
+        }
+    }
+}
+
+/// The on-disk project file: the dump plus the editor's own metadata
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub dump: SysExMessage,
+    #[serde(default)]
+    pub metadata: ProjectMetadata,
+}
+
+/// How a loaded project file relates to the hash stored inside it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrity {
+    /// The content matches the hash written when it was saved
+    Verified,
+    /// Written by an older version, without a hash
+    Unsigned,
+    /// The content was changed outside the editor after it was saved
+    Modified,
+}
+
+#[derive(Serialize)]
+struct SignedProjectRef<'a> {
+    #[serde(flatten)]
+    project: &'a Project,
+    integrity: String,
+}
+
+#[derive(Deserialize)]
+struct SignedProject {
+    #[serde(flatten)]
+    project: Project,
+    #[serde(default)]
+    integrity: Option<String>,
+}
+
+impl Project {
+    /// Parses a project, also accepting the older files that held a bare dump
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        Self::from_json_checked(json).map(|(project, _)| project)
+    }
+
+    pub fn from_json_checked(json: &str) -> Result<(Self, Integrity), serde_json::Error> {
+        match serde_json::from_str::<SignedProject>(json) {
+            Ok(SignedProject {
+                project,
+                integrity: Some(hash),
+            }) => {
+                let signed = unsigned_text(json).map(|text| hex_digest(text.as_bytes()));
+                let integrity = if signed.as_deref() == Some(hash.as_str()) {
+                    Integrity::Verified
+                } else {
+                    Integrity::Modified
+                };
+                Ok((project, integrity))
+            }
+            Ok(SignedProject {
+                project,
+                integrity: None,
+            }) => Ok((project, Integrity::Unsigned)),
+            Err(err) => serde_json::from_str::<SysExMessage>(json)
+                .map(|dump| {
+                    let project = Project {
+                        dump,
+                        metadata: ProjectMetadata::default(),
+                    };
+                    (project, Integrity::Unsigned)
+                })
+                .map_err(|_| err),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&SignedProjectRef {
+            project: self,
+            integrity: self.content_hash()?,
+        })
+    }
+
+    /// SHA-256 over the project serialized without its hash. Loading takes
+    /// it over the file text instead (see [`unsigned_text`]), so files from
+    /// builds that serialize differently still verify.
+    fn content_hash(&self) -> Result<String, serde_json::Error> {
+        let json = serde_json::to_string(self)?;
+        Ok(hex_digest(json.as_bytes()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::load_checked(path).map(|(project, _)| project)
+    }
+
+    pub fn load_checked(path: &Path) -> Result<(Self, Integrity), String> {
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let (project, integrity) = Self::from_json_checked(&json).map_err(|e| {
+            if e.is_eof() {
+                format!("The file appears to be truncated ({})", e)
+            } else {
+                e.to_string()
+            }
+        })?;
+        project.validate()?;
+        Ok((project, integrity))
+    }
+
+    /// Checks what parsing alone lets through, such as metadata for presets
+    /// or banks the device does not have
+    pub fn validate(&self) -> Result<(), String> {
+        if self.metadata.presets.len() > PRESETS {
+            return Err(format!(
+                "The file has labels for {} presets; the FCB1010 has {}",
+                self.metadata.presets.len(),
+                PRESETS
+            ));
+        }
+        if let Some(bank) = self
+            .metadata
+            .locked_banks
+            .iter()
+            .find(|&&bank| bank >= BANKS)
+        {
+            return Err(format!(
+                "The file locks bank {}, which does not exist",
+                bank + 1
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the project, first keeping the file it replaces as the backup
+    /// (see [`backup_path`]) when that file is still the one last read or
+    /// written, `known` being its [`file_fingerprint`]
+    pub fn save(&self, path: &Path, known: Option<&str>) -> Result<(), String> {
+        let json = self.to_json().map_err(|e| e.to_string())?;
+        if known.is_some() && file_fingerprint(path).as_deref() == known {
+            fs::copy(path, backup_path(path)).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+/// The file text the hash was taken over: the file without its
+/// `integrity` member, which is written after the project serialized as it
+/// was when saved
+fn unsigned_text(json: &str) -> Option<String> {
+    let start = json.rfind("\"integrity\"")?;
+    let colon = start + json[start..].find(':')?;
+    let value = json[colon + 1..].trim_start().strip_prefix('"')?;
+    let end = json.len() - value.len() + value.find('"')? + 1;
+    let before = json[..start].trim_end();
+    let after = &json[end..];
+    Some(match before.strip_suffix(',') {
+        Some(before) => format!("{}{}", before, after),
+        None => format!("{}{}", before, after.trim_start().trim_start_matches(',')),
+    })
+}
+
+/// Where the last readable version of a project is kept: `preset_data.syx`
+/// is backed up to `preset_data.syx.bak`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Hash of the file as it is on disk now, to notice changes made by others
+pub fn file_fingerprint(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|data| hex_digest(&data))
+}
+
+fn hex_digest(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_projects_verify() {
+        let mut project = Project::default();
+        project.metadata.preset_mut(3).name = "Lead \"integrity\"".to_string();
+        let json = project.to_json().unwrap();
+        let (loaded, integrity) = Project::from_json_checked(&json).unwrap();
+        assert_eq!(integrity, Integrity::Verified);
+        assert_eq!(loaded, project);
+
+        let edited = json.replacen("Lead", "Solo", 1);
+        let (_, integrity) = Project::from_json_checked(&edited).unwrap();
+        assert_eq!(integrity, Integrity::Modified);
+    }
+
+    #[test]
+    fn files_from_other_builds_verify() {
+        // Written by a build without `metadata.board`: loading fills it in,
+        // so serializing again would not give the text that was hashed
+        let project = Project::default();
+        let mut value = serde_json::to_value(&project).unwrap();
+        value["metadata"].as_object_mut().unwrap().remove("board");
+        let text = serde_json::to_string(&value).unwrap();
+        let json = format!(
+            "{},\"integrity\":\"{}\"}}",
+            text.strip_suffix('}').unwrap(),
+            hex_digest(text.as_bytes())
+        );
+
+        let (_, integrity) = Project::from_json_checked(&json).unwrap();
+        assert_eq!(integrity, Integrity::Verified);
+        assert_eq!(unsigned_text(&json).as_deref(), Some(text.as_str()));
+    }
+
+    #[test]
+    fn only_the_known_file_is_backed_up() {
+        let dir = std::env::temp_dir().join(format!("fcb1010-project-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("preset_data.syx");
+        let project = Project::default();
+
+        fs::write(&path, "written by someone else").unwrap();
+        project.save(&path, None).unwrap();
+        let backed_up_foreign = backup_path(&path).exists();
+
+        let known = file_fingerprint(&path);
+        project.save(&path, known.as_deref()).unwrap();
+        let backup = fs::read_to_string(backup_path(&path)).ok();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(!backed_up_foreign);
+        assert_eq!(backup, Some(saved));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::daemon::list_backups;
+use crate::firmware::FirmwareSetting;
+use crate::model::SysExMessage;
+use crate::project::{backup_path, Project, ProjectMetadata};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Only the newest daemon backups are offered
+const OFFERED_DUMPS: usize = 5;
+
+/// Something the damaged project can be replaced with
+pub struct Candidate {
+    /// What it is and when it was written
+    pub label: String,
+    pub project: Project,
+}
+
+/// A project file that could not be read at startup. It stays on disk, and
+/// the editor is not saved over it, until the user picks what replaces it.
+pub struct Recovery {
+    pub path: PathBuf,
+    pub error: String,
+    /// The file as text, for copying out what is still readable
+    pub raw: String,
+    pub show_raw: bool,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Recovery {
+    /// Looks for the project's backup and the dumps the backup daemon stored
+    /// in `backup_dir`, skipping any that cannot be read either
+    pub fn new(path: &Path, error: String, backup_dir: &Path) -> Self {
+        let raw = fs::read(path)
+            .map(|data| String::from_utf8_lossy(&data).into_owned())
+            .unwrap_or_default();
+
+        let mut candidates = Vec::new();
+        let backup = backup_path(path);
+        if let Ok(project) = Project::load(&backup) {
+            candidates.push(Candidate {
+                label: format!("Backup from the last save ({})", modified(&backup)),
+                project,
+            });
+        }
+        let dumps = list_backups(backup_dir).unwrap_or_default();
+        for dump in dumps.iter().rev().take(OFFERED_DUMPS) {
+            let decoded = fs::read(dump)
+                .ok()
+                .and_then(|data| SysExMessage::decode(&data, FirmwareSetting::AutoDetect).ok());
+            if let Some(dump_message) = decoded {
+                candidates.push(Candidate {
+                    label: format!(
+                        "Dump {} from the device, without names and notes",
+                        dump.file_name().unwrap_or_default().to_string_lossy()
+                    ),
+                    project: Project {
+                        dump: dump_message,
+                        metadata: ProjectMetadata::default(),
+                    },
+                });
+            }
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            error,
+            raw,
+            show_raw: false,
+            candidates,
+        }
+    }
+
+    /// Renames the damaged file next to where it was, so saving the recovered
+    /// project cannot destroy what is left in it
+    pub fn set_aside(&self) -> Result<PathBuf, String> {
+        set_aside(&self.path)
+    }
+}
+
+/// Renames a file that could not be read to `<name>.damaged-<time>` next to
+/// it, before anything is saved in its place
+pub fn set_aside(path: &Path) -> Result<PathBuf, String> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(
+        ".damaged-{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let aside = PathBuf::from(name);
+    fs::rename(path, &aside).map_err(|e| e.to_string())?;
+    Ok(aside)
+}
+
+fn modified(path: &Path) -> String {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|time| {
+            chrono::DateTime::<chrono::Local>::from(time)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| "unknown time".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_the_readable_backups_and_keeps_the_damaged_file() {
+        let dir = std::env::temp_dir().join(format!("fcb1010-recovery-{}", std::process::id()));
+        let backups = dir.join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        let path = dir.join("preset_data.syx");
+        fs::write(&path, "{\"dump\":").unwrap();
+        let saved = Project::default();
+        fs::write(backup_path(&path), saved.to_json().unwrap()).unwrap();
+        fs::write(
+            backups.join("fcb1010-20260101-120000.syx"),
+            saved.dump.encode(),
+        )
+        .unwrap();
+        fs::write(backups.join("fcb1010-20260102-120000.syx"), [0xf0, 0xf7]).unwrap();
+
+        let recovery = Recovery::new(&path, "truncated".to_string(), &backups);
+        let aside = recovery.set_aside();
+        let kept = aside.as_ref().map(|aside| fs::read_to_string(aside).ok());
+        let moved = !path.exists();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(recovery.raw, "{\"dump\":");
+        assert_eq!(recovery.candidates.len(), 2);
+        assert_eq!(recovery.candidates[0].project, saved);
+        assert!(recovery.candidates[1].label.contains("20260101"));
+        assert_eq!(kept, Ok(Some("{\"dump\":".to_string())));
+        assert!(moved);
+    }
+}
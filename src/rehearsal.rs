@@ -0,0 +1,156 @@
+use crate::widgets::NumberBase;
+use eframe::egui;
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A message from the board and when it arrived
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RehearsalEvent {
+    /// Milliseconds after the recording started
+    pub at_ms: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// The program changes, control changes and notes the board sent during a
+/// rehearsal, kept in the project to look into what was pressed when
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rehearsal {
+    /// When the recording started, RFC 3339 local time
+    pub recorded: String,
+    pub events: Vec<RehearsalEvent>,
+}
+
+impl Rehearsal {
+    pub fn length(&self) -> Duration {
+        Duration::from_millis(self.events.last().map_or(0, |event| event.at_ms))
+    }
+}
+
+/// "PC 12 ch 1", "CC 80 = 127 ch 1" or "Note on 60 vel 100 ch 1", with the
+/// values in `base`; channels stay 1-16
+pub fn describe(bytes: &[u8], base: NumberBase) -> String {
+    let Some(&status) = bytes.first() else {
+        return String::new();
+    };
+    let channel = (status & 0x0f) + 1;
+    let value = |value: u8| base.format(value);
+    match (status & 0xf0, bytes) {
+        (0xc0, &[_, program]) => format!("PC {} ch {}", value(program), channel),
+        (0xb0, &[_, number, v]) => {
+            format!("CC {} = {} ch {}", value(number), value(v), channel)
+        }
+        (0x90, &[_, note, velocity]) if velocity > 0 => format!(
+            "Note on {} vel {} ch {}",
+            value(note),
+            value(velocity),
+            channel
+        ),
+        (0x80 | 0x90, &[_, note, _]) => format!("Note off {} ch {}", value(note), channel),
+        _ => crate::console::format_bytes(bytes, base),
+    }
+}
+
+/// Records what the board sends on an input port with timestamps
+#[derive(Default)]
+pub struct RehearsalRecorder {
+    connection: Option<MidiInputConnection<()>>,
+    recording: Option<(String, Arc<Mutex<Vec<RehearsalEvent>>>)>,
+}
+
+impl RehearsalRecorder {
+    pub fn start(&mut self, port_index: usize, ctx: &egui::Context) -> Result<(), String> {
+        self.stop();
+
+        let midi_in = MidiInput::new("MIDI Input").map_err(|e| e.to_string())?;
+        let port = midi_in
+            .ports()
+            .get(port_index)
+            .cloned()
+            .ok_or("MIDI port is no longer available")?;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&events);
+        let started = Instant::now();
+        let ctx = ctx.clone();
+        let connection = midi_in
+            .connect(
+                &port,
+                "midir-rehearsal-recorder",
+                move |_, message, _| {
+                    // Only what a pedal press sends: notes, CCs and PCs
+                    if message
+                        .first()
+                        .is_some_and(|&b| matches!(b & 0xf0, 0x80 | 0x90 | 0xb0 | 0xc0))
+                    {
+                        received.lock().unwrap().push(RehearsalEvent {
+                            at_ms: started.elapsed().as_millis() as u64,
+                            bytes: message.to_vec(),
+                        });
+                        ctx.request_repaint();
+                    }
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        self.recording = Some((chrono::Local::now().to_rfc3339(), events));
+        Ok(())
+    }
+
+    /// Stops listening and returns what was recorded, if a recording ran
+    pub fn stop(&mut self) -> Option<Rehearsal> {
+        self.connection = None;
+        let (recorded, events) = self.recording.take()?;
+        let events = std::mem::take(&mut *events.lock().unwrap());
+        Some(Rehearsal { recorded, events })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Messages recorded so far
+    pub fn count(&self) -> usize {
+        self.recording
+            .as_ref()
+            .map_or(0, |(_, events)| events.lock().unwrap().len())
+    }
+}
+
+/// A rehearsal being played back in its original timing
+pub struct Replay {
+    started: Instant,
+    next: usize,
+}
+
+impl Replay {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            next: 0,
+        }
+    }
+
+    /// The messages that became due since the last call and how long until
+    /// the next one; `None` once every message was played
+    pub fn due<'a>(&mut self, rehearsal: &'a Rehearsal) -> (Vec<&'a [u8]>, Option<Duration>) {
+        let elapsed = self.started.elapsed();
+        let mut due = Vec::new();
+        while let Some(event) = rehearsal.events.get(self.next) {
+            let at = Duration::from_millis(event.at_ms);
+            if at > elapsed {
+                return (due, Some(at - elapsed));
+            }
+            due.push(event.bytes.as_slice());
+            self.next += 1;
+        }
+        (due, None)
+    }
+
+    /// Where the playback stands
+    pub fn position(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::model::{PresetField, SysExMessage, PEDALS_PER_BANK};
+use crate::numbering::Numbering;
+use crate::spec::field_title;
+use crate::values::Value7;
+use std::collections::BTreeSet;
+
+/// The kinds of message a preset sends that can be searched for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageType {
+    ProgramChange,
+    ControlChange,
+    Note,
+}
+
+impl MessageType {
+    pub const ALL: [MessageType; 3] = [
+        MessageType::ProgramChange,
+        MessageType::ControlChange,
+        MessageType::Note,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageType::ProgramChange => "Program change",
+            MessageType::ControlChange => "Control change",
+            MessageType::Note => "Note",
+        }
+    }
+
+    /// The preset slots sending this kind of message: the number field, the
+    /// value field if any, and the global channel function they use
+    fn slots(self) -> Vec<(PresetField, Option<PresetField>, usize)> {
+        match self {
+            MessageType::ProgramChange => (0..5)
+                .map(|i| (PresetField::ProgramChange(i), None, i))
+                .collect(),
+            MessageType::ControlChange => (0..2)
+                .map(|i| {
+                    (
+                        PresetField::ControlNumber(i),
+                        Some(PresetField::ControlValue(i)),
+                        5 + i,
+                    )
+                })
+                .collect(),
+            MessageType::Note => vec![(PresetField::Note, None, 9)],
+        }
+    }
+}
+
+/// What to look for; `None` matches anything
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Query {
+    pub kind: MessageType,
+    /// Program, controller or note number
+    pub number: Option<u8>,
+    /// Control change value
+    pub value: Option<u8>,
+    /// Channel 0-15 of the function the slot sends on
+    pub channel: Option<u8>,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            kind: MessageType::ControlChange,
+            number: None,
+            value: None,
+            channel: None,
+        }
+    }
+}
+
+/// New number and value for the hits; `None` keeps what is there
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Replacement {
+    pub number: Option<u8>,
+    pub value: Option<u8>,
+}
+
+/// One preset slot matching the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub index: usize,
+    pub number_field: PresetField,
+    pub value_field: Option<PresetField>,
+    pub description: String,
+    pub accepted: bool,
+}
+
+impl Hit {
+    pub fn edits(&self, replacement: &Replacement) -> Vec<(PresetField, Value7)> {
+        let mut edits = Vec::new();
+        if let Some(number) = replacement.number {
+            edits.push((self.number_field, Value7::clamped(number)));
+        }
+        if let (Some(field), Some(value)) = (self.value_field, replacement.value) {
+            edits.push((field, Value7::clamped(value)));
+        }
+        edits
+    }
+}
+
+/// Every switched-on slot of every preset outside the skipped banks that
+/// matches
+pub fn find(
+    message: &SysExMessage,
+    query: &Query,
+    skipped_banks: &BTreeSet<usize>,
+    numbering: Numbering,
+) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    for index in 0..message.presets().len() {
+        if skipped_banks.contains(&(index / PEDALS_PER_BANK)) {
+            continue;
+        }
+        let preset = message.preset(index);
+        for (number_field, value_field, function) in query.kind.slots() {
+            if !preset.sends(number_field) {
+                continue;
+            }
+            let channel = message.global_channels()[function];
+            let number = preset.get(number_field);
+            let value = value_field.map(|field| preset.get(field));
+            let matches = query.number.is_none_or(|n| n == number)
+                && query.channel.is_none_or(|c| c == channel.get())
+                && (value_field.is_none() || query.value.is_none() || query.value == value);
+            if !matches {
+                continue;
+            }
+
+            let mut description = format!(
+                "Preset {} {}: {} {}",
+                numbering.preset(index),
+                field_title(number_field),
+                query.kind.label(),
+                number
+            );
+            if let Some(value) = value {
+                description.push_str(&format!(" = {}", value));
+            }
+            description.push_str(&format!(" on channel {}", channel.number()));
+            hits.push(Hit {
+                index,
+                number_field,
+                value_field,
+                description,
+                accepted: true,
+            });
+        }
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb1010_core::builder::DumpBuilder;
+
+    #[test]
+    fn finds_matching_slots_outside_skipped_banks() {
+        let message = DumpBuilder::new()
+            .pedal(1)
+            .cc(1, 7, 100)
+            .pedal(2)
+            .cc(2, 7, 50)
+            .pc(1, 7)
+            .bank(1)
+            .pedal(1)
+            .cc(1, 7, 100)
+            .build()
+            .unwrap();
+        let query = Query {
+            number: Some(7),
+            value: Some(100),
+            ..Query::default()
+        };
+
+        let hits = find(&message, &query, &BTreeSet::from([1]), Numbering::Linear);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, 0);
+        assert_eq!(
+            hits[0].description,
+            "Preset 1 CC 1 number: Control change 7 = 100 on channel 1"
+        );
+        let replacement = Replacement {
+            number: Some(11),
+            value: None,
+        };
+        assert_eq!(
+            hits[0].edits(&replacement),
+            [(PresetField::ControlNumber(0), Value7::clamped(11))]
+        );
+
+        let anything = Query::default();
+        assert_eq!(
+            find(&message, &anything, &BTreeSet::new(), Numbering::Linear).len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::cc_dictionary::CcProfile;
+use crate::model::{SwitchMode, SysExMessage};
+use crate::project::ProjectMetadata;
+use crate::spec::GLOBAL_FUNCTIONS;
+use crate::values::Channel;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The rig profile format written by this version; older ones are read too
+pub const RIG_FILE_VERSION: u32 = 1;
+pub const RIG_EXTENSION: &str = "fcbrig.json";
+
+/// How a rig is wired, kept apart from any dump: the global channels, the
+/// relay switch modes and the device CC numbers are named after. Applying it
+/// leaves the presets alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RigProfile {
+    pub version: u32,
+    /// e.g. "Helix + Strymon"
+    pub name: String,
+    pub channels: [Channel; 10],
+    pub switch_modes: [SwitchMode; 2],
+    #[serde(default)]
+    pub cc_profile: CcProfile,
+}
+
+impl RigProfile {
+    pub fn from_project(message: &SysExMessage, metadata: &ProjectMetadata, name: &str) -> Self {
+        Self {
+            version: RIG_FILE_VERSION,
+            name: name.to_string(),
+            channels: *message.global_channels(),
+            switch_modes: *message.switch_modes(),
+            cc_profile: metadata.cc_profile,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let profile: Self = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if profile.version > RIG_FILE_VERSION {
+            return Err(format!(
+                "The rig profile is format version {}; this editor reads up to {}",
+                profile.version, RIG_FILE_VERSION
+            ));
+        }
+        Ok(profile)
+    }
+
+    /// Puts the profile's settings into the dump and project; true if the
+    /// dump's global settings changed
+    pub fn apply(&self, message: &mut SysExMessage, metadata: &mut ProjectMetadata) -> bool {
+        let mut changed = false;
+        for (function, &channel) in self.channels.iter().enumerate() {
+            changed |= message.set_global_channel(function, channel);
+        }
+        for (relay, &mode) in self.switch_modes.iter().enumerate() {
+            changed |= message.set_switch_mode(relay, mode);
+        }
+        metadata.cc_profile = self.cc_profile;
+        changed
+    }
+
+    /// What applying the profile would change, e.g. "Note channel 1 → 3"
+    pub fn differences(&self, message: &SysExMessage, metadata: &ProjectMetadata) -> Vec<String> {
+        let mut differences = Vec::new();
+        for (function, (&ours, theirs)) in self
+            .channels
+            .iter()
+            .zip(message.global_channels())
+            .enumerate()
+        {
+            if ours != *theirs {
+                differences.push(format!(
+                    "{} channel {} → {}",
+                    GLOBAL_FUNCTIONS[function],
+                    theirs.number(),
+                    ours.number()
+                ));
+            }
+        }
+        for (relay, (&ours, theirs)) in self
+            .switch_modes
+            .iter()
+            .zip(message.switch_modes())
+            .enumerate()
+        {
+            if ours != *theirs {
+                differences.push(format!(
+                    "Switch {} {} → {}",
+                    relay + 1,
+                    theirs.label(),
+                    ours.label()
+                ));
+            }
+        }
+        if self.cc_profile != metadata.cc_profile {
+            differences.push(format!(
+                "CC names {} → {}",
+                metadata.cc_profile.label(),
+                self.cc_profile.label()
+            ));
+        }
+        differences
+    }
+}
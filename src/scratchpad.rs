@@ -0,0 +1,24 @@
+use crate::model::Preset;
+use crate::project::PresetMetadata;
+use serde::{Deserialize, Serialize};
+
+pub const SCRATCHPAD_SLOTS: usize = 10;
+
+/// A preset being drafted outside the dump. The scratchpad is part of the
+/// project metadata, never of the `SysExMessage`, so nothing that encodes,
+/// sends or exports a dump can reach it; only copying a draft into a real
+/// slot makes it part of the dump.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScratchPreset {
+    #[serde(default)]
+    pub preset: Preset,
+    #[serde(default)]
+    pub labels: PresetMetadata,
+}
+
+impl ScratchPreset {
+    /// Whether anything was drafted in the slot
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::model::{ExpressionPart, PresetField, SysExMessage, MAX_CONTROLLER_NUMBER};
+use crate::spec::global_function;
+
+// Lists of presets longer than this are cut short
+const LISTED_PRESETS: usize = 8;
+
+/// Settings in the dump that are almost certainly mistakes, one line per
+/// kind with the presets it affects; empty when the dump looks sane
+pub fn check(message: &SysExMessage) -> Vec<String> {
+    let mut bank_selects = Vec::new();
+    let mut reversed = [Vec::new(), Vec::new()];
+    let mut channel_mode = [Vec::new(), Vec::new()];
+    let mut fighting = Vec::new();
+    let channel = |field: PresetField| message.global_channels()[global_function(field)];
+
+    for (index, preset) in message.presets().iter().enumerate() {
+        for slot in 0..2 {
+            if let Some((number, value)) = preset.control_change(slot) {
+                if number.get() == 0 && value.get() != 0 {
+                    bank_selects.push(index);
+                }
+            }
+        }
+
+        let pedals = [
+            (
+                preset.expression_pedal_a(),
+                PresetField::ExpressionA as fn(_) -> _,
+            ),
+            (preset.expression_pedal_b(), PresetField::ExpressionB),
+        ];
+        for (pedal, (expression, pedal_field)) in pedals.into_iter().enumerate() {
+            let Some(expression) = expression else {
+                continue;
+            };
+            if expression.max < expression.min {
+                reversed[pedal].push(index);
+            }
+            if expression.controller.get() > MAX_CONTROLLER_NUMBER {
+                channel_mode[pedal].push(index);
+            }
+            // A switched CC and a swept one on the same controller fight
+            let controller = pedal_field(ExpressionPart::Controller);
+            let clash = (0..2).any(|slot| {
+                let number = PresetField::ControlNumber(slot);
+                preset.sends(number)
+                    && preset.get(number) == preset.get(controller)
+                    && channel(number) == channel(controller)
+            });
+            if clash && !fighting.contains(&index) {
+                fighting.push(index);
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+    if !bank_selects.is_empty() {
+        bank_selects.dedup();
+        problems.push(format!(
+            "{} with CC number 0 and a nonzero value, which selects a bank on most gear ({})",
+            count(bank_selects.len()),
+            list(&bank_selects)
+        ));
+    }
+    for (pedal, name) in ["A", "B"].into_iter().enumerate() {
+        if !reversed[pedal].is_empty() {
+            problems.push(format!(
+                "Expression {} max is below min in {}",
+                name,
+                list(&reversed[pedal])
+            ));
+        }
+        if !channel_mode[pedal].is_empty() {
+            problems.push(format!(
+                "Expression {} sends a channel mode message (CC 120-127) in {}",
+                name,
+                list(&channel_mode[pedal])
+            ));
+        }
+    }
+    if !fighting.is_empty() {
+        problems.push(format!(
+            "An expression pedal sweeps the controller a CC slot switches, on the same channel, in {}",
+            list(&fighting)
+        ));
+    }
+    problems
+}
+
+fn count(presets: usize) -> String {
+    match presets {
+        1 => "1 preset is set".to_string(),
+        n => format!("{} presets are set", n),
+    }
+}
+
+/// "preset 12", "presets 3, 7 and 12" or the first few and how many more
+fn list(presets: &[usize]) -> String {
+    let numbers: Vec<String> = presets
+        .iter()
+        .take(LISTED_PRESETS)
+        .map(|index| (index + 1).to_string())
+        .collect();
+    let more = presets.len().saturating_sub(LISTED_PRESETS);
+    match (numbers.as_slice(), more) {
+        ([only], _) => format!("preset {}", only),
+        ([first @ .., last], 0) => format!("presets {} and {}", first.join(", "), last),
+        (shown, more) => format!("presets {} and {} more", shown.join(", "), more),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::Value7;
+    use fcb1010_core::builder::DumpBuilder;
+
+    #[test]
+    fn lists_likely_mistakes_by_kind() {
+        let mut message = DumpBuilder::new()
+            .pedal(1)
+            .cc(1, 0, 5)
+            .pedal(2)
+            .cc(2, 0, 9)
+            .expression_a(11, 100, 20)
+            .pedal(3)
+            .cc(1, 7, 0)
+            .expression_b(7, 0, 127)
+            .pedal(4)
+            .expression_a(1, 0, 127)
+            .build()
+            .unwrap();
+        // The builder refuses channel mode messages; older files hold them
+        message.set_preset_field(
+            3,
+            PresetField::ExpressionA(ExpressionPart::Controller),
+            Value7::clamped(121),
+        );
+
+        assert_eq!(
+            check(&message),
+            [
+                "2 presets are set with CC number 0 and a nonzero value, which selects a bank \
+                 on most gear (presets 1 and 2)",
+                "Expression A max is below min in preset 2",
+                "Expression A sends a channel mode message (CC 120-127) in preset 4",
+                "An expression pedal sweeps the controller a CC slot switches, on the same \
+                 channel, in preset 3",
+            ]
+        );
+        assert!(check(&DumpBuilder::new().build().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn cuts_long_lists_short() {
+        let presets: Vec<usize> = (0..10).collect();
+        assert_eq!(list(&presets), "presets 1, 2, 3, 4, 5, 6, 7, 8 and 2 more");
+        assert_eq!(list(&presets[..3]), "presets 1, 2 and 3");
+    }
+}
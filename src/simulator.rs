@@ -0,0 +1,109 @@
+use crate::model::PEDALS_PER_BANK;
+use crate::tour::TourStep;
+use eframe::egui::Key;
+use std::time::{Duration, Instant};
+
+const BANKS: usize = 10;
+
+/// The computer keys that press footswitches 1 to 10
+pub const PEDAL_KEYS: [Key; PEDALS_PER_BANK] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Num0,
+];
+/// Rock the expression pedals toward the toe and the heel
+pub const EXPRESSION_UP: Key = Key::ArrowUp;
+pub const EXPRESSION_DOWN: Key = Key::ArrowDown;
+
+/// The simulator's callout in the guided tour
+pub const TOUR_STEP: TourStep = TourStep {
+    target: "simulator",
+    title: "Try presets without the pedal",
+    text: "The simulator's virtual footswitches send what the board would, so presets can be played from the computer, also with keys 1-0.",
+};
+
+// How far one key press moves the expression pedals, out of 127
+const EXPRESSION_STEP: u8 = 8;
+
+/// A sequence of pedal presses being captured
+pub struct Recording {
+    started: Instant,
+    events: Vec<(Duration, Vec<u8>)>,
+}
+
+/// Virtual footswitches: pedal presses preview the preset of the current bank
+#[derive(Default)]
+pub struct Simulator {
+    pub bank: usize,
+    /// The preset of the last press, whose expression pedals are rocked
+    pub selected: Option<usize>,
+    /// Where the expression pedals stand, from 0 (heel) to 127 (toe)
+    pub expression: u8,
+    recording: Option<Recording>,
+    /// The last finished recording, ready to export
+    recorded: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Simulator {
+    pub fn bank_up(&mut self) {
+        self.bank = (self.bank + 1) % BANKS;
+    }
+
+    pub fn bank_down(&mut self) {
+        self.bank = (self.bank + BANKS - 1) % BANKS;
+    }
+
+    /// The preset a pedal of the current bank selects
+    pub fn preset_index(&self, pedal: usize) -> usize {
+        self.bank * PEDALS_PER_BANK + pedal
+    }
+
+    /// Moves the expression pedals one step, returning whether they moved
+    pub fn rock(&mut self, toward_toe: bool) -> bool {
+        let before = self.expression;
+        self.expression = if toward_toe {
+            self.expression.saturating_add(EXPRESSION_STEP).min(127)
+        } else {
+            self.expression.saturating_sub(EXPRESSION_STEP)
+        };
+        self.expression != before
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording {
+            started: Instant::now(),
+            events: Vec::new(),
+        });
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            self.recorded = recording.events;
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Adds the messages of a press to the recording, if one is running
+    pub fn record(&mut self, messages: &[Vec<u8>]) {
+        if let Some(recording) = &mut self.recording {
+            let at = recording.started.elapsed();
+            recording
+                .events
+                .extend(messages.iter().map(|message| (at, message.clone())));
+        }
+    }
+
+    pub fn recorded(&self) -> &[(Duration, Vec<u8>)] {
+        &self.recorded
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+// Ticks per quarter note, at the default tempo of 120 bpm
+const DIVISION: u16 = 480;
+const MICROSECONDS_PER_QUARTER: u32 = 500_000;
+
+/// Writes channel messages at the given times as a single-track (format 0)
+/// Standard MIDI File
+pub fn write_smf(events: &[(Duration, Vec<u8>)]) -> Vec<u8> {
+    let mut track = Vec::new();
+
+    // Tempo meta event, so every player agrees on the timing
+    track.extend_from_slice(&[0x00, 0xff, 0x51, 0x03]);
+    track.extend_from_slice(&MICROSECONDS_PER_QUARTER.to_be_bytes()[1..]);
+
+    let mut last_tick = 0;
+    for (at, message) in events {
+        let tick = to_ticks(*at).max(last_tick);
+        write_variable_length(&mut track, tick - last_tick);
+        track.extend_from_slice(message);
+        last_tick = tick;
+    }
+
+    // End of track
+    track.extend_from_slice(&[0x00, 0xff, 0x2f, 0x00]);
+
+    let mut file = Vec::new();
+    file.extend_from_slice(b"MThd");
+    file.extend_from_slice(&6u32.to_be_bytes());
+    file.extend_from_slice(&0u16.to_be_bytes()); // format 0
+    file.extend_from_slice(&1u16.to_be_bytes()); // one track
+    file.extend_from_slice(&DIVISION.to_be_bytes());
+    file.extend_from_slice(b"MTrk");
+    file.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    file.extend_from_slice(&track);
+    file
+}
+
+fn to_ticks(at: Duration) -> u32 {
+    let ticks = at.as_micros() * u128::from(DIVISION) / u128::from(MICROSECONDS_PER_QUARTER);
+    ticks.min(0x0fff_ffff) as u32
+}
+
+/// A SysEx message found in a track of a Standard MIDI File
+pub struct SmfSysEx {
+    /// Counted from 0, in the order of the file
+    pub track: usize,
+    /// Ticks since the start of the track
+    pub tick: u32,
+    /// The whole message from F0 to F7
+    pub bytes: Vec<u8>,
+}
+
+/// Every SysEx message in the tracks of a Standard MIDI File. Messages split
+/// into F7 continuation packets, as some recorders store long dumps, are
+/// joined again.
+pub fn read_sysex(data: &[u8]) -> Result<Vec<SmfSysEx>, String> {
+    if !data.starts_with(b"MThd") {
+        return Err("Not a Standard MIDI File".to_string());
+    }
+    let mut found = Vec::new();
+    let mut position = 0;
+    let mut track = 0;
+    while position + 8 <= data.len() {
+        let id = &data[position..position + 4];
+        let length = u32::from_be_bytes([
+            data[position + 4],
+            data[position + 5],
+            data[position + 6],
+            data[position + 7],
+        ]) as usize;
+        let start = position + 8;
+        // A truncated last chunk is read as far as it goes
+        let chunk = &data[start..(start + length).min(data.len())];
+        if id == b"MTrk" {
+            read_track(chunk, track, &mut found)
+                .map_err(|e| format!("Track {}: {}", track + 1, e))?;
+            track += 1;
+        }
+        position = start + length;
+    }
+    Ok(found)
+}
+
+fn read_track(track: &[u8], index: usize, found: &mut Vec<SmfSysEx>) -> Result<(), String> {
+    let mut position = 0;
+    let mut tick = 0u32;
+    let mut running = None;
+    // A SysEx message still waiting for its F7 continuation packets
+    let mut unfinished: Option<SmfSysEx> = None;
+    while position < track.len() {
+        tick = tick.saturating_add(read_variable_length(track, &mut position)?);
+        let status = *take(track, &mut position, 1)?.first().unwrap_or(&0);
+        match status {
+            0xf0 | 0xf7 => {
+                running = None;
+                let length = read_variable_length(track, &mut position)? as usize;
+                let packet = take(track, &mut position, length)?;
+                let mut message = match (status, unfinished.take()) {
+                    (0xf7, Some(message)) => message,
+                    // An F7 packet without an open message escapes raw bytes
+                    (0xf7, None) => continue,
+                    _ => SmfSysEx {
+                        track: index,
+                        tick,
+                        bytes: vec![0xf0],
+                    },
+                };
+                message.bytes.extend_from_slice(packet);
+                if message.bytes.last() == Some(&0xf7) {
+                    found.push(message);
+                } else {
+                    unfinished = Some(message);
+                }
+            }
+            0xff => {
+                let kind = *take(track, &mut position, 1)?.first().unwrap_or(&0);
+                let length = read_variable_length(track, &mut position)? as usize;
+                take(track, &mut position, length)?;
+                if kind == 0x2f {
+                    break;
+                }
+            }
+            _ => {
+                let status = if status & 0x80 != 0 {
+                    running = Some(status);
+                    status
+                } else {
+                    // Running status: this was already the first data byte
+                    position -= 1;
+                    running.ok_or("data byte without a status byte")?
+                };
+                let data_bytes = match status & 0xf0 {
+                    0xc0 | 0xd0 => 1,
+                    _ => 2,
+                };
+                take(track, &mut position, data_bytes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn take<'a>(data: &'a [u8], position: &mut usize, count: usize) -> Result<&'a [u8], String> {
+    let bytes = data
+        .get(*position..*position + count)
+        .ok_or("the track ends in the middle of an event")?;
+    *position += count;
+    Ok(bytes)
+}
+
+fn read_variable_length(data: &[u8], position: &mut usize) -> Result<u32, String> {
+    let mut value = 0u32;
+    for _ in 0..4 {
+        let byte = take(data, position, 1)?[0];
+        value = (value << 7) | u32::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("a delta time or length is longer than four bytes".to_string())
+}
+
+// Seven bits per byte, most significant first, high bit set on all but the last
+fn write_variable_length(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
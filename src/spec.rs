@@ -0,0 +1,188 @@
+use crate::firmware::Firmware;
+use crate::model::{ExpressionPart, PresetField, MAX_CONTROLLER_NUMBER};
+
+/// What the FCB1010 manual says about one kind of stored value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min: u8,
+    pub max: u8,
+    /// The value of a fresh preset, which the pedal treats as nothing set
+    pub default: u8,
+    /// Firmware revisions whose dumps contain the value
+    pub firmware: &'static [Firmware],
+}
+
+const PROGRAM_CHANGE: FieldSpec = FieldSpec {
+    name: "Program change",
+    description: "Program change sent when the pedal is pressed, on the channel of its PC \
+                  function in Global Settings.",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const CONTROL_NUMBER: FieldSpec = FieldSpec {
+    name: "Control change number",
+    description: "Controller number of a control change sent when the pedal is pressed.",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const CONTROL_VALUE: FieldSpec = FieldSpec {
+    name: "Control change value",
+    description: "Value sent with the control change when the pedal is pressed.",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const EXPRESSION_CONTROLLER: FieldSpec = FieldSpec {
+    name: "Expression pedal controller",
+    description: "Controller number the expression pedal sends while it is moved in this preset.",
+    min: 0,
+    max: MAX_CONTROLLER_NUMBER,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const EXPRESSION_MIN: FieldSpec = FieldSpec {
+    name: "Expression pedal minimum",
+    description: "Value sent with the pedal fully up (heel down).",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const EXPRESSION_MAX: FieldSpec = FieldSpec {
+    name: "Expression pedal maximum",
+    description: "Value sent with the pedal fully down (toe down). Must not be below the minimum.",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const NOTE: FieldSpec = FieldSpec {
+    name: "Note",
+    description: "Note number sent when the pedal is pressed.",
+    min: 0,
+    max: 127,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+pub const GLOBAL_CHANNEL: FieldSpec = FieldSpec {
+    name: "MIDI channel",
+    description: "Channel used by this function in every preset. Stored as 0-15 for channels 1-16.",
+    min: 0,
+    max: 15,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+const SWITCH_MODE: FieldSpec = FieldSpec {
+    name: "Relay switch mode",
+    description: "Whether the relay output follows the footswitch or flips on every press.",
+    min: 0,
+    max: 1,
+    default: 0,
+    firmware: &Firmware::ALL,
+};
+
+/// The functions the global channels belong to, in dump order
+pub const GLOBAL_FUNCTIONS: [&str; 10] = [
+    "PC 1", "PC 2", "PC 3", "PC 4", "PC 5", "CC 1", "CC 2", "EXP A", "EXP B", "Note",
+];
+
+/// The global function whose channel the field is sent on
+pub fn global_function(field: PresetField) -> usize {
+    field.slot()
+}
+
+pub fn preset_field(field: PresetField) -> &'static FieldSpec {
+    match field {
+        PresetField::ProgramChange(_) => &PROGRAM_CHANGE,
+        PresetField::ControlNumber(_) => &CONTROL_NUMBER,
+        PresetField::ControlValue(_) => &CONTROL_VALUE,
+        PresetField::ExpressionA(part) | PresetField::ExpressionB(part) => match part {
+            ExpressionPart::Controller => &EXPRESSION_CONTROLLER,
+            ExpressionPart::Min => &EXPRESSION_MIN,
+            ExpressionPart::Max => &EXPRESSION_MAX,
+        },
+        PresetField::Note => &NOTE,
+    }
+}
+
+pub fn is_default(field: PresetField, value: u8) -> bool {
+    preset_field(field).default == value
+}
+
+impl FieldSpec {
+    /// The tooltip: title, description, range, firmware and where the byte
+    /// lives with the current layout
+    pub fn tooltip(&self, title: &str, location: &str) -> String {
+        let firmware: Vec<&str> = self.firmware.iter().map(|f| f.label()).collect();
+        format!(
+            "{} – {}\n{}\nRange: {}–{}, default {}\nFirmware: {}\n{}",
+            title,
+            self.name,
+            self.description,
+            self.min,
+            self.max,
+            self.default,
+            firmware.join(", "),
+            location
+        )
+    }
+}
+
+pub fn preset_tooltip(field: PresetField, firmware: &Firmware) -> String {
+    let offset = firmware.memory_map().offset_of(field);
+    preset_field(field).tooltip(
+        &field_title(field),
+        &format!(
+            "Byte {} of the preset record ({})",
+            offset,
+            firmware.label()
+        ),
+    )
+}
+
+pub fn global_channel_tooltip(function: usize, firmware: &Firmware) -> String {
+    let offset = firmware.memory_map().global_channels + function;
+    GLOBAL_CHANNEL.tooltip(
+        GLOBAL_FUNCTIONS[function],
+        &format!("Dump offset 0x{:03x} ({})", offset, firmware.label()),
+    )
+}
+
+pub fn switch_mode_tooltip(switch: usize, firmware: &Firmware) -> String {
+    let offset = firmware.memory_map().switch_modes + switch;
+    SWITCH_MODE.tooltip(
+        &format!("Switch {}", switch + 1),
+        &format!("Dump offset 0x{:03x} ({})", offset, firmware.label()),
+    )
+}
+
+pub fn field_title(field: PresetField) -> String {
+    let part = |part: ExpressionPart| match part {
+        ExpressionPart::Controller => "controller",
+        ExpressionPart::Min => "min",
+        ExpressionPart::Max => "max",
+    };
+    match field {
+        PresetField::ProgramChange(i) => format!("PC {}", i + 1),
+        PresetField::ControlNumber(i) => format!("CC {} number", i + 1),
+        PresetField::ControlValue(i) => format!("CC {} value", i + 1),
+        PresetField::ExpressionA(p) => format!("EP A {}", part(p)),
+        PresetField::ExpressionB(p) => format!("EP B {}", part(p)),
+        PresetField::Note => "Note".to_string(),
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Running totals for the transfers made through one port
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PortStats {
+    pub sends: u64,
+    pub receives: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub failures: u64,
+    /// Sends repeated after the port was reopened, and transfers started
+    /// right after the same kind of transfer on the port failed
+    pub retries: u64,
+    #[serde(default)]
+    send_micros: u64,
+    /// From the first byte of each dump to its last
+    #[serde(default)]
+    receive_micros: u64,
+    #[serde(default)]
+    send_failed: bool,
+    #[serde(default)]
+    receive_failed: bool,
+}
+
+impl PortStats {
+    pub fn average_send(&self) -> Option<Duration> {
+        average(self.send_micros, self.sends)
+    }
+
+    /// How long dumps took to arrive once they started, on average
+    pub fn average_receive(&self) -> Option<Duration> {
+        average(self.receive_micros, self.receives)
+    }
+
+    /// Fraction of transfers that failed, 0.0 when there were none
+    pub fn failure_rate(&self) -> f64 {
+        let attempts = self.sends + self.receives + self.failures;
+        if attempts == 0 {
+            0.0
+        } else {
+            self.failures as f64 / attempts as f64
+        }
+    }
+
+    fn attempt(&mut self, send: bool, failed: bool) {
+        let last_failed = if send {
+            &mut self.send_failed
+        } else {
+            &mut self.receive_failed
+        };
+        if *last_failed {
+            self.retries += 1;
+        }
+        *last_failed = failed;
+        if failed {
+            self.failures += 1;
+        }
+    }
+}
+
+fn average(total_micros: u64, count: u64) -> Option<Duration> {
+    (count > 0).then(|| Duration::from_micros(total_micros / count))
+}
+
+/// Transfer statistics of every port the editor has used, kept across sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransferStats {
+    #[serde(default)]
+    ports: BTreeMap<String, PortStats>,
+}
+
+impl TransferStats {
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) {
+        if let Ok(json) = serde_json::to_string(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = (&String, &PortStats)> {
+        self.ports.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.ports.clear();
+    }
+
+    /// A successful send takes its byte count and duration, a failed one
+    /// neither. `retried` is a send that only went through the second time.
+    pub fn record_send(
+        &mut self,
+        port: &str,
+        bytes: usize,
+        result: Result<Duration, ()>,
+        retried: bool,
+    ) {
+        let stats = self.ports.entry(port.to_string()).or_default();
+        stats.attempt(true, result.is_err());
+        if retried {
+            stats.retries += 1;
+        }
+        if let Ok(duration) = result {
+            stats.sends += 1;
+            stats.bytes_sent += bytes as u64;
+            stats.send_micros += duration.as_micros() as u64;
+        }
+    }
+
+    /// `result` holds how long the dump took from its first byte
+    pub fn record_receive(&mut self, port: &str, bytes: usize, result: Result<Duration, ()>) {
+        let stats = self.ports.entry(port.to_string()).or_default();
+        stats.attempt(false, result.is_err());
+        if let Ok(duration) = result {
+            stats.receives += 1;
+            stats.bytes_received += bytes as u64;
+            stats.receive_micros += duration.as_micros() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORT: &str = "UM-ONE";
+
+    #[test]
+    fn counts_retries_of_the_same_transfer() {
+        let mut stats = TransferStats::default();
+        let took = Ok(Duration::from_millis(40));
+        stats.record_send(PORT, 10, Err(()), false);
+        // A receive after a failed send is not a retry of it
+        stats.record_receive(PORT, 10, took);
+        assert_eq!(stats.ports[PORT].retries, 0);
+        stats.record_send(PORT, 10, took, false);
+        assert_eq!(stats.ports[PORT].retries, 1);
+        stats.record_send(PORT, 10, took, true);
+        assert_eq!(stats.ports[PORT].retries, 2);
+        stats.record_send(PORT, 10, took, false);
+        assert_eq!(stats.ports[PORT].retries, 2);
+        assert_eq!(stats.ports[PORT].failures, 1);
+    }
+}
@@ -0,0 +1,131 @@
+use eframe::egui::Rect;
+use std::collections::HashMap;
+
+/// One callout of the guided tour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TourStep {
+    /// The widget the callout points at, as passed to [`Tour::mark`]
+    pub target: &'static str,
+    pub title: &'static str,
+    pub text: &'static str,
+}
+
+/// Where the tour stands
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TourState {
+    #[default]
+    Idle,
+    /// Showing the step at this position
+    Showing(usize),
+}
+
+/// What the buttons of a callout ask for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TourAction {
+    Next,
+    Back,
+    Skip,
+}
+
+/// The steps the editor itself shows; features add theirs with
+/// [`Tour::register`]
+const BUILT_IN_STEPS: [TourStep; 4] = [
+    TourStep {
+        target: "ports",
+        title: "Pick your MIDI interface",
+        text: "Choose the interface the FCB1010 is connected to. Dumps are sent to and received from it, and presets are previewed on it.",
+    },
+    TourStep {
+        target: "receive",
+        title: "Read the pedal's memory",
+        text: "Click Receive, then start a SysEx dump on the FCB1010. Its presets replace what is in the editor.",
+    },
+    TourStep {
+        target: "send",
+        title: "Write the presets to the pedal",
+        text: "Send puts the editor's presets into the FCB1010's memory. Dumps are checked for likely mistakes first.",
+    },
+    TourStep {
+        target: "presets",
+        title: "Edit the presets",
+        text: "Every card is one pedal of a bank: the programs, controllers, expression pedals and note it sends. Switch a message off with the checkbox next to it.",
+    },
+];
+
+/// A guided tour of callouts, one step at a time
+pub struct Tour {
+    steps: Vec<TourStep>,
+    state: TourState,
+    /// Where the targets were drawn this frame
+    targets: HashMap<&'static str, Rect>,
+}
+
+impl Default for Tour {
+    fn default() -> Self {
+        Self {
+            steps: BUILT_IN_STEPS.to_vec(),
+            state: TourState::Idle,
+            targets: HashMap::new(),
+        }
+    }
+}
+
+impl Tour {
+    /// Adds a step after the ones already there
+    pub fn register(&mut self, step: TourStep) {
+        self.steps.push(step);
+    }
+
+    pub fn start(&mut self) {
+        self.state = if self.steps.is_empty() {
+            TourState::Idle
+        } else {
+            TourState::Showing(0)
+        };
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state != TourState::Idle
+    }
+
+    /// The step shown, its position and how many there are
+    pub fn current(&self) -> Option<(&TourStep, usize, usize)> {
+        match self.state {
+            TourState::Idle => None,
+            TourState::Showing(position) => self
+                .steps
+                .get(position)
+                .map(|step| (step, position, self.steps.len())),
+        }
+    }
+
+    /// Moves on, returning whether that finished the tour
+    pub fn apply(&mut self, action: TourAction) -> bool {
+        let TourState::Showing(position) = self.state else {
+            return false;
+        };
+        self.state = match action {
+            TourAction::Next if position + 1 < self.steps.len() => TourState::Showing(position + 1),
+            TourAction::Back => TourState::Showing(position.saturating_sub(1)),
+            TourAction::Next | TourAction::Skip => TourState::Idle,
+        };
+        self.state == TourState::Idle
+    }
+
+    /// Records where `target` was drawn, so a step can point at it
+    pub fn mark(&mut self, target: &'static str, rect: Rect) {
+        if self.is_running() {
+            self.targets.insert(target, rect);
+        }
+    }
+
+    /// Where the current step's target was drawn this frame, forgetting the
+    /// rest; `None` when it is not on screen
+    pub fn take_target(&mut self) -> Option<Rect> {
+        let rect = self
+            .current()
+            .and_then(|(step, _, _)| self.targets.get(step.target).copied());
+        self.targets.clear();
+        rect
+    }
+}
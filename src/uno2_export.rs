@@ -0,0 +1,117 @@
+use crate::model::{bank_and_pedal, ExpressionPart, PresetField, SwitchMode, SysExMessage};
+use crate::project::ProjectMetadata;
+use crate::spec::global_function;
+
+/// A dump translated into an UnO2 configuration script, with what could not
+/// be translated
+pub struct Uno2Export {
+    pub script: String,
+    /// One line per setting without an UnO2 statement; they are also in the
+    /// script as `; UNMAPPED` comments
+    pub unmapped: Vec<String>,
+}
+
+/// Translates the presets into the text script the UnO2 firmware is set up
+/// with, for moving a configuration to an EPROM upgraded to UnO2.
+///
+/// UnO2 gives every message its own channel, so the global channels of this
+/// layout are written out per message. Switched off slots are left out and
+/// names and notes become comments. This is a starting point to check against
+/// the UnO2 manual, not a file to load unseen.
+pub fn uno2_script(message: &SysExMessage, metadata: &ProjectMetadata) -> Uno2Export {
+    let channel = |field: PresetField| message.global_channels()[global_function(field)].number();
+    let mut unmapped = Vec::new();
+    let mut script = format!(
+        "; FCB1010 UnO2 configuration, translated from a {} dump\n\
+         ; Check it against the UnO2 manual before loading it\n",
+        message.firmware().label()
+    );
+
+    for (relay, mode) in message.switch_modes().iter().enumerate() {
+        if *mode == SwitchMode::Toggle {
+            unmapped.push(format!(
+                "Relay SW{} is set to toggle; UnO2 sets relays per preset",
+                relay + 1
+            ));
+        }
+    }
+    let unknown = message.unknown_nonzero_bytes();
+    if unknown > 0 {
+        unmapped.push(format!(
+            "{} bytes outside the known memory map are set and are not translated",
+            unknown
+        ));
+    }
+    for line in &unmapped {
+        script.push_str(&format!("; UNMAPPED: {}\n", line));
+    }
+
+    for (index, preset) in message.presets().iter().enumerate() {
+        let (bank, pedal) = bank_and_pedal(index);
+        if pedal == 0 {
+            script.push_str(&format!("\nBANK {:02}\n", bank));
+        }
+        script.push_str(&format!("  PRESET {}\n", pedal + 1));
+        if let Some(labels) = metadata.presets.get(index) {
+            if !labels.name.is_empty() {
+                script.push_str(&format!("    ; {}\n", labels.name));
+            }
+            for line in labels.notes.lines() {
+                script.push_str(&format!("    ; {}\n", line));
+            }
+        }
+
+        for slot in 0..5 {
+            if let Some(program) = preset.program_change(slot) {
+                script.push_str(&format!(
+                    "    PC {} CH {}\n",
+                    program.get(),
+                    channel(PresetField::ProgramChange(slot))
+                ));
+            }
+        }
+        for slot in 0..2 {
+            if let Some((number, value)) = preset.control_change(slot) {
+                script.push_str(&format!(
+                    "    CC {} {} CH {}\n",
+                    number.get(),
+                    value.get(),
+                    channel(PresetField::ControlNumber(slot))
+                ));
+            }
+        }
+        let pedals = [
+            (
+                "EXPA",
+                preset.expression_pedal_a(),
+                PresetField::ExpressionA as fn(_) -> _,
+            ),
+            (
+                "EXPB",
+                preset.expression_pedal_b(),
+                PresetField::ExpressionB,
+            ),
+        ];
+        for (name, pedal, field) in pedals {
+            if let Some(pedal) = pedal {
+                script.push_str(&format!(
+                    "    {} CC {} {}-{} CH {}\n",
+                    name,
+                    pedal.controller.get(),
+                    pedal.min.get(),
+                    pedal.max.get(),
+                    channel(field(ExpressionPart::Controller))
+                ));
+            }
+        }
+        if let Some(note) = preset.note() {
+            script.push_str(&format!(
+                "    NOTE {} CH {}\n",
+                note.get(),
+                channel(PresetField::Note)
+            ));
+        }
+    }
+
+    Uno2Export { script, unmapped }
+}
@@ -0,0 +1,84 @@
+use crate::model::SysExMessage;
+
+// Global channel slots, in the order the FCB1010 stores them
+const CC_CHANNELS: usize = 5;
+const EXPRESSION_CHANNELS: usize = 7;
+const NOTE_CHANNEL: usize = 9;
+
+/// Messages the presets send on one channel
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelCount {
+    pub programs: usize,
+    /// Control changes and expression pedals
+    pub controls: usize,
+    pub notes: usize,
+}
+
+impl ChannelCount {
+    pub fn total(&self) -> usize {
+        self.programs + self.controls + self.notes
+    }
+}
+
+/// How often every MIDI channel and controller number is used across a dump.
+/// Slots that are switched off are not counted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelUsage {
+    pub channels: [ChannelCount; 16],
+    /// Presets sending each controller number, by channel
+    pub controllers: [[usize; 128]; 16],
+}
+
+impl ChannelUsage {
+    pub fn of(message: &SysExMessage) -> Self {
+        let mut usage = Self {
+            channels: [ChannelCount::default(); 16],
+            controllers: [[0; 128]; 16],
+        };
+        let channel = |function: usize| usize::from(message.global_channels()[function].get());
+
+        for preset in message.presets() {
+            for slot in 0..5 {
+                if preset.program_change(slot).is_some() {
+                    usage.channels[channel(slot)].programs += 1;
+                }
+            }
+            for slot in 0..2 {
+                if let Some((number, _)) = preset.control_change(slot) {
+                    let channel = channel(CC_CHANNELS + slot);
+                    usage.channels[channel].controls += 1;
+                    usage.controllers[channel][usize::from(number.get())] += 1;
+                }
+            }
+            let pedals = [preset.expression_pedal_a(), preset.expression_pedal_b()];
+            for (offset, pedal) in pedals.into_iter().enumerate() {
+                if let Some(pedal) = pedal {
+                    let channel = channel(EXPRESSION_CHANNELS + offset);
+                    usage.channels[channel].controls += 1;
+                    usage.controllers[channel][usize::from(pedal.controller.get())] += 1;
+                }
+            }
+            if preset.note().is_some() {
+                usage.channels[channel(NOTE_CHANNEL)].notes += 1;
+            }
+        }
+        usage
+    }
+
+    /// The highest count of any controller on any channel, to scale the heatmap
+    pub fn busiest_controller(&self) -> usize {
+        self.controllers
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The global functions set to send on `channel` (0-15)
+pub fn functions_on(message: &SysExMessage, channel: u8) -> Vec<usize> {
+    (0..10)
+        .filter(|&function| message.global_channels()[function].get() == channel)
+        .collect()
+}
@@ -0,0 +1,181 @@
+use crate::cc_dictionary::CcDictionary;
+use crate::model::{bank_and_pedal, ExpressionPart, PresetField, SysExMessage};
+use crate::numbering::Numbering;
+use crate::project::ProjectMetadata;
+use crate::spec::global_function;
+use crate::widgets::describe_program;
+use serde_json::{json, Value};
+
+/// A standalone page with every bank and preset and the messages it sends,
+/// searchable in any browser without a server or the editor. Slots that are
+/// switched off are left out.
+pub fn interactive_html(
+    message: &SysExMessage,
+    metadata: &ProjectMetadata,
+    dictionary: &CcDictionary,
+    numbering: Numbering,
+) -> String {
+    let channel = |field: PresetField| message.global_channels()[global_function(field)].number();
+    let presets: Vec<Value> = message
+        .presets()
+        .iter()
+        .enumerate()
+        .map(|(index, preset)| {
+            let used = |field: PresetField| preset.sends(field);
+            let mut messages = Vec::new();
+            for slot in 0..5 {
+                let field = PresetField::ProgramChange(slot);
+                if used(field) {
+                    messages.push(format!(
+                        "PC {}: {} (ch {})",
+                        slot + 1,
+                        describe_program(preset.get(field), &metadata.program_names),
+                        channel(field)
+                    ));
+                }
+            }
+            for slot in 0..2 {
+                let (number, value) = (
+                    PresetField::ControlNumber(slot),
+                    PresetField::ControlValue(slot),
+                );
+                if used(number) {
+                    messages.push(format!(
+                        "CC {}: {} = {} (ch {})",
+                        slot + 1,
+                        dictionary.describe(preset.get(number)),
+                        preset.get(value),
+                        channel(number)
+                    ));
+                }
+            }
+            for (name, pedal) in [
+                ("EXP A", PresetField::ExpressionA as fn(_) -> _),
+                ("EXP B", PresetField::ExpressionB),
+            ] {
+                let controller = pedal(ExpressionPart::Controller);
+                if used(controller) {
+                    messages.push(format!(
+                        "{}: {} from {} to {} (ch {})",
+                        name,
+                        dictionary.describe(preset.get(controller)),
+                        preset.get(pedal(ExpressionPart::Min)),
+                        preset.get(pedal(ExpressionPart::Max)),
+                        channel(controller)
+                    ));
+                }
+            }
+            if used(PresetField::Note) {
+                messages.push(format!(
+                    "Note: {} (ch {})",
+                    preset.get(PresetField::Note),
+                    channel(PresetField::Note)
+                ));
+            }
+
+            let (bank, pedal) = bank_and_pedal(index);
+            let labels = metadata.presets.get(index).cloned().unwrap_or_default();
+            json!({
+                "preset": index + 1,
+                "label": numbering.preset(index),
+                "bank": bank + 1,
+                "bank_label": numbering.bank(bank),
+                "pedal": pedal + 1,
+                "name": labels.name,
+                "notes": labels.notes,
+                "messages": messages,
+            })
+        })
+        .collect();
+
+    // Keep the data from ending the script element early
+    let data = Value::Array(presets).to_string().replace("</", "<\\/");
+    PAGE.replace("/*DATA*/", &data)
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>FCB1010 configuration</title>
+<style>
+body { font: 14px sans-serif; margin: 1em; }
+input { font-size: 1em; padding: 4px; width: 24em; }
+.bank { margin-top: 1em; }
+.presets { display: flex; flex-wrap: wrap; gap: 8px; }
+.preset { border: 1px solid #999; border-radius: 4px; padding: 6px; width: 15em; }
+.preset h3 { font-size: 1em; margin: 0 0 4px; }
+.notes { color: #555; font-style: italic; }
+.messages { margin: 4px 0 0; padding-left: 1.2em; }
+.empty { color: #999; }
+</style>
+</head>
+<body>
+<h1>FCB1010 configuration</h1>
+<p><input id="search" type="search" placeholder="Search names, notes and messages (e.g. CC 80, ch 3)" autofocus>
+<span id="count"></span></p>
+<div id="banks"></div>
+<script>
+const presets = /*DATA*/;
+const banks = document.getElementById("banks");
+const count = document.getElementById("count");
+
+function text(preset) {
+  return [preset.name, preset.notes, "preset " + preset.label].concat(preset.messages).join("\n").toLowerCase();
+}
+
+function render() {
+  const query = document.getElementById("search").value.trim().toLowerCase();
+  const shown = presets.filter(p => !query || text(p).includes(query));
+  banks.textContent = "";
+  for (let bank = 1; bank <= 10; bank++) {
+    const inBank = shown.filter(p => p.bank === bank);
+    if (inBank.length === 0) continue;
+    const section = document.createElement("div");
+    section.className = "bank";
+    const heading = document.createElement("h2");
+    heading.textContent = "Bank " + inBank[0].bank_label;
+    section.appendChild(heading);
+    const list = document.createElement("div");
+    list.className = "presets";
+    for (const preset of inBank) {
+      const card = document.createElement("div");
+      card.className = "preset";
+      const title = document.createElement("h3");
+      title.textContent = "Pedal " + preset.pedal + (preset.name ? ": " + preset.name : "");
+      card.appendChild(title);
+      if (preset.notes) {
+        const notes = document.createElement("div");
+        notes.className = "notes";
+        notes.textContent = preset.notes;
+        card.appendChild(notes);
+      }
+      if (preset.messages.length === 0) {
+        const empty = document.createElement("div");
+        empty.className = "empty";
+        empty.textContent = "Sends nothing";
+        card.appendChild(empty);
+      } else {
+        const messages = document.createElement("ul");
+        messages.className = "messages";
+        for (const message of preset.messages) {
+          const item = document.createElement("li");
+          item.textContent = message;
+          messages.appendChild(item);
+        }
+        card.appendChild(messages);
+      }
+      list.appendChild(card);
+    }
+    section.appendChild(list);
+    banks.appendChild(section);
+  }
+  count.textContent = shown.length + " of " + presets.length + " presets";
+}
+
+document.getElementById("search").addEventListener("input", render);
+render();
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,339 @@
+use crate::cc_dictionary::CcDictionary;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Which widget the preset editors use for numeric MIDI values
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum ValueWidget {
+    #[default]
+    DragValue,
+    Slider,
+    TextEntry,
+}
+
+impl ValueWidget {
+    pub const ALL: [ValueWidget; 3] = [
+        ValueWidget::DragValue,
+        ValueWidget::Slider,
+        ValueWidget::TextEntry,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ValueWidget::DragValue => "Drag value",
+            ValueWidget::Slider => "Slider",
+            ValueWidget::TextEntry => "Text entry",
+        }
+    }
+}
+
+/// How MIDI values are shown and typed; device manuals and forum posts
+/// often give them in hex
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum NumberBase {
+    #[default]
+    Decimal,
+    Hex,
+}
+
+impl NumberBase {
+    pub const ALL: [NumberBase; 2] = [NumberBase::Decimal, NumberBase::Hex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NumberBase::Decimal => "Decimal",
+            NumberBase::Hex => "Hex",
+        }
+    }
+
+    /// "64" or "0x40"
+    pub fn format(self, value: u8) -> String {
+        match self {
+            NumberBase::Decimal => value.to_string(),
+            NumberBase::Hex => format!("0x{:02X}", value),
+        }
+    }
+
+    /// "64 = 0x40", for tooltips
+    pub fn both(value: u8) -> String {
+        format!("{} = 0x{:02X}", value, value)
+    }
+
+    /// Reads a typed value. "0x40", "40h" and "$40" are hex either way; a
+    /// bare number is read in this base.
+    pub fn parse(self, text: &str) -> Option<u32> {
+        let text = text.trim();
+        let lower = text.to_ascii_lowercase();
+        if let Some(digits) = lower
+            .strip_prefix("0x")
+            .or_else(|| lower.strip_prefix('$'))
+            .or_else(|| lower.strip_suffix('h'))
+        {
+            return u32::from_str_radix(digits, 16).ok();
+        }
+        match self {
+            NumberBase::Decimal => text.parse().ok(),
+            NumberBase::Hex => u32::from_str_radix(text, 16).ok(),
+        }
+    }
+}
+
+/// Edits a single byte with the configured widget type, shown in `base`
+pub fn value_editor(
+    ui: &mut egui::Ui,
+    value: &mut u8,
+    range: RangeInclusive<u8>,
+    widget: ValueWidget,
+    base: NumberBase,
+) -> egui::Response {
+    let response = match widget {
+        ValueWidget::DragValue => ui.add(MidiValueWidget::new(value, range).base(base)),
+        ValueWidget::Slider => ui.add(
+            egui::Slider::new(value, range)
+                .custom_formatter(move |value, _| base.format(value as u8))
+                .custom_parser(move |text| base.parse(text).map(f64::from)),
+        ),
+        ValueWidget::TextEntry => text_entry(ui, value, range, base),
+    };
+    response.on_hover_text(NumberBase::both(*value))
+}
+
+// Horizontal drag distance, in points, that moves the value by one step
+const POINTS_PER_STEP: f32 = 4.0;
+
+#[derive(Clone, Default)]
+struct MidiValueState {
+    /// Drag distance not yet turned into a step
+    remainder: f32,
+    /// Text being typed after a double-click
+    editing: Option<String>,
+}
+
+/// A drag value for MIDI bytes: dragging moves by 1, by 10 while Shift is
+/// held, and by 1 at half speed while Ctrl is held. Double-click to type.
+pub struct MidiValueWidget<'a> {
+    value: &'a mut u8,
+    range: RangeInclusive<u8>,
+    base: NumberBase,
+}
+
+impl<'a> MidiValueWidget<'a> {
+    pub fn new(value: &'a mut u8, range: RangeInclusive<u8>) -> Self {
+        Self {
+            value,
+            range,
+            base: NumberBase::default(),
+        }
+    }
+
+    pub fn base(mut self, base: NumberBase) -> Self {
+        self.base = base;
+        self
+    }
+
+    fn clamp(&self, value: i32) -> u8 {
+        value.clamp(*self.range.start() as i32, *self.range.end() as i32) as u8
+    }
+}
+
+impl egui::Widget for MidiValueWidget<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let id = ui.next_auto_id().with("midi_value");
+        let mut state = ui
+            .data_mut(|d| d.get_temp::<MidiValueState>(id))
+            .unwrap_or_default();
+        let before = *self.value;
+
+        let mut response = if let Some(mut text) = state.editing.take() {
+            let response = ui.add(egui::TextEdit::singleline(&mut text).desired_width(32.0));
+            if !response.has_focus() && !response.lost_focus() {
+                response.request_focus();
+            }
+            if response.lost_focus() {
+                let cancelled = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                if let (false, Some(parsed)) = (cancelled, self.base.parse(&text)) {
+                    *self.value = self.clamp(parsed.min(i32::MAX as u32) as i32);
+                }
+            } else {
+                state.editing = Some(text);
+            }
+            response
+        } else {
+            let response = ui.add(
+                egui::Button::new(self.base.format(*self.value))
+                    .min_size(egui::vec2(32.0, 0.0))
+                    .sense(egui::Sense::click_and_drag()),
+            );
+
+            if response.double_clicked() {
+                state.editing = Some(self.base.format(*self.value));
+            } else if response.dragged() {
+                let modifiers = ui.input(|i| i.modifiers);
+                let (step, distance) = if modifiers.command {
+                    (1, POINTS_PER_STEP * 2.0)
+                } else if modifiers.shift {
+                    (10, POINTS_PER_STEP)
+                } else {
+                    (1, POINTS_PER_STEP)
+                };
+
+                state.remainder += response.drag_delta().x;
+                let steps = (state.remainder / distance).trunc();
+                state.remainder -= steps * distance;
+                *self.value = self.clamp(*self.value as i32 + steps as i32 * step);
+            } else {
+                state.remainder = 0.0;
+            }
+            response.on_hover_text("Drag to change (Shift: ×10, Ctrl: fine), double-click to type")
+        };
+
+        ui.data_mut(|d| d.insert_temp(id, state));
+        if *self.value != before {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+fn text_entry(
+    ui: &mut egui::Ui,
+    value: &mut u8,
+    range: RangeInclusive<u8>,
+    base: NumberBase,
+) -> egui::Response {
+    // Keep the raw text while the field has focus, so it can be cleared and retyped
+    let id = ui.next_auto_id();
+    let mut text = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| base.format(*value));
+
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut text)
+            .id(id)
+            .desired_width(32.0),
+    );
+
+    if response.changed() {
+        if let Some(parsed) = base
+            .parse(&text)
+            .and_then(|parsed| u8::try_from(parsed).ok())
+        {
+            *value = parsed.clamp(*range.start(), *range.end());
+        }
+    }
+
+    if response.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text));
+    } else {
+        ui.data_mut(|d| d.remove::<String>(id));
+    }
+
+    response
+}
+
+/// A combo box naming the controllers of the active CC dictionary.
+/// Returns the newly picked controller, if the user chose one.
+pub fn controller_picker(
+    ui: &mut egui::Ui,
+    controller: u8,
+    dictionary: &CcDictionary,
+) -> Option<u8> {
+    let mut selected = controller;
+    egui::ComboBox::from_id_source(ui.next_auto_id().with("controller_picker"))
+        .width(140.0)
+        .selected_text(dictionary.describe(controller))
+        .show_ui(ui, |ui| {
+            for (number, _) in dictionary.entries() {
+                ui.selectable_value(&mut selected, number, dictionary.describe(number));
+            }
+        });
+    (selected != controller).then_some(selected)
+}
+
+/// A combo box listing the imported patch names of the target synth.
+/// Returns the newly picked program, if the user chose one.
+pub fn program_picker(ui: &mut egui::Ui, program: u8, names: &BTreeMap<u8, String>) -> Option<u8> {
+    let mut selected = program;
+    egui::ComboBox::from_id_source(ui.next_auto_id().with("program_picker"))
+        .width(140.0)
+        .selected_text(describe_program(program, names))
+        .show_ui(ui, |ui| {
+            for &number in names.keys() {
+                ui.selectable_value(&mut selected, number, describe_program(number, names));
+            }
+        });
+    (selected != program).then_some(selected)
+}
+
+/// "PC 20 – Lead Stack", or just "PC 20" without a name
+pub fn describe_program(program: u8, names: &BTreeMap<u8, String>) -> String {
+    match names.get(&program) {
+        Some(name) => format!("PC {} – {}", program, name),
+        None => format!("PC {}", program),
+    }
+}
+
+/// A vertical bar showing an incoming 0-127 value, with the configured
+/// min/max range marked so the pedal sweep can be checked against it
+pub fn vertical_meter(
+    ui: &mut egui::Ui,
+    value: Option<u8>,
+    min: u8,
+    max: u8,
+    base: NumberBase,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(24.0, 128.0), egui::Sense::hover());
+    let visuals = ui.visuals();
+    let painter = ui.painter();
+    let y_of = |v: u8| rect.bottom() - rect.height() * f32::from(v.min(127)) / 127.0;
+
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    if let Some(value) = value {
+        let fill = egui::Rect::from_min_max(egui::pos2(rect.left(), y_of(value)), rect.max);
+        let color = if (min..=max).contains(&value) {
+            egui::Color32::from_rgb(80, 180, 80)
+        } else {
+            egui::Color32::from_rgb(200, 120, 40)
+        };
+        painter.rect_filled(fill, 2.0, color);
+    }
+    for bound in [min, max] {
+        painter.hline(
+            rect.x_range(),
+            y_of(bound),
+            egui::Stroke::new(1.0, visuals.strong_text_color()),
+        );
+    }
+    painter.rect_stroke(rect, 2.0, visuals.widgets.noninteractive.bg_stroke);
+
+    let range = format!("range {}–{}", base.format(min), base.format(max));
+    response.on_hover_text(match value {
+        Some(value) => format!("{} ({})", NumberBase::both(value), range),
+        None => format!("No value received yet ({})", range),
+    })
+}
+
+/// A checkbox-enabled value: unchecked means "any". `offset` is added for
+/// display, e.g. 1 to show channels 0-15 as 1-16.
+pub fn optional_value(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<u8>,
+    range: RangeInclusive<u8>,
+    offset: u8,
+) -> bool {
+    let mut enabled = value.is_some();
+    let mut shown = value.unwrap_or(*range.start()) + offset;
+    let mut changed = ui.checkbox(&mut enabled, label).changed();
+    let shown_range = range.start() + offset..=range.end() + offset;
+    changed |= ui
+        .add_enabled(
+            enabled,
+            egui::DragValue::new(&mut shown).clamp_range(shown_range),
+        )
+        .changed();
+    *value = enabled.then_some(shown - offset);
+    changed
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// The Windows MIDI API the editor talks through. Some interfaces only pass
+/// large SysEx reliably over one of them. midir fixes the API when the editor
+/// is built: WinMM by default, WinRT with the `winrt` feature.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum MidiBackend {
+    #[default]
+    WinMm,
+    WinRt,
+}
+
+impl MidiBackend {
+    pub const ALL: [MidiBackend; 2] = [MidiBackend::WinMm, MidiBackend::WinRt];
+
+    /// The API this build talks through
+    pub const BUILT: MidiBackend = if cfg!(feature = "winrt") {
+        MidiBackend::WinRt
+    } else {
+        MidiBackend::WinMm
+    };
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MidiBackend::WinMm => "WinMM",
+            MidiBackend::WinRt => "WinRT",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            MidiBackend::WinMm => "The classic API every interface driver supports",
+            MidiBackend::WinRt => "The newer API of Windows 10 and later",
+        }
+    }
+
+    /// How to get a build that talks through this API
+    pub fn build_hint(self) -> &'static str {
+        match self {
+            MidiBackend::WinMm => "a build without the `winrt` feature",
+            MidiBackend::WinRt => "a build with `--features winrt`",
+        }
+    }
+}
+
+/// Windows rounds sleeps up to its timer tick, 15.6 ms unless a program asks
+/// for less. While one of these is held the tick is 1 ms, so pauses between
+/// messages last as long as they were set to. Does nothing elsewhere.
+pub struct HighResolutionTimer(());
+
+impl HighResolutionTimer {
+    pub fn request() -> Self {
+        #[cfg(windows)]
+        // SAFETY: only changes the system timer resolution; undone on drop
+        unsafe {
+            windows_sys::Win32::Media::timeBeginPeriod(1);
+        }
+        Self(())
+    }
+}
+
+impl Drop for HighResolutionTimer {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        // SAFETY: pairs with the timeBeginPeriod call in `request`
+        unsafe {
+            windows_sys::Win32::Media::timeEndPeriod(1);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::model::{Preset, PresetField, PEDALS_PER_BANK};
+use crate::values::Value7;
+use fcb1010_core::builder::{BuildError, DumpBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Where user wizards are kept, one JSON file each, next to the config
+pub const WIZARD_DIR: &str = "wizards";
+
+// Shipped with the editor, in the format of user wizards
+const BUILT_IN: [&str; 2] = [
+    include_str!("wizards/helix.json"),
+    include_str!("wizards/amp_loops.json"),
+];
+
+/// Something the wizard asks before generating, answered with a MIDI value
+#[derive(Debug, Clone, Deserialize)]
+pub struct Question {
+    pub key: String,
+    pub label: String,
+    pub default: u8,
+    pub max: u8,
+}
+
+/// How a field's value is worked out from the answers: a plain number,
+/// `{"answer": key, "step": n}` for the answer plus `step` for every pedal
+/// after the first of the rule, or `{"count": step}` for `step` times the
+/// pedal's position in the rule, starting at 0
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Fixed(u8),
+    Answer {
+        answer: String,
+        #[serde(default)]
+        step: i16,
+    },
+    Counting {
+        count: i16,
+    },
+}
+
+/// Fields set on a run of pedals of the generated bank
+#[derive(Debug, Clone)]
+pub struct PedalRule {
+    pub pedals: Range<usize>,
+    /// The preset name; `{n}` is replaced by the pedal's position in the rule
+    pub name: String,
+    pub fields: Vec<(PresetField, Value)>,
+}
+
+/// A guided setup that fills a bank from a few answers
+#[derive(Debug, Clone)]
+pub struct Wizard {
+    pub name: String,
+    pub description: String,
+    pub questions: Vec<Question>,
+    pub rules: Vec<PedalRule>,
+}
+
+// A wizard file as written: pedals are counted from 1, both ends included,
+// and fields are named by their `PresetField` key
+#[derive(Deserialize)]
+struct WizardFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    questions: Vec<Question>,
+    rules: Vec<RuleFile>,
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    pedals: [usize; 2],
+    #[serde(default)]
+    name: String,
+    fields: BTreeMap<String, Value>,
+}
+
+/// The built-in wizards followed by the `.json` files in `dir`, sorted by
+/// file name; a user wizard with the name of a built-in one replaces it.
+/// Files that cannot be read are skipped and explained in the second list.
+pub fn load_all(dir: &Path) -> (Vec<Wizard>, Vec<String>) {
+    let mut wizards: Vec<Wizard> = BUILT_IN
+        .iter()
+        .map(|json| Wizard::parse(json).expect("built-in wizards are valid"))
+        .collect();
+    let mut problems = Vec::new();
+
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    for path in paths {
+        let wizard = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| Wizard::parse(&json));
+        match wizard {
+            Ok(wizard) => match wizards.iter_mut().find(|w| w.name == wizard.name) {
+                Some(existing) => *existing = wizard,
+                None => wizards.push(wizard),
+            },
+            Err(e) => problems.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    (wizards, problems)
+}
+
+/// A bank generated by a wizard, waiting to be applied
+#[derive(Debug, Clone)]
+pub struct WizardResult {
+    /// The new presets and their names, by index
+    pub presets: Vec<(usize, Preset, String)>,
+}
+
+impl Wizard {
+    /// Reads a wizard from JSON, checking every pedal, field key, answer
+    /// and number in it
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let file: WizardFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        for question in &file.questions {
+            if question.max > 127 || question.default > question.max {
+                return Err(format!(
+                    "Question {} has to stay within 0-127 and start at most at its maximum",
+                    question.key
+                ));
+            }
+        }
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let [first, last] = rule.pedals;
+                if first < 1 || first > last || last > PEDALS_PER_BANK {
+                    return Err(format!(
+                        "Pedals {}-{} are not a run of pedals 1-{}",
+                        first, last, PEDALS_PER_BANK
+                    ));
+                }
+                let fields = rule
+                    .fields
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let field = PresetField::from_key(&key)
+                            .ok_or_else(|| format!("Unknown field {}", key))?;
+                        match &value {
+                            Value::Fixed(number) if *number > 127 => {
+                                return Err(format!("{} is above 127 for {}", number, key));
+                            }
+                            Value::Answer { answer, .. }
+                                if !file.questions.iter().any(|q| &q.key == answer) =>
+                            {
+                                return Err(format!("{} uses {}, which is not asked", key, answer));
+                            }
+                            _ => {}
+                        }
+                        Ok((field, value))
+                    })
+                    .collect::<Result<_, String>>()?;
+                Ok(PedalRule {
+                    pedals: first - 1..last,
+                    name: rule.name,
+                    fields,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            name: file.name,
+            description: file.description,
+            questions: file.questions,
+            rules,
+        })
+    }
+
+    pub fn default_answers(&self) -> Vec<u8> {
+        self.questions.iter().map(|q| q.default).collect()
+    }
+
+    /// Builds the bank from the answers, given in `questions` order
+    pub fn generate(&self, bank: usize, answers: &[u8]) -> Result<WizardResult, BuildError> {
+        let answer = |key: &str| {
+            self.questions
+                .iter()
+                .position(|q| q.key == key)
+                .and_then(|i| answers.get(i))
+                .copied()
+                .unwrap_or(0)
+        };
+
+        let mut builder = DumpBuilder::new().bank(bank);
+        let mut names = vec![String::new(); PEDALS_PER_BANK];
+        for rule in &self.rules {
+            for pedal in rule.pedals.clone() {
+                let position = (pedal - rule.pedals.start) as i16;
+                if !rule.name.is_empty() {
+                    names[pedal] = rule.name.replace("{n}", &(position + 1).to_string());
+                }
+                builder = builder.pedal(pedal + 1);
+                for (field, value) in &rule.fields {
+                    let value = match value {
+                        Value::Fixed(value) => i16::from(*value),
+                        Value::Answer { answer: key, step } => {
+                            i16::from(answer(key)) + step * position
+                        }
+                        Value::Counting { count } => count * position,
+                    };
+                    builder = builder.set(*field, Value7::saturating(value.into()).get());
+                }
+            }
+        }
+
+        let message = builder.build()?;
+        let presets = names
+            .into_iter()
+            .enumerate()
+            .map(|(pedal, name)| {
+                let index = bank * PEDALS_PER_BANK + pedal;
+                (index, *message.preset(index), name)
+            })
+            .collect();
+        Ok(WizardResult { presets })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_wizards_fill_a_bank() {
+        let (wizards, problems) = load_all(Path::new("no such folder"));
+        assert!(problems.is_empty());
+        assert_eq!(wizards.len(), BUILT_IN.len());
+
+        let helix = &wizards[0];
+        let result = helix.generate(2, &[10, 69]).unwrap();
+        let (index, preset, name) = &result.presets[2];
+        assert_eq!((*index, name.as_str()), (22, "Preset 3"));
+        assert_eq!(preset.get(PresetField::ProgramChange(0)), 12);
+        let (_, preset, name) = &result.presets[7];
+        assert_eq!(name, "Snapshot 3");
+        assert_eq!(preset.get(PresetField::ControlNumber(0)), 69);
+        assert_eq!(preset.get(PresetField::ControlValue(0)), 2);
+        assert!(!preset.is_enabled(0));
+    }
+
+    #[test]
+    fn refuses_broken_wizards() {
+        let wizard = |rule: &str| {
+            format!(
+                r#"{{"name": "Test", "questions": [{{"key": "cc", "label": "CC", "default": 1, "max": 119}}],
+                    "rules": [{}]}}"#,
+                rule
+            )
+        };
+        assert!(Wizard::parse(&wizard(r#"{"pedals": [1, 10], "fields": {"pc1": 5}}"#)).is_ok());
+        for broken in [
+            r#"{"pedals": [0, 3], "fields": {}}"#,
+            r#"{"pedals": [4, 11], "fields": {}}"#,
+            r#"{"pedals": [1, 2], "fields": {"pc6": 5}}"#,
+            r#"{"pedals": [1, 2], "fields": {"note": 128}}"#,
+            r#"{"pedals": [1, 2], "fields": {"cc1_number": {"answer": "volume"}}}"#,
+        ] {
+            assert!(Wizard::parse(&wizard(broken)).is_err(), "{}", broken);
+        }
+    }
+
+    #[test]
+    fn user_wizards_are_added_or_replace_built_in_ones() {
+        let dir = std::env::temp_dir().join(format!("fcb1010-wizards-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let own = r#"{"name": "Set up for Line 6 Helix", "rules": [{"pedals": [1, 1], "fields": {"note": 60}}]}"#;
+        fs::write(dir.join("helix.json"), own).unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+        fs::write(dir.join("notes.txt"), "not a wizard").unwrap();
+
+        let (wizards, problems) = load_all(&dir);
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(wizards.len(), BUILT_IN.len());
+        assert_eq!(wizards[0].rules.len(), 1);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("broken.json"));
+    }
+}